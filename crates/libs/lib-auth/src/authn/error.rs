@@ -0,0 +1,38 @@
+use derive_more::From;
+use serde::Serialize;
+
+use crate::pwd;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Serialize, From)]
+pub enum Error {
+    NoBackendConfigured,
+    BackendNotFound(String),
+
+    // -- Local
+    LocalAccountNotFound,
+    UserHasNoPwd,
+    PwdNotMatching,
+
+    // -- Ldap
+    LdapConnect(String),
+    LdapServiceBindFail(String),
+    LdapSearch(String),
+    LdapUserNotFound,
+    LdapUserNotUnique,
+
+    // -- Modules
+    #[from]
+    Pwd(pwd::Error),
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
@@ -0,0 +1,265 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use ldap3::{ldap_escape, Ldap, LdapConnAsync, LdapConnSettings, Scope, SearchEntry};
+
+use crate::config::LdapConfig;
+
+use super::{AuthBackend, AuthIdentity, BackendKind, Error, LocalAccount, Result};
+
+const CONN_TIMEOUT_SEC: u64 = 5;
+
+/// Search + bind against an LDAP / Active Directory server.
+///
+/// 1. Bind with the service account (or anonymously when `BIND_DN` is empty).
+/// 2. Search the user entry with `USER_FILTER` under `BASE_DN`.
+/// 3. Bind with the entry DN and the user password.
+pub struct LdapBackend {
+    config: LdapConfig,
+}
+
+impl LdapBackend {
+    pub fn new(config: LdapConfig) -> Self {
+        Self { config }
+    }
+
+    async fn connect(&self) -> Result<Ldap> {
+        let settings =
+            LdapConnSettings::new().set_conn_timeout(Duration::from_secs(CONN_TIMEOUT_SEC));
+        let (conn, ldap) = LdapConnAsync::with_settings(settings, &self.config.URL)
+            .await
+            .map_err(|ex| Error::LdapConnect(ex.to_string()))?;
+        ldap3::drive!(conn);
+
+        Ok(ldap)
+    }
+
+    async fn find_user_entry(&self, ldap: &mut Ldap, username: &str) -> Result<SearchEntry> {
+        let config = &self.config;
+
+        // -- Service bind (anonymous search when no bind dn)
+        if !config.BIND_DN.is_empty() {
+            ldap.simple_bind(&config.BIND_DN, &config.BIND_PWD)
+                .await
+                .and_then(|res| res.success())
+                .map_err(|ex| Error::LdapServiceBindFail(ex.to_string()))?;
+        }
+
+        // -- Search the user entry
+        let filter = config
+            .USER_FILTER
+            .replace("{username}", &ldap_escape(username));
+        let (entries, _res) = ldap
+            .search(
+                &config.BASE_DN,
+                Scope::Subtree,
+                &filter,
+                vec![config.DEPARTMENT_ATTR.as_str()],
+            )
+            .await
+            .and_then(|res| res.success())
+            .map_err(|ex| Error::LdapSearch(ex.to_string()))?;
+
+        let mut entries = entries.into_iter();
+        let entry = entries.next().ok_or(Error::LdapUserNotFound)?;
+        if entries.next().is_some() {
+            return Err(Error::LdapUserNotUnique);
+        }
+
+        Ok(SearchEntry::construct(entry))
+    }
+}
+
+#[async_trait]
+impl AuthBackend for LdapBackend {
+    async fn authenticate(
+        &self,
+        username: &str,
+        pwd_clear: &str,
+        _local: Option<&LocalAccount>,
+    ) -> Result<AuthIdentity> {
+        // NOTE: An empty pwd would be an "unauthenticated bind",
+        //       which most servers accept.
+        if pwd_clear.is_empty() {
+            return Err(Error::PwdNotMatching);
+        }
+
+        let mut ldap = self.connect().await?;
+
+        let entry = self.find_user_entry(&mut ldap, username).await;
+
+        // -- Bind as the user
+        let res = match entry {
+            Ok(entry) => ldap
+                .simple_bind(&entry.dn, pwd_clear)
+                .await
+                .and_then(|res| res.success())
+                .map(|_| entry)
+                .map_err(|_| Error::PwdNotMatching),
+            Err(ex) => Err(ex),
+        };
+
+        // Best effort, the connection is dropped anyway.
+        let _ = ldap.unbind().await;
+
+        let entry = res?;
+        let department = entry
+            .attrs
+            .get(&self.config.DEPARTMENT_ATTR)
+            .and_then(|values| values.first())
+            .cloned();
+
+        Ok(AuthIdentity {
+            username: username.to_string(),
+            backend: BackendKind::Ldap,
+            department,
+            provision_centers: self.config.CENTERS.clone(),
+            scheme_status: None,
+        })
+    }
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use crate::authn::mock_ldap::{self, MockLdapEntry};
+
+    use super::*;
+
+    const FX_SERVICE_DN: &str = "cn=service,dc=ies,dc=test";
+    const FX_SERVICE_PWD: &str = "service_pwd";
+
+    async fn fx_backend(bind_dn: &str, bind_pwd: &str) -> Result<LdapBackend> {
+        let url = mock_ldap::start(
+            vec![
+                MockLdapEntry {
+                    dn: "uid=jgarcia,ou=people,dc=ies,dc=test",
+                    uid: "jgarcia",
+                    pwd: "secret",
+                    department: Some("Matemáticas"),
+                },
+                MockLdapEntry {
+                    dn: "uid=mlopez,ou=people,dc=ies,dc=test",
+                    uid: "mlopez",
+                    pwd: "secret2",
+                    department: None,
+                },
+            ],
+            (FX_SERVICE_DN, FX_SERVICE_PWD),
+        )
+        .await?;
+
+        Ok(LdapBackend::new(LdapConfig {
+            URL: url,
+            BASE_DN: "ou=people,dc=ies,dc=test".to_string(),
+            USER_FILTER: "(uid={username})".to_string(),
+            BIND_DN: bind_dn.to_string(),
+            BIND_PWD: bind_pwd.to_string(),
+            DEPARTMENT_ATTR: "departmentNumber".to_string(),
+            CENTERS: vec!["default".to_string()],
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_ldap_authenticate_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let backend = fx_backend(FX_SERVICE_DN, FX_SERVICE_PWD).await?;
+
+        // -- Exec
+        let identity = backend.authenticate("jgarcia", "secret", None).await?;
+
+        // -- Check
+        assert_eq!(identity.username, "jgarcia");
+        assert_eq!(identity.backend, BackendKind::Ldap);
+        assert_eq!(identity.department.as_deref(), Some("Matemáticas"));
+        assert_eq!(identity.provision_centers, ["default"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ldap_authenticate_anonymous_search_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let backend = fx_backend("", "").await?;
+
+        // -- Exec
+        let identity = backend.authenticate("mlopez", "secret2", None).await?;
+
+        // -- Check
+        assert_eq!(identity.username, "mlopez");
+        assert_eq!(identity.department, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ldap_authenticate_err_pwd_not_matching() -> Result<()> {
+        // -- Setup & Fixtures
+        let backend = fx_backend(FX_SERVICE_DN, FX_SERVICE_PWD).await?;
+
+        // -- Exec
+        let res = backend.authenticate("jgarcia", "wrong", None).await;
+
+        // -- Check
+        assert!(
+            matches!(res, Err(Error::PwdNotMatching)),
+            "Should have matched `Err(Error::PwdNotMatching)` but was `{res:?}`"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ldap_authenticate_err_empty_pwd() -> Result<()> {
+        // -- Setup & Fixtures
+        let backend = fx_backend(FX_SERVICE_DN, FX_SERVICE_PWD).await?;
+
+        // -- Exec
+        let res = backend.authenticate("jgarcia", "", None).await;
+
+        // -- Check
+        assert!(
+            matches!(res, Err(Error::PwdNotMatching)),
+            "Should have matched `Err(Error::PwdNotMatching)` but was `{res:?}`"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ldap_authenticate_err_user_not_found() -> Result<()> {
+        // -- Setup & Fixtures
+        let backend = fx_backend(FX_SERVICE_DN, FX_SERVICE_PWD).await?;
+
+        // -- Exec
+        let res = backend.authenticate("nobody", "secret", None).await;
+
+        // -- Check
+        assert!(
+            matches!(res, Err(Error::LdapUserNotFound)),
+            "Should have matched `Err(Error::LdapUserNotFound)` but was `{res:?}`"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ldap_authenticate_err_service_bind() -> Result<()> {
+        // -- Setup & Fixtures
+        let backend = fx_backend(FX_SERVICE_DN, "wrong").await?;
+
+        // -- Exec
+        let res = backend.authenticate("jgarcia", "secret", None).await;
+
+        // -- Check
+        assert!(
+            matches!(res, Err(Error::LdapServiceBindFail(_))),
+            "Should have matched `Err(Error::LdapServiceBindFail(_))` but was `{res:?}`"
+        );
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
@@ -0,0 +1,39 @@
+use async_trait::async_trait;
+
+use crate::pwd::{self, ContentToHash};
+
+use super::{AuthBackend, AuthIdentity, BackendKind, Error, LocalAccount, Result};
+
+/// Validates against the password hash stored in `users.pwd`.
+pub struct LocalBackend;
+
+#[async_trait]
+impl AuthBackend for LocalBackend {
+    async fn authenticate(
+        &self,
+        username: &str,
+        pwd_clear: &str,
+        local: Option<&LocalAccount>,
+    ) -> Result<AuthIdentity> {
+        let account = local.ok_or(Error::LocalAccountNotFound)?;
+        let pwd_ref = account.pwd.clone().ok_or(Error::UserHasNoPwd)?;
+
+        let scheme_status = pwd::validate_pwd(
+            ContentToHash {
+                salt: account.pwd_salt,
+                content: pwd_clear.to_string(),
+            },
+            pwd_ref,
+        )
+        .await
+        .map_err(|_| Error::PwdNotMatching)?;
+
+        Ok(AuthIdentity {
+            username: username.to_string(),
+            backend: BackendKind::Local,
+            department: None,
+            provision_centers: Vec::new(),
+            scheme_status: Some(scheme_status),
+        })
+    }
+}
@@ -0,0 +1,227 @@
+//! Minimal in-process LDAP server for the `ldap` backend tests.
+//!
+//! Only speaks what `LdapBackend` uses: simple bind, search with an
+//! equality filter (`(uid=...)`), and unbind.
+
+use bytes::BytesMut;
+use lber::common::TagClass;
+use lber::parse::parse_tag;
+use lber::structure::{StructureTag, PL};
+use lber::structures::{ASNTag, Enumerated, Integer};
+use lber::write::encode_into;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const RES_SUCCESS: i64 = 0;
+const RES_INVALID_CREDENTIALS: i64 = 49;
+
+// LDAP protocol op application tags.
+const OP_BIND_REQ: u64 = 0;
+const OP_BIND_RES: u64 = 1;
+const OP_UNBIND_REQ: u64 = 2;
+const OP_SEARCH_REQ: u64 = 3;
+const OP_SEARCH_ENTRY: u64 = 4;
+const OP_SEARCH_DONE: u64 = 5;
+
+// Filter choice context tag.
+const FILTER_EQUALITY: u64 = 3;
+
+#[derive(Clone)]
+pub struct MockLdapEntry {
+    pub dn: &'static str,
+    pub uid: &'static str,
+    pub pwd: &'static str,
+    pub department: Option<&'static str>,
+}
+
+/// Start the server on a random local port and return its `ldap://` url.
+pub async fn start(
+    entries: Vec<MockLdapEntry>,
+    service: (&'static str, &'static str),
+) -> std::io::Result<String> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("ldap://{}", listener.local_addr()?);
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let entries = entries.clone();
+            tokio::spawn(async move {
+                let _ = serve(stream, &entries, service).await;
+            });
+        }
+    });
+
+    Ok(url)
+}
+
+async fn serve(
+    mut stream: TcpStream,
+    entries: &[MockLdapEntry],
+    service: (&str, &str),
+) -> std::io::Result<()> {
+    let mut buf: Vec<u8> = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+
+        // Process every complete message in the buffer.
+        while let Ok((rest, msg)) = parse_tag(&buf) {
+            let consumed = buf.len() - rest.len();
+            let responses = handle_message(msg, entries, service);
+            buf.drain(..consumed);
+
+            let Some(responses) = responses else {
+                return Ok(()); // Unbind
+            };
+            for response in responses {
+                let mut out = BytesMut::new();
+                encode_into(&mut out, response)?;
+                stream.write_all(&out).await?;
+            }
+        }
+    }
+}
+
+/// Returns `None` when the connection should be closed.
+fn handle_message(
+    msg: StructureTag,
+    entries: &[MockLdapEntry],
+    service: (&str, &str),
+) -> Option<Vec<StructureTag>> {
+    let mut parts = msg.expect_constructed()?.into_iter();
+    let msg_id = parse_int(parts.next()?);
+    let op = parts.next()?;
+
+    match op.id {
+        OP_BIND_REQ => {
+            let mut fields = op.expect_constructed()?.into_iter().skip(1); // version
+            let name = parse_string(fields.next()?);
+            let pwd = parse_string(fields.next()?);
+
+            let accepted = (name.is_empty() && pwd.is_empty())
+                || (name == service.0 && pwd == service.1)
+                || entries.iter().any(|e| e.dn == name && e.pwd == pwd);
+            let code = if accepted {
+                RES_SUCCESS
+            } else {
+                RES_INVALID_CREDENTIALS
+            };
+
+            Some(vec![message(msg_id, ldap_result(OP_BIND_RES, code))])
+        }
+
+        OP_SEARCH_REQ => {
+            let filter = op.expect_constructed()?.into_iter().nth(6)?;
+            let uid = find_equality_value(filter)?;
+
+            let mut responses: Vec<StructureTag> = entries
+                .iter()
+                .filter(|e| e.uid == uid)
+                .map(|e| message(msg_id, search_entry(e)))
+                .collect();
+            responses.push(message(msg_id, ldap_result(OP_SEARCH_DONE, RES_SUCCESS)));
+
+            Some(responses)
+        }
+
+        OP_UNBIND_REQ => None,
+
+        _ => Some(Vec::new()),
+    }
+}
+
+// region:    --- BER Helpers
+
+fn find_equality_value(filter: StructureTag) -> Option<String> {
+    if filter.class == TagClass::Context && filter.id == FILTER_EQUALITY {
+        return filter.expect_constructed()?.pop().map(parse_string);
+    }
+    // and/or/not, look in the sub filters.
+    match filter.payload {
+        PL::C(subs) => subs.into_iter().find_map(find_equality_value),
+        PL::P(_) => None,
+    }
+}
+
+fn parse_int(tag: StructureTag) -> i64 {
+    tag.expect_primitive()
+        .unwrap_or_default()
+        .iter()
+        .fold(0, |res, &byte| (res << 8) | byte as i64)
+}
+
+fn parse_string(tag: StructureTag) -> String {
+    String::from_utf8(tag.expect_primitive().unwrap_or_default()).unwrap_or_default()
+}
+
+fn octet_string(value: &str) -> StructureTag {
+    StructureTag {
+        class: TagClass::Universal,
+        id: 4,
+        payload: PL::P(value.as_bytes().to_vec()),
+    }
+}
+
+fn constructed(class: TagClass, id: u64, tags: Vec<StructureTag>) -> StructureTag {
+    StructureTag {
+        class,
+        id,
+        payload: PL::C(tags),
+    }
+}
+
+fn message(msg_id: i64, op: StructureTag) -> StructureTag {
+    let msg_id = Integer {
+        inner: msg_id,
+        ..Default::default()
+    }
+    .into_structure();
+    constructed(TagClass::Universal, 16, vec![msg_id, op])
+}
+
+fn ldap_result(op_id: u64, code: i64) -> StructureTag {
+    let code = Enumerated {
+        inner: code,
+        ..Default::default()
+    }
+    .into_structure();
+    constructed(
+        TagClass::Application,
+        op_id,
+        vec![code, octet_string(""), octet_string("")],
+    )
+}
+
+fn search_entry(entry: &MockLdapEntry) -> StructureTag {
+    let mut attrs = vec![attribute("uid", entry.uid)];
+    if let Some(department) = entry.department {
+        attrs.push(attribute("departmentNumber", department));
+    }
+
+    constructed(
+        TagClass::Application,
+        OP_SEARCH_ENTRY,
+        vec![
+            octet_string(entry.dn),
+            constructed(TagClass::Universal, 16, attrs),
+        ],
+    )
+}
+
+fn attribute(name: &str, value: &str) -> StructureTag {
+    constructed(
+        TagClass::Universal,
+        16,
+        vec![
+            octet_string(name),
+            constructed(TagClass::Universal, 17, vec![octet_string(value)]),
+        ],
+    )
+}
+
+// endregion: --- BER Helpers
@@ -0,0 +1,260 @@
+//! Pluggable authentication backends.
+//!
+//! A backend validates a username / clear password pair and returns the
+//! `AuthIdentity` the web layer uses to find (or provision) the `users` row.
+//!
+//! - `local` validates against the `users.pwd` hash (see the `pwd` module).
+//! - `ldap` searches the user entry and binds with its DN (LDAP / Active Directory).
+//!
+//! Backends are tried in the `SERVICE_AUTH_BACKENDS` order (e.g., "local,ldap"),
+//! the first one accepting the credentials wins. For an existing user, only its
+//! `users.auth_source` backend is tried.
+
+// region:    --- Modules
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+pub use self::ldap::LdapBackend;
+pub use self::local::LocalBackend;
+
+use crate::auth_config;
+use crate::config::ldap_config;
+use crate::pwd::SchemeStatus;
+
+pub use self::error::{Error, Result};
+
+mod error;
+mod ldap;
+mod local;
+
+#[cfg(test)]
+mod mock_ldap;
+
+// endregion: --- Modules
+
+// region:    --- Types
+
+/// What the `users` table holds for the account, when the user already exists.
+pub struct LocalAccount {
+    /// Scheme prefixed hash (`#_scheme_id_#....`).
+    pub pwd: Option<String>,
+    pub pwd_salt: Uuid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    Local,
+    Ldap,
+}
+
+impl BackendKind {
+    /// Value stored in `users.auth_source`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackendKind::Local => "local",
+            BackendKind::Ldap => "ldap",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AuthIdentity {
+    pub username: String,
+    pub backend: BackendKind,
+    /// Department name reported by the directory (used for provisioning).
+    pub department: Option<String>,
+    /// Codes of the centers the user can be provisioned in (none for the local backend).
+    pub provision_centers: Vec<String>,
+    /// Only set by the local backend, `Outdated` when the pwd should be re-hashed.
+    pub scheme_status: Option<SchemeStatus>,
+}
+
+// endregion: --- Types
+
+#[async_trait]
+pub trait AuthBackend: Send + Sync {
+    async fn authenticate(
+        &self,
+        username: &str,
+        pwd_clear: &str,
+        local: Option<&LocalAccount>,
+    ) -> Result<AuthIdentity>;
+}
+
+enum AuthBackendDispatcher {
+    Local(LocalBackend),
+    Ldap(LdapBackend),
+}
+
+impl AuthBackendDispatcher {
+    fn new(backend_name: &str) -> Result<Self> {
+        match backend_name {
+            "local" => Ok(AuthBackendDispatcher::Local(LocalBackend)),
+            "ldap" => Ok(AuthBackendDispatcher::Ldap(LdapBackend::new(
+                ldap_config().clone(),
+            ))),
+            _ => Err(Error::BackendNotFound(backend_name.to_string())),
+        }
+    }
+
+    fn kind(&self) -> BackendKind {
+        match self {
+            AuthBackendDispatcher::Local(_) => BackendKind::Local,
+            AuthBackendDispatcher::Ldap(_) => BackendKind::Ldap,
+        }
+    }
+}
+
+#[async_trait]
+impl AuthBackend for AuthBackendDispatcher {
+    async fn authenticate(
+        &self,
+        username: &str,
+        pwd_clear: &str,
+        local: Option<&LocalAccount>,
+    ) -> Result<AuthIdentity> {
+        match self {
+            AuthBackendDispatcher::Local(backend) => {
+                backend.authenticate(username, pwd_clear, local).await
+            }
+            AuthBackendDispatcher::Ldap(backend) => {
+                backend.authenticate(username, pwd_clear, local).await
+            }
+        }
+    }
+}
+
+pub fn get_backend(backend_name: &str) -> Result<impl AuthBackend> {
+    AuthBackendDispatcher::new(backend_name)
+}
+
+/// Try each configured backend in order, only the `auth_source` one when set
+/// (an existing user, e.g., not a local user by a same name ldap entry).
+///
+/// Returns the error of the last backend tried when none accepts the credentials.
+pub async fn authenticate(
+    username: &str,
+    pwd_clear: &str,
+    local: Option<&LocalAccount>,
+    auth_source: Option<&str>,
+) -> Result<AuthIdentity> {
+    let backends = auth_config()
+        .AUTH_BACKENDS
+        .iter()
+        .map(|backend_name| AuthBackendDispatcher::new(backend_name))
+        .collect::<Result<Vec<_>>>()?;
+
+    authenticate_with(&backends, username, pwd_clear, local, auth_source).await
+}
+
+async fn authenticate_with(
+    backends: &[AuthBackendDispatcher],
+    username: &str,
+    pwd_clear: &str,
+    local: Option<&LocalAccount>,
+    auth_source: Option<&str>,
+) -> Result<AuthIdentity> {
+    let mut last_error = Error::NoBackendConfigured;
+
+    for backend in backends {
+        if auth_source.is_some_and(|auth_source| auth_source != backend.kind().as_str()) {
+            continue;
+        }
+        match backend.authenticate(username, pwd_clear, local).await {
+            Ok(identity) => return Ok(identity),
+            Err(ex) => last_error = ex,
+        }
+    }
+
+    Err(last_error)
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use crate::authn::mock_ldap::{self, MockLdapEntry};
+    use crate::config::LdapConfig;
+    use crate::pwd::{self, ContentToHash};
+
+    use super::*;
+
+    const FX_SERVICE_DN: &str = "cn=service,dc=ies,dc=test";
+    const FX_SERVICE_PWD: &str = "service_pwd";
+
+    async fn fx_backends() -> Result<Vec<AuthBackendDispatcher>> {
+        let url = mock_ldap::start(
+            vec![MockLdapEntry {
+                dn: "uid=jgarcia,ou=people,dc=ies,dc=test",
+                uid: "jgarcia",
+                pwd: "secret",
+                department: None,
+            }],
+            (FX_SERVICE_DN, FX_SERVICE_PWD),
+        )
+        .await?;
+        let ldap = LdapBackend::new(LdapConfig {
+            URL: url,
+            BASE_DN: "ou=people,dc=ies,dc=test".to_string(),
+            USER_FILTER: "(uid={username})".to_string(),
+            BIND_DN: FX_SERVICE_DN.to_string(),
+            BIND_PWD: FX_SERVICE_PWD.to_string(),
+            DEPARTMENT_ATTR: "departmentNumber".to_string(),
+            CENTERS: vec!["default".to_string()],
+        });
+
+        Ok(vec![
+            AuthBackendDispatcher::Local(LocalBackend),
+            AuthBackendDispatcher::Ldap(ldap),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_auth_source_skips_other_backends_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let backends = fx_backends().await?;
+        // A (stale) local pwd, same as the ldap one.
+        let pwd_salt = Uuid::new_v4();
+        let pwd = pwd::hash_pwd(ContentToHash {
+            content: "secret".to_string(),
+            salt: pwd_salt,
+        })
+        .await?;
+        let local = LocalAccount {
+            pwd: Some(pwd),
+            pwd_salt,
+        };
+
+        // -- Exec
+        let identity_any =
+            authenticate_with(&backends, "jgarcia", "secret", Some(&local), None).await?;
+        let identity_ldap =
+            authenticate_with(&backends, "jgarcia", "secret", Some(&local), Some("ldap")).await?;
+
+        // -- Check
+        assert_eq!(identity_any.backend, BackendKind::Local);
+        assert_eq!(identity_ldap.backend, BackendKind::Ldap);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_err_auth_source_not_configured() -> Result<()> {
+        // -- Setup & Fixtures
+        let backends = vec![AuthBackendDispatcher::Local(LocalBackend)];
+
+        // -- Exec
+        let res = authenticate_with(&backends, "jgarcia", "secret", None, Some("ldap")).await;
+
+        // -- Check
+        assert!(
+            matches!(res, Err(Error::NoBackendConfigured)),
+            "Should have matched `Error::NoBackendConfigured` but was `{res:?}`"
+        );
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
@@ -1,6 +1,6 @@
 use std::sync::OnceLock;
 
-use lib_utils::envs::{get_env_b64u_as_u8s, get_env_parse};
+use lib_utils::envs::{get_env, get_env_b64u_as_u8s, get_env_parse};
 
 pub fn auth_config() -> &'static AuthConfig {
     static INSTANCE: OnceLock<AuthConfig> = OnceLock::new();
@@ -11,6 +11,16 @@ pub fn auth_config() -> &'static AuthConfig {
     })
 }
 
+/// Only loaded when the `ldap` backend is enabled in `SERVICE_AUTH_BACKENDS`.
+pub fn ldap_config() -> &'static LdapConfig {
+    static INSTANCE: OnceLock<LdapConfig> = OnceLock::new();
+
+    INSTANCE.get_or_init(|| {
+        LdapConfig::load_from_env()
+            .unwrap_or_else(|ex| panic!("FATAL - WHILE LOADING CONF - Cause: {ex:?}"))
+    })
+}
+
 #[allow(non_snake_case)]
 pub struct AuthConfig {
     // -- Crypt
@@ -18,6 +28,10 @@ pub struct AuthConfig {
 
     pub TOKEN_KEY: Vec<u8>,
     pub TOKEN_DURATION_SEC: f64,
+
+    // -- Authn
+    /// Backend names, in the order they are tried (e.g., "local,ldap").
+    pub AUTH_BACKENDS: Vec<String>,
 }
 
 impl AuthConfig {
@@ -28,6 +42,50 @@ impl AuthConfig {
 
             TOKEN_KEY: get_env_b64u_as_u8s("SERVICE_TOKEN_KEY")?,
             TOKEN_DURATION_SEC: get_env_parse("SERVICE_TOKEN_DURATION_SEC")?,
+
+            // -- Authn
+            AUTH_BACKENDS: get_env("SERVICE_AUTH_BACKENDS")?
+                .split(',')
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .collect(),
+        })
+    }
+}
+
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct LdapConfig {
+    /// e.g., "ldap://127.0.0.1:389" or "ldaps://dc.example.org"
+    pub URL: String,
+    /// Search base for the user entries.
+    pub BASE_DN: String,
+    /// Search filter, `{username}` is replaced by the escaped login name.
+    /// e.g., "(uid={username})" or "(sAMAccountName={username})"
+    pub USER_FILTER: String,
+    /// Service account used for the search. Empty for an anonymous search.
+    pub BIND_DN: String,
+    pub BIND_PWD: String,
+    /// Entry attribute mapped to `departments.name` on provisioning.
+    pub DEPARTMENT_ATTR: String,
+    /// Codes of the centers the directory users can be provisioned in (e.g., "default").
+    pub CENTERS: Vec<String>,
+}
+
+impl LdapConfig {
+    fn load_from_env() -> lib_utils::envs::Result<LdapConfig> {
+        Ok(LdapConfig {
+            URL: get_env("SERVICE_LDAP_URL")?,
+            BASE_DN: get_env("SERVICE_LDAP_BASE_DN")?,
+            USER_FILTER: get_env("SERVICE_LDAP_USER_FILTER")?,
+            BIND_DN: get_env("SERVICE_LDAP_BIND_DN")?,
+            BIND_PWD: get_env("SERVICE_LDAP_BIND_PWD")?,
+            DEPARTMENT_ATTR: get_env("SERVICE_LDAP_DEPARTMENT_ATTR")?,
+            CENTERS: get_env("SERVICE_LDAP_CENTERS")?
+                .split(',')
+                .map(|code| code.trim().to_string())
+                .filter(|code| !code.is_empty())
+                .collect(),
         })
     }
 }
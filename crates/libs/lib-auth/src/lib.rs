@@ -1,5 +1,6 @@
 use config::auth_config;
 
+pub mod authn;
 mod config;
 pub mod pwd;
-pub mod token;
\ No newline at end of file
+pub mod token;
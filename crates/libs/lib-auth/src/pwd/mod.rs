@@ -7,7 +7,7 @@ use uuid::Uuid;
 
 pub use scheme::SchemeStatus;
 
-use crate::pwd::scheme::{DEFAULT_SCHEME, get_scheme, Scheme};
+use crate::pwd::scheme::{get_scheme, Scheme, DEFAULT_SCHEME};
 
 pub use self::error::{Error, Result};
 
@@ -87,11 +87,11 @@ impl FromStr for PwdParts {
             r#"^#(\w+)#(.*)"#, // a literal regex
             pwd_with_scheme
         )
-            .map(|(_, scheme, hashed)| Self {
-                scheme_name: scheme.to_string(),
-                hashed: hashed.to_string(),
-            })
-            .ok_or(Error::PwdWithSchemeFailedParse)
+        .map(|(_, scheme, hashed)| Self {
+            scheme_name: scheme.to_string(),
+            hashed: hashed.to_string(),
+        })
+        .ok_or(Error::PwdWithSchemeFailedParse)
     }
 }
 
@@ -4,8 +4,8 @@ use sha2::Sha512;
 use lib_utils::b64::b64u_encode;
 
 use crate::auth_config;
-use crate::pwd::ContentToHash;
 use crate::pwd::scheme::Scheme;
+use crate::pwd::ContentToHash;
 
 use super::{Error, Result};
 
@@ -1,7 +1,7 @@
 use std::sync::OnceLock;
 
-use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
 use argon2::password_hash::SaltString;
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
 
 use crate::config::auth_config;
 use crate::pwd::ContentToHash;
@@ -46,7 +46,7 @@ fn get_argon2() -> &'static Argon2<'static> {
             Version::V0x13,      // Same as Argon2::default()
             Params::default(),
         )
-            .unwrap() // TODO - needs to fail early
+        .unwrap() // TODO - needs to fail early
     })
 }
 
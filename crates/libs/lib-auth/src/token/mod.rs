@@ -81,6 +81,59 @@ pub fn validate_web_token(origin_token: &Token, salt: Uuid) -> Result<()> {
 
 // endregion: --- Web Token Gen and Validation
 
+// region:    --- Display Token Gen and Validation
+
+/// Token of a staff-room display, valid until `exp` (Rfc3339).
+///
+/// Note: Same key as the web tokens, but signed with the display salt,
+///       so it cannot pass as a web token (and the reverse).
+pub fn generate_display_token(ident: &str, exp: &str, salt: Uuid) -> Result<Token> {
+    let config = &auth_config();
+    let sign_b64u = _token_sign_into_b64u(ident, exp, salt, &config.TOKEN_KEY)?;
+
+    Ok(Token {
+        ident: ident.to_string(),
+        exp: exp.to_string(),
+        sign_b64u,
+    })
+}
+
+pub fn validate_display_token(origin_token: &Token, salt: Uuid) -> Result<()> {
+    let config = &auth_config();
+    _validate_token_sign_and_exp(origin_token, salt, &config.TOKEN_KEY)?;
+
+    Ok(())
+}
+
+// endregion: --- Display Token Gen and Validation
+
+// region:    --- Calendar Token Gen and Validation
+
+/// Calendar apps keep the feed url, so the token does not expire
+/// (revoked by changing the user calendar salt).
+const CALENDAR_TOKEN_EXP: &str = "9999-12-31T23:59:59Z";
+
+/// Token of a user iCalendar feed url.
+pub fn generate_calendar_token(ident: &str, salt: Uuid) -> Result<Token> {
+    let config = &auth_config();
+    let sign_b64u = _token_sign_into_b64u(ident, CALENDAR_TOKEN_EXP, salt, &config.TOKEN_KEY)?;
+
+    Ok(Token {
+        ident: ident.to_string(),
+        exp: CALENDAR_TOKEN_EXP.to_string(),
+        sign_b64u,
+    })
+}
+
+pub fn validate_calendar_token(origin_token: &Token, salt: Uuid) -> Result<()> {
+    let config = &auth_config();
+    _validate_token_sign_and_exp(origin_token, salt, &config.TOKEN_KEY)?;
+
+    Ok(())
+}
+
+// endregion: --- Calendar Token Gen and Validation
+
 // region:    --- (private) Token Gen and Validation
 
 fn _generate_token(ident: &str, duration_sec: f64, salt: Uuid, key: &[u8]) -> Result<Token> {
@@ -225,5 +278,46 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_validate_display_token_err_other_salt() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_ident = "1000";
+        let fx_exp = now_utc_plus_sec_str(60.);
+        let fx_salt = Uuid::parse_str("f05e8961-d6ad-4086-9e78-a6de065e5453").unwrap();
+        let fx_other_salt = Uuid::parse_str("4f1a8e0c-3c5d-4e0b-9b7a-2d6c1e3f5a7b").unwrap();
+        let fx_token = generate_display_token(fx_ident, &fx_exp, fx_salt)?;
+
+        // -- Exec
+        let res_ok = validate_display_token(&fx_token, fx_salt);
+        let res_err = validate_display_token(&fx_token, fx_other_salt);
+
+        // -- Check
+        res_ok?;
+        assert!(
+            matches!(res_err, Err(Error::SignatureNotMatching)),
+            "Should have matched `Err(Error::SignatureNotMatching)` but was `{res_err:?}`"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_calendar_token_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_ident = "1000";
+        let fx_salt = Uuid::parse_str("f05e8961-d6ad-4086-9e78-a6de065e5453").unwrap();
+        let fx_token_str = generate_calendar_token(fx_ident, fx_salt)?.to_string();
+
+        // -- Exec
+        let token: Token = fx_token_str.parse()?;
+        let res = validate_calendar_token(&token, fx_salt);
+
+        // -- Check
+        res?;
+        assert_eq!(token.ident, fx_ident);
+
+        Ok(())
+    }
 }
 // endregion: --- Tests
@@ -2,13 +2,13 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use sqlx::{Pool, Postgres};
 use sqlx::postgres::PgPoolOptions;
+use sqlx::{Pool, Postgres};
 use tracing::info;
 
 use crate::ctx::Ctx;
-use crate::model::ModelManager;
 use crate::model::user::{User, UserBmc};
+use crate::model::ModelManager;
 
 type Db = Pool<Postgres>;
 
@@ -44,7 +44,6 @@ pub async fn init_dev_db() -> Result<(), Box<dyn std::error::Error>> {
         let sql_recreate_db_file = sql_dir.join(SQL_RECREATE_DB_FILE_NAME);
         let root_db = new_db_pool(PG_DEV_POSTGRES_URL).await?;
         pexec(&root_db, &sql_recreate_db_file).await?;
-
     }
 
     // -- Get sql files.
@@ -78,7 +77,7 @@ pub async fn init_dev_db() -> Result<(), Box<dyn std::error::Error>> {
         .await?
         .unwrap();
     UserBmc::update_pwd(&ctx, &mm, admin_user.id, DEMO_PWD).await?;
-    
+
     info!("{:<12} - init_dev_db - set admin pwd", "FOR-DEV-ONLY");
 
     Ok(())
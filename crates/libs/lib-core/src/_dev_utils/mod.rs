@@ -6,16 +6,16 @@ use tracing::info;
 
 use crate::ctx::Ctx;
 use crate::model::building::{BuildingBmc, BuildingForCreate};
-use crate::model::classroom_type::{ClassroomTypeBmc, ClassroomTypeForCreate};
-use crate::model::{self, ModelManager};
 use crate::model::center_schedule_hour::{CenterScheduleHourBmc, CenterScheduleHourForCreate};
 use crate::model::classroom::{ClassroomBmc, ClassroomForCreate};
+use crate::model::classroom_type::{ClassroomTypeBmc, ClassroomTypeForCreate};
 use crate::model::department::{DepartmentBmc, DepartmentForCreate};
 use crate::model::group::{GroupBmc, GroupForCreate};
 use crate::model::schedule::{ScheduleBmc, ScheduleForCreate};
 use crate::model::schedule_hour::{ScheduleHourBmc, ScheduleHourForCreate};
 use crate::model::subject::{SubjectBmc, SubjectForCreate};
 use crate::model::user::{UserBmc, UserForCreate};
+use crate::model::{self, ModelManager};
 
 mod dev_db;
 
@@ -35,17 +35,14 @@ pub async fn init_dev() {
 }
 
 /// Initialize test environment.
+///
+/// Note: The db is recreated once, but each test gets its own pool, as the
+///       connections are bound to the runtime of the test that opened them.
 pub async fn init_test() -> ModelManager {
-    static INIT: OnceCell<ModelManager> = OnceCell::const_new();
-
-    let mm = INIT
-        .get_or_init(|| async {
-            init_dev().await;
-            ModelManager::new().await.unwrap()
-        })
-        .await;
+    init_dev().await;
 
-    mm.clone()
+    // Single connection, see NOTE 1) in `model::store`.
+    ModelManager::new_with_max_connections(1).await.unwrap()
 }
 
 pub async fn seed_user(ctx: &Ctx, mm: &ModelManager, name: &str) -> model::Result<i64> {
@@ -58,10 +55,10 @@ pub async fn seed_user(ctx: &Ctx, mm: &ModelManager, name: &str) -> model::Resul
             is_admin: false,
             department_id: None,
             active: true,
-            substituting_id: None
+            substituting_id: None,
         },
     )
-        .await
+    .await
 }
 
 pub async fn seed_department(ctx: &Ctx, mm: &ModelManager, name: &str) -> model::Result<i64> {
@@ -69,13 +66,20 @@ pub async fn seed_department(ctx: &Ctx, mm: &ModelManager, name: &str) -> model:
         ctx,
         mm,
         DepartmentForCreate {
-            name: name.to_string()
+            name: name.to_string(),
         },
     )
-        .await
+    .await
 }
 
-pub async fn seed_subject(ctx: &Ctx, mm: &ModelManager, name: &str, department_id: i64, is_guard: bool, is_complementary: bool) -> model::Result<i64> {
+pub async fn seed_subject(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    name: &str,
+    department_id: i64,
+    is_guard: bool,
+    is_complementary: bool,
+) -> model::Result<i64> {
     SubjectBmc::create(
         ctx,
         mm,
@@ -83,14 +87,21 @@ pub async fn seed_subject(ctx: &Ctx, mm: &ModelManager, name: &str, department_i
             name: name.to_string(),
             department_id,
             is_guard,
-            is_complementary
+            is_complementary,
         },
     )
-        .await
+    .await
 }
 
-
-pub async fn seed_group(ctx: &Ctx, mm: &ModelManager, letter: &str, course: i32, stage: i32, year: i32, tutor_name: String) -> model::Result<i64> {
+pub async fn seed_group(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    letter: &str,
+    course: i32,
+    stage: i32,
+    year: i32,
+    tutor_name: String,
+) -> model::Result<i64> {
     GroupBmc::create(
         ctx,
         mm,
@@ -102,11 +113,20 @@ pub async fn seed_group(ctx: &Ctx, mm: &ModelManager, letter: &str, course: i32,
             letter: letter.to_string(),
         },
     )
-        .await
+    .await
 }
 
-
-pub async fn seed_classroom(ctx: &Ctx, mm: &ModelManager, building: i64, floor: i32, number: i32, name: &str, type_c: i64, description: &str) -> model::Result<i64> {
+#[allow(clippy::too_many_arguments)]
+pub async fn seed_classroom(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    building: i64,
+    floor: i32,
+    number: i32,
+    name: &str,
+    type_c: i64,
+    description: &str,
+) -> model::Result<i64> {
     ClassroomBmc::create(
         ctx,
         mm,
@@ -119,12 +139,22 @@ pub async fn seed_classroom(ctx: &Ctx, mm: &ModelManager, building: i64, floor:
             description: description.to_string(),
         },
     )
-        .await
+    .await
 }
 
-pub async fn seed_schedule(ctx: &Ctx, mm: &ModelManager, course: i32, teacher_id: i64, group_id: i64) -> model::Result<i64> {
-    let teacher = if teacher_id == -1 { None } else { Some(teacher_id) };
-    let group= if group_id == -1 { None } else { Some(group_id) };
+pub async fn seed_schedule(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    course: i32,
+    teacher_id: i64,
+    group_id: i64,
+) -> model::Result<i64> {
+    let teacher = if teacher_id == -1 {
+        None
+    } else {
+        Some(teacher_id)
+    };
+    let group = if group_id == -1 { None } else { Some(group_id) };
 
     ScheduleBmc::create(
         ctx,
@@ -132,14 +162,23 @@ pub async fn seed_schedule(ctx: &Ctx, mm: &ModelManager, course: i32, teacher_id
         ScheduleForCreate {
             course,
             user_id: teacher,
-            group_id: group
+            group_id: group,
         },
     )
-        .await
+    .await
 }
 
-
-pub async fn seed_schedule_hour(ctx: &Ctx, mm: &ModelManager, schedule_id: i64, subject_name: &str, classroom_name: &str, week_day: i32, n_hour: i32, course: i32) -> model::Result<i64> {
+#[allow(clippy::too_many_arguments)]
+pub async fn seed_schedule_hour(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    schedule_id: i64,
+    subject_name: &str,
+    classroom_name: &str,
+    week_day: i32,
+    n_hour: i32,
+    course: i32,
+) -> model::Result<i64> {
     ScheduleHourBmc::create(
         ctx,
         mm,
@@ -150,13 +189,19 @@ pub async fn seed_schedule_hour(ctx: &Ctx, mm: &ModelManager, schedule_id: i64,
             week_day,
             n_hour,
             course,
-            notes: Some("".to_string())
+            notes: Some("".to_string()),
         },
     )
-        .await
+    .await
 }
 
-pub async fn seed_center_schedule_hour(ctx: &Ctx, mm: &ModelManager, n_hour: i32, start_time: Time, end_time: Time) -> model::Result<i64> {
+pub async fn seed_center_schedule_hour(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    n_hour: i32,
+    start_time: Time,
+    end_time: Time,
+) -> model::Result<i64> {
     CenterScheduleHourBmc::create(
         ctx,
         mm,
@@ -166,11 +211,15 @@ pub async fn seed_center_schedule_hour(ctx: &Ctx, mm: &ModelManager, n_hour: i32
             end_time,
         },
     )
-        .await
+    .await
 }
 
 // Function to seed building
-pub async fn seed_building(ctx: &Ctx, mm: &ModelManager, building_name: &str) -> model::Result<i64> {
+pub async fn seed_building(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    building_name: &str,
+) -> model::Result<i64> {
     BuildingBmc::create(
         ctx,
         mm,
@@ -178,11 +227,15 @@ pub async fn seed_building(ctx: &Ctx, mm: &ModelManager, building_name: &str) ->
             building_name: building_name.to_string(),
         },
     )
-        .await
+    .await
 }
 
 // Function to seed classroom type
-pub async fn seed_classroom_type(ctx: &Ctx, mm: &ModelManager, type_name: &str) -> model::Result<i64> {
+pub async fn seed_classroom_type(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    type_name: &str,
+) -> model::Result<i64> {
     ClassroomTypeBmc::create(
         ctx,
         mm,
@@ -190,5 +243,5 @@ pub async fn seed_classroom_type(ctx: &Ctx, mm: &ModelManager, type_name: &str)
             type_name: type_name.to_string(),
         },
     )
-        .await
-}
\ No newline at end of file
+    .await
+}
@@ -1,6 +1,8 @@
 use std::sync::OnceLock;
 
-use lib_utils::envs::get_env;
+use lib_utils::envs::{self, get_env};
+
+use crate::model::control::EscalationStep;
 
 pub fn core_config() -> &'static CoreConfig {
     static INSTANCE: OnceLock<CoreConfig> = OnceLock::new();
@@ -16,6 +18,11 @@ pub struct CoreConfig {
     // -- Db
     pub DB_URL: String,
     pub MONGO_DB_URL: String,
+    /// "local" or "pg" (see `model::event`).
+    pub EVENT_BUS: String,
+    // -- Guards
+    /// After the free `Guardia` hours, in order.
+    pub GUARD_ESCALATION: Vec<EscalationStep>,
     // -- Web
     pub WEB_FOLDER: String,
 }
@@ -26,6 +33,14 @@ impl CoreConfig {
             // -- Db
             DB_URL: get_env("SERVICE_DB_URL")?,
             MONGO_DB_URL: get_env("SERVICE_MONGO_DB_URL")?,
+            EVENT_BUS: get_env("SERVICE_EVENT_BUS").unwrap_or_else(|_| "local".to_string()),
+
+            // -- Guards
+            GUARD_ESCALATION: match get_env("SERVICE_GUARD_ESCALATION") {
+                Ok(steps) => EscalationStep::parse_chain(&steps)
+                    .map_err(|_| envs::Error::WrongFormat("SERVICE_GUARD_ESCALATION"))?,
+                Err(_) => EscalationStep::DEFAULT_CHAIN.to_vec(),
+            },
 
             // -- Web
             WEB_FOLDER: get_env("SERVICE_WEB_FOLDER")?,
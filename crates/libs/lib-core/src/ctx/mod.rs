@@ -1,30 +1,46 @@
-// region:    --- Modules
-
-pub use self::error::{Error, Result};
-
-mod error;
-
-// endregion: --- Modules
+/// The default center (at id = 1), of the single school deployments.
+pub const DEFAULT_CENTER_ID: i64 = 1;
 
 #[derive(Clone, Debug)]
 pub struct Ctx {
     user_id: i64,
-    isadmin: bool
+    isadmin: bool,
+    /// The tenant of the request, `None` for all the centers (root ctx only).
+    center_id: Option<i64>,
+    super_admin: bool,
 }
 
 // Constructors.
 impl Ctx {
     pub fn root_ctx() -> Self {
-        Ctx { user_id: 0 , isadmin: true}
+        Ctx {
+            user_id: 0,
+            isadmin: true,
+            center_id: None,
+            super_admin: true,
+        }
     }
 
-    pub fn new(user_id: i64, isadmin: bool) -> Result<Self> {
-        if user_id == 0 {
-            Err(Error::CtxCannotNewRootCtx)
-        } else {
-            Ok(Self { user_id, isadmin })
+    /// A user ctx, always scoped to its center (never the root ctx, even for the user 0).
+    pub fn new(user_id: i64, isadmin: bool, center_id: i64) -> Self {
+        Self {
+            user_id,
+            isadmin,
+            center_id: Some(center_id),
+            super_admin: false,
         }
     }
+
+    /// Scopes the ctx to one center (e.g., the root ctx of a center job).
+    pub fn with_center(mut self, center_id: i64) -> Self {
+        self.center_id = Some(center_id);
+        self
+    }
+
+    pub fn with_super_admin(mut self, super_admin: bool) -> Self {
+        self.super_admin = super_admin;
+        self
+    }
 }
 
 // Property Accessors.
@@ -35,4 +51,10 @@ impl Ctx {
     pub fn admin(&self) -> bool {
         self.isadmin
     }
+    pub fn center_id(&self) -> Option<i64> {
+        self.center_id
+    }
+    pub fn super_admin(&self) -> bool {
+        self.super_admin
+    }
 }
@@ -0,0 +1,822 @@
+//! Portable archive of a center data (staging to production, school hand over).
+//!
+//! - Versioned JSON document (see `ARCHIVE_VERSION`), written and read by the
+//!   `center-archive` tool.
+//! - Every row of the configuration tables, soft deleted ones included,
+//!   with their ids as references.
+//! - The users have no password, token or calendar salts (new ones on import),
+//!   so they log in again with a password reset, LDAP or OpenID Connect.
+//! - One center, the ctx one (the default center for the root ctx without center).
+//! - The import needs an empty center (only the root user and the default
+//!   department), the ids are remapped to the new ones, in one transaction.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sqlx::FromRow;
+use time::{OffsetDateTime, Time};
+
+use crate::ctx::{Ctx, DEFAULT_CENTER_ID};
+use crate::model::base::PostgresDbBmc;
+use crate::model::building::BuildingBmc;
+use crate::model::center_schedule_hour::CenterScheduleHourBmc;
+use crate::model::classroom::ClassroomBmc;
+use crate::model::classroom_type::ClassroomTypeBmc;
+use crate::model::department::DepartmentBmc;
+use crate::model::group::GroupBmc;
+use crate::model::schedule::ScheduleBmc;
+use crate::model::schedule_hour::ScheduleHourBmc;
+use crate::model::subject::SubjectBmc;
+use crate::model::user::UserBmc;
+use crate::model::{Error, ModelManager, Result};
+
+/// Bumped on any incompatible change of the archive types.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Created with the schema, kept (not archived) on both sides.
+const ROOT_USER_ID: i64 = 0;
+/// Users `department_id` default, mapped onto the target one.
+const DEFAULT_DEPARTMENT_ID: i64 = 1;
+
+// region:    --- Archive Types
+
+#[serde_as]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CenterArchive {
+    pub version: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub exported_at: OffsetDateTime,
+    pub departments: Vec<ArchiveDepartment>,
+    pub users: Vec<ArchiveUser>,
+    pub subjects: Vec<ArchiveSubject>,
+    pub groups: Vec<ArchiveGroup>,
+    pub buildings: Vec<ArchiveBuilding>,
+    pub classroom_types: Vec<ArchiveClassroomType>,
+    pub classrooms: Vec<ArchiveClassroom>,
+    pub schedules: Vec<ArchiveSchedule>,
+    pub schedule_hours: Vec<ArchiveScheduleHour>,
+    pub center_schedule_hours: Vec<ArchiveCenterScheduleHour>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ArchiveDepartment {
+    pub id: i64,
+    pub name: String,
+    #[serde(with = "time::serde::rfc3339::option")]
+    #[serde(default)]
+    pub deleted_at: Option<OffsetDateTime>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ArchiveUser {
+    pub id: i64,
+    pub username: String,
+    pub is_admin: bool,
+    pub active: Option<bool>,
+    pub department_id: Option<i64>,
+    pub substituting_id: Option<i64>,
+    pub substitutions: Option<i64>,
+    pub auth_source: String,
+    #[serde(with = "time::serde::rfc3339::option")]
+    #[serde(default)]
+    pub deleted_at: Option<OffsetDateTime>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ArchiveSubject {
+    pub id: i64,
+    pub name: String,
+    pub department_id: i64,
+    pub is_guard: bool,
+    pub is_complementary: bool,
+    #[serde(with = "time::serde::rfc3339::option")]
+    #[serde(default)]
+    pub deleted_at: Option<OffsetDateTime>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ArchiveGroup {
+    pub id: i64,
+    pub course: i32,
+    pub stage: i32,
+    pub year: i32,
+    pub letter: Option<String>,
+    pub tutor_name: Option<String>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    #[serde(default)]
+    pub deleted_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ArchiveBuilding {
+    pub id: i64,
+    pub building_name: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ArchiveClassroomType {
+    pub id: i64,
+    pub type_name: Option<String>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ArchiveClassroom {
+    pub id: i64,
+    pub building: Option<i64>,
+    pub floor: Option<i32>,
+    pub number: Option<i32>,
+    pub name: Option<String>,
+    pub type_c: Option<i64>,
+    pub description: Option<String>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    #[serde(default)]
+    pub deleted_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ArchiveSchedule {
+    pub id: i64,
+    pub user_id: Option<i64>,
+    pub group_id: Option<i64>,
+    pub course: i32,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ArchiveScheduleHour {
+    pub id: i64,
+    pub schedule_id: i64,
+    pub subject_name: String,
+    pub classroom_name: String,
+    pub week_day: i32,
+    pub n_hour: i32,
+    pub course: i32,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ArchiveCenterScheduleHour {
+    pub id: i64,
+    pub n_hour: i32,
+    pub start_time: Time,
+    pub end_time: Time,
+}
+
+/// Rows created by table.
+#[derive(Debug, Default, Serialize)]
+pub struct ArchiveImportReport {
+    pub departments: usize,
+    pub users: usize,
+    pub subjects: usize,
+    pub groups: usize,
+    pub buildings: usize,
+    pub classroom_types: usize,
+    pub classrooms: usize,
+    pub schedules: usize,
+    pub schedule_hours: usize,
+    pub center_schedule_hours: usize,
+}
+
+// endregion: --- Archive Types
+
+pub struct ArchiveBmc;
+
+impl ArchiveBmc {
+    pub async fn export(ctx: &Ctx, mm: &ModelManager) -> Result<CenterArchive> {
+        let center = format!("WHERE center_id = {}", archive_center_id(ctx));
+        Ok(CenterArchive {
+            version: ARCHIVE_VERSION,
+            exported_at: OffsetDateTime::now_utc(),
+            departments: fetch_rows(mm, "id, name, deleted_at", DepartmentBmc::TABLE, &center)
+                .await?,
+            users: fetch_rows(
+                mm,
+                "id, username, is_admin, active, department_id, substituting_id, substitutions, \
+                 auth_source, deleted_at",
+                UserBmc::TABLE,
+                &format!("{center} AND id <> {ROOT_USER_ID}"),
+            )
+            .await?,
+            subjects: fetch_rows(
+                mm,
+                "id, name, department_id, is_guard, is_complementary, deleted_at",
+                SubjectBmc::TABLE,
+                &center,
+            )
+            .await?,
+            groups: fetch_rows(
+                mm,
+                "id, course, stage, year, letter, tutor_name, deleted_at",
+                GroupBmc::TABLE,
+                &center,
+            )
+            .await?,
+            buildings: fetch_rows(mm, "id, building_name", BuildingBmc::TABLE, &center).await?,
+            classroom_types: fetch_rows(mm, "id, type_name", ClassroomTypeBmc::TABLE, &center)
+                .await?,
+            classrooms: fetch_rows(
+                mm,
+                "id, building, floor, number, name, type_c, description, deleted_at",
+                ClassroomBmc::TABLE,
+                &center,
+            )
+            .await?,
+            schedules: fetch_rows(
+                mm,
+                "id, user_id, group_id, course",
+                ScheduleBmc::TABLE,
+                &center,
+            )
+            .await?,
+            schedule_hours: fetch_rows(
+                mm,
+                "id, schedule_id, subject_name, classroom_name, week_day, n_hour, course, notes",
+                ScheduleHourBmc::TABLE,
+                &center,
+            )
+            .await?,
+            center_schedule_hours: fetch_rows(
+                mm,
+                "id, n_hour, start_time, end_time",
+                CenterScheduleHourBmc::TABLE,
+                &center,
+            )
+            .await?,
+        })
+    }
+
+    /// Into an empty center, all or nothing.
+    pub async fn import(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        archive: CenterArchive,
+    ) -> Result<ArchiveImportReport> {
+        if archive.version != ARCHIVE_VERSION {
+            return Err(Error::ArchiveVersionUnsupported {
+                version: archive.version,
+                supported: ARCHIVE_VERSION,
+            });
+        }
+
+        let mm = mm.new_with_txn();
+        mm.begin_txn().await?;
+
+        let center_id = archive_center_id(ctx);
+        check_empty(&mm, center_id).await?;
+
+        let mut importer = Importer {
+            ctx,
+            mm: &mm,
+            center_id,
+            report: ArchiveImportReport::default(),
+            departments: HashMap::new(),
+            users: HashMap::from([(ROOT_USER_ID, ROOT_USER_ID)]),
+            groups: HashMap::new(),
+            buildings: HashMap::new(),
+            classroom_types: HashMap::new(),
+            schedules: HashMap::new(),
+        };
+        importer.departments(archive.departments).await?;
+        importer.users(archive.users).await?;
+        importer.subjects(archive.subjects).await?;
+        importer.groups(archive.groups).await?;
+        importer.buildings(archive.buildings).await?;
+        importer.classroom_types(archive.classroom_types).await?;
+        importer.classrooms(archive.classrooms).await?;
+        importer.schedules(archive.schedules).await?;
+        importer.schedule_hours(archive.schedule_hours).await?;
+        importer
+            .center_schedule_hours(archive.center_schedule_hours)
+            .await?;
+        let report = importer.report;
+
+        mm.commit_txn().await?;
+
+        Ok(report)
+    }
+}
+
+fn archive_center_id(ctx: &Ctx) -> i64 {
+    ctx.center_id().unwrap_or(DEFAULT_CENTER_ID)
+}
+
+// region:    --- Export
+
+async fn fetch_rows<T>(
+    mm: &ModelManager,
+    columns: &str,
+    table: &str,
+    filter: &str,
+) -> Result<Vec<T>>
+where
+    T: for<'r> FromRow<'r, sqlx::postgres::PgRow> + Send + Unpin,
+{
+    let sql = format!("SELECT {columns} FROM {table} {filter} ORDER BY id");
+    let rows = mm.dbx().fetch_all(sqlx::query_as::<_, T>(&sql)).await?;
+
+    Ok(rows)
+}
+
+// endregion: --- Export
+
+// region:    --- Import
+
+async fn check_empty(mm: &ModelManager, center_id: i64) -> Result<()> {
+    let tables = [
+        (
+            DepartmentBmc::TABLE,
+            format!("AND id <> {DEFAULT_DEPARTMENT_ID}"),
+        ),
+        (UserBmc::TABLE, format!("AND id <> {ROOT_USER_ID}")),
+        (SubjectBmc::TABLE, String::new()),
+        (GroupBmc::TABLE, String::new()),
+        (BuildingBmc::TABLE, String::new()),
+        (ClassroomTypeBmc::TABLE, String::new()),
+        (ClassroomBmc::TABLE, String::new()),
+        (ScheduleBmc::TABLE, String::new()),
+        (ScheduleHourBmc::TABLE, String::new()),
+        (CenterScheduleHourBmc::TABLE, String::new()),
+    ];
+
+    for (table, filter) in tables {
+        let sql =
+            format!("SELECT EXISTS (SELECT 1 FROM {table} WHERE center_id = {center_id} {filter})");
+        let (exists,) = mm
+            .dbx()
+            .fetch_one(sqlx::query_as::<_, (bool,)>(&sql))
+            .await?;
+        if exists {
+            return Err(Error::ArchiveTargetNotEmpty { table });
+        }
+    }
+
+    Ok(())
+}
+
+/// Archive id -> new id, by table.
+type IdMap = HashMap<i64, i64>;
+
+struct Importer<'a> {
+    ctx: &'a Ctx,
+    mm: &'a ModelManager,
+    center_id: i64,
+    report: ArchiveImportReport,
+    departments: IdMap,
+    users: IdMap,
+    groups: IdMap,
+    buildings: IdMap,
+    classroom_types: IdMap,
+    schedules: IdMap,
+}
+
+impl Importer<'_> {
+    /// The default department is updated in place (created when missing)
+    /// in the default center, a new one otherwise.
+    async fn departments(&mut self, rows: Vec<ArchiveDepartment>) -> Result<()> {
+        let user_id = self.ctx.user_id();
+        for row in rows {
+            let deleted_by = row.deleted_at.map(|_| user_id);
+            let id = if row.id == DEFAULT_DEPARTMENT_ID && self.center_id == DEFAULT_CENTER_ID {
+                let sql = format!(
+                    "INSERT INTO {} (id, name, deleted_at, deleted_by, cid, ctime, mid, mtime) \
+                     VALUES ($1, $2, $3, $4, $5, now(), $5, now()) \
+                     ON CONFLICT (id) DO UPDATE SET name = $2, deleted_at = $3, deleted_by = $4, \
+                        mid = $5, mtime = now() \
+                     RETURNING id",
+                    DepartmentBmc::TABLE
+                );
+                self.insert(
+                    sqlx::query_as(&sql)
+                        .bind(DEFAULT_DEPARTMENT_ID)
+                        .bind(row.name)
+                        .bind(row.deleted_at)
+                        .bind(deleted_by)
+                        .bind(user_id),
+                )
+                .await?
+            } else {
+                let sql = format!(
+                    "INSERT INTO {} (name, deleted_at, deleted_by, cid, ctime, mid, mtime, \
+                        center_id) \
+                     VALUES ($1, $2, $3, $4, now(), $4, now(), $5) RETURNING id",
+                    DepartmentBmc::TABLE
+                );
+                self.insert(
+                    sqlx::query_as(&sql)
+                        .bind(row.name)
+                        .bind(row.deleted_at)
+                        .bind(deleted_by)
+                        .bind(user_id)
+                        .bind(self.center_id),
+                )
+                .await?
+            };
+            self.departments.insert(row.id, id);
+            self.report.departments += 1;
+        }
+
+        Ok(())
+    }
+
+    /// `substituting_id` set once all the users are created.
+    async fn users(&mut self, rows: Vec<ArchiveUser>) -> Result<()> {
+        let user_id = self.ctx.user_id();
+        let sql = format!(
+            "INSERT INTO {} (username, is_admin, active, department_id, substitutions, \
+                auth_source, deleted_at, deleted_by, cid, ctime, mid, mtime, center_id) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, now(), $9, now(), $10) RETURNING id",
+            UserBmc::TABLE
+        );
+        for row in &rows {
+            let department_id = remap_opt(&self.departments, row.department_id);
+            let id = self
+                .insert(
+                    sqlx::query_as(&sql)
+                        .bind(&row.username)
+                        .bind(row.is_admin)
+                        .bind(row.active)
+                        .bind(department_id)
+                        .bind(row.substitutions)
+                        .bind(&row.auth_source)
+                        .bind(row.deleted_at)
+                        .bind(row.deleted_at.map(|_| user_id))
+                        .bind(user_id)
+                        .bind(self.center_id),
+                )
+                .await?;
+            self.users.insert(row.id, id);
+            self.report.users += 1;
+        }
+
+        let sql = format!(
+            "UPDATE {} SET substituting_id = $2 WHERE id = $1",
+            UserBmc::TABLE
+        );
+        for row in rows {
+            let Some(substituting_id) = remap_opt(&self.users, row.substituting_id) else {
+                continue;
+            };
+            self.mm
+                .dbx()
+                .execute(
+                    sqlx::query(&sql)
+                        .bind(self.users[&row.id])
+                        .bind(substituting_id),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn subjects(&mut self, rows: Vec<ArchiveSubject>) -> Result<()> {
+        let user_id = self.ctx.user_id();
+        let sql = format!(
+            "INSERT INTO {} (name, department_id, is_guard, is_complementary, \
+                deleted_at, deleted_by, cid, ctime, mid, mtime, center_id) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, now(), $7, now(), $8) RETURNING id",
+            SubjectBmc::TABLE
+        );
+        for row in rows {
+            let department_id = remap(&self.departments, row.department_id);
+            self.insert(
+                sqlx::query_as(&sql)
+                    .bind(row.name)
+                    .bind(department_id)
+                    .bind(row.is_guard)
+                    .bind(row.is_complementary)
+                    .bind(row.deleted_at)
+                    .bind(row.deleted_at.map(|_| user_id))
+                    .bind(user_id)
+                    .bind(self.center_id),
+            )
+            .await?;
+            self.report.subjects += 1;
+        }
+
+        Ok(())
+    }
+
+    async fn groups(&mut self, rows: Vec<ArchiveGroup>) -> Result<()> {
+        let user_id = self.ctx.user_id();
+        let sql = format!(
+            "INSERT INTO {} (course, stage, year, letter, tutor_name, \
+                deleted_at, deleted_by, cid, ctime, mid, mtime, center_id) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, now(), $8, now(), $9) RETURNING id",
+            GroupBmc::TABLE
+        );
+        for row in rows {
+            let id = self
+                .insert(
+                    sqlx::query_as(&sql)
+                        .bind(row.course)
+                        .bind(row.stage)
+                        .bind(row.year)
+                        .bind(row.letter)
+                        .bind(row.tutor_name)
+                        .bind(row.deleted_at)
+                        .bind(row.deleted_at.map(|_| user_id))
+                        .bind(user_id)
+                        .bind(self.center_id),
+                )
+                .await?;
+            self.groups.insert(row.id, id);
+            self.report.groups += 1;
+        }
+
+        Ok(())
+    }
+
+    async fn buildings(&mut self, rows: Vec<ArchiveBuilding>) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO {} (building_name, cid, ctime, mid, mtime, center_id) \
+             VALUES ($1, $2, now(), $2, now(), $3) RETURNING id",
+            BuildingBmc::TABLE
+        );
+        for row in rows {
+            let id = self
+                .insert(
+                    sqlx::query_as(&sql)
+                        .bind(row.building_name)
+                        .bind(self.ctx.user_id())
+                        .bind(self.center_id),
+                )
+                .await?;
+            self.buildings.insert(row.id, id);
+            self.report.buildings += 1;
+        }
+
+        Ok(())
+    }
+
+    async fn classroom_types(&mut self, rows: Vec<ArchiveClassroomType>) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO {} (type_name, cid, ctime, mid, mtime, center_id) \
+             VALUES ($1, $2, now(), $2, now(), $3) RETURNING id",
+            ClassroomTypeBmc::TABLE
+        );
+        for row in rows {
+            let id = self
+                .insert(
+                    sqlx::query_as(&sql)
+                        .bind(row.type_name)
+                        .bind(self.ctx.user_id())
+                        .bind(self.center_id),
+                )
+                .await?;
+            self.classroom_types.insert(row.id, id);
+            self.report.classroom_types += 1;
+        }
+
+        Ok(())
+    }
+
+    async fn classrooms(&mut self, rows: Vec<ArchiveClassroom>) -> Result<()> {
+        let user_id = self.ctx.user_id();
+        let sql = format!(
+            "INSERT INTO {} (building, floor, number, name, type_c, description, \
+                deleted_at, deleted_by, cid, ctime, mid, mtime, center_id) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, now(), $9, now(), $10) RETURNING id",
+            ClassroomBmc::TABLE
+        );
+        for row in rows {
+            let building = remap_opt(&self.buildings, row.building);
+            let type_c = remap_opt(&self.classroom_types, row.type_c);
+            self.insert(
+                sqlx::query_as(&sql)
+                    .bind(building)
+                    .bind(row.floor)
+                    .bind(row.number)
+                    .bind(row.name)
+                    .bind(type_c)
+                    .bind(row.description)
+                    .bind(row.deleted_at)
+                    .bind(row.deleted_at.map(|_| user_id))
+                    .bind(user_id)
+                    .bind(self.center_id),
+            )
+            .await?;
+            self.report.classrooms += 1;
+        }
+
+        Ok(())
+    }
+
+    async fn schedules(&mut self, rows: Vec<ArchiveSchedule>) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO {} (user_id, group_id, course, cid, ctime, mid, mtime, center_id) \
+             VALUES ($1, $2, $3, $4, now(), $4, now(), $5) RETURNING id",
+            ScheduleBmc::TABLE
+        );
+        for row in rows {
+            let user_id = remap_opt(&self.users, row.user_id);
+            let group_id = remap_opt(&self.groups, row.group_id);
+            let id = self
+                .insert(
+                    sqlx::query_as(&sql)
+                        .bind(user_id)
+                        .bind(group_id)
+                        .bind(row.course)
+                        .bind(self.ctx.user_id())
+                        .bind(self.center_id),
+                )
+                .await?;
+            self.schedules.insert(row.id, id);
+            self.report.schedules += 1;
+        }
+
+        Ok(())
+    }
+
+    async fn schedule_hours(&mut self, rows: Vec<ArchiveScheduleHour>) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO {} (schedule_id, subject_name, classroom_name, week_day, n_hour, \
+                course, notes, cid, ctime, mid, mtime, center_id) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, now(), $8, now(), $9) RETURNING id",
+            ScheduleHourBmc::TABLE
+        );
+        for row in rows {
+            let schedule_id = remap(&self.schedules, row.schedule_id);
+            self.insert(
+                sqlx::query_as(&sql)
+                    .bind(schedule_id)
+                    .bind(row.subject_name)
+                    .bind(row.classroom_name)
+                    .bind(row.week_day)
+                    .bind(row.n_hour)
+                    .bind(row.course)
+                    .bind(row.notes)
+                    .bind(self.ctx.user_id())
+                    .bind(self.center_id),
+            )
+            .await?;
+            self.report.schedule_hours += 1;
+        }
+
+        Ok(())
+    }
+
+    async fn center_schedule_hours(&mut self, rows: Vec<ArchiveCenterScheduleHour>) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO {} (n_hour, start_time, end_time, cid, ctime, mid, mtime, center_id) \
+             VALUES ($1, $2, $3, $4, now(), $4, now(), $5) RETURNING id",
+            CenterScheduleHourBmc::TABLE
+        );
+        for row in rows {
+            self.insert(
+                sqlx::query_as(&sql)
+                    .bind(row.n_hour)
+                    .bind(row.start_time)
+                    .bind(row.end_time)
+                    .bind(self.ctx.user_id())
+                    .bind(self.center_id),
+            )
+            .await?;
+            self.report.center_schedule_hours += 1;
+        }
+
+        Ok(())
+    }
+
+    /// The new row id.
+    async fn insert<'q>(
+        &self,
+        query: sqlx::query::QueryAs<'q, sqlx::Postgres, (i64,), sqlx::postgres::PgArguments>,
+    ) -> Result<i64> {
+        let (id,) = self.mm.dbx().fetch_one(query).await?;
+        Ok(id)
+    }
+}
+
+/// Unknown references (not in the archive) are kept as is,
+/// so the foreign keys fail the import.
+fn remap(ids: &IdMap, id: i64) -> i64 {
+    ids.get(&id).copied().unwrap_or(id)
+}
+
+fn remap_opt(ids: &IdMap, id: Option<i64>) -> Option<i64> {
+    id.map(|id| remap(ids, id))
+}
+
+// endregion: --- Import
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serial_test::serial;
+
+    use crate::_dev_utils;
+    use crate::ctx::Ctx;
+    use crate::model::archive::{ArchiveBmc, CenterArchive, ARCHIVE_VERSION};
+    use crate::model::group::GroupBmc;
+    use crate::model::schedule::ScheduleBmc;
+    use crate::model::user::UserBmc;
+    use crate::model::{Error, ModelManager};
+
+    /// Empties the archived tables, in the `mm` transaction.
+    async fn clear_tables(mm: &ModelManager) -> Result<()> {
+        let sql = "TRUNCATE lesson_plan_attachments, lesson_plans, substitutions, schedule_hours, \
+                   schedules, classrooms, classroom_types, buildings, subjects, groups, \
+                   center_schedule_hours";
+        mm.dbx().execute(sqlx::query(sql)).await?;
+        mm.dbx()
+            .execute(sqlx::query("DELETE FROM users WHERE id <> 0"))
+            .await?;
+        mm.dbx()
+            .execute(sqlx::query("DELETE FROM departments WHERE id <> 1"))
+            .await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_export_import_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_username = "test_export_import_ok user";
+        let user_id = _dev_utils::seed_user(&ctx, &mm, fx_username).await?;
+        let group_id = _dev_utils::seed_group(&ctx, &mm, "Z", 1, 1, 2025, String::new()).await?;
+        let schedule_id = _dev_utils::seed_schedule(&ctx, &mm, 2025, user_id, group_id).await?;
+        _dev_utils::seed_schedule_hour(&ctx, &mm, schedule_id, "Música", "Aula 9", 0, 1, 2025)
+            .await?;
+        let archive = ArchiveBmc::export(&ctx, &mm).await?;
+        let json = serde_json::to_string(&archive)?;
+
+        // -- Exec
+        // Note: Rolled back when `txn_mm` is dropped.
+        let txn_mm = mm.new_with_txn();
+        txn_mm.begin_txn().await?;
+        clear_tables(&txn_mm).await?;
+        let archive: CenterArchive = serde_json::from_str(&json)?;
+        let report = ArchiveBmc::import(&ctx, &txn_mm, archive).await?;
+        let reexport = ArchiveBmc::export(&ctx, &txn_mm).await?;
+
+        // -- Check
+        assert!(!json.contains("pwd"), "Should not export password hashes");
+        assert_eq!(report.users, reexport.users.len());
+        assert_eq!(report.schedule_hours, reexport.schedule_hours.len());
+        let user = reexport
+            .users
+            .iter()
+            .find(|user| user.username == fx_username)
+            .expect("Should have the user");
+        let schedule = reexport
+            .schedules
+            .iter()
+            .find(|schedule| schedule.user_id == Some(user.id))
+            .expect("Should have the user schedule (remapped user_id)");
+        let hours: Vec<&str> = reexport
+            .schedule_hours
+            .iter()
+            .filter(|hour| hour.schedule_id == schedule.id)
+            .map(|hour| hour.subject_name.as_str())
+            .collect();
+        assert_eq!(hours, ["Música"]);
+
+        // -- Clean
+        drop(txn_mm);
+        ScheduleBmc::delete(&ctx, &mm, schedule_id).await?;
+        GroupBmc::delete(&ctx, &mm, group_id).await?;
+        UserBmc::delete(&ctx, &mm, user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_import_err_not_empty() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let archive = ArchiveBmc::export(&ctx, &mm).await?;
+        let old_archive = CenterArchive {
+            version: ARCHIVE_VERSION + 1,
+            ..ArchiveBmc::export(&ctx, &mm).await?
+        };
+
+        // -- Exec
+        let res = ArchiveBmc::import(&ctx, &mm, archive).await;
+        let old_res = ArchiveBmc::import(&ctx, &mm, old_archive).await;
+
+        // -- Check
+        assert!(
+            matches!(res, Err(Error::ArchiveTargetNotEmpty { .. })),
+            "Should refuse the seeded database, was {res:?}"
+        );
+        assert!(
+            matches!(old_res, Err(Error::ArchiveVersionUnsupported { .. })),
+            "Should refuse other versions, was {old_res:?}"
+        );
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
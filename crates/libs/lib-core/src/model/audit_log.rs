@@ -0,0 +1,345 @@
+//! Audit trail of the mutations done through the `base` functions.
+//!
+//! Each `base::create/update/delete/restore` writes one `audit_log` row in the same
+//! transaction as the mutation, with the changed columns as
+//! `{"column": {"old": ..., "new": ...}}`.
+//!
+//! The entries are center scoped, at the center of the mutated row (or the ctx one).
+
+use modql::field::{Fields, HasFields};
+use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsString, OpValsValue};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use serde_with::serde_as;
+use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::OffsetDateTime;
+
+use lib_utils::time::now_utc;
+
+use crate::ctx::{Ctx, DEFAULT_CENTER_ID};
+use crate::model::base::{self, PostgresDbBmc};
+use crate::model::modql_utils::time_to_sea_value;
+use crate::model::store::dbx::Dbx;
+use crate::model::ModelManager;
+use crate::model::Result;
+
+/// Columns not worth an audit entry (already in the entry itself).
+const SKIPPED_COLUMNS: &[&str] = &["cid", "ctime", "mid", "mtime"];
+
+/// Columns logged as changed, but without their values (`auth` is the push subscription secret).
+const REDACTED_COLUMNS: &[&str] = &["pwd", "pwd_salt", "token_salt", "calendar_salt", "auth"];
+const REDACTED: &str = "#redacted#";
+
+// region:    --- AuditLog Types
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
+pub struct AuditLog {
+    pub id: i64,
+    pub center_id: i64,
+    pub table_name: String,
+    pub entity_id: i64,
+    pub actor_id: i64,
+    pub op: String,
+    pub diff: Value,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub ctime: OffsetDateTime,
+}
+
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct AuditLogFilter {
+    id: Option<OpValsInt64>,
+    center_id: Option<OpValsInt64>,
+    table_name: Option<OpValsString>,
+    entity_id: Option<OpValsInt64>,
+    actor_id: Option<OpValsInt64>,
+    op: Option<OpValsString>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    ctime: Option<OpValsValue>,
+}
+
+/// Marker trait
+pub trait AuditLogBy: HasFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
+
+impl AuditLogBy for AuditLog {}
+
+#[derive(Debug, Clone, Copy)]
+pub enum AuditOp {
+    Create,
+    Update,
+    Delete,
+    Restore,
+}
+
+impl AuditOp {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AuditOp::Create => "create",
+            AuditOp::Update => "update",
+            AuditOp::Delete => "delete",
+            AuditOp::Restore => "restore",
+        }
+    }
+}
+
+// endregion: --- AuditLog Types
+
+pub struct AuditLogBmc;
+
+impl PostgresDbBmc for AuditLogBmc {
+    const TABLE: &'static str = "audit_log";
+    const CENTER_SCOPED: bool = true;
+}
+
+/// Note: No create/update/delete, entries are only written by the `base` functions.
+impl AuditLogBmc {
+    pub async fn get<E>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
+    where
+        E: AuditLogBy,
+    {
+        base::get::<Self, _>(ctx, mm, id).await
+    }
+
+    pub async fn list(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<AuditLogFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<AuditLog>> {
+        base::list::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
+}
+
+// region:    --- Writers (base only)
+
+/// Row as json, `None` when not found.
+pub(in crate::model) async fn snapshot(
+    dbx: &Dbx,
+    table: &'static str,
+    id: i64,
+) -> Result<Option<Value>> {
+    // Note: `table` is always a `PostgresDbBmc::TABLE` const, never user input.
+    let sql = format!(r#"SELECT to_jsonb(t) FROM "{table}" t WHERE t.id = $1"#);
+    let row = dbx
+        .fetch_optional(sqlx::query_as::<_, (Value,)>(&sql).bind(id))
+        .await?;
+
+    Ok(row.map(|(value,)| value))
+}
+
+pub(in crate::model) async fn log_mutation(
+    dbx: &Dbx,
+    ctx: &Ctx,
+    table: &'static str,
+    entity_id: i64,
+    op: AuditOp,
+    old: Option<&Value>,
+    new: Option<&Value>,
+) -> Result<()> {
+    let diff = diff(old, new);
+    // Note: The root ctx is not scoped, so the center is the row one.
+    let center_id = ctx
+        .center_id()
+        .or_else(|| {
+            new.or(old)
+                .and_then(|row| row.get("center_id"))
+                .and_then(Value::as_i64)
+        })
+        .unwrap_or(DEFAULT_CENTER_ID);
+
+    let query = sqlx::query(
+        "INSERT INTO audit_log (center_id, table_name, entity_id, actor_id, op, diff, ctime)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)",
+    )
+    .bind(center_id)
+    .bind(table)
+    .bind(entity_id)
+    .bind(ctx.user_id())
+    .bind(op.as_str())
+    .bind(diff)
+    .bind(now_utc());
+    dbx.execute(query).await?;
+
+    Ok(())
+}
+
+/// Changed columns only, as `{"column": {"old": ..., "new": ...}}`.
+fn diff(old: Option<&Value>, new: Option<&Value>) -> Value {
+    let empty = Map::new();
+    let old = old.and_then(Value::as_object).unwrap_or(&empty);
+    let new = new.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut diff = Map::new();
+    for column in old
+        .keys()
+        .chain(new.keys().filter(|k| !old.contains_key(*k)))
+    {
+        if SKIPPED_COLUMNS.contains(&column.as_str()) {
+            continue;
+        }
+        let old_val = old.get(column).unwrap_or(&Value::Null);
+        let new_val = new.get(column).unwrap_or(&Value::Null);
+        if old_val == new_val {
+            continue;
+        }
+
+        let change = if REDACTED_COLUMNS.contains(&column.as_str()) {
+            json!({"old": REDACTED, "new": REDACTED})
+        } else {
+            json!({"old": old_val, "new": new_val})
+        };
+        diff.insert(column.clone(), change);
+    }
+
+    Value::Object(diff)
+}
+
+/// Row with the secret columns values replaced (e.g., for client errors).
+pub(in crate::model) fn redact(mut row: Value) -> Value {
+    if let Some(row) = row.as_object_mut() {
+        for column in REDACTED_COLUMNS {
+            if let Some(value) = row.get_mut(*column) {
+                *value = json!(REDACTED);
+            }
+        }
+    }
+    row
+}
+
+// endregion: --- Writers (base only)
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::{Context, Result};
+    use serde_json::json;
+    use serial_test::serial;
+
+    use crate::_dev_utils;
+    use crate::ctx::{Ctx, DEFAULT_CENTER_ID};
+    use crate::model::audit_log::{AuditLogBmc, AuditLogFilter};
+    use crate::model::calendar::CalendarBmc;
+    use crate::model::department::{DepartmentBmc, DepartmentForCreate, DepartmentForUpdate};
+    use crate::model::user::UserBmc;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_audit_create_update_delete_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::new(1000, true, DEFAULT_CENTER_ID);
+        let fx_name = "Department_test_audit_ok";
+        let fx_name_new = "Department_test_audit_ok new";
+
+        // -- Exec
+        let id = DepartmentBmc::create(
+            &ctx,
+            &mm,
+            DepartmentForCreate {
+                name: fx_name.to_string(),
+            },
+        )
+        .await?;
+        DepartmentBmc::update(
+            &ctx,
+            &mm,
+            id,
+            DepartmentForUpdate {
+                name: Some(fx_name_new.to_string()),
+            },
+        )
+        .await?;
+        DepartmentBmc::delete(&ctx, &mm, id).await?;
+
+        // -- Check
+        let filters: Vec<AuditLogFilter> = serde_json::from_value(json!([{
+            "table_name": "departments",
+            "entity_id": id
+        }]))?;
+        let entries = AuditLogBmc::list(&ctx, &mm, Some(filters), None).await?;
+        let ops: Vec<&str> = entries.iter().map(|e| e.op.as_str()).collect();
+        assert_eq!(ops, ["create", "update", "delete"]);
+        assert!(entries.iter().all(|e| e.actor_id == 1000));
+        assert!(entries.iter().all(|e| e.center_id == DEFAULT_CENTER_ID));
+        let other_center_ctx = Ctx::new(1000, true, DEFAULT_CENTER_ID + 1);
+        let filters: Vec<AuditLogFilter> = serde_json::from_value(json!([{
+            "table_name": "departments",
+            "entity_id": id
+        }]))?;
+        let other_center_entries =
+            AuditLogBmc::list(&other_center_ctx, &mm, Some(filters), None).await?;
+        assert!(
+            other_center_entries.is_empty(),
+            "Should not list other center entries"
+        );
+
+        let update = entries.get(1).context("Should have update entry")?;
+        assert_eq!(
+            update.diff,
+            json!({"name": {"old": fx_name, "new": fx_name_new}})
+        );
+        // departments are soft deleted
+        let delete = entries.get(2).context("Should have delete entry")?;
+        assert_eq!(delete.diff["deleted_by"], json!({"old": null, "new": 1000}));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_audit_calendar_salt_redacted() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let user_id =
+            _dev_utils::seed_user(&ctx, &mm, "test_audit_calendar_salt_redacted user").await?;
+
+        // -- Exec
+        CalendarBmc::rotate_feed_token(&ctx, &mm, user_id).await?;
+
+        // -- Check
+        let filters: Vec<AuditLogFilter> = serde_json::from_value(json!([{
+            "table_name": "users",
+            "entity_id": user_id,
+            "op": "update"
+        }]))?;
+        let entries = AuditLogBmc::list(&ctx, &mm, Some(filters), None).await?;
+        let update = entries.first().context("Should have update entry")?;
+        assert_eq!(
+            update.diff,
+            json!({"calendar_salt": {"old": super::REDACTED, "new": super::REDACTED}})
+        );
+        let filters: Vec<AuditLogFilter> = serde_json::from_value(json!([{
+            "table_name": "users",
+            "entity_id": user_id,
+            "op": "create"
+        }]))?;
+        let entries = AuditLogBmc::list(&ctx, &mm, Some(filters), None).await?;
+        let create = entries.first().context("Should have create entry")?;
+        assert_eq!(create.diff["calendar_salt"]["new"], json!(super::REDACTED));
+
+        // -- Clean
+        UserBmc::delete(&ctx, &mm, user_id).await?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_audit_diff_redacted() {
+        // -- Setup & Fixtures
+        let old = json!({"username": "a", "pwd": "#01#hash", "mtime": "t1"});
+        let new = json!({"username": "a", "pwd": "#02#hash", "mtime": "t2"});
+
+        // -- Exec
+        let diff = super::diff(Some(&old), Some(&new));
+
+        // -- Check
+        assert_eq!(
+            diff,
+            json!({"pwd": {"old": super::REDACTED, "new": super::REDACTED}})
+        );
+    }
+}
+// endregion: --- Tests
@@ -1,16 +1,27 @@
+use std::future::Future;
+
 use modql::field::{Field, Fields, HasFields};
 use modql::filter::{FilterGroups, ListOptions};
 use modql::SIden;
-use sea_query::{Condition, Expr, Iden, IntoIden, PostgresQueryBuilder, Query, TableRef};
+use schemars::JsonSchema;
+use sea_query::{
+    Condition, DynIden, Expr, Iden, IntoIden, PostgresQueryBuilder, Query, SimpleExpr, TableRef,
+    UpdateStatement,
+};
 use sea_query_binder::SqlxBinder;
-use sqlx::FromRow;
+use serde::Deserialize;
+use serde_json::Value;
 use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::OffsetDateTime;
 
 use lib_utils::time::now_utc;
 
 use crate::ctx::Ctx;
-use crate::model::{Error, Result};
+use crate::model::audit_log::{self, AuditOp};
+use crate::model::store::dbx::Dbx;
 use crate::model::ModelManager;
+use crate::model::{Error, Result};
 
 // region: Postgres
 
@@ -27,12 +38,34 @@ pub enum TimestampIden {
     Cid,
     Ctime,
     Mid,
-    Mtime
+    Mtime,
+}
+
+#[derive(Iden)]
+pub enum CenterIden {
+    CenterId,
+}
+
+#[derive(Iden)]
+pub enum SoftDeleteIden {
+    DeletedAt,
+    DeletedBy,
 }
 
 pub trait PostgresDbBmc {
     const TABLE: &'static str;
 
+    /// When true, `delete` only stamps `deleted_at/deleted_by`
+    /// and `get/list` skip those rows (unless `*_including_deleted`).
+    /// The table must have both columns.
+    const SOFT_DELETE: bool = false;
+
+    /// When true, the rows belong to one center (tenant), `create` sets the
+    /// `center_id` of the ctx and `get/list/update/delete` only see its rows.
+    /// The root ctx without center sees all of them.
+    /// The table must have the `center_id` column.
+    const CENTER_SCOPED: bool = false;
+
     fn table_ref() -> TableRef {
         TableRef::Table(SIden(Self::TABLE).into_iden())
     }
@@ -67,14 +100,26 @@ pub fn compute_list_options(list_options: Option<ListOptions>) -> Result<ListOpt
 }
 
 pub async fn create<MC, E>(ctx: &Ctx, mm: &ModelManager, data: E) -> Result<i64>
-    where
-        MC: PostgresDbBmc,
-        E: HasFields,
+where
+    MC: PostgresDbBmc,
+    E: HasFields,
 {
-    let db = mm.postgres_db();
+    let mm = mm.new_with_txn();
+    let dbx = mm.dbx();
 
+    dbx.begin_txn().await?;
+    let res = create_in_txn::<MC, E>(ctx, dbx, data).await;
+    end_txn(dbx, res).await
+}
+
+async fn create_in_txn<MC, E>(ctx: &Ctx, dbx: &Dbx, data: E) -> Result<i64>
+where
+    MC: PostgresDbBmc,
+    E: HasFields,
+{
     // -- Extract fields (name / sea-query value expression)
     let mut fields = data.not_none_fields();
+    add_center_for_create::<MC>(&mut fields, ctx);
     add_timestamps_for_create(&mut fields, ctx.user_id());
     let (columns, sea_values) = fields.for_sea_insert();
 
@@ -88,20 +133,24 @@ pub async fn create<MC, E>(ctx: &Ctx, mm: &ModelManager, data: E) -> Result<i64>
 
     // -- Exec query
     let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let (id, ) = sqlx::query_as_with::<_, (i64, ), _>(&sql, values)
-        .fetch_one(db)
+    let (id,) = dbx
+        .fetch_one(sqlx::query_as_with::<_, (i64,), _>(&sql, values))
         .await?;
 
+    // -- Audit
+    let new = audit_log::snapshot(dbx, MC::TABLE, id).await?;
+    audit_log::log_mutation(dbx, ctx, MC::TABLE, id, AuditOp::Create, None, new.as_ref()).await?;
+
     Ok(id)
 }
 
-pub async fn get<MC, E>(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
-    where
-        MC: PostgresDbBmc,
-        E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
-        E: HasFields,
+pub async fn get<MC, E>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
+where
+    MC: PostgresDbBmc,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
 {
-    let db = mm.postgres_db();
+    let dbx = mm.dbx();
 
     // -- Build query
     let mut query = Query::select();
@@ -109,11 +158,17 @@ pub async fn get<MC, E>(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
         .from(MC::table_ref())
         .columns(E::field_column_refs())
         .and_where(Expr::col(CommonIden::Id).eq(id));
-    
+    if MC::SOFT_DELETE {
+        query.and_where(Expr::col(SoftDeleteIden::DeletedAt).is_null());
+    }
+    if let Some(center_id) = center_scope::<MC>(ctx) {
+        query.and_where(Expr::col(CenterIden::CenterId).eq(center_id));
+    }
+
     // -- Exec query
     let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let entity = sqlx::query_as_with::<_, E, _>(&sql, values)
-        .fetch_optional(db)
+    let entity = dbx
+        .fetch_optional(sqlx::query_as_with::<_, E, _>(&sql, values))
         .await?
         .ok_or(Error::EntityNotFound {
             entity: MC::TABLE,
@@ -124,22 +179,59 @@ pub async fn get<MC, E>(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
 }
 
 pub async fn list<MC, E, F>(
-    _ctx: &Ctx,
+    ctx: &Ctx,
+    mm: &ModelManager,
+    filter: Option<F>,
+    list_options: Option<ListOptions>,
+) -> Result<Vec<E>>
+where
+    MC: PostgresDbBmc,
+    F: Into<FilterGroups>,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
+{
+    list_with_deleted::<MC, E, F>(ctx, mm, filter, list_options, false).await
+}
+
+pub async fn list_including_deleted<MC, E, F>(
+    ctx: &Ctx,
     mm: &ModelManager,
     filter: Option<F>,
     list_options: Option<ListOptions>,
 ) -> Result<Vec<E>>
-    where
-        MC: PostgresDbBmc,
-        F: Into<FilterGroups>,
-        E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
-        E: HasFields,
+where
+    MC: PostgresDbBmc,
+    F: Into<FilterGroups>,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
 {
-    let db = mm.postgres_db();
+    list_with_deleted::<MC, E, F>(ctx, mm, filter, list_options, true).await
+}
+
+async fn list_with_deleted<MC, E, F>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    filter: Option<F>,
+    list_options: Option<ListOptions>,
+    include_deleted: bool,
+) -> Result<Vec<E>>
+where
+    MC: PostgresDbBmc,
+    F: Into<FilterGroups>,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
+{
+    let dbx = mm.dbx();
 
     // -- Build query
     let mut query = Query::select();
     query.from(MC::table_ref()).columns(E::field_column_refs());
+    if MC::SOFT_DELETE && !include_deleted {
+        query.and_where(Expr::col(SoftDeleteIden::DeletedAt).is_null());
+    }
+    if let Some(center_id) = center_scope::<MC>(ctx) {
+        query.and_where(Expr::col(CenterIden::CenterId).eq(center_id));
+    }
 
     // condition from filter
     if let Some(filter) = filter {
@@ -154,20 +246,56 @@ pub async fn list<MC, E, F>(
 
     // -- Exec query
     let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let entities = sqlx::query_as_with::<_, E, _>(&sql, values)
-        .fetch_all(db)
+    let entities = dbx
+        .fetch_all(sqlx::query_as_with::<_, E, _>(&sql, values))
         .await?;
 
     Ok(entities)
 }
 
+/// Patch update, only the `Some` fields of `data` are set.
+/// (Nullable columns are `Option<Option<T>>` in the `ForUpdate` types, `Some(None)` sets NULL)
 pub async fn update<MC, E>(ctx: &Ctx, mm: &ModelManager, id: i64, data: E) -> Result<()>
-    where
-        MC: PostgresDbBmc,
-        E: HasFields,
+where
+    MC: PostgresDbBmc,
+    E: HasFields,
 {
-    let db = mm.postgres_db();
+    update_if_unmodified::<MC, E>(ctx, mm, id, data, None).await
+}
 
+/// Same as `update`, but when `expected_mtime` is given, only updates if the row
+/// `mtime` still matches (i.e., nobody updated it since it was read).
+/// Otherwise returns `Error::ConcurrentModification` with the current row.
+pub async fn update_if_unmodified<MC, E>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    id: i64,
+    data: E,
+    expected_mtime: Option<OffsetDateTime>,
+) -> Result<()>
+where
+    MC: PostgresDbBmc,
+    E: HasFields,
+{
+    let mm = mm.new_with_txn();
+    let dbx = mm.dbx();
+
+    dbx.begin_txn().await?;
+    let res = update_in_txn::<MC, E>(ctx, dbx, id, data, expected_mtime).await;
+    end_txn(dbx, res).await
+}
+
+async fn update_in_txn<MC, E>(
+    ctx: &Ctx,
+    dbx: &Dbx,
+    id: i64,
+    data: E,
+    expected_mtime: Option<OffsetDateTime>,
+) -> Result<()>
+where
+    MC: PostgresDbBmc,
+    E: HasFields,
+{
     // -- Prep data
     let mut fields = data.not_none_fields();
     add_timestamps_for_update(&mut fields, ctx.user_id());
@@ -179,59 +307,475 @@ pub async fn update<MC, E>(ctx: &Ctx, mm: &ModelManager, id: i64, data: E) -> Re
         .table(MC::table_ref())
         .values(fields)
         .and_where(Expr::col(CommonIden::Id).eq(id));
+    if MC::SOFT_DELETE {
+        query.and_where(Expr::col(SoftDeleteIden::DeletedAt).is_null());
+    }
+    if let Some(center_id) = center_scope::<MC>(ctx) {
+        query.and_where(Expr::col(CenterIden::CenterId).eq(center_id));
+    }
+    if let Some(expected_mtime) = expected_mtime {
+        query.and_where(Expr::col(TimestampIden::Mtime).eq(expected_mtime));
+    }
 
     // -- Exec query
+    let old = audit_log::snapshot(dbx, MC::TABLE, id).await?;
     let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let count = sqlx::query_with(&sql, values)
-        .execute(db)
+    let count = dbx
+        .execute(sqlx::query_with(&sql, values))
         .await?
         .rows_affected();
 
     // -- Check result
     if count == 0 {
-        Err(Error::EntityNotFound {
-            entity: MC::TABLE,
-            id,
-        })
+        // Still there, so the mtime did not match.
+        let current = match expected_mtime {
+            Some(_) => audit_log::snapshot(dbx, MC::TABLE, id)
+                .await?
+                .filter(|row| !MC::SOFT_DELETE || row["deleted_at"].is_null())
+                .filter(|row| in_center::<MC>(ctx, row)),
+            None => None,
+        };
+        return Err(match current {
+            Some(current) => Error::ConcurrentModification {
+                entity: MC::TABLE,
+                id,
+                current: audit_log::redact(current),
+            },
+            None => Error::EntityNotFound {
+                entity: MC::TABLE,
+                id,
+            },
+        });
+    }
+
+    // -- Audit
+    let new = audit_log::snapshot(dbx, MC::TABLE, id).await?;
+    audit_log::log_mutation(
+        dbx,
+        ctx,
+        MC::TABLE,
+        id,
+        AuditOp::Update,
+        old.as_ref(),
+        new.as_ref(),
+    )
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete<MC>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
+where
+    MC: PostgresDbBmc,
+{
+    let mm = mm.new_with_txn();
+    let dbx = mm.dbx();
+
+    dbx.begin_txn().await?;
+    let res = delete_in_txn::<MC>(ctx, dbx, &[id]).await;
+    end_txn(dbx, res).await
+}
+
+/// Delete all `ids` with one statement, `EntityNotFound` (and nothing deleted)
+/// if one of them is missing.
+async fn delete_in_txn<MC>(ctx: &Ctx, dbx: &Dbx, ids: &[i64]) -> Result<()>
+where
+    MC: PostgresDbBmc,
+{
+    // -- Build query
+    let (sql, values) = if MC::SOFT_DELETE {
+        let mut fields = Fields::new(vec![
+            Field::new(SoftDeleteIden::DeletedAt.into_iden(), now_utc().into()),
+            Field::new(SoftDeleteIden::DeletedBy.into_iden(), ctx.user_id().into()),
+        ]);
+        add_timestamps_for_update(&mut fields, ctx.user_id());
+
+        let mut query = Query::update();
+        query
+            .table(MC::table_ref())
+            .values(fields.for_sea_update())
+            .and_where(Expr::col(CommonIden::Id).is_in(ids.iter().copied()))
+            .and_where(Expr::col(SoftDeleteIden::DeletedAt).is_null());
+        if let Some(center_id) = center_scope::<MC>(ctx) {
+            query.and_where(Expr::col(CenterIden::CenterId).eq(center_id));
+        }
+        query.build_sqlx(PostgresQueryBuilder)
     } else {
-        Ok(())
+        let mut query = Query::delete();
+        query
+            .from_table(MC::table_ref())
+            .and_where(Expr::col(CommonIden::Id).is_in(ids.iter().copied()));
+        if let Some(center_id) = center_scope::<MC>(ctx) {
+            query.and_where(Expr::col(CenterIden::CenterId).eq(center_id));
+        }
+        query.build_sqlx(PostgresQueryBuilder)
+    };
+
+    // -- Check existence (and keep the old rows for the audit)
+    let mut olds = Vec::with_capacity(ids.len());
+    for &id in ids {
+        let old = audit_log::snapshot(dbx, MC::TABLE, id)
+            .await?
+            .filter(|row| !MC::SOFT_DELETE || row["deleted_at"].is_null())
+            .filter(|row| in_center::<MC>(ctx, row))
+            .ok_or(Error::EntityNotFound {
+                entity: MC::TABLE,
+                id,
+            })?;
+        olds.push((id, old));
+    }
+
+    // -- Exec query
+    dbx.execute(sqlx::query_with(&sql, values)).await?;
+
+    // -- Audit (soft delete keeps the row, so diff with it)
+    for (id, old) in olds {
+        let new = if MC::SOFT_DELETE {
+            audit_log::snapshot(dbx, MC::TABLE, id).await?
+        } else {
+            None
+        };
+        audit_log::log_mutation(
+            dbx,
+            ctx,
+            MC::TABLE,
+            id,
+            AuditOp::Delete,
+            Some(&old),
+            new.as_ref(),
+        )
+        .await?;
     }
+
+    Ok(())
 }
 
-pub async fn delete<MC>(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
-    where
-        MC: PostgresDbBmc,
+/// Undo a soft `delete` (only for `SOFT_DELETE` bmcs).
+pub async fn restore<MC>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
+where
+    MC: PostgresDbBmc,
 {
-    let db = mm.postgres_db();
+    if !MC::SOFT_DELETE {
+        return Err(Error::SoftDeleteNotSupported { entity: MC::TABLE });
+    }
+
+    let mm = mm.new_with_txn();
+    let dbx = mm.dbx();
+
+    // -- Prep data
+    let mut fields = Fields::new(vec![
+        Field::new(
+            SoftDeleteIden::DeletedAt.into_iden(),
+            Option::<OffsetDateTime>::None.into(),
+        ),
+        Field::new(
+            SoftDeleteIden::DeletedBy.into_iden(),
+            Option::<i64>::None.into(),
+        ),
+    ]);
+    add_timestamps_for_update(&mut fields, ctx.user_id());
 
     // -- Build query
-    let mut query = Query::delete();
+    let mut query = Query::update();
     query
-        .from_table(MC::table_ref())
-        .and_where(Expr::col(CommonIden::Id).eq(id));
+        .table(MC::table_ref())
+        .values(fields.for_sea_update())
+        .and_where(Expr::col(CommonIden::Id).eq(id))
+        .and_where(Expr::col(SoftDeleteIden::DeletedAt).is_not_null());
+    if let Some(center_id) = center_scope::<MC>(ctx) {
+        query.and_where(Expr::col(CenterIden::CenterId).eq(center_id));
+    }
 
     // -- Exec query
+    dbx.begin_txn().await?;
+    let res = restore_in_txn::<MC>(ctx, dbx, id, query).await;
+    end_txn(dbx, res).await
+}
+
+async fn restore_in_txn<MC>(ctx: &Ctx, dbx: &Dbx, id: i64, query: UpdateStatement) -> Result<()>
+where
+    MC: PostgresDbBmc,
+{
+    let old = audit_log::snapshot(dbx, MC::TABLE, id).await?;
     let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let count = sqlx::query_with(&sql, values)
-        .execute(db)
+    let count = dbx
+        .execute(sqlx::query_with(&sql, values))
         .await?
         .rows_affected();
 
     // -- Check result
     if count == 0 {
-        Err(Error::EntityNotFound {
+        return Err(Error::EntityNotFound {
             entity: MC::TABLE,
             id,
-        })
-    } else {
-        Ok(())
+        });
+    }
+
+    // -- Audit
+    let new = audit_log::snapshot(dbx, MC::TABLE, id).await?;
+    audit_log::log_mutation(
+        dbx,
+        ctx,
+        MC::TABLE,
+        id,
+        AuditOp::Restore,
+        old.as_ref(),
+        new.as_ref(),
+    )
+    .await
+}
+
+// region:    --- Bulk
+
+/// How the `*_many` functions handle a failing item.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkMode {
+    /// The first failing item fails (and rolls back) the whole call.
+    #[default]
+    AllOrNothing,
+    /// Each item is applied or rolled back on its own (savepoint per item),
+    /// the result of each item is returned.
+    PerItem,
+}
+
+const BULK_SAVEPOINT: &str = "bulk_item";
+
+/// Create all items in one transaction, with multi-row inserts for `AllOrNothing`.
+///
+/// Returns one result per item, in the same order
+/// (only `Ok` ones for `AllOrNothing`, otherwise the call fails).
+pub async fn create_many<MC, E>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    data: Vec<E>,
+    mode: BulkMode,
+) -> Result<Vec<Result<i64>>>
+where
+    MC: PostgresDbBmc,
+    E: HasFields,
+{
+    let mm = mm.new_with_txn();
+    let dbx = mm.dbx();
+
+    dbx.begin_txn().await?;
+    let res = match mode {
+        BulkMode::AllOrNothing => insert_rows::<MC, E>(ctx, dbx, data)
+            .await
+            .map(|ids| ids.into_iter().map(Ok).collect()),
+        BulkMode::PerItem => {
+            for_each_item(dbx, data, |item| create_in_txn::<MC, E>(ctx, dbx, item)).await
+        }
+    };
+    end_txn(dbx, res).await
+}
+
+/// Update all `(id, data, expected_mtime)` items in one transaction
+/// (see `update_if_unmodified`).
+pub async fn update_many<MC, E>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    data: Vec<(i64, E, Option<OffsetDateTime>)>,
+    mode: BulkMode,
+) -> Result<Vec<Result<()>>>
+where
+    MC: PostgresDbBmc,
+    E: HasFields,
+{
+    let mm = mm.new_with_txn();
+    let dbx = mm.dbx();
+
+    dbx.begin_txn().await?;
+    let res = match mode {
+        BulkMode::AllOrNothing => update_rows::<MC, E>(ctx, dbx, data).await,
+        BulkMode::PerItem => {
+            for_each_item(dbx, data, |(id, item, expected_mtime)| {
+                update_in_txn::<MC, E>(ctx, dbx, id, item, expected_mtime)
+            })
+            .await
+        }
+    };
+    end_txn(dbx, res).await
+}
+
+/// Update the items one by one, the first failing one fails them all.
+async fn update_rows<MC, E>(
+    ctx: &Ctx,
+    dbx: &Dbx,
+    data: Vec<(i64, E, Option<OffsetDateTime>)>,
+) -> Result<Vec<Result<()>>>
+where
+    MC: PostgresDbBmc,
+    E: HasFields,
+{
+    let mut results = Vec::with_capacity(data.len());
+    for (id, item, expected_mtime) in data {
+        update_in_txn::<MC, E>(ctx, dbx, id, item, expected_mtime).await?;
+        results.push(Ok(()));
     }
+
+    Ok(results)
 }
 
+/// Delete all `ids` in one transaction (one statement for `AllOrNothing`).
+pub async fn delete_many<MC>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    ids: Vec<i64>,
+    mode: BulkMode,
+) -> Result<Vec<Result<()>>>
+where
+    MC: PostgresDbBmc,
+{
+    let mm = mm.new_with_txn();
+    let dbx = mm.dbx();
+
+    dbx.begin_txn().await?;
+    let res = match mode {
+        BulkMode::AllOrNothing => {
+            let mut unique_ids = ids.clone();
+            unique_ids.sort_unstable();
+            unique_ids.dedup();
+            delete_in_txn::<MC>(ctx, dbx, &unique_ids)
+                .await
+                .map(|_| ids.iter().map(|_| Ok(())).collect())
+        }
+        BulkMode::PerItem => {
+            for_each_item(dbx, ids, |id| async move {
+                delete_in_txn::<MC>(ctx, dbx, &[id]).await
+            })
+            .await
+        }
+    };
+    end_txn(dbx, res).await
+}
+
+/// Multi-row insert, one statement per run of items with the same columns.
+async fn insert_rows<MC, E>(ctx: &Ctx, dbx: &Dbx, data: Vec<E>) -> Result<Vec<i64>>
+where
+    MC: PostgresDbBmc,
+    E: HasFields,
+{
+    // -- Group the rows by columns (`not_none_fields` might differ per item)
+    let mut runs: Vec<(Vec<DynIden>, Vec<Vec<SimpleExpr>>)> = Vec::new();
+    for item in data {
+        let mut fields = item.not_none_fields();
+        add_center_for_create::<MC>(&mut fields, ctx);
+        add_timestamps_for_create(&mut fields, ctx.user_id());
+        let (columns, sea_values) = fields.for_sea_insert();
+
+        match runs.last_mut() {
+            Some((run_columns, rows)) if same_columns(run_columns, &columns) => {
+                rows.push(sea_values)
+            }
+            _ => runs.push((columns, vec![sea_values])),
+        }
+    }
+
+    // -- Exec queries
+    let mut ids = Vec::new();
+    for (columns, rows) in runs {
+        let mut query = Query::insert();
+        query
+            .into_table(MC::table_ref())
+            .columns(columns)
+            .returning(Query::returning().columns([CommonIden::Id]));
+        for row in rows {
+            query.values(row)?;
+        }
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let rows = dbx
+            .fetch_all(sqlx::query_as_with::<_, (i64,), _>(&sql, values))
+            .await?;
+        ids.extend(rows.into_iter().map(|(id,)| id));
+    }
+
+    // -- Audit
+    for &id in &ids {
+        let new = audit_log::snapshot(dbx, MC::TABLE, id).await?;
+        audit_log::log_mutation(dbx, ctx, MC::TABLE, id, AuditOp::Create, None, new.as_ref())
+            .await?;
+    }
+
+    Ok(ids)
+}
+
+fn same_columns(a: &[DynIden], b: &[DynIden]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.to_string() == b.to_string())
+}
+
+/// Run `f` for each item in its own savepoint, so a failing item
+/// only rolls back itself.
+async fn for_each_item<T, R, F, Fut>(dbx: &Dbx, items: Vec<T>, mut f: F) -> Result<Vec<Result<R>>>
+where
+    F: FnMut(T) -> Fut,
+    Fut: Future<Output = Result<R>>,
+{
+    let mut results = Vec::with_capacity(items.len());
+    for item in items {
+        dbx.savepoint(BULK_SAVEPOINT).await?;
+        let res = f(item).await;
+        if res.is_ok() {
+            dbx.release_savepoint(BULK_SAVEPOINT).await?;
+        } else {
+            dbx.rollback_to_savepoint(BULK_SAVEPOINT).await?;
+        }
+        results.push(res);
+    }
+
+    Ok(results)
+}
+
+// endregion: --- Bulk
+
 // endregion: Postgres
 
 // region:    --- Utils
 
+/// Ends the `begin_txn` of a base function, committing on success.
+/// On error, `rollback_txn` keeps a (maybe outer) txn balanced,
+/// e.g., for the import rows or the bulk items, rolled back to their savepoint.
+pub(in crate::model) async fn end_txn<T>(dbx: &Dbx, res: Result<T>) -> Result<T> {
+    match res {
+        Ok(value) => {
+            dbx.commit_txn().await?;
+            Ok(value)
+        }
+        Err(err) => {
+            dbx.rollback_txn().await?;
+            Err(err)
+        }
+    }
+}
+
+/// The `center_id` the queries of `MC` are restricted to (see `CENTER_SCOPED`).
+pub fn center_scope<MC: PostgresDbBmc>(ctx: &Ctx) -> Option<i64> {
+    if MC::CENTER_SCOPED {
+        ctx.center_id()
+    } else {
+        None
+    }
+}
+
+/// Whether the `audit_log::snapshot` row is visible to the ctx center.
+fn in_center<MC: PostgresDbBmc>(ctx: &Ctx, row: &Value) -> bool {
+    match center_scope::<MC>(ctx) {
+        Some(center_id) => row["center_id"].as_i64() == Some(center_id),
+        None => true,
+    }
+}
+
+/// Set the ctx center for create (the root ctx without center keeps the column default).
+fn add_center_for_create<MC: PostgresDbBmc>(fields: &mut Fields, ctx: &Ctx) {
+    if let Some(center_id) = center_scope::<MC>(ctx) {
+        fields.push(Field::new(
+            CenterIden::CenterId.into_iden(),
+            center_id.into(),
+        ));
+    }
+}
+
 /// Update the timestamps info for create
 /// (e.g., cid, ctime, and mid, mtime will be updated with the same values)
 pub fn add_timestamps_for_create(fields: &mut Fields, user_id: i64) {
@@ -1,25 +1,30 @@
 use modql::field::{Fields, HasFields};
 use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsString, OpValsValue};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use sqlx::FromRow;
 use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::OffsetDateTime;
 
 use crate::ctx::Ctx;
 use crate::model::base::{self, PostgresDbBmc};
-use crate::model::ModelManager;
 use crate::model::modql_utils::time_to_sea_value;
+use crate::model::ModelManager;
 use crate::model::Result;
 
 // region:    --- Building Types
 #[serde_as]
-#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
 pub struct Building {
     pub id: i64,
     pub building_name: String,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
 }
 
-#[derive(Fields, Deserialize, Clone)]
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
 pub struct BuildingForCreate {
     pub building_name: String,
 }
@@ -36,9 +41,9 @@ pub struct BuildingFilter {
     mtime: Option<OpValsValue>,
 }
 
-#[derive(Fields, Default, Deserialize)]
+#[derive(Fields, Default, Deserialize, JsonSchema)]
 pub struct BuildingForUpdate {
-    pub building_name: String,
+    pub building_name: Option<String>,
 }
 
 /// Marker trait
@@ -52,10 +57,15 @@ pub struct BuildingBmc;
 
 impl PostgresDbBmc for BuildingBmc {
     const TABLE: &'static str = "buildings";
+    const CENTER_SCOPED: bool = true;
 }
 
 impl BuildingBmc {
-    pub async fn create(ctx: &Ctx, mm: &ModelManager, building_c: BuildingForCreate) -> Result<i64> {
+    pub async fn create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        building_c: BuildingForCreate,
+    ) -> Result<i64> {
         base::create::<Self, _>(ctx, mm, building_c).await
     }
 
@@ -84,6 +94,16 @@ impl BuildingBmc {
         base::update::<Self, _>(ctx, mm, id, building_u).await
     }
 
+    pub async fn update_if_unmodified(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        building_u: BuildingForUpdate,
+        expected_mtime: Option<OffsetDateTime>,
+    ) -> Result<()> {
+        base::update_if_unmodified::<Self, _>(ctx, mm, id, building_u, expected_mtime).await
+    }
+
     pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
         base::delete::<Self>(ctx, mm, id).await
     }
@@ -106,9 +126,9 @@ mod tests {
         // -- Setup & Fixtures
         let mm = _dev_utils::init_test().await;
         let ctx = Ctx::root_ctx();
-        
+
         let fx_building_name = "Principal_test_create_ok_building";
-        
+
         // -- Exec
         let building_c = BuildingForCreate {
             building_name: fx_building_name.to_string(),
@@ -122,10 +142,10 @@ mod tests {
 
         // -- Clean
         BuildingBmc::delete(&ctx, &mm, id).await?;
-        
+
         Ok(())
     }
-    
+
     #[serial]
     #[tokio::test]
     async fn test_update_ok() -> Result<()> {
@@ -134,7 +154,7 @@ mod tests {
         let ctx = Ctx::root_ctx();
         let fx_building_name = "Principal_test_update_ok_building";
         let fx_building_name_new = "Grande_test_update_ok_building";
-       
+
         let fx_building_id = _dev_utils::seed_building(&ctx, &mm, fx_building_name).await?;
 
         // -- Exec
@@ -143,10 +163,10 @@ mod tests {
             &mm,
             fx_building_id,
             BuildingForUpdate {
-                building_name: fx_building_name_new.to_string(),
+                building_name: Some(fx_building_name_new.to_string()),
             },
         )
-            .await?;
+        .await?;
 
         // -- Check
         let building: Building = BuildingBmc::get(&ctx, &mm, fx_building_id).await?;
@@ -154,7 +174,7 @@ mod tests {
 
         // -- Clean
         BuildingBmc::delete(&ctx, &mm, fx_building_id).await?;
-        
+
         Ok(())
     }
 
@@ -179,16 +199,14 @@ mod tests {
         let buildings = BuildingBmc::list(&ctx, &mm, Some(filter), None).await?;
 
         // -- Check
-        let buildings: Vec<String> = buildings.into_iter()
-            .map(|s| s.building_name)
-            .collect();
+        let buildings: Vec<String> = buildings.into_iter().map(|s| s.building_name).collect();
         assert_eq!(buildings.len(), 2);
         assert_eq!(buildings[0], fx_building_name);
 
         // -- Cleanup
         BuildingBmc::delete(&ctx, &mm, fx_building_id_01).await?;
         BuildingBmc::delete(&ctx, &mm, fx_building_id_02).await?;
-        
+
         Ok(())
     }
 }
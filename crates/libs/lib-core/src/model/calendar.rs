@@ -0,0 +1,459 @@
+//! School calendar (the course dates and the holidays), and the timetables on it
+//! (e.g., for the web-server `/api/calendar` iCalendar feeds).
+//!
+//! - A course without `course_calendars` row spans its whole year,
+//!   as `course` is the year (see `ControlBmc::update_guards`).
+//! - The feed token of a user never expires, `rotate_feed_token` revokes it
+//!   (new `calendar_salt`).
+
+use std::collections::HashMap;
+
+use modql::field::{Fields, HasFields};
+use modql::filter::{
+    FilterNodes, ListOptions, OpValInt64, OpValValue, OpValsInt32, OpValsInt64, OpValsString,
+    OpValsValue,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::{Date, Month, OffsetDateTime, Time};
+use uuid::Uuid;
+
+use lib_auth::token::generate_calendar_token;
+use lib_utils::time::iso_date;
+
+use crate::ctx::{Ctx, DEFAULT_CENTER_ID};
+use crate::model::base::{self, PostgresDbBmc};
+use crate::model::center_schedule_hour::CenterScheduleHourBmc;
+use crate::model::modql_utils::{date_to_sea_value, time_to_sea_value};
+use crate::model::schedule::{ScheduleBmc, ScheduleFilter};
+use crate::model::schedule_hour::{ScheduleHour, ScheduleHourBmc, ScheduleHourFilter};
+use crate::model::user::{UserBmc, UserForCalendar, UserForCalendarRotate};
+use crate::model::Result;
+use crate::model::{Error, ModelManager};
+
+/// `subject_name` of the hours without lesson, not in the timetables.
+const FREE_SUBJECT: &str = "Libre";
+
+// region:    --- Calendar Types
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, JsonSchema)]
+pub struct CourseCalendar {
+    pub course: i32,
+    #[serde(with = "iso_date")]
+    #[schemars(with = "String")]
+    pub start_date: Date,
+    #[serde(with = "iso_date")]
+    #[schemars(with = "String")]
+    pub end_date: Date,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
+pub struct Holiday {
+    pub id: i64,
+    #[serde(with = "iso_date")]
+    #[schemars(with = "String")]
+    pub date: Date,
+    pub name: String,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
+}
+
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
+pub struct HolidayForCreate {
+    #[serde(with = "iso_date")]
+    #[schemars(with = "String")]
+    pub date: Date,
+    /// e.g., "Día de la Hispanidad"
+    pub name: String,
+}
+
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct HolidayFilter {
+    id: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "date_to_sea_value")]
+    date: Option<OpValsValue>,
+    name: Option<OpValsString>,
+    cid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    ctime: Option<OpValsValue>,
+    mid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    mtime: Option<OpValsValue>,
+}
+
+/// Marker trait
+pub trait HolidayBy: HasFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
+
+impl HolidayBy for Holiday {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimetableOwner {
+    Teacher(i64),
+    Group(i64),
+}
+
+/// The weekly hours of a teacher or group, and the course dates they repeat on.
+#[derive(Debug, Clone)]
+pub struct Timetable {
+    pub course: CourseCalendar,
+    /// In the course dates.
+    pub holidays: Vec<Date>,
+    pub hours: Vec<TimetableHour>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TimetableHour {
+    pub schedule_hour: ScheduleHour,
+    pub start_time: Time,
+    pub end_time: Time,
+}
+
+// endregion: --- Calendar Types
+
+pub struct HolidayBmc;
+
+impl PostgresDbBmc for HolidayBmc {
+    const TABLE: &'static str = "holidays";
+    const CENTER_SCOPED: bool = true;
+}
+
+impl HolidayBmc {
+    pub async fn create(ctx: &Ctx, mm: &ModelManager, holiday_c: HolidayForCreate) -> Result<i64> {
+        base::create::<Self, _>(ctx, mm, holiday_c).await
+    }
+
+    pub async fn get<E>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
+    where
+        E: HolidayBy,
+    {
+        base::get::<Self, _>(ctx, mm, id).await
+    }
+
+    pub async fn list(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<HolidayFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<Holiday>> {
+        base::list::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
+
+    pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        base::delete::<Self>(ctx, mm, id).await
+    }
+}
+
+pub struct CalendarBmc;
+
+impl CalendarBmc {
+    pub const COURSE_TABLE: &'static str = "course_calendars";
+
+    /// Creates or replaces the course dates (of the ctx center).
+    pub async fn set_course(ctx: &Ctx, mm: &ModelManager, course_c: CourseCalendar) -> Result<()> {
+        let CourseCalendar {
+            course,
+            start_date,
+            end_date,
+        } = course_c;
+        if start_date > end_date {
+            return Err(Error::CourseCalendarInvalidDates {
+                course,
+                start_date: start_date.to_string(),
+                end_date: end_date.to_string(),
+            });
+        }
+
+        let sql = format!(
+            "INSERT INTO {} (center_id, course, start_date, end_date, cid, ctime, mid, mtime) \
+             VALUES ($5, $1, $2, $3, $4, now(), $4, now()) \
+             ON CONFLICT (center_id, course) DO UPDATE SET \
+                 start_date = EXCLUDED.start_date, end_date = EXCLUDED.end_date, \
+                 mid = $4, mtime = now()",
+            Self::COURSE_TABLE
+        );
+        mm.dbx()
+            .execute(
+                sqlx::query(&sql)
+                    .bind(course)
+                    .bind(start_date)
+                    .bind(end_date)
+                    .bind(ctx.user_id())
+                    .bind(course_center_id(ctx)),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_course(ctx: &Ctx, mm: &ModelManager, course: i32) -> Result<CourseCalendar> {
+        let sql = format!(
+            "SELECT course, start_date, end_date FROM {} WHERE course = $1 AND center_id = $2",
+            Self::COURSE_TABLE
+        );
+        let course_calendar = mm
+            .dbx()
+            .fetch_optional(
+                sqlx::query_as::<_, CourseCalendar>(&sql)
+                    .bind(course)
+                    .bind(course_center_id(ctx)),
+            )
+            .await?;
+
+        match course_calendar {
+            Some(course_calendar) => Ok(course_calendar),
+            None => whole_year(course),
+        }
+    }
+
+    /// The `owner` hours of the course, with the center hour times
+    /// (hours without center hour or lesson are skipped).
+    pub async fn timetable(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        owner: TimetableOwner,
+        course: i32,
+    ) -> Result<Timetable> {
+        let course_calendar = Self::get_course(ctx, mm, course).await?;
+
+        // -- Holidays in the course dates
+        let holiday_filters = vec![HolidayFilter {
+            date: Some(OpValsValue(vec![
+                OpValValue::Gte(date_json(course_calendar.start_date)),
+                OpValValue::Lte(date_json(course_calendar.end_date)),
+            ])),
+            ..Default::default()
+        }];
+        let holidays = HolidayBmc::list(ctx, mm, Some(holiday_filters), None)
+            .await?
+            .into_iter()
+            .map(|holiday| holiday.date)
+            .collect();
+
+        // -- Owner schedule hours
+        let owner_filter = match owner {
+            TimetableOwner::Teacher(user_id) => ScheduleFilter {
+                user_id: Some(OpValsInt64::from(user_id)),
+                ..Default::default()
+            },
+            TimetableOwner::Group(group_id) => ScheduleFilter {
+                group_id: Some(OpValsInt64::from(group_id)),
+                ..Default::default()
+            },
+        };
+        let schedule_filters = vec![ScheduleFilter {
+            course: Some(OpValsInt64::from(course as i64)),
+            ..owner_filter
+        }];
+        let schedule_ids: Vec<i64> = ScheduleBmc::list(ctx, mm, Some(schedule_filters), None)
+            .await?
+            .into_iter()
+            .map(|schedule| schedule.id)
+            .collect();
+        if schedule_ids.is_empty() {
+            return Ok(Timetable {
+                course: course_calendar,
+                holidays,
+                hours: Vec::new(),
+            });
+        }
+
+        let schedule_hour_filters = vec![ScheduleHourFilter {
+            schedule_id: Some(OpValsInt64(vec![OpValInt64::In(schedule_ids)])),
+            course: Some(OpValsInt32::from(course)),
+            ..Default::default()
+        }];
+        let schedule_hours =
+            ScheduleHourBmc::list(ctx, mm, Some(schedule_hour_filters), None).await?;
+
+        // -- Center hour times
+        let center_times: HashMap<i32, (Time, Time)> =
+            CenterScheduleHourBmc::list(ctx, mm, None, None)
+                .await?
+                .into_iter()
+                .map(|hour| (hour.n_hour, (hour.start_time, hour.end_time)))
+                .collect();
+
+        let hours = schedule_hours
+            .into_iter()
+            .filter(|schedule_hour| schedule_hour.subject_name != FREE_SUBJECT)
+            .filter_map(|schedule_hour| {
+                let (start_time, end_time) = *center_times.get(&schedule_hour.n_hour)?;
+                Some(TimetableHour {
+                    schedule_hour,
+                    start_time,
+                    end_time,
+                })
+            })
+            .collect();
+
+        Ok(Timetable {
+            course: course_calendar,
+            holidays,
+            hours,
+        })
+    }
+
+    /// Token of the user feed urls (`?token=...`).
+    pub async fn feed_token(ctx: &Ctx, mm: &ModelManager, user_id: i64) -> Result<String> {
+        let user: UserForCalendar = UserBmc::get(ctx, mm, user_id).await?;
+        let token = generate_calendar_token(&user.id.to_string(), user.calendar_salt)?;
+
+        Ok(token.to_string())
+    }
+
+    /// Revokes the user feed urls, `feed_token` then returns a new one.
+    pub async fn rotate_feed_token(ctx: &Ctx, mm: &ModelManager, user_id: i64) -> Result<()> {
+        let user_u = UserForCalendarRotate {
+            calendar_salt: Uuid::new_v4(),
+        };
+
+        base::update::<UserBmc, _>(ctx, mm, user_id, user_u).await
+    }
+}
+
+/// The course dates are per center, the root ctx without center uses the default one.
+fn course_center_id(ctx: &Ctx) -> i64 {
+    ctx.center_id().unwrap_or(DEFAULT_CENTER_ID)
+}
+
+fn whole_year(course: i32) -> Result<CourseCalendar> {
+    let start_date = Date::from_calendar_date(course, Month::January, 1);
+    let end_date = Date::from_calendar_date(course, Month::December, 31);
+
+    match (start_date, end_date) {
+        (Ok(start_date), Ok(end_date)) => Ok(CourseCalendar {
+            course,
+            start_date,
+            end_date,
+        }),
+        _ => Err(Error::CourseCalendarInvalidDates {
+            course,
+            start_date: format!("{course}-01-01"),
+            end_date: format!("{course}-12-31"),
+        }),
+    }
+}
+
+/// As `date_to_sea_value` parses it (`Date` displays as `YYYY-MM-DD`).
+fn date_json(date: Date) -> serde_json::Value {
+    serde_json::Value::String(date.to_string())
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serial_test::serial;
+    use time::macros::{date, time};
+
+    use crate::_dev_utils;
+    use crate::ctx::Ctx;
+    use crate::model::calendar::{
+        CalendarBmc, CourseCalendar, HolidayBmc, HolidayForCreate, TimetableOwner,
+    };
+    use crate::model::schedule::ScheduleBmc;
+    use crate::model::user::UserBmc;
+    use crate::model::Error;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_timetable_teacher_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_course = 2025;
+        let user_id = _dev_utils::seed_user(&ctx, &mm, "test_timetable_teacher_ok user").await?;
+        let schedule_id = _dev_utils::seed_schedule(&ctx, &mm, fx_course, user_id, -1).await?;
+        _dev_utils::seed_schedule_hour(
+            &ctx,
+            &mm,
+            schedule_id,
+            "Matemáticas",
+            "Info 2",
+            0,
+            1,
+            fx_course,
+        )
+        .await?;
+        _dev_utils::seed_schedule_hour(&ctx, &mm, schedule_id, "Libre", "Sala", 0, 2, fx_course)
+            .await?;
+        let fx_course_calendar = CourseCalendar {
+            course: fx_course,
+            start_date: date!(2025 - 09 - 08),
+            end_date: date!(2026 - 06 - 19),
+        };
+        CalendarBmc::set_course(&ctx, &mm, fx_course_calendar).await?;
+        let in_holiday_id = HolidayBmc::create(
+            &ctx,
+            &mm,
+            HolidayForCreate {
+                date: date!(2025 - 10 - 13),
+                name: "test_timetable_teacher_ok in".to_string(),
+            },
+        )
+        .await?;
+        let out_holiday_id = HolidayBmc::create(
+            &ctx,
+            &mm,
+            HolidayForCreate {
+                date: date!(2025 - 08 - 15),
+                name: "test_timetable_teacher_ok out".to_string(),
+            },
+        )
+        .await?;
+
+        // -- Exec
+        let timetable =
+            CalendarBmc::timetable(&ctx, &mm, TimetableOwner::Teacher(user_id), fx_course).await?;
+
+        // -- Check
+        assert_eq!(timetable.course.start_date, date!(2025 - 09 - 08));
+        assert_eq!(timetable.holidays, vec![date!(2025 - 10 - 13)]);
+        assert_eq!(timetable.hours.len(), 1, "Should skip the free hour");
+        let hour = &timetable.hours[0];
+        assert_eq!(hour.schedule_hour.subject_name, "Matemáticas");
+        assert_eq!(hour.start_time, time!(08:55));
+        assert_eq!(hour.end_time, time!(09:50));
+
+        // -- Clean
+        HolidayBmc::delete(&ctx, &mm, in_holiday_id).await?;
+        HolidayBmc::delete(&ctx, &mm, out_holiday_id).await?;
+        ScheduleBmc::delete(&ctx, &mm, schedule_id).await?;
+        UserBmc::delete(&ctx, &mm, user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_set_course_err_dates() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_course_calendar = CourseCalendar {
+            course: 2025,
+            start_date: date!(2026 - 06 - 19),
+            end_date: date!(2025 - 09 - 08),
+        };
+
+        // -- Exec
+        let res = CalendarBmc::set_course(&ctx, &mm, fx_course_calendar).await;
+
+        // -- Check
+        assert!(
+            matches!(
+                res,
+                Err(Error::CourseCalendarInvalidDates { course: 2025, .. })
+            ),
+            "Should have matched `Err(CourseCalendarInvalidDates)` but was `{res:?}`"
+        );
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
@@ -0,0 +1,256 @@
+//! Centers, the tenants of the server (one school each).
+//!
+//! - The center scoped tables have a `center_id` (see `PostgresDbBmc::CENTER_SCOPED`),
+//!   the ctx center is set at login.
+//! - The default center (`DEFAULT_CENTER_ID`) holds the single school deployments data.
+//! - Only the super admins create centers, `provision` also creates the center
+//!   "Ninguno" department and its first admin.
+
+use modql::field::{Fields, HasFields};
+use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsString, OpValsValue};
+use schemars::JsonSchema;
+use sea_query::{Expr, Iden, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::OffsetDateTime;
+
+use crate::ctx::Ctx;
+use crate::model::base::{self, PostgresDbBmc};
+use crate::model::department::{DepartmentBmc, DepartmentForCreate};
+use crate::model::modql_utils::time_to_sea_value;
+use crate::model::user::{UserBmc, UserForCreate};
+use crate::model::ModelManager;
+use crate::model::Result;
+
+/// The users `department_id` default, one per center.
+const NONE_DEPARTMENT_NAME: &str = "Ninguno";
+
+// region:    --- Center Types
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
+pub struct Center {
+    pub id: i64,
+    pub code: String,
+    pub name: String,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
+}
+
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
+pub struct CenterForCreate {
+    /// Login center, e.g., "ies-jerte".
+    pub code: String,
+    pub name: String,
+}
+
+#[derive(Deserialize, Clone, JsonSchema)]
+pub struct CenterForProvision {
+    #[serde(flatten)]
+    pub center: CenterForCreate,
+    pub admin_username: String,
+    pub admin_pwd: String,
+}
+
+/// The provisioned center and its first admin.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CenterProvisioned {
+    #[serde(flatten)]
+    pub center: Center,
+    pub admin_id: i64,
+}
+
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct CenterFilter {
+    id: Option<OpValsInt64>,
+    code: Option<OpValsString>,
+    name: Option<OpValsString>,
+
+    cid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    ctime: Option<OpValsValue>,
+    mid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    mtime: Option<OpValsValue>,
+}
+
+/// Marker trait
+pub trait CenterBy: HasFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
+
+impl CenterBy for Center {}
+
+#[derive(Iden)]
+enum CenterIden {
+    Code,
+}
+
+// endregion: --- Center Types
+
+pub struct CenterBmc;
+
+impl PostgresDbBmc for CenterBmc {
+    const TABLE: &'static str = "centers";
+}
+
+impl CenterBmc {
+    pub async fn create(ctx: &Ctx, mm: &ModelManager, center_c: CenterForCreate) -> Result<i64> {
+        base::create::<Self, _>(ctx, mm, center_c).await
+    }
+
+    /// New center, with its "Ninguno" department and first admin, all or nothing.
+    pub async fn provision(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        center_p: CenterForProvision,
+    ) -> Result<CenterProvisioned> {
+        let CenterForProvision {
+            center,
+            admin_username,
+            admin_pwd,
+        } = center_p;
+
+        let mm = mm.new_with_txn();
+        mm.begin_txn().await?;
+
+        let center_id = Self::create(ctx, &mm, center).await?;
+        let center_ctx = ctx.clone().with_center(center_id);
+
+        let department_c = DepartmentForCreate {
+            name: NONE_DEPARTMENT_NAME.to_string(),
+        };
+        let department_id = DepartmentBmc::create(&center_ctx, &mm, department_c).await?;
+
+        let user_c = UserForCreate {
+            username: admin_username,
+            is_admin: true,
+            pwd: String::new(),
+            active: true,
+            department_id: Some(department_id),
+            substituting_id: None,
+        };
+        let admin_id = UserBmc::create(&center_ctx, &mm, user_c).await?;
+        UserBmc::update_pwd(&center_ctx, &mm, admin_id, &admin_pwd).await?;
+
+        let center = Self::get(ctx, &mm, center_id).await?;
+        mm.commit_txn().await?;
+
+        Ok(CenterProvisioned { center, admin_id })
+    }
+
+    pub async fn get<E>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
+    where
+        E: CenterBy,
+    {
+        base::get::<Self, _>(ctx, mm, id).await
+    }
+
+    /// The login center.
+    pub async fn first_by_code<E>(_ctx: &Ctx, mm: &ModelManager, code: &str) -> Result<Option<E>>
+    where
+        E: CenterBy,
+    {
+        let dbx = mm.dbx();
+
+        // -- Build query
+        let mut query = Query::select();
+        query
+            .from(Self::table_ref())
+            .columns(E::field_idens())
+            .and_where(Expr::col(CenterIden::Code).eq(code));
+
+        // -- Exec query
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let center = dbx
+            .fetch_optional(sqlx::query_as_with::<_, E, _>(&sql, values))
+            .await?;
+
+        Ok(center)
+    }
+
+    pub async fn list(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<CenterFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<Center>> {
+        base::list::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serial_test::serial;
+
+    use crate::_dev_utils;
+    use crate::ctx::{Ctx, DEFAULT_CENTER_ID};
+    use crate::model::center::{Center, CenterBmc, CenterForCreate, CenterForProvision};
+    use crate::model::department::DepartmentBmc;
+    use crate::model::user::{User, UserBmc};
+    use crate::model::Error;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_provision_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_code = "test_provision_ok";
+        let fx_username = "admin";
+
+        // -- Exec
+        // Note: Rolled back when `txn_mm` is dropped.
+        let txn_mm = mm.new_with_txn();
+        txn_mm.begin_txn().await?;
+        let center_p = CenterForProvision {
+            center: CenterForCreate {
+                code: fx_code.to_string(),
+                name: "IES test_provision_ok".to_string(),
+            },
+            admin_username: fx_username.to_string(),
+            admin_pwd: "welcome".to_string(),
+        };
+        let provisioned = CenterBmc::provision(&ctx, &txn_mm, center_p).await?;
+
+        // -- Check
+        let center: Center = CenterBmc::first_by_code(&ctx, &txn_mm, fx_code)
+            .await?
+            .expect("Should have the center");
+        assert_eq!(center.id, provisioned.center.id);
+
+        // The same username as the default center admin.
+        let center_ctx = Ctx::root_ctx().with_center(center.id);
+        let admin: User = UserBmc::get(&center_ctx, &txn_mm, provisioned.admin_id).await?;
+        assert_eq!(admin.username, fx_username);
+        assert!(admin.is_admin);
+        let departments: Vec<String> = DepartmentBmc::list(&center_ctx, &txn_mm, None, None)
+            .await?
+            .into_iter()
+            .map(|department| department.name)
+            .collect();
+        assert_eq!(departments, ["Ninguno"]);
+
+        let default_ctx = Ctx::root_ctx().with_center(DEFAULT_CENTER_ID);
+        let res = UserBmc::get::<User>(&default_ctx, &txn_mm, provisioned.admin_id).await;
+        assert!(
+            matches!(
+                res,
+                Err(Error::EntityNotFound {
+                    entity: "users",
+                    ..
+                })
+            ),
+            "Should not see the other center user"
+        );
+
+        // -- Clean
+        drop(txn_mm);
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
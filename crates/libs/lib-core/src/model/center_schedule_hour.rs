@@ -1,10 +1,11 @@
 use modql::field::{Fields, HasFields};
 use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsValue};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use sqlx::postgres::PgRow;
 use sqlx::FromRow;
-use time::Time;
+use time::{OffsetDateTime, Time};
 
 use crate::ctx::Ctx;
 use crate::model::base::{self, PostgresDbBmc};
@@ -15,22 +16,29 @@ use crate::model::Result;
 // region:    --- CenterScheduleHour Types
 
 #[serde_as]
-#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
 pub struct CenterScheduleHour {
     pub id: i64,
     pub n_hour: i32,
+    #[schemars(with = "(u8, u8, u8, u32)")]
     pub start_time: Time,
+    #[schemars(with = "(u8, u8, u8, u32)")]
     pub end_time: Time,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
 }
 
-#[derive(Fields, Deserialize, Clone)]
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
 pub struct CenterScheduleHourForCreate {
     pub n_hour: i32,
+    #[schemars(with = "(u8, u8, u8, u32)")]
     pub start_time: Time,
+    #[schemars(with = "(u8, u8, u8, u32)")]
     pub end_time: Time,
 }
 
-#[derive(Fields, Deserialize, Clone)]
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
 pub struct CenterScheduleHourForCheck {
     pub n_hour: i32,
 }
@@ -53,21 +61,13 @@ pub struct CenterScheduleHourFilter {
     mtime: Option<OpValsValue>,
 }
 
-impl Default for CenterScheduleHourForUpdate {
-    fn default() -> Self {
-        Self {
-            n_hour: 0,                  // default value
-            start_time: Time::MIDNIGHT, /* provide a value */
-            end_time: Time::MIDNIGHT,   /* provide a value */
-        }
-    }
-}
-
-#[derive(Fields, Deserialize)]
+#[derive(Fields, Default, Deserialize, JsonSchema)]
 pub struct CenterScheduleHourForUpdate {
-    pub n_hour: i32,
-    pub start_time: Time,
-    pub end_time: Time,
+    pub n_hour: Option<i32>,
+    #[schemars(with = "Option<(u8, u8, u8, u32)>")]
+    pub start_time: Option<Time>,
+    #[schemars(with = "Option<(u8, u8, u8, u32)>")]
+    pub end_time: Option<Time>,
 }
 
 /// Marker trait
@@ -80,6 +80,7 @@ pub struct CenterScheduleHourBmc;
 
 impl PostgresDbBmc for CenterScheduleHourBmc {
     const TABLE: &'static str = "center_schedule_hours";
+    const CENTER_SCOPED: bool = true;
 }
 
 impl CenterScheduleHourBmc {
@@ -116,6 +117,16 @@ impl CenterScheduleHourBmc {
         base::update::<Self, _>(ctx, mm, id, schedule_hour_u).await
     }
 
+    pub async fn update_if_unmodified(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        schedule_hour_u: CenterScheduleHourForUpdate,
+        expected_mtime: Option<OffsetDateTime>,
+    ) -> Result<()> {
+        base::update_if_unmodified::<Self, _>(ctx, mm, id, schedule_hour_u, expected_mtime).await
+    }
+
     pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
         base::delete::<Self>(ctx, mm, id).await
     }
@@ -204,14 +215,9 @@ mod tests {
         let fx_end_time = fx_end_time.replace_hour(8)?;
         let fx_end_time = fx_end_time.replace_minute(50)?;
 
-        let fx_center_schedule_hour_id = _dev_utils::seed_center_schedule_hour(
-            &ctx,
-            &mm,
-            fx_n_hour,
-            fx_start_time,
-            fx_end_time,
-        )
-        .await?;
+        let fx_center_schedule_hour_id =
+            _dev_utils::seed_center_schedule_hour(&ctx, &mm, fx_n_hour, fx_start_time, fx_end_time)
+                .await?;
 
         // -- Exec
         CenterScheduleHourBmc::update(
@@ -219,9 +225,9 @@ mod tests {
             &mm,
             fx_center_schedule_hour_id,
             CenterScheduleHourForUpdate {
-                start_time: fx_start_time,
-                end_time: fx_end_time,
-                n_hour: fx_new_n_hour,
+                start_time: Some(fx_start_time),
+                end_time: Some(fx_end_time),
+                n_hour: Some(fx_new_n_hour),
             },
         )
         .await?;
@@ -282,8 +288,8 @@ mod tests {
         // -- Check
         let schedule_hours: Vec<i32> = schedule_hours.into_iter().map(|csh| csh.n_hour).collect();
 
-        assert_eq!(&schedule_hours[schedule_hours.len()-1], &fx_n_hours[1]);
-        assert_eq!(&schedule_hours[schedule_hours.len()-2], &fx_n_hours[0]);
+        assert_eq!(&schedule_hours[schedule_hours.len() - 1], &fx_n_hours[1]);
+        assert_eq!(&schedule_hours[schedule_hours.len() - 2], &fx_n_hours[0]);
 
         // -- Cleanup
         CenterScheduleHourBmc::delete(&ctx, &mm, fx_center_schedule_hour_id_01).await?;
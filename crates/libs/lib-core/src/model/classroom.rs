@@ -1,21 +1,23 @@
 use modql::field::{Fields, HasFields};
 use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsString, OpValsValue};
+use schemars::JsonSchema;
 use sea_query::{Expr, Iden, PostgresQueryBuilder, Query};
 use sea_query_binder::SqlxBinder;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use sqlx::FromRow;
 use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::OffsetDateTime;
 
 use crate::ctx::Ctx;
 use crate::model::base::{self, PostgresDbBmc};
-use crate::model::ModelManager;
 use crate::model::modql_utils::time_to_sea_value;
+use crate::model::ModelManager;
 use crate::model::Result;
 
 // region:    --- Classroom Types
 #[serde_as]
-#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
 pub struct Classroom {
     pub id: i64,
     pub building: i64,
@@ -24,9 +26,12 @@ pub struct Classroom {
     pub name: String,
     pub type_c: i64,
     pub description: String,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
 }
 
-#[derive(Fields, Deserialize, Clone)]
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
 pub struct ClassroomForCreate {
     pub building: i64,
     pub floor: i32,
@@ -53,14 +58,14 @@ pub struct ClassroomFilter {
     mtime: Option<OpValsValue>,
 }
 
-#[derive(Fields, Default, Deserialize)]
+#[derive(Fields, Default, Deserialize, JsonSchema)]
 pub struct ClassroomForUpdate {
-    pub building: i64,
-    pub floor: i32,
-    pub number: i32,
-    pub name: String,
-    pub type_c: i64,
-    pub description: String,
+    pub building: Option<i64>,
+    pub floor: Option<i32>,
+    pub number: Option<i32>,
+    pub name: Option<String>,
+    pub type_c: Option<i64>,
+    pub description: Option<String>,
 }
 
 /// Marker trait
@@ -74,17 +79,22 @@ enum ClassroomIden {
     TypeC,
 }
 
-
 // endregion: --- Classroom Types
 
 pub struct ClassroomBmc;
 
 impl PostgresDbBmc for ClassroomBmc {
     const TABLE: &'static str = "classrooms";
+    const SOFT_DELETE: bool = true;
+    const CENTER_SCOPED: bool = true;
 }
 
 impl ClassroomBmc {
-    pub async fn create(ctx: &Ctx, mm: &ModelManager, classroom_c: ClassroomForCreate) -> Result<i64> {
+    pub async fn create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        classroom_c: ClassroomForCreate,
+    ) -> Result<i64> {
         base::create::<Self, _>(ctx, mm, classroom_c).await
     }
 
@@ -104,6 +114,14 @@ impl ClassroomBmc {
         base::list::<Self, _, _>(ctx, mm, filters, list_options).await
     }
 
+    pub async fn list_including_deleted(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<ClassroomFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<Classroom>> {
+        base::list_including_deleted::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
 
     pub async fn update(
         ctx: &Ctx,
@@ -114,16 +132,30 @@ impl ClassroomBmc {
         base::update::<Self, _>(ctx, mm, id, classroom_u).await
     }
 
+    pub async fn update_if_unmodified(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        classroom_u: ClassroomForUpdate,
+        expected_mtime: Option<OffsetDateTime>,
+    ) -> Result<()> {
+        base::update_if_unmodified::<Self, _>(ctx, mm, id, classroom_u, expected_mtime).await
+    }
+
     pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
         base::delete::<Self>(ctx, mm, id).await
     }
 
+    pub async fn restore(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        base::restore::<Self>(ctx, mm, id).await
+    }
+
     pub async fn count_classrooms_by_classroom_type(
         _ctx: &Ctx,
         mm: &ModelManager,
         classroom_type_id: i64,
     ) -> Result<i64> {
-        let db = mm.postgres_db();
+        let dbx = mm.dbx();
 
         // -- Build query
         let mut query = Query::select();
@@ -134,7 +166,7 @@ impl ClassroomBmc {
 
         // -- Exec query
         let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-        let result: (i64,) = sqlx::query_as_with(&sql, values).fetch_one(db).await?;
+        let result: (i64,) = dbx.fetch_one(sqlx::query_as_with(&sql, values)).await?;
 
         Ok(result.0)
     }
@@ -150,8 +182,20 @@ mod tests {
     use crate::_dev_utils;
     use crate::ctx::Ctx;
     use crate::model::building::BuildingBmc;
-    use crate::model::classroom::{Classroom, ClassroomBmc, ClassroomForCreate, ClassroomForUpdate};
+    use crate::model::classroom::{
+        Classroom, ClassroomBmc, ClassroomForCreate, ClassroomForUpdate,
+    };
     use crate::model::classroom_type::ClassroomTypeBmc;
+    use crate::model::ModelManager;
+
+    /// The classrooms are soft deleted, so their building and type can only
+    /// be cleaned after purging them.
+    async fn purge_classroom(mm: &ModelManager, id: i64) -> Result<()> {
+        mm.dbx()
+            .execute(sqlx::query("DELETE FROM classrooms WHERE id = $1").bind(id))
+            .await?;
+        Ok(())
+    }
 
     #[serial]
     #[tokio::test]
@@ -159,7 +203,7 @@ mod tests {
         // -- Setup & Fixtures
         let mm = _dev_utils::init_test().await;
         let ctx = Ctx::root_ctx();
-        
+
         let fx_building_name = "Principal_test_create_ok";
         let fx_building_id = _dev_utils::seed_building(&ctx, &mm, fx_building_name).await?;
         let fx_name = "Info_create_ok";
@@ -168,7 +212,7 @@ mod tests {
         let fx_number = 19;
         let fx_type_name = "Aula de informática_test_create_ok";
         let fx_type = _dev_utils::seed_classroom_type(&ctx, &mm, fx_type_name).await?;
-        
+
         // -- Exec
         let classroom_c: ClassroomForCreate = ClassroomForCreate {
             building: fx_building_id,
@@ -192,12 +236,13 @@ mod tests {
 
         // -- Clean
         ClassroomBmc::delete(&ctx, &mm, id).await?;
+        purge_classroom(&mm, id).await?;
         ClassroomTypeBmc::delete(&ctx, &mm, fx_type).await?;
         BuildingBmc::delete(&ctx, &mm, fx_building_id).await?;
-        
+
         Ok(())
     }
-    
+
     #[serial]
     #[tokio::test]
     async fn test_update_ok() -> Result<()> {
@@ -221,8 +266,18 @@ mod tests {
         let fx_number_new = 29;
         let fx_type_name_new = "Aula de informática_test_update_ok_new";
         let fx_type_new = _dev_utils::seed_classroom_type(&ctx, &mm, fx_type_name_new).await?;
-        
-        let fx_classroom_id = _dev_utils::seed_classroom(&ctx, &mm, fx_building_id, fx_floor, fx_number, fx_name, fx_type, fx_description).await?;
+
+        let fx_classroom_id = _dev_utils::seed_classroom(
+            &ctx,
+            &mm,
+            fx_building_id,
+            fx_floor,
+            fx_number,
+            fx_name,
+            fx_type,
+            fx_description,
+        )
+        .await?;
 
         // -- Exec
         ClassroomBmc::update(
@@ -230,15 +285,15 @@ mod tests {
             &mm,
             fx_classroom_id,
             ClassroomForUpdate {
-                building: fx_building_id_new,
-                floor: fx_floor_new,
-                number: fx_number_new,
-                name: fx_name_new.to_string(),
-                type_c: fx_type_new,
-                description: fx_description_new.to_string(),
+                building: Some(fx_building_id_new),
+                floor: Some(fx_floor_new),
+                number: Some(fx_number_new),
+                name: Some(fx_name_new.to_string()),
+                type_c: Some(fx_type_new),
+                description: Some(fx_description_new.to_string()),
             },
         )
-            .await?;
+        .await?;
 
         // -- Check
         let classroom: Classroom = ClassroomBmc::get(&ctx, &mm, fx_classroom_id).await?;
@@ -251,6 +306,7 @@ mod tests {
 
         // -- Clean
         ClassroomBmc::delete(&ctx, &mm, fx_classroom_id).await?;
+        purge_classroom(&mm, fx_classroom_id).await?;
         ClassroomTypeBmc::delete(&ctx, &mm, fx_type).await?;
         ClassroomTypeBmc::delete(&ctx, &mm, fx_type_new).await?;
         BuildingBmc::delete(&ctx, &mm, fx_building_id).await?;
@@ -272,7 +328,7 @@ mod tests {
         let fx_floor = 1;
         let fx_number = 19;
         let fx_type_name = "Aula de informática_test_list_by_name_ok";
-        let fx_type = _dev_utils::seed_classroom_type(&ctx, &mm, &fx_type_name).await?;
+        let fx_type = _dev_utils::seed_classroom_type(&ctx, &mm, fx_type_name).await?;
 
         let fx_building_name2 = "secundario";
         let fx_building_id2 = _dev_utils::seed_building(&ctx, &mm, fx_building_name2).await?;
@@ -281,10 +337,30 @@ mod tests {
         let fx_floor_new = 2;
         let fx_number_new = 29;
         let fx_type_name2 = "Aula de informática_test_list_by_name_ok2";
-        let fx_type2 = _dev_utils::seed_classroom_type(&ctx, &mm, &fx_type_name2).await?;
+        let fx_type2 = _dev_utils::seed_classroom_type(&ctx, &mm, fx_type_name2).await?;
 
-        let fx_classroom_id_01 = _dev_utils::seed_classroom(&ctx, &mm, fx_building_id, fx_floor, fx_number, fx_name, fx_type, fx_description).await?;
-        let fx_classroom_id_02 = _dev_utils::seed_classroom(&ctx, &mm, fx_building_id2, fx_floor_new, fx_number_new, fx_name_new, fx_type2, fx_description_new).await?;
+        let fx_classroom_id_01 = _dev_utils::seed_classroom(
+            &ctx,
+            &mm,
+            fx_building_id,
+            fx_floor,
+            fx_number,
+            fx_name,
+            fx_type,
+            fx_description,
+        )
+        .await?;
+        let fx_classroom_id_02 = _dev_utils::seed_classroom(
+            &ctx,
+            &mm,
+            fx_building_id2,
+            fx_floor_new,
+            fx_number_new,
+            fx_name_new,
+            fx_type2,
+            fx_description_new,
+        )
+        .await?;
 
         // -- Exec
         let filter_json = json!({
@@ -295,7 +371,8 @@ mod tests {
         let classrooms = ClassroomBmc::list(&ctx, &mm, Some(filter), None).await?;
 
         // -- Check
-        let classrooms: Vec<String> = classrooms.into_iter()
+        let classrooms: Vec<String> = classrooms
+            .into_iter()
             // .filter(|s| s.letter.starts_with("test_"))
             .map(|s| s.name.clone())
             .collect();
@@ -304,7 +381,9 @@ mod tests {
 
         // -- Cleanup
         ClassroomBmc::delete(&ctx, &mm, fx_classroom_id_01).await?;
+        purge_classroom(&mm, fx_classroom_id_01).await?;
         ClassroomBmc::delete(&ctx, &mm, fx_classroom_id_02).await?;
+        purge_classroom(&mm, fx_classroom_id_02).await?;
         ClassroomTypeBmc::delete(&ctx, &mm, fx_type).await?;
         ClassroomTypeBmc::delete(&ctx, &mm, fx_type2).await?;
         BuildingBmc::delete(&ctx, &mm, fx_building_id).await?;
@@ -331,8 +410,28 @@ mod tests {
         let fx_type_name = "Aula de informática_test_count_classrooms_by_classroom_type_classroom";
         let fx_type_1 = _dev_utils::seed_classroom_type(&ctx, &mm, fx_type_name).await?;
 
-        let fx_classroom_id_01 = _dev_utils::seed_classroom(&ctx, &mm, fx_building_id, fx_floor, fx_number, fx_name, fx_type_1, fx_description).await?;
-        let fx_classroom_id_02 = _dev_utils::seed_classroom(&ctx, &mm, fx_building_id, fx_floor2, fx_number, fx_name2, fx_type_1, fx_description).await?;
+        let fx_classroom_id_01 = _dev_utils::seed_classroom(
+            &ctx,
+            &mm,
+            fx_building_id,
+            fx_floor,
+            fx_number,
+            fx_name,
+            fx_type_1,
+            fx_description,
+        )
+        .await?;
+        let fx_classroom_id_02 = _dev_utils::seed_classroom(
+            &ctx,
+            &mm,
+            fx_building_id,
+            fx_floor2,
+            fx_number,
+            fx_name2,
+            fx_type_1,
+            fx_description,
+        )
+        .await?;
 
         // -- Exec
         let count = ClassroomBmc::count_classrooms_by_classroom_type(&ctx, &mm, fx_type_1).await?;
@@ -342,10 +441,12 @@ mod tests {
 
         // -- Clean
         ClassroomBmc::delete(&ctx, &mm, fx_classroom_id_01).await?;
+        purge_classroom(&mm, fx_classroom_id_01).await?;
         ClassroomBmc::delete(&ctx, &mm, fx_classroom_id_02).await?;
+        purge_classroom(&mm, fx_classroom_id_02).await?;
         ClassroomTypeBmc::delete(&ctx, &mm, fx_type_1).await?;
         BuildingBmc::delete(&ctx, &mm, fx_building_id).await?;
-        
+
         Ok(())
     }
 }
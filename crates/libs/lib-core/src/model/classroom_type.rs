@@ -1,25 +1,30 @@
 use modql::field::{Fields, HasFields};
 use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsString, OpValsValue};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use sqlx::FromRow;
 use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::OffsetDateTime;
 
 use crate::ctx::Ctx;
 use crate::model::base::{self, PostgresDbBmc};
-use crate::model::ModelManager;
 use crate::model::modql_utils::time_to_sea_value;
+use crate::model::ModelManager;
 use crate::model::Result;
 
 // region:    --- ClassroomType Types
 #[serde_as]
-#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
 pub struct ClassroomType {
     pub id: i64,
     pub type_name: String,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
 }
 
-#[derive(Fields, Deserialize, Clone)]
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
 pub struct ClassroomTypeForCreate {
     pub type_name: String,
 }
@@ -35,9 +40,9 @@ pub struct ClassroomTypeFilter {
     mtime: Option<OpValsValue>,
 }
 
-#[derive(Fields, Default, Deserialize)]
+#[derive(Fields, Default, Deserialize, JsonSchema)]
 pub struct ClassroomTypeForUpdate {
-    pub type_name: String,
+    pub type_name: Option<String>,
 }
 
 /// Marker trait
@@ -51,10 +56,15 @@ pub struct ClassroomTypeBmc;
 
 impl PostgresDbBmc for ClassroomTypeBmc {
     const TABLE: &'static str = "classroom_types";
+    const CENTER_SCOPED: bool = true;
 }
 
 impl ClassroomTypeBmc {
-    pub async fn create(ctx: &Ctx, mm: &ModelManager, classroom_type_c: ClassroomTypeForCreate) -> Result<i64> {
+    pub async fn create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        classroom_type_c: ClassroomTypeForCreate,
+    ) -> Result<i64> {
         base::create::<Self, _>(ctx, mm, classroom_type_c).await
     }
 
@@ -83,6 +93,16 @@ impl ClassroomTypeBmc {
         base::update::<Self, _>(ctx, mm, id, classroom_type_u).await
     }
 
+    pub async fn update_if_unmodified(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        classroom_type_u: ClassroomTypeForUpdate,
+        expected_mtime: Option<OffsetDateTime>,
+    ) -> Result<()> {
+        base::update_if_unmodified::<Self, _>(ctx, mm, id, classroom_type_u, expected_mtime).await
+    }
+
     pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
         base::delete::<Self>(ctx, mm, id).await
     }
@@ -97,7 +117,9 @@ mod tests {
 
     use crate::_dev_utils;
     use crate::ctx::Ctx;
-    use crate::model::classroom_type::{ClassroomType, ClassroomTypeBmc, ClassroomTypeForCreate, ClassroomTypeForUpdate};
+    use crate::model::classroom_type::{
+        ClassroomType, ClassroomTypeBmc, ClassroomTypeForCreate, ClassroomTypeForUpdate,
+    };
 
     #[serial]
     #[tokio::test]
@@ -105,9 +127,9 @@ mod tests {
         // -- Setup & Fixtures
         let mm = _dev_utils::init_test().await;
         let ctx = Ctx::root_ctx();
-        
+
         let fx_type_name = "Laboratory";
-        
+
         // -- Exec
         let classroom_type_c = ClassroomTypeForCreate {
             type_name: fx_type_name.to_string(),
@@ -121,10 +143,10 @@ mod tests {
 
         // -- Clean
         ClassroomTypeBmc::delete(&ctx, &mm, id).await?;
-        
+
         Ok(())
     }
-    
+
     #[serial]
     #[tokio::test]
     async fn test_update_ok() -> Result<()> {
@@ -134,7 +156,7 @@ mod tests {
         let fx_type_name = "Laboratory";
 
         let fx_type_name_new = "Lecture Hall";
-        
+
         let fx_classroom_type_id = _dev_utils::seed_classroom_type(&ctx, &mm, fx_type_name).await?;
 
         // -- Exec
@@ -143,18 +165,19 @@ mod tests {
             &mm,
             fx_classroom_type_id,
             ClassroomTypeForUpdate {
-                type_name: fx_type_name_new.to_string(),
+                type_name: Some(fx_type_name_new.to_string()),
             },
         )
-            .await?;
+        .await?;
 
         // -- Check
-        let classroom_type: ClassroomType = ClassroomTypeBmc::get(&ctx, &mm, fx_classroom_type_id).await?;
+        let classroom_type: ClassroomType =
+            ClassroomTypeBmc::get(&ctx, &mm, fx_classroom_type_id).await?;
         assert_eq!(classroom_type.type_name, fx_type_name_new);
 
         // -- Clean
         ClassroomTypeBmc::delete(&ctx, &mm, fx_classroom_type_id).await?;
-        
+
         Ok(())
     }
 
@@ -167,28 +190,29 @@ mod tests {
         let fx_type_name = "Laboratory";
         let fx_type_name_new = "Lecture Hall";
 
-        let fx_classroom_type_id_01 = _dev_utils::seed_classroom_type(&ctx, &mm, fx_type_name).await?;
-        let fx_classroom_type_id_02 = _dev_utils::seed_classroom_type(&ctx, &mm, fx_type_name_new).await?;
+        let fx_classroom_type_id_01 =
+            _dev_utils::seed_classroom_type(&ctx, &mm, fx_type_name).await?;
+        let fx_classroom_type_id_02 =
+            _dev_utils::seed_classroom_type(&ctx, &mm, fx_type_name_new).await?;
 
         // -- Exec
         let filter_json = json!({
-            "type_name": {"$contains": "Lab"}, // partial match example
+            "type_name": {"$contains": "ratory"}, // partial match example (not the seed "Laboratorio")
         });
         let filter = vec![serde_json::from_value(filter_json)?];
 
         let classroom_types = ClassroomTypeBmc::list(&ctx, &mm, Some(filter), None).await?;
 
         // -- Check
-        let classroom_types: Vec<String> = classroom_types.into_iter()
-            .map(|s| s.type_name)
-            .collect();
+        let classroom_types: Vec<String> =
+            classroom_types.into_iter().map(|s| s.type_name).collect();
         assert_eq!(classroom_types.len(), 1);
         assert_eq!(classroom_types[0], fx_type_name);
 
         // -- Cleanup
         ClassroomTypeBmc::delete(&ctx, &mm, fx_classroom_type_id_01).await?;
         ClassroomTypeBmc::delete(&ctx, &mm, fx_classroom_type_id_02).await?;
-        
+
         Ok(())
     }
 }
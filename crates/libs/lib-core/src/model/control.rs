@@ -1,5 +1,11 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
 use modql::field::{Fields, HasFields};
-use modql::filter::{OpValInt32, OpValInt64, OpValString, OpValsInt32, OpValsInt64, OpValsString};
+use modql::filter::{
+    OpValInt32, OpValInt64, OpValString, OpValValue, OpValsInt32, OpValsInt64, OpValsString,
+    OpValsValue,
+};
 use sea_query::Iden;
 use serde::Serialize;
 use serde_with::serde_as;
@@ -7,14 +13,22 @@ use sqlx::postgres::PgRow;
 use sqlx::FromRow;
 use time::{OffsetDateTime, Time};
 
+use crate::core_config;
 use crate::ctx::Ctx;
 use crate::model::base::PostgresDbBmc;
 use crate::model::center_schedule_hour::CenterScheduleHourBmc;
+use crate::model::event::{event_center_id, ModelEvent};
+use crate::model::lesson_plan::LessonPlanBmc;
+use crate::model::notification::{Notification, NotificationBmc};
 use crate::model::schedule::{Schedule, ScheduleBmc, ScheduleFilter};
 use crate::model::schedule_hour::{
     ScheduleHour, ScheduleHourBmc, ScheduleHourFilter, ScheduleHourForUpdate,
 };
-use crate::model::user::UserBmc;
+use crate::model::subject::SubjectBmc;
+use crate::model::substitution::{
+    SubstitutionBmc, SubstitutionFilter, SubstitutionForRecord, STATUS_UNCOVERED,
+};
+use crate::model::user::{User, UserBmc};
 use crate::model::ModelManager;
 use crate::model::Result;
 
@@ -30,6 +44,108 @@ pub trait ControlBy: HasFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
 
 impl ControlBy for Control {}
 
+/// Today's guard assignments and absent teachers, for the staff-room display
+/// (see `ControlBmc::display_board`).
+///
+/// Note: Shown without login, so only non-sensitive fields.
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplayBoard {
+    pub week_day: i32,
+    pub guards: Vec<DisplayGuard>,
+    pub absent_teachers: Vec<DisplayTeacher>,
+    /// The classes left without cover (see `EscalationStep`).
+    pub uncovered: Vec<DisplayUncovered>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplayGuard {
+    pub n_hour: i32,
+    pub teacher: String,
+    pub classroom_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplayTeacher {
+    pub username: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplayUncovered {
+    pub n_hour: i32,
+    pub classroom_name: String,
+}
+
+/// What `update_guards` does, in order, with the absent teacher classes left
+/// once the free `Guardia` hours run out (`SERVICE_GUARD_ESCALATION`).
+///
+/// The classes still uncovered are recorded (see `SubstitutionBmc`) and
+/// published as `ModelEvent::ClassUncovered` (e.g., for the display screens).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscalationStep {
+    /// The teachers in center on a complementary subject hour (`is_complementary`) cover them.
+    Complementary,
+    /// The heads of studies (`is_head_of_studies`) are notified of the ones left.
+    HeadOfStudies,
+}
+
+impl EscalationStep {
+    pub const DEFAULT_CHAIN: [EscalationStep; 2] =
+        [EscalationStep::Complementary, EscalationStep::HeadOfStudies];
+
+    /// e.g., "complementary,head_of_studies", empty for no escalation.
+    pub fn parse_chain(steps: &str) -> core::result::Result<Vec<Self>, String> {
+        steps
+            .split(',')
+            .map(str::trim)
+            .filter(|step| !step.is_empty())
+            .map(EscalationStep::from_str)
+            .collect()
+    }
+}
+
+/// Where an absent teacher class ends up, see `escalate`.
+#[derive(Debug, PartialEq, Eq)]
+enum Escalation<T> {
+    /// By the given cover hour.
+    Covered(T),
+    /// Left uncovered, the heads of studies are notified.
+    HeadsOfStudies,
+    /// Left uncovered, no step of the chain applied.
+    Uncovered,
+}
+
+/// Runs the `chain` steps in order for a class the free `Guardia` hours did not cover,
+/// up to the first one that applies.
+fn escalate<T>(
+    chain: &[EscalationStep],
+    complementary_hours: &mut impl Iterator<Item = T>,
+) -> Escalation<T> {
+    for step in chain {
+        match step {
+            EscalationStep::Complementary => {
+                if let Some(complementary_hour) = complementary_hours.next() {
+                    return Escalation::Covered(complementary_hour);
+                }
+            }
+            EscalationStep::HeadOfStudies => return Escalation::HeadsOfStudies,
+        }
+    }
+
+    Escalation::Uncovered
+}
+
+impl FromStr for EscalationStep {
+    type Err = String;
+
+    fn from_str(step: &str) -> core::result::Result<Self, Self::Err> {
+        match step {
+            "complementary" => Ok(EscalationStep::Complementary),
+            "head_of_studies" => Ok(EscalationStep::HeadOfStudies),
+            other => Err(other.to_string()),
+        }
+    }
+}
+
 // #[derive(Iden)]
 // enum ControlIden {
 //     Id,
@@ -39,6 +155,11 @@ impl ControlBy for Control {}
 
 // endregion: --- Control Types
 
+/// `notes` of a guard hour assigned by `update_guards`, followed by the classroom name.
+const GUARD_NOTE_PREFIX: &str = "Sustitución en ";
+/// `notes` of an absent teacher hour covered by a guard.
+const COVERED_NOTE: &str = "Cubierta";
+
 pub struct ControlBmc;
 
 impl PostgresDbBmc for ControlBmc {
@@ -54,25 +175,35 @@ impl Iden for CustomIden {
 }
 
 impl ControlBmc {
+    /// Covers the current hour classes of the absent teachers, with the free `Guardia` hours
+    /// then the `SERVICE_GUARD_ESCALATION` steps.
+    ///
+    /// All or nothing, the guards, substitutions and notifications are written in one
+    /// transaction (the events are sent on commit, see `ModelManager::publish`).
     pub async fn update_guards(ctx: &Ctx, mm: &ModelManager) -> Result<()> {
+        // Note: Dropping `mm` without commit rolls the transaction back.
+        let mm = &mm.new_with_txn();
+        mm.begin_txn().await?;
+
+        let escalation = &core_config().GUARD_ESCALATION;
         let now: Time = get_current_time(true, 12, 35);
         let current_week_day: i32 = OffsetDateTime::now_utc().weekday() as i32;
         let course = OffsetDateTime::now_utc().year();
 
-        let center_schedule_hours = CenterScheduleHourBmc::list(&ctx, &mm, None, None).await?;
+        let center_schedule_hours = CenterScheduleHourBmc::list(ctx, mm, None, None).await?;
         let current_n_hour = center_schedule_hours
             .iter()
             .find(|schedule_hour| now >= schedule_hour.start_time && now <= schedule_hour.end_time)
             .map_or(0, |schedule_hour| schedule_hour.n_hour);
 
-        let users = UserBmc::list(&ctx, &mm, None, None).await?;
+        let users = UserBmc::list(ctx, mm, None, None).await?;
         let not_in_center_users: Vec<_> = users.iter().filter(|user| !user.in_center).collect();
         let not_in_center_user_ids: Vec<_> =
             not_in_center_users.iter().map(|user| user.id).collect();
 
         let schedules = ScheduleBmc::list(
-            &ctx,
-            &mm,
+            ctx,
+            mm,
             current_schedule_filters(not_in_center_user_ids, course as i64),
             None,
         )
@@ -81,25 +212,49 @@ impl ControlBmc {
             schedules.iter().map(|schedule| schedule.id).collect();
 
         let current_schedule_hours = ScheduleHourBmc::list(
-            &ctx,
-            &mm,
+            ctx,
+            mm,
             current_hour_filters(current_week_day, course, current_n_hour),
             None,
         )
         .await?;
-        let mut current_guard_hours = ScheduleHourBmc::list(
-            &ctx,
-            &mm,
+        let current_guard_hours = ScheduleHourBmc::list(
+            ctx,
+            mm,
             guard_filters(
-                not_in_center_user_schedules_ids,
+                not_in_center_user_schedules_ids.clone(),
                 current_week_day,
                 course,
                 current_n_hour,
             ),
             None,
         )
-        .await?
-        .into_iter();
+        .await?;
+        let complementary_hours = if escalation.contains(&EscalationStep::Complementary) {
+            let complementary_subject_names: Vec<String> = SubjectBmc::list(ctx, mm, None, None)
+                .await?
+                .into_iter()
+                .filter(|subject| subject.is_complementary)
+                .map(|subject| subject.name)
+                .collect();
+            ScheduleHourBmc::list(
+                ctx,
+                mm,
+                complementary_filters(
+                    not_in_center_user_schedules_ids,
+                    complementary_subject_names,
+                    current_week_day,
+                    course,
+                    current_n_hour,
+                ),
+                None,
+            )
+            .await?
+        } else {
+            Vec::new()
+        };
+        let mut guard_hours = current_guard_hours.into_iter();
+        let mut complementary_hours = complementary_hours.into_iter();
 
         let not_in_center_users_ids: Vec<i64> =
             not_in_center_users.iter().map(|user| user.id).collect();
@@ -124,22 +279,202 @@ impl ControlBmc {
                 non_cover_schedule_ids.contains(&schedule_hour.schedule_id)
                     && schedule_hour.subject_name != "Libre"
                     && schedule_hour.subject_name != "Guardia"
-                    && schedule_hour.notes != Some("Cubierta".to_string())
+                    && schedule_hour.notes.as_deref() != Some(COVERED_NOTE)
             })
             .collect();
 
+        let today = OffsetDateTime::now_utc().date();
+        let absent_id_by_schedule_id: HashMap<i64, i64> = non_cover_schedules
+            .iter()
+            .filter_map(|schedule| Some((schedule.id, schedule.user_id?)))
+            .collect();
+
+        // The uncovered classes, and whether the heads of studies are notified.
+        let mut uncovered_hours: Vec<(&ScheduleHour, bool)> = Vec::new();
         for schedule_hour in relevant_schedule_hours {
-            if let Some(guard_hour) = current_guard_hours.next() {
-                let notes = Some(format!("Sustitución en {}", schedule_hour.classroom_name));
-                let notes2 = Some("Cubierta".to_string());
+            let mut substitution_r = absent_id_by_schedule_id
+                .get(&schedule_hour.schedule_id)
+                .map(|absent_id| SubstitutionForRecord {
+                    date: today,
+                    course,
+                    week_day: current_week_day,
+                    n_hour: current_n_hour,
+                    absent_id: *absent_id,
+                    covering_id: None,
+                    classroom_name: schedule_hour.classroom_name.clone(),
+                });
+
+            let cover = match guard_hours.next() {
+                Some(guard_hour) => Escalation::Covered(guard_hour),
+                None => escalate(escalation, &mut complementary_hours),
+            };
+            if let Escalation::Covered(guard_hour) = &cover {
+                let notes = Some(format!(
+                    "{GUARD_NOTE_PREFIX}{}",
+                    schedule_hour.classroom_name
+                ));
+                let notes2 = Some(COVERED_NOTE.to_string());
+
+                update_schedule_hour(ctx, mm, guard_hour, notes).await?;
+                update_schedule_hour(ctx, mm, schedule_hour, notes2).await?;
+
+                mm.publish(ModelEvent::GuardAssigned {
+                    center_id: event_center_id(ctx),
+                    guard_schedule_hour_id: guard_hour.id,
+                    guard_schedule_id: guard_hour.schedule_id,
+                    covered_schedule_id: schedule_hour.schedule_id,
+                    covered_schedule_hour_id: schedule_hour.id,
+                    classroom_name: schedule_hour.classroom_name.clone(),
+                    week_day: current_week_day,
+                    n_hour: current_n_hour,
+                })
+                .await;
 
-                update_schedule_hour(&ctx, &mm, &guard_hour, notes).await?;
-                update_schedule_hour(&ctx, &mm, schedule_hour, notes2).await?;
+                let guard_schedule: Schedule =
+                    ScheduleBmc::get(ctx, mm, guard_hour.schedule_id).await?;
+                if let Some(user_id) = guard_schedule.user_id {
+                    let notification = Notification::GuardAssigned {
+                        classroom_name: schedule_hour.classroom_name.clone(),
+                        week_day: current_week_day,
+                        n_hour: current_n_hour,
+                        lesson_plan: LessonPlanBmc::note(ctx, mm, schedule_hour.id, today).await?,
+                    };
+                    NotificationBmc::enqueue(ctx, mm, user_id, &notification).await?;
+                }
+                if let Some(substitution_r) = substitution_r.as_mut() {
+                    substitution_r.covering_id = guard_schedule.user_id;
+                }
+            }
+
+            if let Some(substitution_r) = substitution_r {
+                // Note: Only alerted once, the uncovered class is seen again on the next runs.
+                let recorded = SubstitutionBmc::record(ctx, mm, substitution_r).await?;
+                if recorded {
+                    match cover {
+                        Escalation::Covered(_) => {}
+                        Escalation::HeadsOfStudies => uncovered_hours.push((schedule_hour, true)),
+                        Escalation::Uncovered => uncovered_hours.push((schedule_hour, false)),
+                    }
+                }
             }
         }
 
+        if !uncovered_hours.is_empty() {
+            let heads_of_studies: Vec<&User> = users
+                .iter()
+                .filter(|user| user.is_head_of_studies && user.active)
+                .collect();
+            alert_uncovered(
+                ctx,
+                mm,
+                &uncovered_hours,
+                &heads_of_studies,
+                current_week_day,
+                current_n_hour,
+            )
+            .await?;
+        }
+
+        mm.commit_txn().await?;
+
         Ok(())
     }
+
+    /// Today's board, from the guard hours assigned by `update_guards`,
+    /// the teachers not in center with lessons today and the uncovered classes.
+    pub async fn display_board(ctx: &Ctx, mm: &ModelManager) -> Result<DisplayBoard> {
+        let week_day: i32 = OffsetDateTime::now_utc().weekday() as i32;
+        let course = OffsetDateTime::now_utc().year();
+
+        let users = UserBmc::list(ctx, mm, None, None).await?;
+        let schedules =
+            ScheduleBmc::list(ctx, mm, course_schedule_filters(course as i64), None).await?;
+        let user_id_by_schedule_id: HashMap<i64, i64> = schedules
+            .iter()
+            .filter_map(|schedule| Some((schedule.id, schedule.user_id?)))
+            .collect();
+        let username_by_user_id: HashMap<i64, &str> = users
+            .iter()
+            .map(|user| (user.id, user.username.as_str()))
+            .collect();
+
+        // -- Guards
+        let guard_hours =
+            ScheduleHourBmc::list(ctx, mm, assigned_guard_filters(week_day, course), None).await?;
+        let mut guards: Vec<DisplayGuard> = guard_hours
+            .iter()
+            .filter_map(|guard_hour| {
+                let classroom_name = guard_hour
+                    .notes
+                    .as_deref()?
+                    .strip_prefix(GUARD_NOTE_PREFIX)?;
+                let user_id = user_id_by_schedule_id.get(&guard_hour.schedule_id)?;
+                Some(DisplayGuard {
+                    n_hour: guard_hour.n_hour,
+                    teacher: username_by_user_id.get(user_id)?.to_string(),
+                    classroom_name: classroom_name.to_string(),
+                })
+            })
+            .collect();
+        guards.sort_by_key(|guard| guard.n_hour);
+
+        // -- Absent teachers
+        let not_in_center_schedule_ids: Vec<i64> = schedules
+            .iter()
+            .filter(|schedule| {
+                users
+                    .iter()
+                    .any(|user| Some(user.id) == schedule.user_id && user.active && !user.in_center)
+            })
+            .map(|schedule| schedule.id)
+            .collect();
+        let lesson_hours = ScheduleHourBmc::list(
+            ctx,
+            mm,
+            lesson_filters(not_in_center_schedule_ids, week_day, course),
+            None,
+        )
+        .await?;
+        let absent_teachers = users
+            .iter()
+            .filter(|user| {
+                lesson_hours.iter().any(|lesson_hour| {
+                    user_id_by_schedule_id.get(&lesson_hour.schedule_id) == Some(&user.id)
+                })
+            })
+            .map(|user| DisplayTeacher {
+                username: user.username.clone(),
+            })
+            .collect();
+
+        // -- Uncovered classes
+        let uncovered_filters = vec![SubstitutionFilter {
+            date: Some(OpValsValue(vec![OpValValue::Eq(
+                serde_json::Value::String(OffsetDateTime::now_utc().date().to_string()),
+            )])),
+            status: Some(OpValsString(vec![OpValString::Eq(
+                STATUS_UNCOVERED.to_string(),
+            )])),
+            ..Default::default()
+        }];
+        let mut uncovered: Vec<DisplayUncovered> =
+            SubstitutionBmc::list(ctx, mm, Some(uncovered_filters), None)
+                .await?
+                .into_iter()
+                .map(|substitution| DisplayUncovered {
+                    n_hour: substitution.n_hour,
+                    classroom_name: substitution.classroom_name,
+                })
+                .collect();
+        uncovered.sort_by_key(|uncovered| uncovered.n_hour);
+
+        Ok(DisplayBoard {
+            week_day,
+            guards,
+            absent_teachers,
+            uncovered,
+        })
+    }
 }
 
 fn get_current_time(testing: bool, hour: u8, minute: u8) -> Time {
@@ -151,6 +486,42 @@ fn get_current_time(testing: bool, hour: u8, minute: u8) -> Time {
     now
 }
 
+/// `ModelEvent::ClassUncovered` of each class, and the mail to the heads of studies
+/// (only for the classes escalated to them).
+async fn alert_uncovered(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    uncovered_hours: &[(&ScheduleHour, bool)],
+    heads_of_studies: &[&User],
+    week_day: i32,
+    n_hour: i32,
+) -> Result<()> {
+    for (schedule_hour, to_heads) in uncovered_hours {
+        mm.publish(ModelEvent::ClassUncovered {
+            center_id: event_center_id(ctx),
+            absent_schedule_id: schedule_hour.schedule_id,
+            classroom_name: schedule_hour.classroom_name.clone(),
+            week_day,
+            n_hour,
+        })
+        .await;
+
+        if !to_heads {
+            continue;
+        }
+        let notification = Notification::ClassUncovered {
+            classroom_name: schedule_hour.classroom_name.clone(),
+            week_day,
+            n_hour,
+        };
+        for head in heads_of_studies {
+            NotificationBmc::enqueue(ctx, mm, head.id, &notification).await?;
+        }
+    }
+
+    Ok(())
+}
+
 async fn update_schedule_hour(
     ctx: &Ctx,
     mm: &ModelManager,
@@ -158,15 +529,10 @@ async fn update_schedule_hour(
     notes: Option<String>,
 ) -> Result<()> {
     let schedule_hour_u = ScheduleHourForUpdate {
-        schedule_id: schedule_hour.schedule_id,
-        classroom_name: schedule_hour.classroom_name.to_string(),
-        subject_name: schedule_hour.subject_name.to_string(),
-        week_day: schedule_hour.week_day,
-        n_hour: schedule_hour.n_hour,
-        course: schedule_hour.course,
-        notes,
+        notes: Some(notes.into()),
+        ..Default::default()
     };
-    ScheduleHourBmc::update(&ctx, &mm, schedule_hour.id, schedule_hour_u).await
+    ScheduleHourBmc::update(ctx, mm, schedule_hour.id, schedule_hour_u).await
 }
 
 fn current_schedule_filters(
@@ -230,6 +596,61 @@ fn guard_filters(
     }])
 }
 
+fn complementary_filters(
+    not_in_center_user_schedules_ids: Vec<i64>,
+    complementary_subject_names: Vec<String>,
+    current_week_day: i32,
+    course: i32,
+    current_n_hour: i32,
+) -> Option<Vec<ScheduleHourFilter>> {
+    Some(vec![ScheduleHourFilter {
+        schedule_id: Some(OpValsInt64(vec![OpValInt64::NotIn(
+            not_in_center_user_schedules_ids,
+        )])),
+        subject_name: Some(OpValsString(vec![OpValString::In(
+            complementary_subject_names,
+        )])),
+        week_day: Some(OpValsInt32(vec![OpValInt32::Eq(current_week_day)])),
+        course: Some(OpValsInt32(vec![OpValInt32::Eq(course)])),
+        n_hour: Some(OpValsInt32(vec![OpValInt32::Eq(current_n_hour)])),
+        notes: Some(OpValsString(vec![OpValString::Null(true)])),
+        ..Default::default()
+    }])
+}
+
+fn course_schedule_filters(course: i64) -> Option<Vec<ScheduleFilter>> {
+    Some(vec![ScheduleFilter {
+        course: Some(OpValsInt64(vec![OpValInt64::Eq(course)])),
+        ..Default::default()
+    }])
+}
+
+/// The `Guardia` and complementary hours assigned (by their `notes`).
+fn assigned_guard_filters(week_day: i32, course: i32) -> Option<Vec<ScheduleHourFilter>> {
+    Some(vec![ScheduleHourFilter {
+        week_day: Some(OpValsInt32(vec![OpValInt32::Eq(week_day)])),
+        course: Some(OpValsInt32(vec![OpValInt32::Eq(course)])),
+        notes: Some(OpValsString(vec![OpValString::StartsWith(
+            GUARD_NOTE_PREFIX.to_string(),
+        )])),
+        ..Default::default()
+    }])
+}
+
+fn lesson_filters(
+    schedule_ids: Vec<i64>,
+    week_day: i32,
+    course: i32,
+) -> Option<Vec<ScheduleHourFilter>> {
+    Some(vec![ScheduleHourFilter {
+        schedule_id: Some(OpValsInt64(vec![OpValInt64::In(schedule_ids)])),
+        subject_name: Some(OpValsString(vec![OpValString::Not("Libre".to_string())])),
+        week_day: Some(OpValsInt32(vec![OpValInt32::Eq(week_day)])),
+        course: Some(OpValsInt32(vec![OpValInt32::Eq(course)])),
+        ..Default::default()
+    }])
+}
+
 // region:    --- Tests
 #[cfg(test)]
 mod tests {
@@ -238,8 +659,17 @@ mod tests {
     // use serde_json::json;
     use serial_test::serial;
 
-    // use crate::_dev_utils;
-    // use crate::ctx::Ctx;
+    use time::OffsetDateTime;
+
+    use crate::_dev_utils;
+    use crate::ctx::Ctx;
+    use crate::model::control::{
+        escalate, ControlBmc, Escalation, EscalationStep, GUARD_NOTE_PREFIX,
+    };
+    use crate::model::notification::{NotificationBmc, NotificationPrefsForUpdate};
+    use crate::model::schedule::ScheduleBmc;
+    use crate::model::schedule_hour::{ScheduleHour, ScheduleHourBmc, ScheduleHourForUpdate};
+    use crate::model::user::{UserBmc, UserForUpdate};
 
     #[serial]
     #[tokio::test]
@@ -259,5 +689,228 @@ mod tests {
 
         Ok(())
     }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_display_board_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let now = OffsetDateTime::now_utc();
+        let (week_day, course) = (now.weekday() as i32, now.year());
+        let fx_guard_username = "test_display_board_ok guard";
+        let fx_absent_username = "test_display_board_ok absent";
+        let fx_classroom_name = "Info 2";
+
+        let guard_user_id = _dev_utils::seed_user(&ctx, &mm, fx_guard_username).await?;
+        let absent_user_id = _dev_utils::seed_user(&ctx, &mm, fx_absent_username).await?;
+        let guard_schedule_id =
+            _dev_utils::seed_schedule(&ctx, &mm, course, guard_user_id, -1).await?;
+        let absent_schedule_id =
+            _dev_utils::seed_schedule(&ctx, &mm, course, absent_user_id, -1).await?;
+        let guard_hour_id = _dev_utils::seed_schedule_hour(
+            &ctx,
+            &mm,
+            guard_schedule_id,
+            "Guardia",
+            "Sala",
+            week_day,
+            1,
+            course,
+        )
+        .await?;
+        _dev_utils::seed_schedule_hour(
+            &ctx,
+            &mm,
+            absent_schedule_id,
+            "Matemáticas",
+            fx_classroom_name,
+            week_day,
+            1,
+            course,
+        )
+        .await?;
+        let guard_hour_u = ScheduleHourForUpdate {
+            notes: Some(Some(format!("{GUARD_NOTE_PREFIX}{fx_classroom_name}")).into()),
+            ..Default::default()
+        };
+        ScheduleHourBmc::update(&ctx, &mm, guard_hour_id, guard_hour_u).await?;
+
+        // -- Exec
+        let board = ControlBmc::display_board(&ctx, &mm).await?;
+
+        // -- Check
+        assert_eq!(board.week_day, week_day);
+        assert!(board.guards.iter().any(|guard| guard.n_hour == 1
+            && guard.teacher == fx_guard_username
+            && guard.classroom_name == fx_classroom_name));
+        assert!(board
+            .absent_teachers
+            .iter()
+            .any(|teacher| teacher.username == fx_absent_username));
+
+        // -- Clean
+        ScheduleBmc::delete(&ctx, &mm, guard_schedule_id).await?;
+        ScheduleBmc::delete(&ctx, &mm, absent_schedule_id).await?;
+        UserBmc::delete(&ctx, &mm, guard_user_id).await?;
+        UserBmc::delete(&ctx, &mm, absent_user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_guards_err_rolled_back() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let now = OffsetDateTime::now_utc();
+        let (week_day, course) = (now.weekday() as i32, now.year());
+        // `update_guards` runs at 12:35 (the hour 5 of the seed).
+        let fx_n_hour = 5;
+        let guard_user_id =
+            _dev_utils::seed_user(&ctx, &mm, "test_update_guards_err_rolled_back guard").await?;
+        let absent_user_id =
+            _dev_utils::seed_user(&ctx, &mm, "test_update_guards_err_rolled_back absent").await?;
+        let user_u = UserForUpdate {
+            in_center: Some(true),
+            ..Default::default()
+        };
+        UserBmc::update(&ctx, &mm, guard_user_id, user_u).await?;
+        let prefs_u = NotificationPrefsForUpdate {
+            email: Some(Some(
+                "guard@test_update_guards_err_rolled_back.es".to_string(),
+            )),
+            ..Default::default()
+        };
+        NotificationBmc::update_prefs(&ctx, &mm, guard_user_id, prefs_u).await?;
+        let guard_schedule_id =
+            _dev_utils::seed_schedule(&ctx, &mm, course, guard_user_id, -1).await?;
+        let absent_schedule_id =
+            _dev_utils::seed_schedule(&ctx, &mm, course, absent_user_id, -1).await?;
+        let guard_hour_id = _dev_utils::seed_schedule_hour(
+            &ctx,
+            &mm,
+            guard_schedule_id,
+            "Guardia",
+            "Sala",
+            week_day,
+            fx_n_hour,
+            course,
+        )
+        .await?;
+        let guard_hour_u = ScheduleHourForUpdate {
+            notes: Some(None::<String>.into()),
+            ..Default::default()
+        };
+        ScheduleHourBmc::update(&ctx, &mm, guard_hour_id, guard_hour_u).await?;
+        _dev_utils::seed_schedule_hour(
+            &ctx,
+            &mm,
+            absent_schedule_id,
+            "Matemáticas",
+            "Info 2",
+            week_day,
+            fx_n_hour,
+            course,
+        )
+        .await?;
+        // Fails the substitution record, after the guard is assigned and notified.
+        mm.dbx()
+            .execute(sqlx::query(
+                "ALTER TABLE substitutions \
+                 ADD CONSTRAINT test_update_guards_fail CHECK (false) NOT VALID",
+            ))
+            .await?;
+
+        // -- Exec
+        let res = ControlBmc::update_guards(&ctx, &mm).await;
+        mm.dbx()
+            .execute(sqlx::query(
+                "ALTER TABLE substitutions DROP CONSTRAINT test_update_guards_fail",
+            ))
+            .await?;
+
+        // -- Check
+        assert!(
+            res.is_err(),
+            "Should have failed on the substitution record"
+        );
+        let sql = format!(
+            "SELECT count(*) FROM {} WHERE user_id = $1",
+            NotificationBmc::OUTBOX_TABLE
+        );
+        let (outbox_count,) = mm
+            .dbx()
+            .fetch_one(sqlx::query_as::<_, (i64,)>(&sql).bind(guard_user_id))
+            .await?;
+        assert_eq!(outbox_count, 0, "Should have no outbox row left");
+        let guard_hour: ScheduleHour = ScheduleHourBmc::get(&ctx, &mm, guard_hour_id).await?;
+        assert_eq!(
+            guard_hour.notes, None,
+            "Should have the guard hour still free"
+        );
+
+        // -- Clean
+        ScheduleBmc::delete(&ctx, &mm, guard_schedule_id).await?;
+        ScheduleBmc::delete(&ctx, &mm, absent_schedule_id).await?;
+        UserBmc::delete(&ctx, &mm, guard_user_id).await?;
+        UserBmc::delete(&ctx, &mm, absent_user_id).await?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_escalation_parse_chain_ok() -> Result<()> {
+        // -- Exec & Check
+        assert_eq!(
+            EscalationStep::parse_chain(" head_of_studies , complementary")
+                .map_err(anyhow::Error::msg)?,
+            [EscalationStep::HeadOfStudies, EscalationStep::Complementary]
+        );
+        assert_eq!(
+            EscalationStep::parse_chain("").map_err(anyhow::Error::msg)?,
+            Vec::<EscalationStep>::new()
+        );
+        assert_eq!(
+            EscalationStep::parse_chain("complementary,principal"),
+            Err("principal".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_escalate_chain_order_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_complementary_hours = [1, 2];
+        let fx_reversed_chain = [EscalationStep::HeadOfStudies, EscalationStep::Complementary];
+
+        // -- Exec & Check
+        assert_eq!(
+            escalate(
+                &EscalationStep::DEFAULT_CHAIN,
+                &mut fx_complementary_hours.into_iter()
+            ),
+            Escalation::Covered(1)
+        );
+        assert_eq!(
+            escalate(&fx_reversed_chain, &mut fx_complementary_hours.into_iter()),
+            Escalation::HeadsOfStudies
+        );
+        assert_eq!(
+            escalate(
+                &EscalationStep::DEFAULT_CHAIN,
+                &mut std::iter::empty::<i64>()
+            ),
+            Escalation::HeadsOfStudies
+        );
+        assert_eq!(
+            escalate(&[], &mut fx_complementary_hours.into_iter()),
+            Escalation::Uncovered
+        );
+
+        Ok(())
+    }
 }
 // endregion: --- Tests
@@ -1,25 +1,33 @@
 use modql::field::{Fields, HasFields};
 use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsString, OpValsValue};
+use schemars::JsonSchema;
+use sea_query::extension::postgres::PgExpr;
+use sea_query::{Expr, Iden, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use sqlx::FromRow;
 use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::OffsetDateTime;
 
 use crate::ctx::Ctx;
-use crate::model::base::{self, PostgresDbBmc};
-use crate::model::ModelManager;
+use crate::model::base::{self, center_scope, CenterIden, PostgresDbBmc};
 use crate::model::modql_utils::time_to_sea_value;
+use crate::model::ModelManager;
 use crate::model::Result;
 
 // region:    --- Department Types
 #[serde_as]
-#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
 pub struct Department {
     pub id: i64,
     pub name: String,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
 }
 
-#[derive(Fields, Deserialize, Clone)]
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
 pub struct DepartmentForCreate {
     pub name: String,
 }
@@ -37,12 +45,11 @@ pub struct DepartmentFilter {
     mtime: Option<OpValsValue>,
 }
 
-#[derive(Fields, Default, Deserialize)]
+#[derive(Fields, Default, Deserialize, JsonSchema)]
 pub struct DepartmentForUpdate {
-    pub name: String,
+    pub name: Option<String>,
 }
 
-
 #[derive(Fields)]
 pub struct DepartmentForInsert {
     pub name: String,
@@ -53,16 +60,27 @@ pub trait DepartmentBy: HasFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
 
 impl DepartmentBy for Department {}
 
+#[derive(Iden)]
+enum DepartmentIden {
+    Name,
+}
+
 // endregion: --- Department Types
 
 pub struct DepartmentBmc;
 
 impl PostgresDbBmc for DepartmentBmc {
     const TABLE: &'static str = "departments";
+    const SOFT_DELETE: bool = true;
+    const CENTER_SCOPED: bool = true;
 }
 
 impl DepartmentBmc {
-    pub async fn create(ctx: &Ctx, mm: &ModelManager, department_c: DepartmentForCreate) -> Result<i64> {
+    pub async fn create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        department_c: DepartmentForCreate,
+    ) -> Result<i64> {
         base::create::<Self, _>(ctx, mm, department_c).await
     }
 
@@ -73,6 +91,33 @@ impl DepartmentBmc {
         base::get::<Self, _>(ctx, mm, id).await
     }
 
+    /// Case insensitive match on the name
+    /// (e.g., department reported by the ldap directory).
+    pub async fn first_by_name<E>(ctx: &Ctx, mm: &ModelManager, name: &str) -> Result<Option<E>>
+    where
+        E: DepartmentBy,
+    {
+        let dbx = mm.dbx();
+
+        // -- Build query
+        let mut query = Query::select();
+        query
+            .from(Self::table_ref())
+            .columns(E::field_idens())
+            .and_where(Expr::col(DepartmentIden::Name).ilike(name));
+        if let Some(center_id) = center_scope::<Self>(ctx) {
+            query.and_where(Expr::col(CenterIden::CenterId).eq(center_id));
+        }
+
+        // -- Exec query
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let department = dbx
+            .fetch_optional(sqlx::query_as_with::<_, E, _>(&sql, values))
+            .await?;
+
+        Ok(department)
+    }
+
     pub async fn list(
         ctx: &Ctx,
         mm: &ModelManager,
@@ -82,6 +127,14 @@ impl DepartmentBmc {
         base::list::<Self, _, _>(ctx, mm, filters, list_options).await
     }
 
+    pub async fn list_including_deleted(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<DepartmentFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<Department>> {
+        base::list_including_deleted::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
 
     pub async fn update(
         ctx: &Ctx,
@@ -92,9 +145,23 @@ impl DepartmentBmc {
         base::update::<Self, _>(ctx, mm, id, department_u).await
     }
 
+    pub async fn update_if_unmodified(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        department_u: DepartmentForUpdate,
+        expected_mtime: Option<OffsetDateTime>,
+    ) -> Result<()> {
+        base::update_if_unmodified::<Self, _>(ctx, mm, id, department_u, expected_mtime).await
+    }
+
     pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
         base::delete::<Self>(ctx, mm, id).await
     }
+
+    pub async fn restore(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        base::restore::<Self>(ctx, mm, id).await
+    }
 }
 
 // region:    --- Tests
@@ -105,8 +172,12 @@ mod tests {
     use serial_test::serial;
 
     use crate::_dev_utils;
-    use crate::ctx::Ctx;
-    use crate::model::department::{Department, DepartmentBmc, DepartmentForCreate, DepartmentForUpdate};
+    use crate::ctx::{Ctx, DEFAULT_CENTER_ID};
+    use crate::model::center::{CenterBmc, CenterForCreate};
+    use crate::model::department::{
+        Department, DepartmentBmc, DepartmentFilter, DepartmentForCreate, DepartmentForUpdate,
+    };
+    use crate::model::Error;
 
     #[serial]
     #[tokio::test]
@@ -132,7 +203,212 @@ mod tests {
 
         Ok(())
     }
-    
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_txn_rollback_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_name = "Prueba_create_txn_rollback_ok";
+
+        // -- Exec
+        let id = {
+            let mm = mm.new_with_txn();
+            mm.begin_txn().await?;
+            let id = DepartmentBmc::create(
+                &ctx,
+                &mm,
+                DepartmentForCreate {
+                    name: fx_name.to_string(),
+                },
+            )
+            .await?;
+            // Visible inside the transaction.
+            let department: Department = DepartmentBmc::get(&ctx, &mm, id).await?;
+            assert_eq!(department.name, fx_name);
+            id
+            // No commit, rolled back on drop.
+        };
+
+        // -- Check
+        let res = DepartmentBmc::get::<Department>(&ctx, &mm, id).await;
+        assert!(
+            matches!(
+                res,
+                Err(Error::EntityNotFound {
+                    entity: "departments",
+                    ..
+                })
+            ),
+            "uncommitted department should not exist"
+        );
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_err_in_savepoint_outer_commit_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_name = "Prueba_create_err_in_savepoint_outer_commit_ok";
+        let fx_name_2 = "Prueba_create_err_in_savepoint_outer_commit_ok 2";
+        let fx_department_c = |name: &str| DepartmentForCreate {
+            name: name.to_string(),
+        };
+
+        // -- Exec
+        let txn_mm = mm.new_with_txn();
+        txn_mm.begin_txn().await?;
+        let id = DepartmentBmc::create(&ctx, &txn_mm, fx_department_c(fx_name)).await?;
+        txn_mm.dbx().savepoint("test_item").await?;
+        // Same name, the inner create fails and is caught.
+        let res = DepartmentBmc::create(&ctx, &txn_mm, fx_department_c(fx_name)).await;
+        assert!(res.is_err(), "Should have failed on the duplicate name");
+        txn_mm.dbx().rollback_to_savepoint("test_item").await?;
+        let id_2 = DepartmentBmc::create(&ctx, &txn_mm, fx_department_c(fx_name_2)).await?;
+        txn_mm.commit_txn().await?;
+        drop(txn_mm);
+
+        // -- Check
+        let department: Department = DepartmentBmc::get(&ctx, &mm, id).await?;
+        assert_eq!(department.name, fx_name);
+        let department: Department = DepartmentBmc::get(&ctx, &mm, id_2).await?;
+        assert_eq!(department.name, fx_name_2);
+
+        // -- Clean
+        DepartmentBmc::delete(&ctx, &mm, id).await?;
+        DepartmentBmc::delete(&ctx, &mm, id_2).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_delete_soft_and_restore_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_name = "Prueba_delete_soft_and_restore_ok";
+        let fx_department_id = _dev_utils::seed_department(&ctx, &mm, fx_name).await?;
+
+        // -- Exec
+        DepartmentBmc::delete(&ctx, &mm, fx_department_id).await?;
+
+        // -- Check deleted
+        let res = DepartmentBmc::get::<Department>(&ctx, &mm, fx_department_id).await;
+        assert!(
+            matches!(
+                res,
+                Err(Error::EntityNotFound {
+                    entity: "departments",
+                    ..
+                })
+            ),
+            "deleted department should not be returned by get"
+        );
+        let filters: Vec<DepartmentFilter> =
+            serde_json::from_value(json!([{ "name": {"$eq": fx_name} }]))?;
+        let departments = DepartmentBmc::list(&ctx, &mm, Some(filters), None).await?;
+        assert!(departments.is_empty());
+        let filters: Vec<DepartmentFilter> =
+            serde_json::from_value(json!([{ "name": {"$eq": fx_name} }]))?;
+        let departments =
+            DepartmentBmc::list_including_deleted(&ctx, &mm, Some(filters), None).await?;
+        assert_eq!(departments.len(), 1);
+
+        // -- Exec & Check restore
+        DepartmentBmc::restore(&ctx, &mm, fx_department_id).await?;
+        let department: Department = DepartmentBmc::get(&ctx, &mm, fx_department_id).await?;
+        assert_eq!(department.name, fx_name);
+
+        // -- Clean
+        DepartmentBmc::delete(&ctx, &mm, fx_department_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_other_center_err_not_found() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_name = "Prueba_other_center_err_not_found";
+        // Note: Rolled back when `txn_mm` is dropped.
+        let txn_mm = mm.new_with_txn();
+        txn_mm.begin_txn().await?;
+        let center_c = CenterForCreate {
+            code: "test_other_center_err_not_found".to_string(),
+            name: "IES test_other_center_err_not_found".to_string(),
+        };
+        let center_id = CenterBmc::create(&ctx, &txn_mm, center_c).await?;
+        let center_ctx = Ctx::root_ctx().with_center(center_id);
+        let default_ctx = Ctx::root_ctx().with_center(DEFAULT_CENTER_ID);
+
+        // -- Exec
+        // Same name in both centers.
+        let default_id = _dev_utils::seed_department(&default_ctx, &txn_mm, fx_name).await?;
+        let center_department_id =
+            _dev_utils::seed_department(&center_ctx, &txn_mm, fx_name).await?;
+
+        // -- Check
+        let res =
+            DepartmentBmc::get::<Department>(&default_ctx, &txn_mm, center_department_id).await;
+        assert!(
+            matches!(
+                res,
+                Err(Error::EntityNotFound {
+                    entity: "departments",
+                    ..
+                })
+            ),
+            "other center department should not be returned by get"
+        );
+        let department_u = DepartmentForUpdate {
+            name: Some("Prueba_other_center_err_not_found updated".to_string()),
+        };
+        let res =
+            DepartmentBmc::update(&default_ctx, &txn_mm, center_department_id, department_u).await;
+        assert!(matches!(
+            res,
+            Err(Error::EntityNotFound {
+                entity: "departments",
+                ..
+            })
+        ));
+        let res = DepartmentBmc::delete(&default_ctx, &txn_mm, center_department_id).await;
+        assert!(matches!(
+            res,
+            Err(Error::EntityNotFound {
+                entity: "departments",
+                ..
+            })
+        ));
+
+        let filters: Vec<DepartmentFilter> =
+            serde_json::from_value(json!([{ "name": {"$eq": fx_name} }]))?;
+        let ids: Vec<i64> = DepartmentBmc::list(&default_ctx, &txn_mm, Some(filters), None)
+            .await?
+            .into_iter()
+            .map(|department| department.id)
+            .collect();
+        assert_eq!(ids, [default_id]);
+
+        // The root ctx without center sees both.
+        let filters: Vec<DepartmentFilter> =
+            serde_json::from_value(json!([{ "name": {"$eq": fx_name} }]))?;
+        let departments = DepartmentBmc::list(&ctx, &txn_mm, Some(filters), None).await?;
+        assert_eq!(departments.len(), 2);
+
+        // -- Clean
+        drop(txn_mm);
+
+        Ok(())
+    }
+
     #[serial]
     #[tokio::test]
     async fn test_update_ok() -> Result<()> {
@@ -149,10 +425,10 @@ mod tests {
             &mm,
             fx_department_id,
             DepartmentForUpdate {
-                name: fx_name_new.to_string(),
+                name: Some(fx_name_new.to_string()),
             },
         )
-            .await?;
+        .await?;
 
         // -- Check
         let department: Department = DepartmentBmc::get(&ctx, &mm, fx_department_id).await?;
@@ -170,10 +446,7 @@ mod tests {
         // -- Setup & Fixtures
         let mm = _dev_utils::init_test().await;
         let ctx = Ctx::root_ctx();
-        let fx_names = &[
-            "Prueba_list_by_name_ok",
-            "Prueba2_list_by_name_ok"
-        ];
+        let fx_names = &["Prueba_list_by_name_ok", "Prueba2_list_by_name_ok"];
         let fx_id_01 = _dev_utils::seed_department(&ctx, &mm, fx_names[0]).await?;
         let fx_id_02 = _dev_utils::seed_department(&ctx, &mm, fx_names[1]).await?;
 
@@ -0,0 +1,224 @@
+//! Display tokens, issued by admins for the staff-room display terminals
+//! (read-only feed, without a teacher login).
+//!
+//! - The token is signed with the row `token_salt` (see `lib_auth::token::generate_display_token`),
+//!   so deleting the row revokes it.
+//! - Only returned once, by `create_display_token`.
+
+use modql::field::{Fields, HasFields};
+use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsString, OpValsValue};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use lib_auth::token::generate_display_token;
+use lib_utils::time::{format_time, now_utc};
+
+use crate::ctx::Ctx;
+use crate::model::base::{self, PostgresDbBmc};
+use crate::model::modql_utils::time_to_sea_value;
+use crate::model::Result;
+use crate::model::{Error, ModelManager};
+
+const VALID_DAYS_MAX: i64 = 400;
+
+// region:    --- DisplayToken Types
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
+pub struct DisplayToken {
+    pub id: i64,
+    pub name: String,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub expires_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
+}
+
+#[derive(Deserialize, Clone, JsonSchema)]
+pub struct DisplayTokenForCreate {
+    /// e.g., "Sala de profesores"
+    pub name: String,
+    /// From now, up to `VALID_DAYS_MAX`.
+    pub valid_days: i64,
+}
+
+#[derive(Fields)]
+struct DisplayTokenForInsert {
+    name: String,
+    expires_at: OffsetDateTime,
+}
+
+#[derive(Clone, FromRow, Fields, Debug)]
+pub struct DisplayTokenForAuth {
+    pub id: i64,
+    pub center_id: i64,
+    pub expires_at: OffsetDateTime,
+    pub token_salt: Uuid,
+}
+
+/// The created display token and its token.
+#[derive(Serialize, JsonSchema)]
+pub struct DisplayTokenIssued {
+    #[serde(flatten)]
+    pub display_token: DisplayToken,
+    pub token: String,
+}
+
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct DisplayTokenFilter {
+    id: Option<OpValsInt64>,
+    name: Option<OpValsString>,
+    cid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    ctime: Option<OpValsValue>,
+    mid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    mtime: Option<OpValsValue>,
+}
+
+/// Marker trait
+pub trait DisplayTokenBy: HasFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
+
+impl DisplayTokenBy for DisplayToken {}
+
+impl DisplayTokenBy for DisplayTokenForAuth {}
+
+// endregion: --- DisplayToken Types
+
+pub struct DisplayTokenBmc;
+
+impl PostgresDbBmc for DisplayTokenBmc {
+    const TABLE: &'static str = "display_tokens";
+    const CENTER_SCOPED: bool = true;
+}
+
+impl DisplayTokenBmc {
+    pub async fn create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        display_token_c: DisplayTokenForCreate,
+    ) -> Result<i64> {
+        let DisplayTokenForCreate { name, valid_days } = display_token_c;
+        if !(1..=VALID_DAYS_MAX).contains(&valid_days) {
+            return Err(Error::DisplayTokenValidDaysOutOfRange {
+                max: VALID_DAYS_MAX,
+                actual: valid_days,
+            });
+        }
+
+        let display_token_i = DisplayTokenForInsert {
+            name,
+            expires_at: now_utc() + Duration::days(valid_days),
+        };
+
+        base::create::<Self, _>(ctx, mm, display_token_i).await
+    }
+
+    /// The token of the display (`ident` is the display token id).
+    pub async fn token(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<String> {
+        let display_token: DisplayTokenForAuth = Self::get(ctx, mm, id).await?;
+        let token = generate_display_token(
+            &display_token.id.to_string(),
+            &format_time(display_token.expires_at),
+            display_token.token_salt,
+        )?;
+
+        Ok(token.to_string())
+    }
+
+    pub async fn get<E>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
+    where
+        E: DisplayTokenBy,
+    {
+        base::get::<Self, _>(ctx, mm, id).await
+    }
+
+    pub async fn list(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<DisplayTokenFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<DisplayToken>> {
+        base::list::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
+
+    /// Revokes the display token.
+    pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        base::delete::<Self>(ctx, mm, id).await
+    }
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serial_test::serial;
+
+    use lib_auth::token::{validate_display_token, Token};
+
+    use crate::_dev_utils;
+    use crate::ctx::Ctx;
+    use crate::model::display_token::{
+        DisplayTokenBmc, DisplayTokenForAuth, DisplayTokenForCreate,
+    };
+    use crate::model::Error;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_token_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_display_token_c = DisplayTokenForCreate {
+            name: "test_create_token_ok display".to_string(),
+            valid_days: 30,
+        };
+
+        // -- Exec
+        let id = DisplayTokenBmc::create(&ctx, &mm, fx_display_token_c).await?;
+        let token: Token = DisplayTokenBmc::token(&ctx, &mm, id).await?.parse()?;
+
+        // -- Check
+        let display_token: DisplayTokenForAuth = DisplayTokenBmc::get(&ctx, &mm, id).await?;
+        assert_eq!(token.ident, id.to_string());
+        validate_display_token(&token, display_token.token_salt)?;
+
+        // -- Clean
+        DisplayTokenBmc::delete(&ctx, &mm, id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_err_valid_days() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_display_token_c = DisplayTokenForCreate {
+            name: "test_create_err_valid_days display".to_string(),
+            valid_days: 0,
+        };
+
+        // -- Exec
+        let res = DisplayTokenBmc::create(&ctx, &mm, fx_display_token_c).await;
+
+        // -- Check
+        assert!(
+            matches!(
+                res,
+                Err(Error::DisplayTokenValidDaysOutOfRange { actual: 0, .. })
+            ),
+            "Should have matched `Err(DisplayTokenValidDaysOutOfRange)` but was `{res:?}`"
+        );
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
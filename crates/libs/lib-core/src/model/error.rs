@@ -1,10 +1,11 @@
 use derive_more::From;
 use serde::Serialize;
-use serde_with::{DisplayFromStr, serde_as};
+use serde_json::Value;
+use serde_with::{serde_as, DisplayFromStr};
 
-use lib_auth::pwd;
+use lib_auth::{pwd, token};
 
-use crate::model::store;
+use crate::model::store::{self, dbx};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -19,12 +20,57 @@ pub enum Error {
         max: i64,
         actual: i64,
     },
+    SoftDeleteNotSupported {
+        entity: &'static str,
+    },
+    /// Update with an `expected_mtime` that no longer matches.
+    ConcurrentModification {
+        entity: &'static str,
+        id: i64,
+        current: Value,
+    },
+    DisplayTokenValidDaysOutOfRange {
+        max: i64,
+        actual: i64,
+    },
+    CourseCalendarInvalidDates {
+        course: i32,
+        start_date: String,
+        end_date: String,
+    },
+    ImportDelimiterInvalid(char),
+    ImportUserPwdMissing {
+        username: String,
+    },
+    ArchiveVersionUnsupported {
+        version: u32,
+        supported: u32,
+    },
+    ArchiveTargetNotEmpty {
+        table: &'static str,
+    },
+    SubstitutionStatusInvalid {
+        status: String,
+    },
+    /// Only the teacher of the schedule hour (or an admin) writes its lesson plans.
+    LessonPlanNotOwner {
+        schedule_hour_id: i64,
+    },
+    LessonPlanAttachmentNotBase64,
+    LessonPlanAttachmentTooLarge {
+        max: usize,
+        actual: usize,
+    },
 
     // -- Modules
     #[from]
     Pwd(pwd::Error),
     #[from]
+    Token(token::Error),
+    #[from]
     Store(store::Error),
+    #[from]
+    Dbx(dbx::Error),
 
     // -- Externals
     #[from]
@@ -33,6 +79,10 @@ pub enum Error {
     SeaQuery(#[serde_as(as = "DisplayFromStr")] sea_query::error::Error),
     #[from]
     ModqlIntoSea(#[serde_as(as = "DisplayFromStr")] modql::filter::IntoSeaError),
+    #[from]
+    Csv(#[serde_as(as = "DisplayFromStr")] csv::Error),
+    #[from]
+    Xml(#[serde_as(as = "DisplayFromStr")] quick_xml::DeError),
 
     MongoEntityNotFound {
         entity: &'static str,
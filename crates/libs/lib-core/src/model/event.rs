@@ -0,0 +1,376 @@
+//! Bus of the model events (e.g., for the web-server `/api/ws` push).
+//!
+//! - The `*Bmc` functions publish with `ModelManager::publish`, after their change.
+//! - Each event has the `center_id` of the change (see `event_center_id`),
+//!   the subscribers only forward the events of their center (`ModelEvent::is_for_center`).
+//! - In a transaction, the events are held until the outermost `commit_txn`
+//!   (and dropped with a rolled back transaction), so subscribers only see committed changes.
+//! - The subscribers always receive from the in-process `broadcast` channel, the `EventBackend`
+//!   is how the published events get there (`SERVICE_EVENT_BUS`):
+//!   - `local` (default), sent directly, only this instance sees them.
+//!   - `pg`, Postgres `NOTIFY` on `EVENT_CHANNEL`, and the `LISTEN` task of each instance
+//!     (this one included) forwards them, for multi-instance deployments.
+
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use tokio::sync::broadcast;
+use tracing::{error, warn};
+
+use crate::ctx::{Ctx, DEFAULT_CENTER_ID};
+use crate::model::store::PostgresDb;
+use crate::model::Result;
+
+/// Events buffered for slow subscribers, older ones are skipped for them
+/// (`broadcast::error::RecvError::Lagged`).
+const BUS_CAPACITY: usize = 256;
+
+/// Postgres `LISTEN/NOTIFY` channel of the `pg` backend.
+pub const EVENT_CHANNEL: &str = "model_events";
+
+/// Postgres rejects `NOTIFY` payloads of 8000 bytes or more.
+const NOTIFY_PAYLOAD_MAX: usize = 7999;
+
+/// Wait before retrying a failed `LISTEN` connection.
+const LISTEN_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+// region:    --- ModelEvent Types
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", content = "data")]
+pub enum ModelEvent {
+    CheckIn {
+        center_id: i64,
+        user_id: i64,
+    },
+    CheckOut {
+        center_id: i64,
+        user_id: i64,
+    },
+    /// The guard hour (`guard_schedule_id`) now covers an absent teacher hour.
+    GuardAssigned {
+        center_id: i64,
+        guard_schedule_hour_id: i64,
+        guard_schedule_id: i64,
+        covered_schedule_id: i64,
+        covered_schedule_hour_id: i64,
+        classroom_name: String,
+        week_day: i32,
+        n_hour: i32,
+    },
+    /// No guard left for an absent teacher hour (see `EscalationStep`).
+    ClassUncovered {
+        center_id: i64,
+        absent_schedule_id: i64,
+        classroom_name: String,
+        week_day: i32,
+        n_hour: i32,
+    },
+    /// Schedule hours created, updated or deleted.
+    ScheduleChanged {
+        center_id: i64,
+        schedule_id: i64,
+    },
+    Announcement {
+        center_id: i64,
+        author_id: i64,
+        message: String,
+    },
+}
+
+impl ModelEvent {
+    pub fn center_id(&self) -> i64 {
+        match self {
+            ModelEvent::CheckIn { center_id, .. }
+            | ModelEvent::CheckOut { center_id, .. }
+            | ModelEvent::GuardAssigned { center_id, .. }
+            | ModelEvent::ClassUncovered { center_id, .. }
+            | ModelEvent::ScheduleChanged { center_id, .. }
+            | ModelEvent::Announcement { center_id, .. } => *center_id,
+        }
+    }
+
+    /// Whether a subscriber of `center_id` gets the event (all of them for `None`, the root ctx).
+    pub fn is_for_center(&self, center_id: Option<i64>) -> bool {
+        center_id.is_none_or(|center_id| center_id == self.center_id())
+    }
+}
+
+/// The `center_id` of the events published with the `ctx`
+/// (the root ctx without center is the default center, as the rows it creates).
+pub fn event_center_id(ctx: &Ctx) -> i64 {
+    ctx.center_id().unwrap_or(DEFAULT_CENTER_ID)
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct AnnouncementForCreate {
+    pub message: String,
+}
+
+// endregion: --- ModelEvent Types
+
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<ModelEvent>,
+    backend: EventBackend,
+}
+
+#[derive(Debug, Clone)]
+enum EventBackend {
+    Local,
+    /// `NOTIFY` with the db pool, received back by the `LISTEN` task.
+    PgNotify(PostgresDb),
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(BUS_CAPACITY);
+        EventBus {
+            tx,
+            backend: EventBackend::Local,
+        }
+    }
+}
+
+impl EventBus {
+    /// Bus shared by all the instances on the same database.
+    ///
+    /// Note: The `LISTEN` connection has its own pool (from `db_url`),
+    ///       so it does not hold one of the `db` pool connections.
+    pub async fn new_pg(db: PostgresDb, db_url: &str) -> Result<Self> {
+        let mut listener = PgListener::connect(db_url).await?;
+        listener.listen(EVENT_CHANNEL).await?;
+
+        let (tx, _) = broadcast::channel(BUS_CAPACITY);
+        tokio::spawn(forward_notifications(listener, tx.clone()));
+
+        Ok(EventBus {
+            tx,
+            backend: EventBackend::PgNotify(db),
+        })
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ModelEvent> {
+        self.tx.subscribe()
+    }
+
+    /// Note: Failures are only logged, the change is already committed.
+    pub(in crate::model) async fn send(&self, event: ModelEvent) {
+        match &self.backend {
+            EventBackend::Local => {
+                // Note: Only fails when there is no subscriber, nothing to do then.
+                let _ = self.tx.send(event);
+            }
+            EventBackend::PgNotify(db) => {
+                if let Err(ex) = notify(db, &event).await {
+                    error!("{:<12} - notify {event:?} - {ex}", "EVENT-BUS");
+                }
+            }
+        }
+    }
+}
+
+async fn notify(db: &PostgresDb, event: &ModelEvent) -> Result<()> {
+    let payload = match serde_json::to_string(event) {
+        Ok(payload) => payload,
+        Err(ex) => {
+            warn!("{:<12} - cannot serialize, dropped - {ex}", "EVENT-BUS");
+            return Ok(());
+        }
+    };
+    if payload.len() > NOTIFY_PAYLOAD_MAX {
+        warn!(
+            "{:<12} - payload too large ({} bytes), dropped",
+            "EVENT-BUS",
+            payload.len()
+        );
+        return Ok(());
+    }
+
+    sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(EVENT_CHANNEL)
+        .bind(payload)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// `LISTEN` loop, forwards the notified events to the local subscribers.
+///
+/// Note: `PgListener::recv` reconnects on the next call after a lost connection
+///       (the events notified meanwhile are lost).
+async fn forward_notifications(mut listener: PgListener, tx: broadcast::Sender<ModelEvent>) {
+    loop {
+        match listener.recv().await {
+            Ok(notification) => match serde_json::from_str::<ModelEvent>(notification.payload()) {
+                Ok(event) => {
+                    let _ = tx.send(event);
+                }
+                Err(ex) => warn!("{:<12} - unknown event, skipped - {ex}", "EVENT-BUS"),
+            },
+            Err(ex) => {
+                error!("{:<12} - listen - {ex}", "EVENT-BUS");
+                tokio::time::sleep(LISTEN_RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use serial_test::serial;
+    use tokio::time::timeout;
+
+    use crate::_dev_utils;
+    use crate::core_config;
+    use crate::ctx::{Ctx, DEFAULT_CENTER_ID};
+    use crate::model::event::{event_center_id, EventBus, ModelEvent};
+
+    #[serial]
+    #[tokio::test]
+    async fn test_publish_after_commit_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let mut rx = mm.subscribe();
+        let fx_user_id = 1000;
+
+        // -- Exec
+        let mm_txn = mm.new_with_txn();
+        mm_txn.begin_txn().await?;
+        mm_txn
+            .publish(ModelEvent::CheckIn {
+                center_id: DEFAULT_CENTER_ID,
+                user_id: fx_user_id,
+            })
+            .await;
+        let before_commit = rx.try_recv();
+        mm_txn.commit_txn().await?;
+
+        // -- Check
+        assert!(before_commit.is_err(), "Should not be sent before commit");
+        let event = rx.try_recv()?;
+        assert!(matches!(event, ModelEvent::CheckIn { user_id, .. } if user_id == fx_user_id));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_publish_rollback_dropped_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let mut rx = mm.subscribe();
+
+        // -- Exec
+        {
+            let mm_txn = mm.new_with_txn();
+            mm_txn.begin_txn().await?;
+            mm_txn
+                .publish(ModelEvent::CheckIn {
+                    center_id: DEFAULT_CENTER_ID,
+                    user_id: 1000,
+                })
+                .await;
+            // No commit, rolled back on drop.
+        }
+        mm.publish(ModelEvent::CheckOut {
+            center_id: DEFAULT_CENTER_ID,
+            user_id: 1001,
+        })
+        .await;
+
+        // -- Check
+        let event = rx.try_recv()?;
+        assert!(matches!(event, ModelEvent::CheckOut { user_id: 1001, .. }));
+        assert!(rx.try_recv().is_err(), "Should have no other event");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_pg_bus_notify_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let bus = EventBus::new_pg(mm.dbx().db().clone(), &core_config().DB_URL).await?;
+        let mut rx = bus.subscribe();
+        let fx_user_id = 1000;
+
+        // -- Exec
+        bus.send(ModelEvent::CheckIn {
+            center_id: DEFAULT_CENTER_ID,
+            user_id: fx_user_id,
+        })
+        .await;
+
+        // -- Check
+        // Received back through the `LISTEN` task.
+        let event = timeout(Duration::from_secs(5), rx.recv()).await??;
+        assert!(matches!(event, ModelEvent::CheckIn { user_id, .. } if user_id == fx_user_id));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_publish_other_center_not_for_subscriber() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let mut rx = mm.subscribe();
+        let fx_other_center_id = 2;
+        let default_ctx = Ctx::new(1000, false, DEFAULT_CENTER_ID);
+        let other_ctx = Ctx::new(1001, false, fx_other_center_id);
+
+        // -- Exec
+        for ctx in [&default_ctx, &other_ctx] {
+            mm.publish(ModelEvent::Announcement {
+                center_id: event_center_id(ctx),
+                author_id: ctx.user_id(),
+                message: "test_publish_other_center_not_for_subscriber".to_string(),
+            })
+            .await;
+        }
+
+        // -- Check
+        let mut default_events = Vec::new();
+        let mut other_events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if event.is_for_center(default_ctx.center_id()) {
+                default_events.push(event.clone());
+            }
+            if event.is_for_center(other_ctx.center_id()) {
+                other_events.push(event);
+            }
+        }
+        assert!(
+            matches!(
+                default_events.as_slice(),
+                [ModelEvent::Announcement {
+                    author_id: 1000,
+                    ..
+                }]
+            ),
+            "Should only get its center event, got {default_events:?}"
+        );
+        assert!(
+            matches!(
+                other_events.as_slice(),
+                [ModelEvent::Announcement {
+                    author_id: 1001,
+                    ..
+                }]
+            ),
+            "Should only get its center event, got {other_events:?}"
+        );
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
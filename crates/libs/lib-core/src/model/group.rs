@@ -1,33 +1,38 @@
 use modql::field::{Fields, HasFields};
 use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsString, OpValsValue};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use sqlx::FromRow;
 use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::OffsetDateTime;
 
 use crate::ctx::Ctx;
 use crate::model::base::{self, PostgresDbBmc};
-use crate::model::ModelManager;
 use crate::model::modql_utils::time_to_sea_value;
+use crate::model::ModelManager;
 use crate::model::Result;
 
 // region:    --- Group Types
 #[serde_as]
-#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
 pub struct Group {
     pub id: i64,
-    pub course: i32,    // 1º, 2º
-    pub stage: i32,     // ESO, Bachiller, Ciclos
-    pub year: i32,      // 2023/2024, 2024/2025
+    pub course: i32, // 1º, 2º
+    pub stage: i32,  // ESO, Bachiller, Ciclos
+    pub year: i32,   // 2023/2024, 2024/2025
     pub letter: String,
     pub tutor_name: String,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
 }
 
-#[derive(Fields, Deserialize, Clone)]
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
 pub struct GroupForCreate {
-    pub course: i32,    // 1º, 2º
-    pub stage: i32,     // ESO, Bachiller, Ciclos
-    pub year: i32,      // 2023/2024, 2024/2025
+    pub course: i32, // 1º, 2º
+    pub stage: i32,  // ESO, Bachiller, Ciclos
+    pub year: i32,   // 2023/2024, 2024/2025
     pub letter: String,
     pub tutor_name: String,
 }
@@ -49,13 +54,13 @@ pub struct GroupFilter {
     mtime: Option<OpValsValue>,
 }
 
-#[derive(Fields, Default, Deserialize)]
+#[derive(Fields, Default, Deserialize, JsonSchema)]
 pub struct GroupForUpdate {
-    pub course: i32,    // 1º, 2º
-    pub stage: i32,     // ESO, Bachiller, Ciclos
-    pub year: i32,      // 2023/2024, 2024/2025
-    pub letter: String,
-    pub tutor_name: String,
+    pub course: Option<i32>, // 1º, 2º
+    pub stage: Option<i32>,  // ESO, Bachiller, Ciclos
+    pub year: Option<i32>,   // 2023/2024, 2024/2025
+    pub letter: Option<String>,
+    pub tutor_name: Option<String>,
 }
 
 /// Marker trait
@@ -69,6 +74,8 @@ pub struct GroupBmc;
 
 impl PostgresDbBmc for GroupBmc {
     const TABLE: &'static str = "groups";
+    const SOFT_DELETE: bool = true;
+    const CENTER_SCOPED: bool = true;
 }
 
 impl GroupBmc {
@@ -92,6 +99,14 @@ impl GroupBmc {
         base::list::<Self, _, _>(ctx, mm, filters, list_options).await
     }
 
+    pub async fn list_including_deleted(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<GroupFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<Group>> {
+        base::list_including_deleted::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
 
     pub async fn update(
         ctx: &Ctx,
@@ -102,9 +117,23 @@ impl GroupBmc {
         base::update::<Self, _>(ctx, mm, id, group_u).await
     }
 
+    pub async fn update_if_unmodified(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        group_u: GroupForUpdate,
+        expected_mtime: Option<OffsetDateTime>,
+    ) -> Result<()> {
+        base::update_if_unmodified::<Self, _>(ctx, mm, id, group_u, expected_mtime).await
+    }
+
     pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
         base::delete::<Self>(ctx, mm, id).await
     }
+
+    pub async fn restore(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        base::restore::<Self>(ctx, mm, id).await
+    }
 }
 
 // region:    --- Tests
@@ -133,7 +162,7 @@ mod tests {
 
         let fx_username = "username_create_ok";
         let fx_department_name = "department_name_create_ok";
-        
+
         let fx_user_id = _dev_utils::seed_user(&ctx, &mm, fx_username).await?;
         let fx_department_id = _dev_utils::seed_department(&ctx, &mm, fx_department_name).await?;
 
@@ -143,7 +172,7 @@ mod tests {
             stage: fx_stage,
             tutor_name: fx_username.to_string(),
             letter: fx_letter.to_string(),
-            year: fx_year
+            year: fx_year,
         };
 
         let id = GroupBmc::create(&ctx, &mm, group_c).await?;
@@ -160,10 +189,10 @@ mod tests {
         GroupBmc::delete(&ctx, &mm, id).await?;
         DepartmentBmc::delete(&ctx, &mm, fx_department_id).await?;
         UserBmc::delete(&ctx, &mm, fx_user_id).await?;
-        
+
         Ok(())
     }
-    
+
     #[serial]
     #[tokio::test]
     async fn test_update_ok() -> Result<()> {
@@ -174,7 +203,7 @@ mod tests {
         let fx_stage = 111;
         let fx_year = 2024;
         let fx_letter = "A";
-        
+
         let fx_course_new = 222;
         let fx_stage_new = 222;
         let fx_year_new = 2025;
@@ -188,7 +217,16 @@ mod tests {
         let fx_user_id = _dev_utils::seed_user(&ctx, &mm, fx_usernames[0]).await?;
         let fx_user_id_new = _dev_utils::seed_user(&ctx, &mm, fx_usernames[1]).await?;
         let fx_department_id = _dev_utils::seed_department(&ctx, &mm, fx_department_name).await?;
-        let fx_group_id = _dev_utils::seed_group(&ctx, &mm, fx_letter, fx_course, fx_stage, fx_year, fx_tutor_name.to_string()).await?;
+        let fx_group_id = _dev_utils::seed_group(
+            &ctx,
+            &mm,
+            fx_letter,
+            fx_course,
+            fx_stage,
+            fx_year,
+            fx_tutor_name.to_string(),
+        )
+        .await?;
 
         // -- Exec
         GroupBmc::update(
@@ -196,14 +234,14 @@ mod tests {
             &mm,
             fx_group_id,
             GroupForUpdate {
-                course: fx_course_new,
-                stage: fx_stage_new,
-                tutor_name: fx_tutor_name_new.to_string(),
-                letter: fx_letter_new.to_string(),
-                year: fx_year_new
+                course: Some(fx_course_new),
+                stage: Some(fx_stage_new),
+                tutor_name: Some(fx_tutor_name_new.to_string()),
+                letter: Some(fx_letter_new.to_string()),
+                year: Some(fx_year_new),
             },
         )
-            .await?;
+        .await?;
 
         // -- Check
         let group: Group = GroupBmc::get(&ctx, &mm, fx_group_id).await?;
@@ -218,7 +256,7 @@ mod tests {
         DepartmentBmc::delete(&ctx, &mm, fx_department_id).await?;
         UserBmc::delete(&ctx, &mm, fx_user_id).await?;
         UserBmc::delete(&ctx, &mm, fx_user_id_new).await?;
-        
+
         Ok(())
     }
 
@@ -228,23 +266,37 @@ mod tests {
         // -- Setup & Fixtures
         let mm = _dev_utils::init_test().await;
         let ctx = Ctx::root_ctx();
-        let fx_letters = &[
-            "test_A",
-            "test_B"
-        ];
+        let fx_letters = &["test_A", "test_B"];
         let fx_course = 1;
         let fx_stage = 1;
         let fx_year = 2024;
-        
+
         let fx_username = "username_list_by_name_ok";
         let fx_department_name = "department_name_list_by_name_ok";
 
         let fx_user_id = _dev_utils::seed_user(&ctx, &mm, fx_username).await?;
         let fx_department_id = _dev_utils::seed_department(&ctx, &mm, fx_department_name).await?;
 
-
-        let fx_id_01 = _dev_utils::seed_group(&ctx, &mm, fx_letters[0], fx_course, fx_stage, fx_year, fx_username.to_string()).await?;
-        let fx_id_02 = _dev_utils::seed_group(&ctx, &mm, fx_letters[1], fx_course, fx_stage, fx_year, fx_username.to_string()).await?;
+        let fx_id_01 = _dev_utils::seed_group(
+            &ctx,
+            &mm,
+            fx_letters[0],
+            fx_course,
+            fx_stage,
+            fx_year,
+            fx_username.to_string(),
+        )
+        .await?;
+        let fx_id_02 = _dev_utils::seed_group(
+            &ctx,
+            &mm,
+            fx_letters[1],
+            fx_course,
+            fx_stage,
+            fx_year,
+            fx_username.to_string(),
+        )
+        .await?;
 
         // -- Exec
         let filter_json = json!({
@@ -255,7 +307,8 @@ mod tests {
         let groups = GroupBmc::list(&ctx, &mm, Some(filter), None).await?;
 
         // -- Check
-        let groups: Vec<String> = groups.into_iter()
+        let groups: Vec<String> = groups
+            .into_iter()
             .filter(|s| s.letter.starts_with("test_"))
             .map(|s| s.letter)
             .collect();
@@ -267,7 +320,7 @@ mod tests {
         GroupBmc::delete(&ctx, &mm, fx_id_02).await?;
         UserBmc::delete(&ctx, &mm, fx_user_id).await?;
         DepartmentBmc::delete(&ctx, &mm, fx_department_id).await?;
-        
+
         Ok(())
     }
 }
@@ -0,0 +1,706 @@
+//! Timetable import from the regional school management export (ITACA / Séneca style XML).
+//!
+//! The export subset used:
+//!
+//! ```xml
+//! <horarios centro="46000000" curso="2025">
+//!   <docentes><docente codigo="12345678Z" nombre="profesor1"/></docentes>
+//!   <grupos><grupo codigo="1ESOA" curso="1" ensenanza="ESO" letra="A"/></grupos>
+//!   <materias><materia codigo="MAT" nombre="Matemáticas"/></materias>
+//!   <aulas><aula codigo="A01" nombre="Aula 1"/></aulas>
+//!   <sesiones>
+//!     <sesion docente="12345678Z" grupo="1ESOA" materia="MAT" aula="A01" dia="L" hora="1"/>
+//!   </sesiones>
+//! </horarios>
+//! ```
+//!
+//! - Teachers (`username`), subjects and classrooms (`name`) are matched by `codigo`, then by
+//!   `nombre` (case insensitive). Groups by `curso`, `ensenanza` and `letra`, of the `curso` year.
+//! - What is not matched is reported, and its sessions skipped (the other ones are applied).
+//! - Re-imports are idempotent: the hours of each teacher and group schedule of the export
+//!   are synced (created, updated, and deleted when no longer in the export).
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::ctx::Ctx;
+use crate::model::base::PostgresDbBmc;
+use crate::model::calendar::TimetableOwner;
+use crate::model::classroom::ClassroomBmc;
+use crate::model::group::GroupBmc;
+use crate::model::import::{ImportBmc, ImportEntity};
+use crate::model::schedule::{ScheduleBmc, ScheduleForCreate};
+use crate::model::schedule_hour::{ScheduleHourBmc, ScheduleHourForCreate, ScheduleHourForUpdate};
+use crate::model::subject::SubjectBmc;
+use crate::model::user::UserBmc;
+use crate::model::{ModelManager, Result};
+
+// region:    --- Import Types
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TimetableImport {
+    pub xml: String,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TimetableImportReport {
+    pub course: i32,
+    pub dry_run: bool,
+    pub applied: bool,
+    pub sessions: usize,
+    /// Sessions with something unmatched or invalid.
+    pub skipped: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    pub unmatched: Vec<ImportUnmatched>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+pub struct ImportUnmatched {
+    pub entity: ImportEntity,
+    pub code: String,
+    pub name: Option<String>,
+}
+
+// endregion: --- Import Types
+
+// region:    --- Export Types
+
+#[derive(Debug, Deserialize)]
+struct ItacaExport {
+    #[serde(rename = "@curso")]
+    course: i32,
+    #[serde(rename = "docentes", default)]
+    teachers: ItacaList<ItacaItem>,
+    #[serde(rename = "grupos", default)]
+    groups: ItacaList<ItacaGroup>,
+    #[serde(rename = "materias", default)]
+    subjects: ItacaList<ItacaItem>,
+    #[serde(rename = "aulas", default)]
+    classrooms: ItacaList<ItacaItem>,
+    #[serde(rename = "sesiones", default)]
+    sessions: ItacaList<ItacaSession>,
+}
+
+/// e.g., `<docentes><docente .../><docente .../></docentes>`
+#[derive(Debug, Deserialize)]
+struct ItacaList<T> {
+    #[serde(rename = "$value", default = "Vec::new")]
+    items: Vec<T>,
+}
+
+impl<T> Default for ItacaList<T> {
+    fn default() -> Self {
+        ItacaList { items: Vec::new() }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ItacaItem {
+    #[serde(rename = "@codigo")]
+    code: String,
+    #[serde(rename = "@nombre")]
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ItacaGroup {
+    #[serde(rename = "@codigo")]
+    code: String,
+    #[serde(rename = "@curso")]
+    course: i32,
+    #[serde(rename = "@ensenanza")]
+    stage: String,
+    #[serde(rename = "@letra")]
+    letter: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ItacaSession {
+    #[serde(rename = "@docente")]
+    teacher: String,
+    #[serde(rename = "@grupo")]
+    group: Option<String>,
+    #[serde(rename = "@materia")]
+    subject: String,
+    #[serde(rename = "@aula")]
+    classroom: Option<String>,
+    #[serde(rename = "@dia")]
+    week_day: String,
+    #[serde(rename = "@hora")]
+    n_hour: i32,
+}
+
+// endregion: --- Export Types
+
+/// The hours of one schedule, by `(week_day, n_hour)`.
+#[derive(Default)]
+struct ScheduleSync {
+    hours: BTreeMap<(i32, i32), (String, String)>,
+    /// Skipped sessions slots, kept as they are.
+    keep: BTreeSet<(i32, i32)>,
+}
+
+impl ImportBmc {
+    pub async fn import_itaca_timetable(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        import: TimetableImport,
+    ) -> Result<TimetableImportReport> {
+        let TimetableImport { xml, dry_run } = import;
+        let export: ItacaExport = quick_xml::de::from_str(&xml)?;
+        let course = export.course;
+
+        let mut report = TimetableImportReport {
+            course,
+            dry_run,
+            applied: false,
+            sessions: export.sessions.items.len(),
+            skipped: 0,
+            created: 0,
+            updated: 0,
+            deleted: 0,
+            unmatched: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        // -- Resolve
+        let resolver = Resolver::load(ctx, mm, &export).await?;
+        let mut syncs: HashMap<TimetableOwner, ScheduleSync> = HashMap::new();
+        for (idx, session) in export.sessions.items.iter().enumerate() {
+            let teacher = resolver.teacher(&session.teacher, &mut report.unmatched);
+            let group = match &session.group {
+                Some(code) => resolver.group(code, &mut report.unmatched).map(Some),
+                None => Some(None),
+            };
+            let subject = resolver.subject(&session.subject, &mut report.unmatched);
+            let classroom = match &session.classroom {
+                Some(code) => resolver.classroom(code, &mut report.unmatched),
+                None => Some(String::new()),
+            };
+            let Some(week_day) = parse_week_day(&session.week_day) else {
+                report.errors.push(format!(
+                    "session {}: invalid dia '{}'",
+                    idx + 1,
+                    session.week_day
+                ));
+                report.skipped += 1;
+                continue;
+            };
+
+            let owners: Vec<TimetableOwner> = teacher
+                .map(TimetableOwner::Teacher)
+                .into_iter()
+                .chain(group.flatten().map(TimetableOwner::Group))
+                .collect();
+            let slot = (week_day, session.n_hour);
+            match (teacher, group, subject, classroom) {
+                (Some(_), Some(_), Some(subject), Some(classroom)) => {
+                    for owner in owners {
+                        let sync = syncs.entry(owner).or_default();
+                        sync.hours
+                            .insert(slot, (subject.clone(), classroom.clone()));
+                    }
+                }
+                _ => {
+                    for owner in owners {
+                        syncs.entry(owner).or_default().keep.insert(slot);
+                    }
+                    report.skipped += 1;
+                }
+            }
+        }
+
+        // -- Apply
+        // Note: Dropping `mm` without commit rolls the transaction back.
+        let mm = mm.new_with_txn();
+        mm.begin_txn().await?;
+        for (owner, sync) in syncs {
+            let schedule_id = get_or_create_schedule(ctx, &mm, owner, course).await?;
+            sync_schedule_hours(ctx, &mm, schedule_id, course, sync, &mut report).await?;
+        }
+
+        if !dry_run {
+            mm.commit_txn().await?;
+            report.applied = true;
+        }
+
+        Ok(report)
+    }
+}
+
+// region:    --- Resolve
+
+/// The export codes, resolved to the center data.
+struct Resolver {
+    /// Export code -> `(user_id, name)`.
+    teachers: HashMap<String, (Option<i64>, Option<String>)>,
+    /// Export code -> `group_id`.
+    groups: HashMap<String, Option<i64>>,
+    /// Export code -> subject name.
+    subjects: HashMap<String, (Option<String>, Option<String>)>,
+    /// Export code -> classroom name.
+    classrooms: HashMap<String, (Option<String>, Option<String>)>,
+}
+
+impl Resolver {
+    /// The names of the ctx center.
+    async fn load(ctx: &Ctx, mm: &ModelManager, export: &ItacaExport) -> Result<Self> {
+        let center_id = ctx.center_id();
+
+        // -- Users
+        let sql = format!(
+            "SELECT username, id FROM {} \
+             WHERE deleted_at IS NULL AND ($1::BIGINT IS NULL OR center_id = $1)",
+            UserBmc::TABLE
+        );
+        let users: HashMap<String, i64> = mm
+            .dbx()
+            .fetch_all(sqlx::query_as::<_, (String, i64)>(&sql).bind(center_id))
+            .await?
+            .into_iter()
+            .map(|(username, id)| (username.to_lowercase(), id))
+            .collect();
+        let teachers = export
+            .teachers
+            .items
+            .iter()
+            .map(|item| {
+                (
+                    item.code.clone(),
+                    (match_item(&users, item), item.name.clone()),
+                )
+            })
+            .collect();
+
+        // -- Groups
+        let sql = format!(
+            "SELECT course, stage, letter, id FROM {} \
+             WHERE year = $1 AND deleted_at IS NULL AND ($2::BIGINT IS NULL OR center_id = $2)",
+            GroupBmc::TABLE
+        );
+        let center_groups: HashMap<(i32, i32, String), i64> = mm
+            .dbx()
+            .fetch_all(
+                sqlx::query_as::<_, (i32, i32, Option<String>, i64)>(&sql)
+                    .bind(export.course)
+                    .bind(center_id),
+            )
+            .await?
+            .into_iter()
+            .map(|(course, stage, letter, id)| {
+                (
+                    (course, stage, letter.unwrap_or_default().to_lowercase()),
+                    id,
+                )
+            })
+            .collect();
+        let groups = export
+            .groups
+            .items
+            .iter()
+            .map(|group| {
+                let group_id = parse_stage(&group.stage).and_then(|stage| {
+                    let key = (group.course, stage, group.letter.trim().to_lowercase());
+                    center_groups.get(&key).copied()
+                });
+                (group.code.clone(), group_id)
+            })
+            .collect();
+
+        // -- Subjects
+        let sql = format!(
+            "SELECT name FROM {} \
+             WHERE deleted_at IS NULL AND ($1::BIGINT IS NULL OR center_id = $1)",
+            SubjectBmc::TABLE
+        );
+        let names = fetch_names(mm, &sql, center_id).await?;
+        let subjects = export
+            .subjects
+            .items
+            .iter()
+            .map(|item| {
+                (
+                    item.code.clone(),
+                    (match_item(&names, item), item.name.clone()),
+                )
+            })
+            .collect();
+
+        // -- Classrooms
+        let sql = format!(
+            "SELECT name FROM {} WHERE name IS NOT NULL AND deleted_at IS NULL \
+                 AND ($1::BIGINT IS NULL OR center_id = $1)",
+            ClassroomBmc::TABLE
+        );
+        let names = fetch_names(mm, &sql, center_id).await?;
+        let classrooms = export
+            .classrooms
+            .items
+            .iter()
+            .map(|item| {
+                (
+                    item.code.clone(),
+                    (match_item(&names, item), item.name.clone()),
+                )
+            })
+            .collect();
+
+        Ok(Resolver {
+            teachers,
+            groups,
+            subjects,
+            classrooms,
+        })
+    }
+
+    fn teacher(&self, code: &str, unmatched: &mut Vec<ImportUnmatched>) -> Option<i64> {
+        resolve(&self.teachers, ImportEntity::User, code, unmatched)
+    }
+
+    fn group(&self, code: &str, unmatched: &mut Vec<ImportUnmatched>) -> Option<i64> {
+        let group_id = self.groups.get(code).copied().flatten();
+        if group_id.is_none() {
+            push_unmatched(unmatched, ImportEntity::Group, code, None);
+        }
+
+        group_id
+    }
+
+    fn subject(&self, code: &str, unmatched: &mut Vec<ImportUnmatched>) -> Option<String> {
+        resolve(&self.subjects, ImportEntity::Subject, code, unmatched)
+    }
+
+    fn classroom(&self, code: &str, unmatched: &mut Vec<ImportUnmatched>) -> Option<String> {
+        resolve(&self.classrooms, ImportEntity::Classroom, code, unmatched)
+    }
+}
+
+fn resolve<T: Clone>(
+    items: &HashMap<String, (Option<T>, Option<String>)>,
+    entity: ImportEntity,
+    code: &str,
+    unmatched: &mut Vec<ImportUnmatched>,
+) -> Option<T> {
+    match items.get(code) {
+        Some((Some(value), _)) => Some(value.clone()),
+        Some((None, name)) => {
+            push_unmatched(unmatched, entity, code, name.clone());
+            None
+        }
+        // Not in the export lists.
+        None => {
+            push_unmatched(unmatched, entity, code, None);
+            None
+        }
+    }
+}
+
+fn push_unmatched(
+    unmatched: &mut Vec<ImportUnmatched>,
+    entity: ImportEntity,
+    code: &str,
+    name: Option<String>,
+) {
+    let item = ImportUnmatched {
+        entity,
+        code: code.to_string(),
+        name,
+    };
+    if !unmatched.contains(&item) {
+        unmatched.push(item);
+    }
+}
+
+/// By `codigo`, then `nombre` (`center` keys are lowercase).
+fn match_item<T: Clone>(center: &HashMap<String, T>, item: &ItacaItem) -> Option<T> {
+    std::iter::once(&item.code)
+        .chain(item.name.as_ref())
+        .find_map(|key| center.get(&key.trim().to_lowercase()).cloned())
+}
+
+/// Lowercase name -> name (`sql` filtered by the `$1` center).
+async fn fetch_names(
+    mm: &ModelManager,
+    sql: &str,
+    center_id: Option<i64>,
+) -> Result<HashMap<String, String>> {
+    let names = mm
+        .dbx()
+        .fetch_all(sqlx::query_as::<_, (String,)>(sql).bind(center_id))
+        .await?
+        .into_iter()
+        .map(|(name,)| (name.to_lowercase(), name))
+        .collect();
+
+    Ok(names)
+}
+
+/// `L`, `M`, `X`, `J`, `V` (or `1` to `5`), to the `week_day` (0 is Monday).
+fn parse_week_day(day: &str) -> Option<i32> {
+    match day.trim().to_uppercase().as_str() {
+        "L" | "1" => Some(0),
+        "M" | "2" => Some(1),
+        "X" | "3" => Some(2),
+        "J" | "4" => Some(3),
+        "V" | "5" => Some(4),
+        _ => None,
+    }
+}
+
+/// The `groups.stage` (1: ESO, 2: Bachiller, 3: Ciclos).
+fn parse_stage(stage: &str) -> Option<i32> {
+    let stage = stage.trim().to_uppercase();
+    if stage.starts_with("ESO") {
+        Some(1)
+    } else if stage.starts_with("BACH") {
+        Some(2)
+    } else if stage.starts_with("FP") || stage.starts_with("CF") || stage.starts_with("CICLO") {
+        Some(3)
+    } else {
+        stage.parse().ok()
+    }
+}
+
+// endregion: --- Resolve
+
+// region:    --- Apply
+
+async fn get_or_create_schedule(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    owner: TimetableOwner,
+    course: i32,
+) -> Result<i64> {
+    let (column, owner_id, schedule_c) = match owner {
+        TimetableOwner::Teacher(user_id) => (
+            "user_id",
+            user_id,
+            ScheduleForCreate {
+                user_id: Some(user_id),
+                group_id: None,
+                course,
+            },
+        ),
+        TimetableOwner::Group(group_id) => (
+            "group_id",
+            group_id,
+            ScheduleForCreate {
+                user_id: None,
+                group_id: Some(group_id),
+                course,
+            },
+        ),
+    };
+
+    let sql = format!(
+        "SELECT id FROM {} WHERE {column} = $1 AND course = $2",
+        ScheduleBmc::TABLE
+    );
+    let schedule = mm
+        .dbx()
+        .fetch_optional(
+            sqlx::query_as::<_, (i64,)>(&sql)
+                .bind(owner_id)
+                .bind(course),
+        )
+        .await?;
+
+    match schedule {
+        Some((schedule_id,)) => Ok(schedule_id),
+        None => ScheduleBmc::create(ctx, mm, schedule_c).await,
+    }
+}
+
+async fn sync_schedule_hours(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    schedule_id: i64,
+    course: i32,
+    sync: ScheduleSync,
+    report: &mut TimetableImportReport,
+) -> Result<()> {
+    let ScheduleSync { mut hours, keep } = sync;
+
+    let sql = format!(
+        "SELECT id, week_day, n_hour, subject_name, classroom_name FROM {} \
+         WHERE schedule_id = $1 AND course = $2",
+        ScheduleHourBmc::TABLE
+    );
+    let existing = mm
+        .dbx()
+        .fetch_all(
+            sqlx::query_as::<_, (i64, i32, i32, String, String)>(&sql)
+                .bind(schedule_id)
+                .bind(course),
+        )
+        .await?;
+
+    // -- Update or delete the existing hours
+    for (id, week_day, n_hour, subject_name, classroom_name) in existing {
+        let slot = (week_day, n_hour);
+        match hours.remove(&slot) {
+            Some((subject, classroom))
+                if subject == subject_name && classroom == classroom_name => {}
+            Some((subject, classroom)) => {
+                let schedule_hour_u = ScheduleHourForUpdate {
+                    subject_name: Some(subject),
+                    classroom_name: Some(classroom),
+                    ..Default::default()
+                };
+                ScheduleHourBmc::update(ctx, mm, id, schedule_hour_u).await?;
+                report.updated += 1;
+            }
+            None if keep.contains(&slot) => {}
+            None => {
+                ScheduleHourBmc::delete(ctx, mm, id).await?;
+                report.deleted += 1;
+            }
+        }
+    }
+
+    // -- Create the new ones
+    for ((week_day, n_hour), (subject_name, classroom_name)) in hours {
+        let schedule_hour_c = ScheduleHourForCreate {
+            schedule_id,
+            subject_name,
+            classroom_name,
+            week_day,
+            n_hour,
+            course,
+            notes: None,
+        };
+        ScheduleHourBmc::create(ctx, mm, schedule_hour_c).await?;
+        report.created += 1;
+    }
+
+    Ok(())
+}
+
+// endregion: --- Apply
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serial_test::serial;
+
+    use crate::_dev_utils::{
+        self, seed_building, seed_classroom, seed_classroom_type, seed_department, seed_group,
+        seed_subject, seed_user,
+    };
+    use crate::ctx::Ctx;
+    use crate::model::import::itaca::{ImportUnmatched, TimetableImport};
+    use crate::model::import::{ImportBmc, ImportEntity};
+    use crate::model::schedule::ScheduleBmc;
+    use crate::model::schedule_hour::{ScheduleHour, ScheduleHourBmc, ScheduleHourFilter};
+
+    const FX_XML: &str = include_str!("../../../fixtures/itaca/horarios-2026.xml");
+
+    #[serial]
+    #[tokio::test]
+    async fn test_import_itaca_timetable_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let department_id = seed_department(&ctx, &mm, "test_import_itaca dept").await?;
+        let user_id = seed_user(&ctx, &mm, "test_itaca_teacher_01").await?;
+        seed_group(&ctx, &mm, "Q", 1, 1, 2026, "tutor".to_string()).await?;
+        seed_subject(
+            &ctx,
+            &mm,
+            "Test Itaca Matemáticas",
+            department_id,
+            false,
+            false,
+        )
+        .await?;
+        seed_subject(
+            &ctx,
+            &mm,
+            "Test Itaca Física y Química",
+            department_id,
+            false,
+            false,
+        )
+        .await?;
+        let building_id = seed_building(&ctx, &mm, "test_import_itaca building").await?;
+        let type_id = seed_classroom_type(&ctx, &mm, "test_import_itaca type").await?;
+        seed_classroom(&ctx, &mm, building_id, 9, 901, "TI-A01", type_id, "").await?;
+        let fx_import = || TimetableImport {
+            xml: FX_XML.to_string(),
+            dry_run: false,
+        };
+
+        // -- Exec
+        let report = ImportBmc::import_itaca_timetable(&ctx, &mm, fx_import()).await?;
+        let reimport = ImportBmc::import_itaca_timetable(&ctx, &mm, fx_import()).await?;
+
+        // -- Check
+        assert!(report.applied && report.errors.is_empty(), "{report:?}");
+        assert_eq!((report.sessions, report.skipped), (4, 2));
+        // Two hours in the teacher schedule, and two in the group one.
+        assert_eq!((report.created, report.updated, report.deleted), (4, 0, 0));
+        assert_eq!(
+            report.unmatched,
+            [
+                ImportUnmatched {
+                    entity: ImportEntity::Subject,
+                    code: "TI-GUA".to_string(),
+                    name: Some("Guardia sin dar de alta".to_string()),
+                },
+                ImportUnmatched {
+                    entity: ImportEntity::User,
+                    code: "99999999R".to_string(),
+                    name: Some("Docente Desconocido".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            (reimport.created, reimport.updated, reimport.deleted),
+            (0, 0, 0)
+        );
+
+        let schedule = ScheduleBmc::get_teacher_schedule(&ctx, &mm, user_id).await?;
+        let schedule = schedule.iter().find(|schedule| schedule.course == 2026);
+        let schedule_id = schedule.map(|schedule| schedule.id).unwrap_or_default();
+        let filters: Vec<ScheduleHourFilter> =
+            serde_json::from_value(serde_json::json!([{"schedule_id": schedule_id}]))?;
+        let hours: Vec<ScheduleHour> =
+            ScheduleHourBmc::list(&ctx, &mm, Some(filters), None).await?;
+        let hours: Vec<(i32, i32, &str, &str)> = hours
+            .iter()
+            .map(|hour| {
+                let ScheduleHour {
+                    week_day,
+                    n_hour,
+                    subject_name,
+                    classroom_name,
+                    ..
+                } = hour;
+                (
+                    *week_day,
+                    *n_hour,
+                    subject_name.as_str(),
+                    classroom_name.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            hours,
+            [
+                (0, 1, "Test Itaca Matemáticas", "TI-A01"),
+                (2, 2, "Test Itaca Física y Química", "TI-A01"),
+            ]
+        );
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
@@ -0,0 +1,500 @@
+//! CSV import of the center data (onboarding).
+//!
+//! - One entity per file, the columns are the `*ForCreate` fields
+//!   (`header_map` renames the file headers, e.g., `"Usuario" -> "username"`).
+//! - Upsert by natural key (see `ImportEntity`), soft deleted rows are restored
+//!   (the live row first, then the last deleted one).
+//! - All or nothing: the rows are applied in one transaction, committed only
+//!   when no row failed. With `dry_run`, it is always rolled back, so the report
+//!   also has the database errors (e.g., unknown `department_id`).
+//! - The timetables are imported from the regional administration export (see `itaca`).
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use csv::{ReaderBuilder, StringRecord, Trim};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::ctx::Ctx;
+use crate::model::base::{self, PostgresDbBmc};
+use crate::model::classroom::{ClassroomBmc, ClassroomForCreate};
+use crate::model::group::{GroupBmc, GroupForCreate};
+use crate::model::schedule::{ScheduleBmc, ScheduleForCreate};
+use crate::model::subject::{SubjectBmc, SubjectForCreate};
+use crate::model::user::{UserBmc, UserForCreate, UserForUpdate};
+use crate::model::{Error, ModelManager, Result};
+
+pub mod itaca;
+
+const IMPORT_SAVEPOINT: &str = "import_row";
+
+// region:    --- Import Types
+
+/// Natural keys:
+/// - `User`: `username`
+/// - `Group`: `course`, `stage`, `letter` (of the `year`)
+/// - `Subject`: `name`, `department_id`
+/// - `Classroom`: `name`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportEntity {
+    User,
+    Group,
+    Subject,
+    Classroom,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CsvImport {
+    pub entity: ImportEntity,
+    pub csv: String,
+    /// File header -> field name, the other headers are used as is.
+    #[serde(default)]
+    pub header_map: HashMap<String, String>,
+    /// `,` by default (e.g., `;` for spreadsheet exports).
+    pub delimiter: Option<char>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ImportReport {
+    pub entity: ImportEntity,
+    pub dry_run: bool,
+    /// `true` when committed (not `dry_run`, and no errors).
+    pub applied: bool,
+    pub rows: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub errors: Vec<ImportRowError>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ImportRowError {
+    /// File line (the header is line 1).
+    pub line: u64,
+    pub message: String,
+}
+
+enum Upserted {
+    Created,
+    Updated,
+}
+
+/// A `*ForCreate` row of the file.
+#[async_trait]
+trait ImportRow: DeserializeOwned + Send {
+    async fn upsert(self, ctx: &Ctx, mm: &ModelManager) -> Result<Upserted>;
+}
+
+// endregion: --- Import Types
+
+pub struct ImportBmc;
+
+impl ImportBmc {
+    pub async fn import_csv(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        import: CsvImport,
+    ) -> Result<ImportReport> {
+        match import.entity {
+            ImportEntity::User => import_rows::<UserForCreate>(ctx, mm, import).await,
+            ImportEntity::Group => import_rows::<GroupForCreate>(ctx, mm, import).await,
+            ImportEntity::Subject => import_rows::<SubjectForCreate>(ctx, mm, import).await,
+            ImportEntity::Classroom => import_rows::<ClassroomForCreate>(ctx, mm, import).await,
+        }
+    }
+}
+
+async fn import_rows<T>(ctx: &Ctx, mm: &ModelManager, import: CsvImport) -> Result<ImportReport>
+where
+    T: ImportRow,
+{
+    let CsvImport {
+        entity,
+        csv,
+        header_map,
+        delimiter,
+        dry_run,
+    } = import;
+
+    let mut report = ImportReport {
+        entity,
+        dry_run,
+        applied: false,
+        rows: 0,
+        created: 0,
+        updated: 0,
+        errors: Vec::new(),
+    };
+
+    // -- Parse
+    let mut rows: Vec<(u64, T)> = Vec::new();
+    for (line, row) in parse_csv::<T>(&csv, &header_map, delimiter)? {
+        report.rows += 1;
+        match row {
+            Ok(row) => rows.push((line, row)),
+            Err(message) => report.errors.push(ImportRowError { line, message }),
+        }
+    }
+
+    // -- Apply
+    // Note: Dropping `mm` without commit rolls the transaction back.
+    let mm = mm.new_with_txn();
+    mm.begin_txn().await?;
+    for (line, row) in rows {
+        mm.dbx().savepoint(IMPORT_SAVEPOINT).await?;
+        match row.upsert(ctx, &mm).await {
+            Ok(upserted) => {
+                mm.dbx().release_savepoint(IMPORT_SAVEPOINT).await?;
+                match upserted {
+                    Upserted::Created => report.created += 1,
+                    Upserted::Updated => report.updated += 1,
+                }
+            }
+            Err(err) => {
+                mm.dbx().rollback_to_savepoint(IMPORT_SAVEPOINT).await?;
+                report.errors.push(ImportRowError {
+                    line,
+                    message: err.to_string(),
+                });
+            }
+        }
+    }
+
+    if !dry_run && report.errors.is_empty() {
+        mm.commit_txn().await?;
+        report.applied = true;
+    }
+
+    Ok(report)
+}
+
+/// `(line, row)` of each record, the row `Err` is the line error message.
+fn parse_csv<T>(
+    csv: &str,
+    header_map: &HashMap<String, String>,
+    delimiter: Option<char>,
+) -> Result<Vec<(u64, core::result::Result<T, String>)>>
+where
+    T: DeserializeOwned,
+{
+    let delimiter = match delimiter {
+        None => b',',
+        Some(delimiter) if delimiter.is_ascii() => delimiter as u8,
+        Some(delimiter) => return Err(Error::ImportDelimiterInvalid(delimiter)),
+    };
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .trim(Trim::All)
+        .from_reader(csv.as_bytes());
+
+    let headers: StringRecord = reader
+        .headers()?
+        .iter()
+        .map(|header| header_map.get(header).map(String::as_str).unwrap_or(header))
+        .collect();
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let (line, row) = match record {
+            Ok(record) => {
+                let line = record.position().map(|pos| pos.line()).unwrap_or_default();
+                let row = record
+                    .deserialize::<T>(Some(&headers))
+                    .map_err(|err| err.to_string());
+                (line, row)
+            }
+            Err(err) => {
+                let line = err.position().map(|pos| pos.line()).unwrap_or_default();
+                (line, Err(err.to_string()))
+            }
+        };
+        rows.push((line, row));
+    }
+
+    Ok(rows)
+}
+
+// region:    --- Upserts
+
+/// New users also get their current year schedule (as `create_user`).
+#[async_trait]
+impl ImportRow for UserForCreate {
+    async fn upsert(self, ctx: &Ctx, mm: &ModelManager) -> Result<Upserted> {
+        let sql = format!(
+            "SELECT id, deleted_at IS NOT NULL FROM {} \
+             WHERE username = $1 AND ($2::BIGINT IS NULL OR center_id = $2) \
+             ORDER BY deleted_at DESC NULLS FIRST LIMIT 1",
+            UserBmc::TABLE
+        );
+        let existing = mm
+            .dbx()
+            .fetch_optional(
+                sqlx::query_as::<_, (i64, bool)>(&sql)
+                    .bind(&self.username)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+
+        match existing {
+            Some((id, deleted)) => {
+                if deleted {
+                    UserBmc::restore(ctx, mm, id).await?;
+                }
+                let user_u = UserForUpdate {
+                    is_admin: Some(self.is_admin),
+                    active: Some(self.active),
+                    department_id: Some(self.department_id.into()),
+                    substituting_id: Some(self.substituting_id.into()),
+                    ..Default::default()
+                };
+                UserBmc::update(ctx, mm, id, user_u).await?;
+                // Empty `pwd` keeps the current one.
+                if !self.pwd.is_empty() {
+                    UserBmc::update_pwd(ctx, mm, id, &self.pwd).await?;
+                }
+
+                Ok(Upserted::Updated)
+            }
+            None => {
+                if self.pwd.is_empty() {
+                    return Err(Error::ImportUserPwdMissing {
+                        username: self.username,
+                    });
+                }
+                let pwd = self.pwd.clone();
+                let id = UserBmc::create(ctx, mm, self).await?;
+                UserBmc::update_pwd(ctx, mm, id, &pwd).await?;
+
+                let schedule_c = ScheduleForCreate {
+                    user_id: Some(id),
+                    group_id: None,
+                    course: OffsetDateTime::now_utc().year(),
+                };
+                ScheduleBmc::create(ctx, mm, schedule_c).await?;
+
+                Ok(Upserted::Created)
+            }
+        }
+    }
+}
+
+/// New groups also get their `year` schedule (as `create_group`).
+#[async_trait]
+impl ImportRow for GroupForCreate {
+    async fn upsert(self, ctx: &Ctx, mm: &ModelManager) -> Result<Upserted> {
+        let sql = format!(
+            "SELECT id, deleted_at IS NOT NULL FROM {} \
+             WHERE course = $1 AND stage = $2 AND year = $3 AND letter = $4 \
+                 AND ($5::BIGINT IS NULL OR center_id = $5) \
+             ORDER BY deleted_at DESC NULLS FIRST LIMIT 1",
+            GroupBmc::TABLE
+        );
+        let existing = mm
+            .dbx()
+            .fetch_optional(
+                sqlx::query_as::<_, (i64, bool)>(&sql)
+                    .bind(self.course)
+                    .bind(self.stage)
+                    .bind(self.year)
+                    .bind(&self.letter)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+
+        match existing {
+            Some((id, deleted)) => {
+                if deleted {
+                    GroupBmc::restore(ctx, mm, id).await?;
+                }
+                base::update::<GroupBmc, _>(ctx, mm, id, self).await?;
+
+                Ok(Upserted::Updated)
+            }
+            None => {
+                let course = self.year;
+                let id = GroupBmc::create(ctx, mm, self).await?;
+
+                let schedule_c = ScheduleForCreate {
+                    user_id: None,
+                    group_id: Some(id),
+                    course,
+                };
+                ScheduleBmc::create(ctx, mm, schedule_c).await?;
+
+                Ok(Upserted::Created)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ImportRow for SubjectForCreate {
+    async fn upsert(self, ctx: &Ctx, mm: &ModelManager) -> Result<Upserted> {
+        let sql = format!(
+            "SELECT id, deleted_at IS NOT NULL FROM {} \
+             WHERE name = $1 AND department_id = $2 AND ($3::BIGINT IS NULL OR center_id = $3) \
+             ORDER BY deleted_at DESC NULLS FIRST LIMIT 1",
+            SubjectBmc::TABLE
+        );
+        let existing = mm
+            .dbx()
+            .fetch_optional(
+                sqlx::query_as::<_, (i64, bool)>(&sql)
+                    .bind(&self.name)
+                    .bind(self.department_id)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+
+        match existing {
+            Some((id, deleted)) => {
+                if deleted {
+                    SubjectBmc::restore(ctx, mm, id).await?;
+                }
+                base::update::<SubjectBmc, _>(ctx, mm, id, self).await?;
+
+                Ok(Upserted::Updated)
+            }
+            None => {
+                SubjectBmc::create(ctx, mm, self).await?;
+
+                Ok(Upserted::Created)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ImportRow for ClassroomForCreate {
+    async fn upsert(self, ctx: &Ctx, mm: &ModelManager) -> Result<Upserted> {
+        let sql = format!(
+            "SELECT id, deleted_at IS NOT NULL FROM {} \
+             WHERE name = $1 AND ($2::BIGINT IS NULL OR center_id = $2) \
+             ORDER BY deleted_at DESC NULLS FIRST LIMIT 1",
+            ClassroomBmc::TABLE
+        );
+        let existing = mm
+            .dbx()
+            .fetch_optional(
+                sqlx::query_as::<_, (i64, bool)>(&sql)
+                    .bind(&self.name)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+
+        match existing {
+            Some((id, deleted)) => {
+                if deleted {
+                    ClassroomBmc::restore(ctx, mm, id).await?;
+                }
+                base::update::<ClassroomBmc, _>(ctx, mm, id, self).await?;
+
+                Ok(Upserted::Updated)
+            }
+            None => {
+                ClassroomBmc::create(ctx, mm, self).await?;
+
+                Ok(Upserted::Created)
+            }
+        }
+    }
+}
+
+// endregion: --- Upserts
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serial_test::serial;
+
+    use crate::_dev_utils;
+    use crate::ctx::Ctx;
+    use crate::model::group::{Group, GroupBmc, GroupFilter};
+    use crate::model::import::{CsvImport, ImportBmc, ImportEntity};
+    use crate::model::user::{User, UserBmc};
+
+    #[serial]
+    #[tokio::test]
+    async fn test_import_csv_users_dry_run_errors() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_csv = "Usuario;pwd;is_admin;active;department_id\n\
+                      test_import_csv_01;welcome;false;true;\n\
+                      test_import_csv_02;welcome;maybe;true;\n\
+                      test_import_csv_03;;false;true;\n";
+        let fx_import = CsvImport {
+            entity: ImportEntity::User,
+            csv: fx_csv.to_string(),
+            header_map: [("Usuario".to_string(), "username".to_string())].into(),
+            delimiter: Some(';'),
+            dry_run: true,
+        };
+
+        // -- Exec
+        let report = ImportBmc::import_csv(&ctx, &mm, fx_import).await?;
+
+        // -- Check
+        assert_eq!(report.rows, 3);
+        assert_eq!(report.created, 1);
+        assert!(!report.applied);
+        let lines: Vec<u64> = report.errors.iter().map(|err| err.line).collect();
+        assert_eq!(lines, [3, 4], "bad bool, and new user without pwd");
+        let user = UserBmc::first_by_username::<User>(&ctx, &mm, "test_import_csv_01").await?;
+        assert!(user.is_none(), "dry run should not create the user");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_import_csv_groups_upsert_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_csv = |tutor_name: &str| {
+            format!(
+                "course,stage,year,letter,tutor_name\n\
+                 1,1,2026,Z,{tutor_name}\n\
+                 2,1,2026,Z,{tutor_name}\n"
+            )
+        };
+        let fx_import = |csv: String| CsvImport {
+            entity: ImportEntity::Group,
+            csv,
+            header_map: Default::default(),
+            delimiter: None,
+            dry_run: false,
+        };
+
+        // -- Exec
+        let created = ImportBmc::import_csv(&ctx, &mm, fx_import(fx_csv("Tutor A"))).await?;
+        let updated = ImportBmc::import_csv(&ctx, &mm, fx_import(fx_csv("Tutor B"))).await?;
+
+        // -- Check
+        // Note: The groups might be (soft deleted) from a previous run.
+        assert!(created.applied && created.errors.is_empty(), "{created:?}");
+        assert_eq!(created.created + created.updated, 2);
+        assert!(updated.applied && updated.errors.is_empty(), "{updated:?}");
+        assert_eq!((updated.created, updated.updated), (0, 2));
+        let filters: Vec<GroupFilter> =
+            serde_json::from_value(serde_json::json!([{"year": 2026, "letter": "Z"}]))?;
+        let groups: Vec<Group> = GroupBmc::list(&ctx, &mm, Some(filters), None).await?;
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(|group| group.tutor_name == "Tutor B"));
+
+        // -- Clean
+        for group in groups {
+            GroupBmc::delete(&ctx, &mm, group.id).await?;
+        }
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
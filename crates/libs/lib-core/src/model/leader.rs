@@ -0,0 +1,129 @@
+//! Leader election with a Postgres advisory lock, for the jobs that must run on only one
+//! of the instances (e.g., `ControlBmc::update_guards`).
+//!
+//! - The leader is the instance holding the session level `pg_advisory_lock` of the key,
+//!   on the `LeaderLock` own connection (not one of the pool).
+//! - When the leader stops (or its connection is lost), Postgres releases the lock,
+//!   and another instance takes it on its next `is_leader` check.
+//!
+//! ```
+//! let leader = LeaderLock::new(SCHEDULER_LOCK_KEY);
+//! if leader.is_leader().await? {
+//!     // run the job
+//! }
+//! ```
+
+use sqlx::{Connection, PgConnection};
+use tokio::sync::Mutex;
+
+use crate::core_config;
+use crate::model::Result;
+
+/// Key of the web-server scheduled jobs lock.
+pub const SCHEDULER_LOCK_KEY: i64 = 0x7465_6163_6865_7201;
+
+pub struct LeaderLock {
+    key: i64,
+    session: Mutex<Option<LockSession>>,
+}
+
+struct LockSession {
+    conn: PgConnection,
+    leader: bool,
+}
+
+impl LeaderLock {
+    pub fn new(key: i64) -> Self {
+        LeaderLock {
+            key,
+            session: Mutex::default(),
+        }
+    }
+
+    /// True when this instance holds the lock,
+    /// otherwise tries to take it (without waiting).
+    pub async fn is_leader(&self) -> Result<bool> {
+        let mut session_g = self.session.lock().await;
+
+        // -- Reuse the session, unless its connection (and the lock with it) was lost.
+        let reusable = match session_g.take() {
+            Some(mut session) => session.conn.ping().await.is_ok().then_some(session),
+            None => None,
+        };
+        let mut session = match reusable {
+            Some(session) => session,
+            None => LockSession {
+                conn: PgConnection::connect(&core_config().DB_URL).await?,
+                leader: false,
+            },
+        };
+
+        if !session.leader {
+            let (locked,): (bool,) = sqlx::query_as("SELECT pg_try_advisory_lock($1)")
+                .bind(self.key)
+                .fetch_one(&mut session.conn)
+                .await?;
+            session.leader = locked;
+        }
+
+        let leader = session.leader;
+        *session_g = Some(session);
+
+        Ok(leader)
+    }
+
+    /// Releases the lock (if held), for another instance to take it.
+    pub async fn resign(&self) -> Result<()> {
+        if let Some(mut session) = self.session.lock().await.take() {
+            if session.leader {
+                sqlx::query("SELECT pg_advisory_unlock($1)")
+                    .bind(self.key)
+                    .execute(&mut session.conn)
+                    .await?;
+            }
+            session.conn.close().await?;
+        }
+
+        Ok(())
+    }
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serial_test::serial;
+
+    use crate::_dev_utils;
+    use crate::model::leader::LeaderLock;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_leader_one_at_a_time_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        _dev_utils::init_test().await;
+        let fx_key = 4200;
+        let instance_1 = LeaderLock::new(fx_key);
+        let instance_2 = LeaderLock::new(fx_key);
+
+        // -- Exec & Check
+        assert!(instance_1.is_leader().await?, "First should take the lock");
+        assert!(
+            !instance_2.is_leader().await?,
+            "Second should not while held"
+        );
+        assert!(instance_1.is_leader().await?, "First should still hold it");
+
+        instance_1.resign().await?;
+        assert!(
+            instance_2.is_leader().await?,
+            "Second should take it once released"
+        );
+
+        // -- Clean
+        instance_2.resign().await?;
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
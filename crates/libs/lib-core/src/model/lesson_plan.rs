@@ -0,0 +1,619 @@
+//! Lesson plans, the work an absent teacher leaves for a class
+//! (a `schedule_hours` slot on a date), with its attachments.
+//!
+//! - Written by the teacher of the slot (or an admin), read by the center users
+//!   (e.g., the guard teacher covering it, see `for_covering`).
+//! - The attachments are stored in the db (up to `ATTACHMENT_MAX_SIZE`), uploaded base64url
+//!   encoded, and downloaded from the web-server `/api/lesson-plans/attachments/:id`.
+//! - The guard notification of a covered slot includes it (see `note`).
+
+use modql::field::{Fields, HasFields};
+use modql::filter::{FilterNodes, ListOptions, OpValsInt32, OpValsInt64, OpValsValue};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::{Date, OffsetDateTime};
+
+use lib_utils::b64::b64u_decode;
+use lib_utils::time::iso_date;
+
+use crate::ctx::Ctx;
+use crate::model::base::{self, PostgresDbBmc};
+use crate::model::modql_utils::{date_to_sea_value, time_to_sea_value};
+use crate::model::schedule::{Schedule, ScheduleBmc};
+use crate::model::schedule_hour::{ScheduleHour, ScheduleHourBmc};
+use crate::model::substitution::{SubstitutionBmc, STATUS_COVERED};
+use crate::model::user::UserBmc;
+use crate::model::Result;
+use crate::model::{Error, ModelManager};
+
+/// Decoded size, 10 MiB.
+pub const ATTACHMENT_MAX_SIZE: usize = 10 * 1024 * 1024;
+
+// region:    --- LessonPlan Types
+
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
+pub struct LessonPlan {
+    pub id: i64,
+    pub schedule_hour_id: i64,
+    pub course: i32,
+    #[serde(with = "iso_date")]
+    #[schemars(with = "String")]
+    pub date: Date,
+    pub text: String,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
+}
+
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
+pub struct LessonPlanForCreate {
+    pub schedule_hour_id: i64,
+    /// The schedule hour course.
+    pub course: i32,
+    /// The absence date.
+    #[serde(with = "iso_date")]
+    #[schemars(with = "String")]
+    pub date: Date,
+    pub text: String,
+}
+
+#[derive(Fields, Default, Deserialize, JsonSchema)]
+pub struct LessonPlanForUpdate {
+    pub text: Option<String>,
+}
+
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct LessonPlanFilter {
+    pub id: Option<OpValsInt64>,
+    pub schedule_hour_id: Option<OpValsInt64>,
+    pub course: Option<OpValsInt32>,
+    #[modql(to_sea_value_fn = "date_to_sea_value")]
+    pub date: Option<OpValsValue>,
+
+    pub cid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    pub ctime: Option<OpValsValue>,
+    pub mid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    pub mtime: Option<OpValsValue>,
+}
+
+/// The attachment without its content.
+#[derive(Debug, Clone, FromRow, Serialize, JsonSchema)]
+pub struct LessonPlanAttachment {
+    pub id: i64,
+    pub lesson_plan_id: i64,
+    pub filename: String,
+    pub content_type: String,
+    /// In bytes.
+    pub size: i64,
+}
+
+#[derive(Deserialize, Clone, JsonSchema)]
+pub struct LessonPlanAttachmentForCreate {
+    pub lesson_plan_id: i64,
+    /// e.g., "Ejercicios tema 3.pdf"
+    pub filename: String,
+    /// e.g., "application/pdf"
+    pub content_type: String,
+    /// base64url (no padding).
+    pub content: String,
+}
+
+/// A lesson plan of a class covered by the ctx user.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct CoverLessonPlan {
+    pub id: i64,
+    #[serde(with = "iso_date")]
+    #[schemars(with = "String")]
+    pub date: Date,
+    pub n_hour: i32,
+    pub subject_name: String,
+    pub classroom_name: String,
+    pub absent_username: String,
+    pub text: String,
+    pub attachments: Vec<LessonPlanAttachment>,
+}
+
+#[derive(FromRow)]
+struct CoverLessonPlanRow {
+    id: i64,
+    date: Date,
+    n_hour: i32,
+    subject_name: String,
+    classroom_name: String,
+    absent_username: String,
+    text: String,
+}
+
+/// The lesson plan of the guard notification.
+#[derive(Debug, Clone)]
+pub struct LessonPlanNote {
+    pub text: String,
+    /// The attachments filenames (downloaded from the app).
+    pub attachments: Vec<String>,
+}
+
+/// Marker trait
+pub trait LessonPlanBy: HasFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
+
+impl LessonPlanBy for LessonPlan {}
+
+// endregion: --- LessonPlan Types
+
+pub struct LessonPlanBmc;
+
+impl PostgresDbBmc for LessonPlanBmc {
+    const TABLE: &'static str = "lesson_plans";
+    const CENTER_SCOPED: bool = true;
+}
+
+impl LessonPlanBmc {
+    pub const ATTACHMENT_TABLE: &'static str = "lesson_plan_attachments";
+
+    pub async fn create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        lesson_plan_c: LessonPlanForCreate,
+    ) -> Result<i64> {
+        check_owner(ctx, mm, lesson_plan_c.schedule_hour_id).await?;
+
+        base::create::<Self, _>(ctx, mm, lesson_plan_c).await
+    }
+
+    pub async fn get<E>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
+    where
+        E: LessonPlanBy,
+    {
+        base::get::<Self, _>(ctx, mm, id).await
+    }
+
+    pub async fn list(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<LessonPlanFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<LessonPlan>> {
+        base::list::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
+
+    pub async fn update(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        lesson_plan_u: LessonPlanForUpdate,
+    ) -> Result<()> {
+        Self::update_if_unmodified(ctx, mm, id, lesson_plan_u, None).await
+    }
+
+    pub async fn update_if_unmodified(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        lesson_plan_u: LessonPlanForUpdate,
+        expected_mtime: Option<OffsetDateTime>,
+    ) -> Result<()> {
+        let lesson_plan: LessonPlan = Self::get(ctx, mm, id).await?;
+        check_owner(ctx, mm, lesson_plan.schedule_hour_id).await?;
+
+        base::update_if_unmodified::<Self, _>(ctx, mm, id, lesson_plan_u, expected_mtime).await
+    }
+
+    /// With its attachments.
+    pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        let lesson_plan: LessonPlan = Self::get(ctx, mm, id).await?;
+        check_owner(ctx, mm, lesson_plan.schedule_hour_id).await?;
+
+        base::delete::<Self>(ctx, mm, id).await
+    }
+
+    pub async fn add_attachment(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        attachment_c: LessonPlanAttachmentForCreate,
+    ) -> Result<i64> {
+        let LessonPlanAttachmentForCreate {
+            lesson_plan_id,
+            filename,
+            content_type,
+            content,
+        } = attachment_c;
+
+        let lesson_plan: LessonPlan = Self::get(ctx, mm, lesson_plan_id).await?;
+        check_owner(ctx, mm, lesson_plan.schedule_hour_id).await?;
+
+        // Note: Checked before decoding, the base64url (no padding) is 4/3 of the content.
+        let size = content.len() * 3 / 4;
+        if size > ATTACHMENT_MAX_SIZE {
+            return Err(Error::LessonPlanAttachmentTooLarge {
+                max: ATTACHMENT_MAX_SIZE,
+                actual: size,
+            });
+        }
+        let content = b64u_decode(&content).map_err(|_| Error::LessonPlanAttachmentNotBase64)?;
+
+        // Same center as the lesson plan.
+        let sql = format!(
+            "INSERT INTO {} (center_id, lesson_plan_id, filename, content_type, content, cid, ctime) \
+             SELECT center_id, id, $2, $3, $4, $5, now() FROM {} WHERE id = $1 \
+             RETURNING id",
+            Self::ATTACHMENT_TABLE,
+            Self::TABLE
+        );
+        let (id,) = mm
+            .dbx()
+            .fetch_one(
+                sqlx::query_as::<_, (i64,)>(&sql)
+                    .bind(lesson_plan_id)
+                    .bind(filename)
+                    .bind(content_type)
+                    .bind(content)
+                    .bind(ctx.user_id()),
+            )
+            .await?;
+
+        Ok(id)
+    }
+
+    /// The attachments of the lesson plans, by lesson plan then id.
+    pub async fn attachments(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        lesson_plan_ids: &[i64],
+    ) -> Result<Vec<LessonPlanAttachment>> {
+        let sql = format!(
+            "SELECT id, lesson_plan_id, filename, content_type, \
+                    octet_length(content)::BIGINT AS size \
+             FROM {} \
+             WHERE lesson_plan_id = ANY($1) AND ($2::BIGINT IS NULL OR center_id = $2) \
+             ORDER BY lesson_plan_id, id",
+            Self::ATTACHMENT_TABLE
+        );
+        let attachments = mm
+            .dbx()
+            .fetch_all(
+                sqlx::query_as::<_, LessonPlanAttachment>(&sql)
+                    .bind(lesson_plan_ids)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+
+        Ok(attachments)
+    }
+
+    /// The attachment and its content (e.g., for the download).
+    pub async fn attachment_content(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+    ) -> Result<(LessonPlanAttachment, Vec<u8>)> {
+        let sql = format!(
+            "SELECT id, lesson_plan_id, filename, content_type, \
+                    octet_length(content)::BIGINT AS size, content \
+             FROM {} WHERE id = $1 AND ($2::BIGINT IS NULL OR center_id = $2)",
+            Self::ATTACHMENT_TABLE
+        );
+        let row = mm
+            .dbx()
+            .fetch_optional(
+                sqlx::query_as::<_, (i64, i64, String, String, i64, Vec<u8>)>(&sql)
+                    .bind(id)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+
+        match row {
+            Some((id, lesson_plan_id, filename, content_type, size, content)) => {
+                let attachment = LessonPlanAttachment {
+                    id,
+                    lesson_plan_id,
+                    filename,
+                    content_type,
+                    size,
+                };
+                Ok((attachment, content))
+            }
+            None => Err(Error::EntityNotFound {
+                entity: Self::ATTACHMENT_TABLE,
+                id,
+            }),
+        }
+    }
+
+    pub async fn delete_attachment(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        let (attachment, _) = Self::attachment_content(ctx, mm, id).await?;
+        let lesson_plan: LessonPlan = Self::get(ctx, mm, attachment.lesson_plan_id).await?;
+        check_owner(ctx, mm, lesson_plan.schedule_hour_id).await?;
+
+        let sql = format!("DELETE FROM {} WHERE id = $1", Self::ATTACHMENT_TABLE);
+        mm.dbx().execute(sqlx::query(&sql).bind(id)).await?;
+
+        Ok(())
+    }
+
+    /// The lesson plans of the classes covered by `user_id` on `date`
+    /// (see `SubstitutionBmc`), by hour.
+    pub async fn for_covering(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        user_id: i64,
+        date: Date,
+    ) -> Result<Vec<CoverLessonPlan>> {
+        let sql = format!(
+            "SELECT lp.id, lp.date, h.n_hour, h.subject_name, h.classroom_name, \
+                    u.username AS absent_username, lp.text \
+             FROM {} lp \
+             JOIN {} h ON h.id = lp.schedule_hour_id AND h.course = lp.course \
+             JOIN {} s ON s.id = h.schedule_id AND s.course = h.course \
+             JOIN {} sub ON sub.absent_id = s.user_id AND sub.date = lp.date \
+                 AND sub.n_hour = h.n_hour \
+             JOIN {} u ON u.id = s.user_id \
+             WHERE sub.covering_id = $1 AND sub.status = '{STATUS_COVERED}' AND lp.date = $2 \
+                 AND ($3::BIGINT IS NULL OR lp.center_id = $3) \
+             ORDER BY h.n_hour, lp.id",
+            Self::TABLE,
+            ScheduleHourBmc::TABLE,
+            ScheduleBmc::TABLE,
+            SubstitutionBmc::TABLE,
+            UserBmc::TABLE
+        );
+        let rows = mm
+            .dbx()
+            .fetch_all(
+                sqlx::query_as::<_, CoverLessonPlanRow>(&sql)
+                    .bind(user_id)
+                    .bind(date)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+
+        let lesson_plan_ids: Vec<i64> = rows.iter().map(|row| row.id).collect();
+        let mut attachments = Self::attachments(ctx, mm, &lesson_plan_ids).await?;
+
+        let cover_lesson_plans = rows
+            .into_iter()
+            .map(|row| {
+                let (row_attachments, rest): (Vec<_>, Vec<_>) = attachments
+                    .drain(..)
+                    .partition(|attachment| attachment.lesson_plan_id == row.id);
+                attachments = rest;
+                CoverLessonPlan {
+                    id: row.id,
+                    date: row.date,
+                    n_hour: row.n_hour,
+                    subject_name: row.subject_name,
+                    classroom_name: row.classroom_name,
+                    absent_username: row.absent_username,
+                    text: row.text,
+                    attachments: row_attachments,
+                }
+            })
+            .collect();
+
+        Ok(cover_lesson_plans)
+    }
+
+    /// The lesson plan of the slot on `date`, for the guard notification.
+    pub async fn note(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        schedule_hour_id: i64,
+        date: Date,
+    ) -> Result<Option<LessonPlanNote>> {
+        let sql = format!(
+            "SELECT id, text FROM {} \
+             WHERE schedule_hour_id = $1 AND date = $2 \
+                 AND ($3::BIGINT IS NULL OR center_id = $3)",
+            Self::TABLE
+        );
+        let lesson_plan = mm
+            .dbx()
+            .fetch_optional(
+                sqlx::query_as::<_, (i64, String)>(&sql)
+                    .bind(schedule_hour_id)
+                    .bind(date)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+        let Some((id, text)) = lesson_plan else {
+            return Ok(None);
+        };
+
+        let attachments = Self::attachments(ctx, mm, &[id])
+            .await?
+            .into_iter()
+            .map(|attachment| attachment.filename)
+            .collect();
+
+        Ok(Some(LessonPlanNote { text, attachments }))
+    }
+}
+
+/// The ctx user is the teacher of the schedule hour, or an admin.
+async fn check_owner(ctx: &Ctx, mm: &ModelManager, schedule_hour_id: i64) -> Result<()> {
+    if ctx.admin() {
+        return Ok(());
+    }
+
+    let schedule_hour: ScheduleHour = ScheduleHourBmc::get(ctx, mm, schedule_hour_id).await?;
+    let schedule: Schedule = ScheduleBmc::get(ctx, mm, schedule_hour.schedule_id).await?;
+    if schedule.user_id == Some(ctx.user_id()) {
+        Ok(())
+    } else {
+        Err(Error::LessonPlanNotOwner { schedule_hour_id })
+    }
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use modql::filter::OpValsInt64;
+    use serial_test::serial;
+    use time::macros::date;
+
+    use lib_utils::b64::b64u_encode;
+
+    use crate::_dev_utils;
+    use crate::ctx::{Ctx, DEFAULT_CENTER_ID};
+    use crate::model::lesson_plan::{
+        LessonPlanAttachmentForCreate, LessonPlanBmc, LessonPlanForCreate,
+    };
+    use crate::model::schedule::ScheduleBmc;
+    use crate::model::substitution::{SubstitutionBmc, SubstitutionFilter, SubstitutionForRecord};
+    use crate::model::user::UserBmc;
+    use crate::model::Error;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_for_covering_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_date = date!(2025 - 10 - 06);
+        let fx_text = "Ejercicios 1 a 5 de la página 42.";
+        let fx_content = b"%PDF-1.4 test_for_covering_ok";
+        let absent_id = _dev_utils::seed_user(&ctx, &mm, "test_for_covering_ok absent").await?;
+        let guard_id = _dev_utils::seed_user(&ctx, &mm, "test_for_covering_ok guard").await?;
+        let schedule_id = _dev_utils::seed_schedule(&ctx, &mm, 2025, absent_id, -1).await?;
+        let schedule_hour_id = _dev_utils::seed_schedule_hour(
+            &ctx,
+            &mm,
+            schedule_id,
+            "Matemáticas",
+            "Info 2",
+            0,
+            2,
+            2025,
+        )
+        .await?;
+
+        // The absent teacher leaves the work.
+        let absent_ctx = Ctx::new(absent_id, false, DEFAULT_CENTER_ID);
+        let lesson_plan_c = LessonPlanForCreate {
+            schedule_hour_id,
+            course: 2025,
+            date: fx_date,
+            text: fx_text.to_string(),
+        };
+        let lesson_plan_id = LessonPlanBmc::create(&absent_ctx, &mm, lesson_plan_c).await?;
+        let attachment_c = LessonPlanAttachmentForCreate {
+            lesson_plan_id,
+            filename: "Tema 3.pdf".to_string(),
+            content_type: "application/pdf".to_string(),
+            content: b64u_encode(fx_content),
+        };
+        let attachment_id = LessonPlanBmc::add_attachment(&absent_ctx, &mm, attachment_c).await?;
+
+        let substitution_r = SubstitutionForRecord {
+            date: fx_date,
+            course: 2025,
+            week_day: 0,
+            n_hour: 2,
+            absent_id,
+            covering_id: Some(guard_id),
+            classroom_name: "Info 2".to_string(),
+        };
+        SubstitutionBmc::record(&ctx, &mm, substitution_r).await?;
+
+        // -- Exec
+        let guard_ctx = Ctx::new(guard_id, false, DEFAULT_CENTER_ID);
+        let cover_lesson_plans =
+            LessonPlanBmc::for_covering(&guard_ctx, &mm, guard_id, fx_date).await?;
+        let note = LessonPlanBmc::note(&ctx, &mm, schedule_hour_id, fx_date).await?;
+
+        // -- Check
+        assert_eq!(cover_lesson_plans.len(), 1);
+        let cover_lesson_plan = &cover_lesson_plans[0];
+        assert_eq!(cover_lesson_plan.id, lesson_plan_id);
+        assert_eq!(
+            cover_lesson_plan.absent_username,
+            "test_for_covering_ok absent"
+        );
+        assert_eq!(cover_lesson_plan.text, fx_text);
+        assert_eq!(cover_lesson_plan.attachments.len(), 1);
+        assert_eq!(
+            cover_lesson_plan.attachments[0].size,
+            fx_content.len() as i64
+        );
+
+        let (_, content) =
+            LessonPlanBmc::attachment_content(&guard_ctx, &mm, attachment_id).await?;
+        assert_eq!(content, fx_content);
+
+        let note = note.expect("Should have the note");
+        assert_eq!(note.text, fx_text);
+        assert_eq!(note.attachments, ["Tema 3.pdf"]);
+
+        // -- Clean
+        let substitution_filters = vec![SubstitutionFilter {
+            absent_id: Some(OpValsInt64::from(absent_id)),
+            ..Default::default()
+        }];
+        for substitution in
+            SubstitutionBmc::list(&ctx, &mm, Some(substitution_filters), None).await?
+        {
+            SubstitutionBmc::delete(&ctx, &mm, substitution.id).await?;
+        }
+        // Note: The lesson plan and its attachment are deleted with the schedule hours.
+        ScheduleBmc::delete(&ctx, &mm, schedule_id).await?;
+        UserBmc::delete(&ctx, &mm, absent_id).await?;
+        UserBmc::delete(&ctx, &mm, guard_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_err_not_owner() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let teacher_id =
+            _dev_utils::seed_user(&ctx, &mm, "test_create_err_not_owner teacher").await?;
+        let other_id = _dev_utils::seed_user(&ctx, &mm, "test_create_err_not_owner other").await?;
+        let schedule_id = _dev_utils::seed_schedule(&ctx, &mm, 2025, teacher_id, -1).await?;
+        let schedule_hour_id = _dev_utils::seed_schedule_hour(
+            &ctx,
+            &mm,
+            schedule_id,
+            "Matemáticas",
+            "Info 2",
+            1,
+            3,
+            2025,
+        )
+        .await?;
+
+        // -- Exec
+        let other_ctx = Ctx::new(other_id, false, DEFAULT_CENTER_ID);
+        let lesson_plan_c = LessonPlanForCreate {
+            schedule_hour_id,
+            course: 2025,
+            date: date!(2025 - 10 - 07),
+            text: "Leer el tema 4.".to_string(),
+        };
+        let res = LessonPlanBmc::create(&other_ctx, &mm, lesson_plan_c).await;
+
+        // -- Check
+        assert!(
+            matches!(
+                res,
+                Err(Error::LessonPlanNotOwner { schedule_hour_id: id }) if id == schedule_hour_id
+            ),
+            "Should have matched `LessonPlanNotOwner` but was {res:?}"
+        );
+
+        // -- Clean
+        ScheduleBmc::delete(&ctx, &mm, schedule_id).await?;
+        UserBmc::delete(&ctx, &mm, teacher_id).await?;
+        UserBmc::delete(&ctx, &mm, other_id).await?;
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
@@ -15,49 +15,144 @@
 //! - In frameworks like Axum, Tauri, `ModelManager` are typically used as App State.
 //! - ModelManager are designed to be passed as an argument
 //!   to all Model Controllers functions.
+//! - Multi-step operations use `mm.new_with_txn()` then `begin_txn` / `commit_txn`,
+//!   all the `*Bmc` calls made with that `ModelManager` share the transaction.
+//! - The `ModelManager` also holds the `EventBus`, the `*Bmc` functions `publish`
+//!   their `ModelEvent`s once committed (see `event`).
+//! - For the jobs to run on only one of the instances, see `leader`.
 //!
 
 // region:    --- Modules
 
-use crate::model::store::{new_db_pool, PostgresDb};
+use std::sync::Arc;
 
+use tokio::sync::{broadcast, Mutex};
+
+use crate::core_config;
+use crate::model::event::{EventBus, ModelEvent};
+use crate::model::store::dbx::Dbx;
+use crate::model::store::new_db_pool;
+
+pub use self::base::BulkMode;
 pub use self::error::{Error, Result};
 
+pub mod archive;
+pub mod audit_log;
 mod base;
-mod error;
-pub mod modql_utils;
-mod store;
-pub mod user;
+pub mod building;
+pub mod calendar;
+pub mod center;
+pub mod center_schedule_hour;
+pub mod classroom;
+pub mod classroom_type;
+pub mod control;
 pub mod department;
-pub mod subject;
+pub mod display_token;
+mod error;
+pub mod event;
 pub mod group;
-pub mod classroom;
+pub mod import;
+pub mod leader;
+pub mod lesson_plan;
+pub mod modql_utils;
+pub mod notification;
+pub mod push_subscription;
+pub mod report;
 pub mod schedule;
 pub mod schedule_hour;
-pub mod control;
-pub mod center_schedule_hour;
-pub mod building;
-pub mod classroom_type;
-
+mod store;
+pub mod subject;
+pub mod substitution;
+pub mod user;
 
 // endregion: --- Modules
 
+/// Pool size of the regular runs.
+const DB_MAX_CONNECTIONS: u32 = 5;
+
 #[derive(Clone)]
 pub struct ModelManager {
-    postgres_db: PostgresDb,
+    dbx: Dbx,
+    bus: EventBus,
+    /// Events published in the open transaction, sent on its commit.
+    pending_events: Arc<Mutex<Vec<ModelEvent>>>,
 }
 
 impl ModelManager {
     /// Constructor
     pub async fn new() -> Result<Self> {
-        let postgres_db = new_db_pool().await?;
+        Self::new_with_max_connections(DB_MAX_CONNECTIONS).await
+    }
+
+    /// Constructor, with the pool size (e.g., 1 for the tests).
+    pub async fn new_with_max_connections(max_connections: u32) -> Result<Self> {
+        let postgres_db = new_db_pool(max_connections).await?;
+        let bus = match core_config().EVENT_BUS.as_str() {
+            "pg" => EventBus::new_pg(postgres_db.clone(), &core_config().DB_URL).await?,
+            _ => EventBus::default(),
+        };
+        let dbx = Dbx::new(postgres_db, false);
+
+        Ok(ModelManager {
+            dbx,
+            bus,
+            pending_events: Arc::default(),
+        })
+    }
+
+    /// Model manager running all its queries on one transaction
+    /// (see `begin_txn` / `commit_txn`).
+    ///
+    /// Note: When already with txn, the same transaction is shared.
+    pub fn new_with_txn(&self) -> ModelManager {
+        if self.dbx.with_txn() {
+            return self.clone();
+        }
+
+        let dbx = Dbx::new(self.dbx.db().clone(), true);
+        ModelManager {
+            dbx,
+            bus: self.bus.clone(),
+            pending_events: Arc::default(),
+        }
+    }
+
+    pub async fn begin_txn(&self) -> Result<()> {
+        self.dbx.begin_txn().await?;
+        Ok(())
+    }
+
+    pub async fn commit_txn(&self) -> Result<()> {
+        self.dbx.commit_txn().await?;
+
+        // Only the outermost commit actually commits.
+        if !self.dbx.has_open_txn().await {
+            let events = std::mem::take(&mut *self.pending_events.lock().await);
+            for event in events {
+                self.bus.send(event).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends the event to the bus subscribers,
+    /// when the open transaction (if any) is committed.
+    pub async fn publish(&self, event: ModelEvent) {
+        if self.dbx.has_open_txn().await {
+            self.pending_events.lock().await.push(event);
+        } else {
+            self.bus.send(event).await;
+        }
+    }
 
-        Ok(ModelManager { postgres_db })
+    pub fn subscribe(&self) -> broadcast::Receiver<ModelEvent> {
+        self.bus.subscribe()
     }
 
-    /// Returns the sqlx db pool reference.
+    /// Returns the db executor reference.
     /// (Only for the model layer)
-    pub(in crate::model) fn postgres_db(&self) -> &PostgresDb {
-        &self.postgres_db
+    pub(in crate::model) fn dbx(&self) -> &Dbx {
+        &self.dbx
     }
 }
@@ -1,7 +1,64 @@
 use time::serde::rfc3339;
 
+use lib_utils::time::iso_date;
+
 pub fn time_to_sea_value(
     json_value: serde_json::Value,
 ) -> modql::filter::SeaResult<sea_query::Value> {
     Ok(rfc3339::deserialize(json_value)?.into())
 }
+
+/// `YYYY-MM-DD` filter values of the `date` columns.
+pub fn date_to_sea_value(
+    json_value: serde_json::Value,
+) -> modql::filter::SeaResult<sea_query::Value> {
+    Ok(iso_date::deserialize(json_value)?.into())
+}
+
+// region:    --- NullableValue
+
+/// Value of a nullable column in the `ForUpdate` types,
+/// `Some(NullableValue(None))` sets the column to NULL (see `base::update`).
+///
+/// Note: The modql `Fields` need a `sea_query::Nullable` value,
+///       which a plain `Option<Option<T>>` is not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NullableValue<T>(pub Option<T>);
+
+impl<T> From<Option<T>> for NullableValue<T> {
+    fn from(value: Option<T>) -> Self {
+        NullableValue(value)
+    }
+}
+
+impl<T> From<NullableValue<T>> for sea_query::Value
+where
+    T: Into<sea_query::Value> + sea_query::Nullable,
+{
+    fn from(value: NullableValue<T>) -> Self {
+        value.0.into()
+    }
+}
+
+impl<T> sea_query::Nullable for NullableValue<T>
+where
+    T: sea_query::Nullable,
+{
+    fn null() -> sea_query::Value {
+        T::null()
+    }
+}
+
+/// Explicit `null` to `Some(NullableValue(None))`, with `#[serde(default)]` for the absent ones,
+/// e.g., `#[serde(default, deserialize_with = "nullable_value")]`.
+pub fn nullable_value<'de, D, T>(deserializer: D) -> Result<Option<NullableValue<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    let value = <Option<T> as serde::Deserialize>::deserialize(deserializer)?;
+
+    Ok(Some(NullableValue(value)))
+}
+
+// endregion: --- NullableValue
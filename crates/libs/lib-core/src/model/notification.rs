@@ -0,0 +1,660 @@
+//! Email notifications (e.g., the guard assigned by `ControlBmc::update_guards`).
+//!
+//! - `enqueue` renders the `Notification` template into the `notification_outbox`,
+//!   on the caller transaction (if any), when the user has an email and wants that kind
+//!   (`notification_prefs`, all on by default, the password reset always on).
+//! - `deliver_pending` sends the due outbox mails with a `lib_mail::Mailer`, and retries
+//!   the failed ones (exponential backoff, `failed` after `MAX_ATTEMPTS`).
+//! - The rows are claimed with `FOR UPDATE SKIP LOCKED`, so concurrent deliveries never
+//!   send the same mail, but a crash between a send and the commit sends it again
+//!   (at-least-once).
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::{Date, OffsetDateTime};
+
+use lib_mail::{Mail, Mailer};
+
+use crate::ctx::Ctx;
+use crate::model::base::PostgresDbBmc;
+use crate::model::lesson_plan::LessonPlanNote;
+use crate::model::user::{UserBmc, UserForAuth};
+use crate::model::Result;
+use crate::model::{Error, ModelManager};
+
+const MAX_ATTEMPTS: i32 = 5;
+/// Wait before the first retry, doubled on each failed attempt.
+const RETRY_BASE_SEC: i64 = 60;
+
+/// `week_day` 0 (Monday) to 6.
+const WEEK_DAYS: [&str; 7] = [
+    "lunes",
+    "martes",
+    "miércoles",
+    "jueves",
+    "viernes",
+    "sábado",
+    "domingo",
+];
+
+// region:    --- Notification Types
+
+#[derive(Debug, Clone)]
+pub enum Notification {
+    GuardAssigned {
+        classroom_name: String,
+        week_day: i32,
+        n_hour: i32,
+        /// The work left by the absent teacher, if any.
+        lesson_plan: Option<LessonPlanNote>,
+    },
+    LeaveApproved {
+        from: Date,
+        to: Date,
+    },
+    PasswordReset {
+        reset_url: String,
+    },
+    MissingCheckout {
+        date: Date,
+    },
+    /// To the heads of studies.
+    ClassUncovered {
+        classroom_name: String,
+        week_day: i32,
+        n_hour: i32,
+    },
+}
+
+impl Notification {
+    /// Value stored in `notification_outbox.kind`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Notification::GuardAssigned { .. } => "guard_assigned",
+            Notification::LeaveApproved { .. } => "leave_approved",
+            Notification::PasswordReset { .. } => "password_reset",
+            Notification::MissingCheckout { .. } => "missing_checkout",
+            Notification::ClassUncovered { .. } => "class_uncovered",
+        }
+    }
+
+    /// The mail `(subject, body)`.
+    pub(in crate::model) fn render(&self) -> (String, String) {
+        match self {
+            Notification::GuardAssigned {
+                classroom_name,
+                week_day,
+                n_hour,
+                lesson_plan,
+            } => {
+                let day = usize::try_from(*week_day)
+                    .ok()
+                    .and_then(|week_day| WEEK_DAYS.get(week_day))
+                    .unwrap_or(&"hoy");
+                let mut body = format!(
+                    "Se te ha asignado una guardia en {classroom_name}, el {day} a la {n_hour}ª hora."
+                );
+                if let Some(lesson_plan) = lesson_plan {
+                    body.push_str(&format!("\n\nTarea para el alumnado:\n{}", lesson_plan.text));
+                    if !lesson_plan.attachments.is_empty() {
+                        body.push_str(&format!(
+                            "\n\nAdjuntos (en la aplicación): {}",
+                            lesson_plan.attachments.join(", ")
+                        ));
+                    }
+                }
+                (format!("Guardia asignada en {classroom_name}"), body)
+            }
+            Notification::LeaveApproved { from, to } => (
+                "Permiso aprobado".to_string(),
+                format!("Tu permiso del {from} al {to} ha sido aprobado."),
+            ),
+            Notification::PasswordReset { reset_url } => (
+                "Restablecer contraseña".to_string(),
+                format!(
+                    "Para restablecer tu contraseña, abre este enlace:\n\n{reset_url}\n\n\
+                     Si no lo has solicitado, ignora este mensaje."
+                ),
+            ),
+            Notification::MissingCheckout { date } => (
+                "Salida no registrada".to_string(),
+                format!(
+                    "No has registrado tu salida del centro el {date}. Recuerda registrarla al salir."
+                ),
+            ),
+            Notification::ClassUncovered {
+                classroom_name,
+                week_day,
+                n_hour,
+            } => {
+                let day = usize::try_from(*week_day)
+                    .ok()
+                    .and_then(|week_day| WEEK_DAYS.get(week_day))
+                    .unwrap_or(&"hoy");
+                (
+                    format!("Clase sin cubrir en {classroom_name}"),
+                    format!(
+                        "No queda profesorado de guardia para la clase de {classroom_name}, el {day} a la {n_hour}ª hora."
+                    ),
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, JsonSchema)]
+pub struct NotificationPrefs {
+    pub user_id: i64,
+    pub email: Option<String>,
+    pub guard_assigned: bool,
+    pub leave_approved: bool,
+    pub missing_checkout: bool,
+}
+
+impl NotificationPrefs {
+    fn defaults(user_id: i64) -> Self {
+        NotificationPrefs {
+            user_id,
+            email: None,
+            guard_assigned: true,
+            leave_approved: true,
+            missing_checkout: true,
+        }
+    }
+
+    fn wants(&self, notification: &Notification) -> bool {
+        match notification {
+            Notification::GuardAssigned { .. } => self.guard_assigned,
+            Notification::LeaveApproved { .. } => self.leave_approved,
+            Notification::PasswordReset { .. } => true,
+            Notification::MissingCheckout { .. } => self.missing_checkout,
+            // The heads of studies alerts, always on.
+            Notification::ClassUncovered { .. } => true,
+        }
+    }
+}
+
+#[derive(Deserialize, Default, JsonSchema)]
+pub struct NotificationPrefsForUpdate {
+    #[serde(default, with = "serde_with::rust::double_option")]
+    #[schemars(with = "Option<String>")]
+    pub email: Option<Option<String>>,
+    pub guard_assigned: Option<bool>,
+    pub leave_approved: Option<bool>,
+    pub missing_checkout: Option<bool>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct OutboxMail {
+    pub id: i64,
+    pub user_id: i64,
+    pub kind: String,
+    pub to_addr: String,
+    pub subject: String,
+    pub body: String,
+    /// "pending", "sent" or "failed".
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+}
+
+// endregion: --- Notification Types
+
+pub struct NotificationBmc;
+
+impl NotificationBmc {
+    pub const PREFS_TABLE: &'static str = "notification_prefs";
+    pub const OUTBOX_TABLE: &'static str = "notification_outbox";
+
+    pub async fn get_prefs(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        user_id: i64,
+    ) -> Result<NotificationPrefs> {
+        let sql = format!(
+            "SELECT user_id, email, guard_assigned, leave_approved, missing_checkout \
+             FROM {} WHERE user_id = $1 AND ($2::BIGINT IS NULL OR center_id = $2)",
+            Self::PREFS_TABLE
+        );
+        let prefs = mm
+            .dbx()
+            .fetch_optional(
+                sqlx::query_as::<_, NotificationPrefs>(&sql)
+                    .bind(user_id)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+
+        Ok(prefs.unwrap_or_else(|| NotificationPrefs::defaults(user_id)))
+    }
+
+    /// Only the `Some` fields are changed (`email: Some(None)` removes it).
+    ///
+    /// The row is in the user center (`EntityNotFound` for a user of another center).
+    pub async fn update_prefs(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        user_id: i64,
+        prefs_u: NotificationPrefsForUpdate,
+    ) -> Result<()> {
+        let NotificationPrefsForUpdate {
+            email,
+            guard_assigned,
+            leave_approved,
+            missing_checkout,
+        } = prefs_u;
+        let user: UserForAuth = UserBmc::get(ctx, mm, user_id).await?;
+
+        let sql = format!(
+            "INSERT INTO {table} AS prefs \
+                 (user_id, center_id, email, guard_assigned, leave_approved, missing_checkout, cid, ctime, mid, mtime) \
+             VALUES ($1, $8, $2, COALESCE($3, true), COALESCE($4, true), COALESCE($5, true), $6, now(), $6, now()) \
+             ON CONFLICT (user_id) DO UPDATE SET \
+                 email = CASE WHEN $7 THEN EXCLUDED.email ELSE prefs.email END, \
+                 guard_assigned = COALESCE($3, prefs.guard_assigned), \
+                 leave_approved = COALESCE($4, prefs.leave_approved), \
+                 missing_checkout = COALESCE($5, prefs.missing_checkout), \
+                 mid = $6, mtime = now()",
+            table = Self::PREFS_TABLE
+        );
+        mm.dbx()
+            .execute(
+                sqlx::query(&sql)
+                    .bind(user_id)
+                    .bind(email.clone().flatten())
+                    .bind(guard_assigned)
+                    .bind(leave_approved)
+                    .bind(missing_checkout)
+                    .bind(ctx.user_id())
+                    .bind(email.is_some())
+                    .bind(user.center_id),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// The outbox mail id, `None` when the user has no email or does not want this kind.
+    pub async fn enqueue(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        user_id: i64,
+        notification: &Notification,
+    ) -> Result<Option<i64>> {
+        let prefs = Self::get_prefs(ctx, mm, user_id).await?;
+        let to_addr = match prefs.email.as_deref() {
+            Some(email) if prefs.wants(notification) => email,
+            _ => return Ok(None),
+        };
+
+        let (subject, body) = notification.render();
+        let sql = format!(
+            "INSERT INTO {} (user_id, center_id, kind, to_addr, subject, body, next_attempt_at, ctime) \
+             SELECT $1, center_id, $2, $3, $4, $5, now(), now() FROM {} WHERE id = $1 \
+             RETURNING id",
+            Self::OUTBOX_TABLE,
+            UserBmc::TABLE
+        );
+        let (id,) = mm
+            .dbx()
+            .fetch_one(
+                sqlx::query_as::<_, (i64,)>(&sql)
+                    .bind(user_id)
+                    .bind(notification.kind())
+                    .bind(to_addr)
+                    .bind(subject)
+                    .bind(body),
+            )
+            .await?;
+
+        Ok(Some(id))
+    }
+
+    /// `MissingCheckout` for the active users still in center
+    /// (e.g., from a late evening job). Returns the number enqueued.
+    pub async fn enqueue_missing_checkouts(ctx: &Ctx, mm: &ModelManager) -> Result<usize> {
+        let date = OffsetDateTime::now_utc().date();
+        let users = UserBmc::list(ctx, mm, None, None).await?;
+
+        let mut count = 0;
+        for user in users.iter().filter(|user| user.active && user.in_center) {
+            let notification = Notification::MissingCheckout { date };
+            if Self::enqueue(ctx, mm, user.id, &notification)
+                .await?
+                .is_some()
+            {
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    pub async fn get_outbox_mail(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<OutboxMail> {
+        let sql = format!(
+            "SELECT id, user_id, kind, to_addr, subject, body, status, attempts, last_error \
+             FROM {} WHERE id = $1 AND ($2::BIGINT IS NULL OR center_id = $2)",
+            Self::OUTBOX_TABLE
+        );
+        mm.dbx()
+            .fetch_optional(
+                sqlx::query_as::<_, OutboxMail>(&sql)
+                    .bind(id)
+                    .bind(ctx.center_id()),
+            )
+            .await?
+            .ok_or(Error::EntityNotFound {
+                entity: Self::OUTBOX_TABLE,
+                id,
+            })
+    }
+
+    /// Sends up to `batch_size` due outbox mails (of the ctx center, all for the root ctx),
+    /// returns the number sent.
+    pub async fn deliver_pending(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        mailer: &impl Mailer,
+        batch_size: i64,
+    ) -> Result<usize> {
+        let mm = mm.new_with_txn();
+        mm.begin_txn().await?;
+
+        let sql = format!(
+            "SELECT id, user_id, kind, to_addr, subject, body, status, attempts, last_error \
+             FROM {} WHERE status = 'pending' AND next_attempt_at <= now() \
+                 AND ($2::BIGINT IS NULL OR center_id = $2) \
+             ORDER BY id LIMIT $1 FOR UPDATE SKIP LOCKED",
+            Self::OUTBOX_TABLE
+        );
+        let outbox_mails = mm
+            .dbx()
+            .fetch_all(
+                sqlx::query_as::<_, OutboxMail>(&sql)
+                    .bind(batch_size)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+
+        let mut sent = 0;
+        for outbox_mail in outbox_mails {
+            let mail = Mail {
+                to: outbox_mail.to_addr,
+                subject: outbox_mail.subject,
+                body: outbox_mail.body,
+            };
+            match mailer.send(&mail).await {
+                Ok(()) => {
+                    mark_sent(&mm, outbox_mail.id).await?;
+                    sent += 1;
+                }
+                Err(ex) => {
+                    let attempts = outbox_mail.attempts + 1;
+                    mark_attempt_failed(&mm, outbox_mail.id, attempts, &ex.to_string()).await?;
+                }
+            }
+        }
+
+        mm.commit_txn().await?;
+
+        Ok(sent)
+    }
+}
+
+// region:    --- Outbox Status
+
+async fn mark_sent(mm: &ModelManager, id: i64) -> Result<()> {
+    let sql = format!(
+        "UPDATE {} SET status = 'sent', attempts = attempts + 1, sent_at = now() WHERE id = $1",
+        NotificationBmc::OUTBOX_TABLE
+    );
+    mm.dbx().execute(sqlx::query(&sql).bind(id)).await?;
+
+    Ok(())
+}
+
+async fn mark_attempt_failed(mm: &ModelManager, id: i64, attempts: i32, error: &str) -> Result<()> {
+    let retry_sec = RETRY_BASE_SEC << (attempts - 1).clamp(0, 10);
+    let sql = format!(
+        "UPDATE {} SET attempts = $2, last_error = $3, \
+             status = CASE WHEN $2 >= $4 THEN 'failed' ELSE 'pending' END, \
+             next_attempt_at = now() + make_interval(secs => $5) \
+         WHERE id = $1",
+        NotificationBmc::OUTBOX_TABLE
+    );
+    mm.dbx()
+        .execute(
+            sqlx::query(&sql)
+                .bind(id)
+                .bind(attempts)
+                .bind(error)
+                .bind(MAX_ATTEMPTS)
+                .bind(retry_sec as f64),
+        )
+        .await?;
+
+    Ok(())
+}
+
+// endregion: --- Outbox Status
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::{Context, Result};
+    use async_trait::async_trait;
+    use serial_test::serial;
+
+    use lib_mail::{Mail, Mailer, MemoryMailer};
+
+    use crate::_dev_utils;
+    use crate::ctx::{Ctx, DEFAULT_CENTER_ID};
+    use crate::model::center::{CenterBmc, CenterForCreate};
+    use crate::model::notification::{Notification, NotificationBmc, NotificationPrefsForUpdate};
+    use crate::model::user::UserBmc;
+    use crate::model::{Error, ModelManager};
+
+    struct FailingMailer;
+
+    #[async_trait]
+    impl Mailer for FailingMailer {
+        async fn send(&self, _mail: &Mail) -> lib_mail::Result<()> {
+            Err(lib_mail::Error::SmtpSend("connection refused".to_string()))
+        }
+    }
+
+    fn fx_guard_assigned() -> Notification {
+        Notification::GuardAssigned {
+            classroom_name: "Info 2".to_string(),
+            week_day: 0,
+            n_hour: 3,
+            lesson_plan: None,
+        }
+    }
+
+    async fn seed_user_with_email(ctx: &Ctx, mm: &ModelManager, name: &str) -> Result<i64> {
+        let user_id = _dev_utils::seed_user(ctx, mm, name).await?;
+        let prefs_u = NotificationPrefsForUpdate {
+            email: Some(Some(format!("{}@ies.example.org", name.replace(' ', "_")))),
+            ..Default::default()
+        };
+        NotificationBmc::update_prefs(ctx, mm, user_id, prefs_u).await?;
+
+        Ok(user_id)
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_enqueue_deliver_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_username = "test_enqueue_deliver_ok user";
+        let user_id = seed_user_with_email(&ctx, &mm, fx_username).await?;
+        let mailer = MemoryMailer::default();
+
+        // -- Exec
+        let id = NotificationBmc::enqueue(&ctx, &mm, user_id, &fx_guard_assigned())
+            .await?
+            .context("Should be enqueued")?;
+        NotificationBmc::deliver_pending(&ctx, &mm, &mailer, 100).await?;
+
+        // -- Check
+        let outbox_mail = NotificationBmc::get_outbox_mail(&ctx, &mm, id).await?;
+        assert_eq!(outbox_mail.status, "sent");
+        assert_eq!(outbox_mail.kind, "guard_assigned");
+        let mail = mailer
+            .sent()
+            .into_iter()
+            .find(|mail| mail.to == outbox_mail.to_addr)
+            .context("Should have been sent")?;
+        assert_eq!(mail.subject, "Guardia asignada en Info 2");
+        assert!(mail.body.contains("el lunes a la 3ª hora"));
+
+        // -- Clean
+        UserBmc::delete(&ctx, &mm, user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_enqueue_skipped_by_prefs() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let no_email_user_id =
+            _dev_utils::seed_user(&ctx, &mm, "test_enqueue_skipped_by_prefs no email").await?;
+        let opted_out_user_id =
+            seed_user_with_email(&ctx, &mm, "test_enqueue_skipped_by_prefs opted out").await?;
+        let prefs_u = NotificationPrefsForUpdate {
+            guard_assigned: Some(false),
+            ..Default::default()
+        };
+        NotificationBmc::update_prefs(&ctx, &mm, opted_out_user_id, prefs_u).await?;
+
+        // -- Exec
+        let no_email =
+            NotificationBmc::enqueue(&ctx, &mm, no_email_user_id, &fx_guard_assigned()).await?;
+        let opted_out =
+            NotificationBmc::enqueue(&ctx, &mm, opted_out_user_id, &fx_guard_assigned()).await?;
+
+        // -- Check
+        assert!(no_email.is_none(), "Should skip the user without email");
+        assert!(opted_out.is_none(), "Should skip the opted out kind");
+        let prefs = NotificationBmc::get_prefs(&ctx, &mm, opted_out_user_id).await?;
+        assert!(prefs.email.is_some(), "Should keep the email");
+
+        // -- Clean
+        UserBmc::delete(&ctx, &mm, no_email_user_id).await?;
+        UserBmc::delete(&ctx, &mm, opted_out_user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_deliver_failed_retried() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let user_id = seed_user_with_email(&ctx, &mm, "test_deliver_failed_retried user").await?;
+        let id = NotificationBmc::enqueue(&ctx, &mm, user_id, &fx_guard_assigned())
+            .await?
+            .context("Should be enqueued")?;
+
+        // -- Exec
+        NotificationBmc::deliver_pending(&ctx, &mm, &FailingMailer, 100).await?;
+        // Not due yet (backoff), so not sent with a working mailer either.
+        let mailer = MemoryMailer::default();
+        NotificationBmc::deliver_pending(&ctx, &mm, &mailer, 100).await?;
+
+        // -- Check
+        let outbox_mail = NotificationBmc::get_outbox_mail(&ctx, &mm, id).await?;
+        assert_eq!(outbox_mail.status, "pending");
+        assert_eq!(outbox_mail.attempts, 1);
+        assert!(outbox_mail.last_error.is_some());
+        assert!(mailer
+            .sent()
+            .iter()
+            .all(|mail| mail.to != outbox_mail.to_addr));
+
+        // -- Clean
+        UserBmc::delete(&ctx, &mm, user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_other_center_not_visible() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        // Note: Rolled back when `txn_mm` is dropped.
+        let txn_mm = mm.new_with_txn();
+        txn_mm.begin_txn().await?;
+        let center_c = CenterForCreate {
+            code: "test_notification_other_center".to_string(),
+            name: "IES test_notification_other_center".to_string(),
+        };
+        let center_id = CenterBmc::create(&ctx, &txn_mm, center_c).await?;
+        let center_ctx = Ctx::root_ctx().with_center(center_id);
+        let default_ctx = Ctx::root_ctx().with_center(DEFAULT_CENTER_ID);
+        let user_id =
+            seed_user_with_email(&center_ctx, &txn_mm, "test_other_center_not_visible user")
+                .await?;
+        let id = NotificationBmc::enqueue(&center_ctx, &txn_mm, user_id, &fx_guard_assigned())
+            .await?
+            .context("Should be enqueued")?;
+        let mailer = MemoryMailer::default();
+
+        // -- Exec
+        let prefs = NotificationBmc::get_prefs(&default_ctx, &txn_mm, user_id).await?;
+        let prefs_res = NotificationBmc::update_prefs(
+            &default_ctx,
+            &txn_mm,
+            user_id,
+            NotificationPrefsForUpdate::default(),
+        )
+        .await;
+        let outbox_res = NotificationBmc::get_outbox_mail(&default_ctx, &txn_mm, id).await;
+        NotificationBmc::deliver_pending(&default_ctx, &txn_mm, &mailer, 100).await?;
+
+        // -- Check
+        assert!(prefs.email.is_none(), "Should be the defaults");
+        assert!(
+            matches!(
+                prefs_res,
+                Err(Error::EntityNotFound {
+                    entity: "users",
+                    ..
+                })
+            ),
+            "Should not update the other center prefs"
+        );
+        assert!(
+            matches!(
+                outbox_res,
+                Err(Error::EntityNotFound {
+                    entity: "notification_outbox",
+                    ..
+                })
+            ),
+            "Should not see the other center mail"
+        );
+        let outbox_mail = NotificationBmc::get_outbox_mail(&center_ctx, &txn_mm, id).await?;
+        assert_eq!(
+            outbox_mail.status, "pending",
+            "Should not deliver the other center mail"
+        );
+        assert!(mailer.sent().is_empty());
+
+        // -- Clean
+        drop(txn_mm);
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
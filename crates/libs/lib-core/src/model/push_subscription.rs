@@ -0,0 +1,497 @@
+//! Web Push subscriptions of the teacher PWA, one per user device (browser).
+//!
+//! - `subscribe` is an upsert on the endpoint (per center, in the user center), the browser
+//!   keeps it across logins, so the device follows the last user logged in
+//!   (audited as a create or an update).
+//! - `push_to_user` sends to all the user devices (e.g., the guard assigned alert),
+//!   and deletes the subscriptions the push service reports as gone (404/410).
+
+use modql::field::{Fields, HasFields};
+use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsValue};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use serde_with::serde_as;
+use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use tracing::warn;
+
+use lib_push::{PushSender, PushTarget};
+
+use crate::ctx::Ctx;
+use crate::model::audit_log::{self, AuditOp};
+use crate::model::base::{self, PostgresDbBmc};
+use crate::model::modql_utils::time_to_sea_value;
+use crate::model::notification::Notification;
+use crate::model::store::dbx::Dbx;
+use crate::model::user::{UserBmc, UserForAuth};
+use crate::model::Result;
+use crate::model::{Error, ModelManager};
+
+// region:    --- PushSubscription Types
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
+pub struct PushSubscription {
+    pub id: i64,
+    pub user_id: i64,
+    pub endpoint: String,
+    pub device_name: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
+}
+
+/// As the PWA `PushSubscription.toJSON()`, plus a name for the list of devices.
+#[derive(Deserialize, Clone, JsonSchema)]
+pub struct PushSubscriptionForCreate {
+    pub endpoint: String,
+    pub keys: PushSubscriptionKeys,
+    /// e.g., "Móvil"
+    pub device_name: Option<String>,
+}
+
+#[derive(Deserialize, Clone, JsonSchema)]
+pub struct PushSubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+#[derive(Clone, FromRow, Fields, Debug)]
+pub struct PushSubscriptionForSend {
+    pub id: i64,
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+}
+
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct PushSubscriptionFilter {
+    id: Option<OpValsInt64>,
+    user_id: Option<OpValsInt64>,
+    cid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    ctime: Option<OpValsValue>,
+    mid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    mtime: Option<OpValsValue>,
+}
+
+/// Marker trait
+pub trait PushSubscriptionBy: HasFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
+
+impl PushSubscriptionBy for PushSubscription {}
+
+impl PushSubscriptionBy for PushSubscriptionForSend {}
+
+// endregion: --- PushSubscription Types
+
+pub struct PushSubscriptionBmc;
+
+impl PostgresDbBmc for PushSubscriptionBmc {
+    const TABLE: &'static str = "push_subscriptions";
+    const CENTER_SCOPED: bool = true;
+}
+
+impl PushSubscriptionBmc {
+    pub async fn subscribe(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        user_id: i64,
+        push_subscription_c: PushSubscriptionForCreate,
+    ) -> Result<i64> {
+        let user: UserForAuth = UserBmc::get(ctx, mm, user_id).await?;
+
+        let mm = mm.new_with_txn();
+        let dbx = mm.dbx();
+
+        dbx.begin_txn().await?;
+        let res = Self::subscribe_in_txn(ctx, dbx, &user, push_subscription_c).await;
+        base::end_txn(dbx, res).await
+    }
+
+    async fn subscribe_in_txn(
+        ctx: &Ctx,
+        dbx: &Dbx,
+        user: &UserForAuth,
+        push_subscription_c: PushSubscriptionForCreate,
+    ) -> Result<i64> {
+        let PushSubscriptionForCreate {
+            endpoint,
+            keys,
+            device_name,
+        } = push_subscription_c;
+
+        // -- Exec query
+        let sql = format!(
+            "SELECT id FROM {} WHERE center_id = $1 AND endpoint = $2",
+            Self::TABLE
+        );
+        let old_id = dbx
+            .fetch_optional(
+                sqlx::query_as::<_, (i64,)>(&sql)
+                    .bind(user.center_id)
+                    .bind(&endpoint),
+            )
+            .await?;
+        let old = match old_id {
+            Some((old_id,)) => audit_log::snapshot(dbx, Self::TABLE, old_id).await?,
+            None => None,
+        };
+
+        let sql = format!(
+            "INSERT INTO {} (user_id, center_id, endpoint, p256dh, auth, device_name, cid, ctime, mid, mtime) \
+             VALUES ($1, $7, $2, $3, $4, $5, $6, now(), $6, now()) \
+             ON CONFLICT (center_id, endpoint) DO UPDATE SET \
+                 user_id = EXCLUDED.user_id, p256dh = EXCLUDED.p256dh, auth = EXCLUDED.auth, \
+                 device_name = EXCLUDED.device_name, mid = $6, mtime = now() \
+             RETURNING id",
+            Self::TABLE
+        );
+        let (id,) = dbx
+            .fetch_one(
+                sqlx::query_as::<_, (i64,)>(&sql)
+                    .bind(user.id)
+                    .bind(endpoint)
+                    .bind(keys.p256dh)
+                    .bind(keys.auth)
+                    .bind(device_name)
+                    .bind(ctx.user_id())
+                    .bind(user.center_id),
+            )
+            .await?;
+
+        // -- Audit
+        let op = if old.is_some() {
+            AuditOp::Update
+        } else {
+            AuditOp::Create
+        };
+        let new = audit_log::snapshot(dbx, Self::TABLE, id).await?;
+        audit_log::log_mutation(dbx, ctx, Self::TABLE, id, op, old.as_ref(), new.as_ref()).await?;
+
+        Ok(id)
+    }
+
+    pub async fn get<E>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
+    where
+        E: PushSubscriptionBy,
+    {
+        base::get::<Self, _>(ctx, mm, id).await
+    }
+
+    pub async fn list<E>(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<PushSubscriptionFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<E>>
+    where
+        E: PushSubscriptionBy,
+    {
+        base::list::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
+
+    pub async fn list_for_user<E>(ctx: &Ctx, mm: &ModelManager, user_id: i64) -> Result<Vec<E>>
+    where
+        E: PushSubscriptionBy,
+    {
+        let filters = vec![PushSubscriptionFilter {
+            user_id: Some(OpValsInt64::from(user_id)),
+            ..Default::default()
+        }];
+
+        Self::list(ctx, mm, Some(filters), None).await
+    }
+
+    pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        base::delete::<Self>(ctx, mm, id).await
+    }
+
+    /// Deletes the subscription only when it is one of the `user_id` devices.
+    pub async fn unsubscribe(ctx: &Ctx, mm: &ModelManager, user_id: i64, id: i64) -> Result<()> {
+        let push_subscription: PushSubscription = Self::get(ctx, mm, id).await?;
+        if push_subscription.user_id != user_id {
+            return Err(Error::EntityNotFound {
+                entity: Self::TABLE,
+                id,
+            });
+        }
+
+        Self::delete(ctx, mm, id).await
+    }
+
+    /// Number of devices pushed to.
+    ///
+    /// Note: The other send failures are only logged, the push is not retried.
+    pub async fn push_to_user(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        sender: &impl PushSender,
+        user_id: i64,
+        notification: &Notification,
+    ) -> Result<usize> {
+        let push_subscriptions: Vec<PushSubscriptionForSend> =
+            Self::list_for_user(ctx, mm, user_id).await?;
+        if push_subscriptions.is_empty() {
+            return Ok(0);
+        }
+
+        let (title, body) = notification.render();
+        let payload = json!({
+            "kind": notification.kind(),
+            "title": title,
+            "body": body,
+        })
+        .to_string();
+
+        let mut pushed = 0;
+        for push_subscription in push_subscriptions {
+            let target = PushTarget {
+                endpoint: push_subscription.endpoint,
+                p256dh: push_subscription.p256dh,
+                auth: push_subscription.auth,
+            };
+            match sender.send(&target, payload.as_bytes()).await {
+                Ok(()) => pushed += 1,
+                // Expired, revoked, or never valid, so no push will ever succeed.
+                Err(lib_push::Error::EndpointGone | lib_push::Error::SubscriptionKeyInvalid) => {
+                    Self::delete(ctx, mm, push_subscription.id).await?;
+                }
+                Err(ex) => warn!(
+                    "{:<12} - push subscription {} - {ex}",
+                    "PUSH", push_subscription.id
+                ),
+            }
+        }
+
+        Ok(pushed)
+    }
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::{Context, Result};
+    use serde_json::json;
+    use serial_test::serial;
+
+    use lib_push::MemoryPushSender;
+
+    use crate::_dev_utils;
+    use crate::ctx::{Ctx, DEFAULT_CENTER_ID};
+    use crate::model::audit_log::{AuditLogBmc, AuditLogFilter};
+    use crate::model::center::{CenterBmc, CenterForCreate};
+    use crate::model::notification::Notification;
+    use crate::model::push_subscription::{
+        PushSubscription, PushSubscriptionBmc, PushSubscriptionForCreate, PushSubscriptionKeys,
+    };
+    use crate::model::user::UserBmc;
+    use crate::model::Error;
+
+    fn fx_push_subscription_c(endpoint: &str) -> PushSubscriptionForCreate {
+        PushSubscriptionForCreate {
+            endpoint: endpoint.to_string(),
+            keys: PushSubscriptionKeys {
+                p256dh: "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4".to_string(),
+                auth: "BTBZMqHH6r4Tts7J_aSIgg".to_string(),
+            },
+            device_name: None,
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_push_to_user_gone_deleted() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let user_id =
+            _dev_utils::seed_user(&ctx, &mm, "test_push_to_user_gone_deleted user").await?;
+        let fx_endpoint_ok = "https://push.example.org/test_push_to_user_gone_deleted/ok";
+        let fx_endpoint_gone = "https://push.example.org/test_push_to_user_gone_deleted/gone";
+        PushSubscriptionBmc::subscribe(&ctx, &mm, user_id, fx_push_subscription_c(fx_endpoint_ok))
+            .await?;
+        PushSubscriptionBmc::subscribe(
+            &ctx,
+            &mm,
+            user_id,
+            fx_push_subscription_c(fx_endpoint_gone),
+        )
+        .await?;
+        let sender = MemoryPushSender::default();
+        sender.set_gone(fx_endpoint_gone);
+        let fx_notification = Notification::GuardAssigned {
+            classroom_name: "Info 2".to_string(),
+            week_day: 0,
+            n_hour: 3,
+            lesson_plan: None,
+        };
+
+        // -- Exec
+        let pushed =
+            PushSubscriptionBmc::push_to_user(&ctx, &mm, &sender, user_id, &fx_notification)
+                .await?;
+
+        // -- Check
+        assert_eq!(pushed, 1);
+        let sent_push = sender
+            .sent()
+            .into_iter()
+            .next()
+            .context("Should have one push")?;
+        assert_eq!(sent_push.endpoint, fx_endpoint_ok);
+        let payload: serde_json::Value = serde_json::from_slice(&sent_push.payload)?;
+        assert_eq!(payload["kind"], "guard_assigned");
+        assert_eq!(payload["title"], "Guardia asignada en Info 2");
+
+        let push_subscriptions: Vec<PushSubscription> =
+            PushSubscriptionBmc::list_for_user(&ctx, &mm, user_id).await?;
+        assert_eq!(push_subscriptions.len(), 1, "Gone one should be deleted");
+        assert_eq!(push_subscriptions[0].endpoint, fx_endpoint_ok);
+
+        // -- Clean
+        PushSubscriptionBmc::delete(&ctx, &mm, push_subscriptions[0].id).await?;
+        UserBmc::delete(&ctx, &mm, user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_subscribe_same_endpoint_moved() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let user_id_1 =
+            _dev_utils::seed_user(&ctx, &mm, "test_subscribe_same_endpoint_moved user 01").await?;
+        let user_id_2 =
+            _dev_utils::seed_user(&ctx, &mm, "test_subscribe_same_endpoint_moved user 02").await?;
+        let fx_endpoint = "https://push.example.org/test_subscribe_same_endpoint_moved";
+
+        // -- Exec
+        let id_1 = PushSubscriptionBmc::subscribe(
+            &ctx,
+            &mm,
+            user_id_1,
+            fx_push_subscription_c(fx_endpoint),
+        )
+        .await?;
+        let id_2 = PushSubscriptionBmc::subscribe(
+            &ctx,
+            &mm,
+            user_id_2,
+            fx_push_subscription_c(fx_endpoint),
+        )
+        .await?;
+
+        // -- Check
+        assert_eq!(id_1, id_2, "Should be the same device");
+        let filters: Vec<AuditLogFilter> = serde_json::from_value(json!([{
+            "table_name": "push_subscriptions",
+            "entity_id": id_2
+        }]))?;
+        let entries = AuditLogBmc::list(&ctx, &mm, Some(filters), None).await?;
+        let ops: Vec<&str> = entries.iter().map(|entry| entry.op.as_str()).collect();
+        assert_eq!(ops, ["create", "update"]);
+        assert_eq!(
+            entries[1].diff["user_id"],
+            json!({"old": user_id_1, "new": user_id_2})
+        );
+        let push_subscription: PushSubscription = PushSubscriptionBmc::get(&ctx, &mm, id_2).await?;
+        assert_eq!(push_subscription.user_id, user_id_2);
+        let res = PushSubscriptionBmc::unsubscribe(&ctx, &mm, user_id_1, id_1).await;
+        assert!(res.is_err(), "Should not unsubscribe another user device");
+
+        // -- Clean
+        PushSubscriptionBmc::delete(&ctx, &mm, id_2).await?;
+        UserBmc::delete(&ctx, &mm, user_id_1).await?;
+        UserBmc::delete(&ctx, &mm, user_id_2).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_subscribe_other_center_separate() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_username = "test_subscribe_other_center_separate user";
+        let fx_endpoint = "https://push.example.org/test_subscribe_other_center_separate";
+        // Note: Rolled back when `txn_mm` is dropped.
+        let txn_mm = mm.new_with_txn();
+        txn_mm.begin_txn().await?;
+        let center_c = CenterForCreate {
+            code: "test_subscribe_other_center_separate".to_string(),
+            name: "IES test_subscribe_other_center_separate".to_string(),
+        };
+        let center_id = CenterBmc::create(&ctx, &txn_mm, center_c).await?;
+        let center_ctx = Ctx::root_ctx().with_center(center_id);
+        let default_ctx = Ctx::root_ctx().with_center(DEFAULT_CENTER_ID);
+        let default_user_id = _dev_utils::seed_user(&default_ctx, &txn_mm, fx_username).await?;
+        let center_user_id = _dev_utils::seed_user(&center_ctx, &txn_mm, fx_username).await?;
+
+        // -- Exec
+        // Same device (browser) in both centers.
+        let default_sub_id = PushSubscriptionBmc::subscribe(
+            &default_ctx,
+            &txn_mm,
+            default_user_id,
+            fx_push_subscription_c(fx_endpoint),
+        )
+        .await?;
+        let center_sub_id = PushSubscriptionBmc::subscribe(
+            &center_ctx,
+            &txn_mm,
+            center_user_id,
+            fx_push_subscription_c(fx_endpoint),
+        )
+        .await?;
+
+        // -- Check
+        assert_ne!(
+            default_sub_id, center_sub_id,
+            "Should not move the other center device"
+        );
+        let push_subscription: PushSubscription =
+            PushSubscriptionBmc::get(&default_ctx, &txn_mm, default_sub_id).await?;
+        assert_eq!(push_subscription.user_id, default_user_id);
+        let res =
+            PushSubscriptionBmc::get::<PushSubscription>(&default_ctx, &txn_mm, center_sub_id)
+                .await;
+        assert!(
+            matches!(
+                res,
+                Err(Error::EntityNotFound {
+                    entity: "push_subscriptions",
+                    ..
+                })
+            ),
+            "Should not see the other center subscription"
+        );
+        let res = PushSubscriptionBmc::subscribe(
+            &default_ctx,
+            &txn_mm,
+            center_user_id,
+            fx_push_subscription_c(fx_endpoint),
+        )
+        .await;
+        assert!(
+            matches!(
+                res,
+                Err(Error::EntityNotFound {
+                    entity: "users",
+                    ..
+                })
+            ),
+            "Should not subscribe the other center user"
+        );
+
+        // -- Clean
+        drop(txn_mm);
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
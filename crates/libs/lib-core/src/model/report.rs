@@ -0,0 +1,330 @@
+//! Report data, for the spreadsheet exports (see `lib_export`).
+//!
+//! - The timetable grids are one row per center hour (`center_schedule_hours`),
+//!   one column per lesson day (`WEEK_DAYS`).
+//! - Attendance and substitutions are the current state of the users
+//!   (last check-in/out of the day, substitutions count).
+//! - All of them of the ctx center (all the centers for the root ctx without center).
+
+use serde::Serialize;
+use sqlx::FromRow;
+use time::Time;
+
+use crate::ctx::Ctx;
+use crate::model::base::PostgresDbBmc;
+use crate::model::calendar::{CalendarBmc, TimetableHour, TimetableOwner};
+use crate::model::center_schedule_hour::{CenterScheduleHour, CenterScheduleHourBmc};
+use crate::model::department::DepartmentBmc;
+use crate::model::group::GroupBmc;
+use crate::model::schedule::ScheduleBmc;
+use crate::model::user::{User, UserBmc};
+use crate::model::ModelManager;
+use crate::model::Result;
+
+/// The lesson days, by `week_day` (0 is Monday).
+pub const WEEK_DAYS: [&str; 5] = ["Lunes", "Martes", "Miércoles", "Jueves", "Viernes"];
+
+// region:    --- Report Types
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimetableGrid {
+    /// e.g., teacher username, or "1º ESO A".
+    pub title: String,
+    pub course: i32,
+    pub rows: Vec<TimetableGridRow>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimetableGridRow {
+    pub n_hour: i32,
+    pub start_time: Time,
+    pub end_time: Time,
+    /// One per `WEEK_DAYS`.
+    pub cells: Vec<Option<TimetableGridCell>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimetableGridCell {
+    pub subject_name: String,
+    pub classroom_name: String,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct AttendanceRow {
+    pub username: String,
+    pub department_name: Option<String>,
+    pub in_center: bool,
+    pub last_checkin: Time,
+    pub last_checkout: Time,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct SubstitutionRow {
+    pub username: String,
+    pub substitutions: i64,
+    /// Username of the teacher substituted (`substituting_id`).
+    pub substituting: Option<String>,
+}
+
+// endregion: --- Report Types
+
+pub struct ReportBmc;
+
+impl ReportBmc {
+    pub async fn teacher_grid(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        user_id: i64,
+        course: i32,
+    ) -> Result<TimetableGrid> {
+        let user: User = UserBmc::get(ctx, mm, user_id).await?;
+        let center_hours = CenterScheduleHourBmc::list(ctx, mm, None, None).await?;
+        let timetable =
+            CalendarBmc::timetable(ctx, mm, TimetableOwner::Teacher(user_id), course).await?;
+
+        Ok(timetable_grid(
+            user.username,
+            course,
+            &center_hours,
+            timetable.hours,
+        ))
+    }
+
+    /// One grid per teacher with a schedule in the `course` year.
+    pub async fn teacher_grids(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        course: i32,
+    ) -> Result<Vec<TimetableGrid>> {
+        let sql = format!(
+            "SELECT DISTINCT u.id, u.username FROM {} u \
+             JOIN {} s ON s.user_id = u.id \
+             WHERE s.course = $1 AND u.deleted_at IS NULL \
+                 AND ($2::BIGINT IS NULL OR u.center_id = $2) \
+             ORDER BY u.username",
+            UserBmc::TABLE,
+            ScheduleBmc::TABLE
+        );
+        let teachers = mm
+            .dbx()
+            .fetch_all(
+                sqlx::query_as::<_, (i64, String)>(&sql)
+                    .bind(course)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+        let center_hours = CenterScheduleHourBmc::list(ctx, mm, None, None).await?;
+
+        let mut grids = Vec::with_capacity(teachers.len());
+        for (user_id, username) in teachers {
+            let timetable =
+                CalendarBmc::timetable(ctx, mm, TimetableOwner::Teacher(user_id), course).await?;
+            grids.push(timetable_grid(
+                username,
+                course,
+                &center_hours,
+                timetable.hours,
+            ));
+        }
+
+        Ok(grids)
+    }
+
+    /// One grid per group of the `course` year.
+    pub async fn group_grids(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        course: i32,
+    ) -> Result<Vec<TimetableGrid>> {
+        let sql = format!(
+            "SELECT id, course, stage, letter FROM {} \
+             WHERE year = $1 AND deleted_at IS NULL \
+                 AND ($2::BIGINT IS NULL OR center_id = $2) \
+             ORDER BY stage, course, letter",
+            GroupBmc::TABLE
+        );
+        let groups = mm
+            .dbx()
+            .fetch_all(
+                sqlx::query_as::<_, (i64, i32, i32, Option<String>)>(&sql)
+                    .bind(course)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+        let center_hours = CenterScheduleHourBmc::list(ctx, mm, None, None).await?;
+
+        let mut grids = Vec::with_capacity(groups.len());
+        for (group_id, group_course, stage, letter) in groups {
+            let timetable =
+                CalendarBmc::timetable(ctx, mm, TimetableOwner::Group(group_id), course).await?;
+            let title = group_title(group_course, stage, letter.as_deref().unwrap_or_default());
+            grids.push(timetable_grid(
+                title,
+                course,
+                &center_hours,
+                timetable.hours,
+            ));
+        }
+
+        Ok(grids)
+    }
+
+    /// The active users, by username.
+    pub async fn attendance(ctx: &Ctx, mm: &ModelManager) -> Result<Vec<AttendanceRow>> {
+        let sql = format!(
+            "SELECT u.username, d.name AS department_name, u.in_center, \
+                    u.last_checkin, u.last_checkout \
+             FROM {} u LEFT JOIN {} d ON d.id = u.department_id \
+             WHERE u.deleted_at IS NULL AND u.active IS NOT FALSE \
+                 AND ($1::BIGINT IS NULL OR u.center_id = $1) \
+             ORDER BY u.username",
+            UserBmc::TABLE,
+            DepartmentBmc::TABLE
+        );
+        let rows = mm
+            .dbx()
+            .fetch_all(sqlx::query_as::<_, AttendanceRow>(&sql).bind(ctx.center_id()))
+            .await?;
+
+        Ok(rows)
+    }
+
+    /// The users, most substitutions first.
+    pub async fn substitutions(ctx: &Ctx, mm: &ModelManager) -> Result<Vec<SubstitutionRow>> {
+        let sql = format!(
+            "SELECT u.username, COALESCE(u.substitutions, 0) AS substitutions, \
+                    s.username AS substituting \
+             FROM {0} u LEFT JOIN {0} s ON s.id = u.substituting_id \
+             WHERE u.deleted_at IS NULL AND ($1::BIGINT IS NULL OR u.center_id = $1) \
+             ORDER BY substitutions DESC, u.username",
+            UserBmc::TABLE
+        );
+        let rows = mm
+            .dbx()
+            .fetch_all(sqlx::query_as::<_, SubstitutionRow>(&sql).bind(ctx.center_id()))
+            .await?;
+
+        Ok(rows)
+    }
+}
+
+fn timetable_grid(
+    title: String,
+    course: i32,
+    center_hours: &[CenterScheduleHour],
+    hours: Vec<TimetableHour>,
+) -> TimetableGrid {
+    let mut center_hours: Vec<&CenterScheduleHour> = center_hours.iter().collect();
+    center_hours.sort_by_key(|center_hour| center_hour.n_hour);
+
+    let mut rows: Vec<TimetableGridRow> = center_hours
+        .into_iter()
+        .map(|center_hour| TimetableGridRow {
+            n_hour: center_hour.n_hour,
+            start_time: center_hour.start_time,
+            end_time: center_hour.end_time,
+            cells: vec![None; WEEK_DAYS.len()],
+        })
+        .collect();
+
+    for hour in hours {
+        let schedule_hour = hour.schedule_hour;
+        let row = rows
+            .iter_mut()
+            .find(|row| row.n_hour == schedule_hour.n_hour);
+        let cell = row.and_then(|row| {
+            usize::try_from(schedule_hour.week_day)
+                .ok()
+                .and_then(|week_day| row.cells.get_mut(week_day))
+        });
+        if let Some(cell) = cell {
+            *cell = Some(TimetableGridCell {
+                subject_name: schedule_hour.subject_name,
+                classroom_name: schedule_hour.classroom_name,
+            });
+        }
+    }
+
+    TimetableGrid {
+        title,
+        course,
+        rows,
+    }
+}
+
+/// e.g., "1º ESO A" (`stage` 1: ESO, 2: Bachiller, 3: Ciclos).
+fn group_title(course: i32, stage: i32, letter: &str) -> String {
+    let stage = match stage {
+        1 => "ESO".to_string(),
+        2 => "Bachiller".to_string(),
+        3 => "Ciclos".to_string(),
+        stage => stage.to_string(),
+    };
+
+    format!("{course}º {stage} {letter}").trim_end().to_string()
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serial_test::serial;
+    use time::macros::time;
+
+    use crate::_dev_utils;
+    use crate::ctx::Ctx;
+    use crate::model::report::ReportBmc;
+    use crate::model::schedule::ScheduleBmc;
+    use crate::model::user::UserBmc;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_teacher_grid_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_course = 2025;
+        let user_id = _dev_utils::seed_user(&ctx, &mm, "test_teacher_grid_ok user").await?;
+        let schedule_id = _dev_utils::seed_schedule(&ctx, &mm, fx_course, user_id, -1).await?;
+        _dev_utils::seed_schedule_hour(
+            &ctx,
+            &mm,
+            schedule_id,
+            "Matemáticas",
+            "Info 2",
+            2,
+            1,
+            fx_course,
+        )
+        .await?;
+
+        // -- Exec
+        let grid = ReportBmc::teacher_grid(&ctx, &mm, user_id, fx_course).await?;
+        let grids = ReportBmc::teacher_grids(&ctx, &mm, fx_course).await?;
+
+        // -- Check
+        assert_eq!(grid.title, "test_teacher_grid_ok user");
+        let row = grid
+            .rows
+            .iter()
+            .find(|row| row.n_hour == 1)
+            .expect("Should have hour 1");
+        assert_eq!((row.start_time, row.end_time), (time!(08:55), time!(09:50)));
+        let cells: Vec<Option<&str>> = row
+            .cells
+            .iter()
+            .map(|cell| cell.as_ref().map(|cell| cell.subject_name.as_str()))
+            .collect();
+        assert_eq!(cells, [None, None, Some("Matemáticas"), None, None]);
+        assert!(grids
+            .iter()
+            .any(|grid| grid.title == "test_teacher_grid_ok user"));
+
+        // -- Clean
+        ScheduleBmc::delete(&ctx, &mm, schedule_id).await?;
+        UserBmc::delete(&ctx, &mm, user_id).await?;
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
@@ -1,27 +1,32 @@
 use modql::field::{Fields, HasFields};
 use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsValue};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use sqlx::FromRow;
 use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::OffsetDateTime;
 
 use crate::ctx::Ctx;
 use crate::model::base::{self, PostgresDbBmc};
+use crate::model::modql_utils::{nullable_value, time_to_sea_value, NullableValue};
 use crate::model::ModelManager;
-use crate::model::modql_utils::time_to_sea_value;
 use crate::model::Result;
 
 // region:    --- Schedule Types
 #[serde_as]
-#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
 pub struct Schedule {
     pub id: i64,
     pub user_id: Option<i64>,
     pub group_id: Option<i64>,
     pub course: i32,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
 }
 
-#[derive(Fields, Deserialize, Clone)]
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
 pub struct ScheduleForCreate {
     pub user_id: Option<i64>,
     pub group_id: Option<i64>,
@@ -44,11 +49,15 @@ pub struct ScheduleFilter {
     pub mtime: Option<OpValsValue>,
 }
 
-#[derive(Fields, Default, Deserialize)]
+#[derive(Fields, Default, Deserialize, JsonSchema)]
 pub struct ScheduleForUpdate {
-    pub user_id: Option<i64>,
-    pub group_id: Option<i64>,
-    pub course: i32,
+    #[serde(default, deserialize_with = "nullable_value")]
+    #[schemars(with = "Option<i64>")]
+    pub user_id: Option<NullableValue<i64>>,
+    #[serde(default, deserialize_with = "nullable_value")]
+    #[schemars(with = "Option<i64>")]
+    pub group_id: Option<NullableValue<i64>>,
+    pub course: Option<i32>,
 }
 
 /// Marker trait
@@ -61,10 +70,15 @@ pub struct ScheduleBmc;
 
 impl PostgresDbBmc for ScheduleBmc {
     const TABLE: &'static str = "schedules";
+    const CENTER_SCOPED: bool = true;
 }
 
 impl ScheduleBmc {
-    pub async fn create(ctx: &Ctx, mm: &ModelManager, schedule_c: ScheduleForCreate) -> Result<i64> {
+    pub async fn create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        schedule_c: ScheduleForCreate,
+    ) -> Result<i64> {
         base::create::<Self, _>(ctx, mm, schedule_c).await
     }
 
@@ -75,8 +89,11 @@ impl ScheduleBmc {
         base::get::<Self, _>(ctx, mm, id).await
     }
 
-    pub async fn get_teacher_schedule(ctx: &Ctx, mm: &ModelManager, user_id: i64) -> Result<Vec<Schedule>>
-    {
+    pub async fn get_teacher_schedule(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        user_id: i64,
+    ) -> Result<Vec<Schedule>> {
         let filters = Some(ScheduleFilter {
             id: None,
             user_id: Some(OpValsInt64::from(user_id)),
@@ -91,13 +108,12 @@ impl ScheduleBmc {
         let list_options = Some(ListOptions {
             limit: None,
             offset: None,
-            order_bys: None
+            order_bys: None,
         });
 
-       base::list::<Self, _, _>(ctx, mm, filters, list_options).await
+        base::list::<Self, _, _>(ctx, mm, filters, list_options).await
     }
 
-
     pub async fn list(
         ctx: &Ctx,
         mm: &ModelManager,
@@ -107,7 +123,6 @@ impl ScheduleBmc {
         base::list::<Self, _, _>(ctx, mm, filters, list_options).await
     }
 
-
     pub async fn update(
         ctx: &Ctx,
         mm: &ModelManager,
@@ -117,6 +132,16 @@ impl ScheduleBmc {
         base::update::<Self, _>(ctx, mm, id, schedule_u).await
     }
 
+    pub async fn update_if_unmodified(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        schedule_u: ScheduleForUpdate,
+        expected_mtime: Option<OffsetDateTime>,
+    ) -> Result<()> {
+        base::update_if_unmodified::<Self, _>(ctx, mm, id, schedule_u, expected_mtime).await
+    }
+
     pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
         base::delete::<Self>(ctx, mm, id).await
     }
@@ -146,7 +171,7 @@ mod tests {
         let fx_department_name = "Departamento_schedule_create_ok";
 
         let fx_user_id = _dev_utils::seed_user(&ctx, &mm, fx_username).await?;
-        let fx_department_id= _dev_utils::seed_department(&ctx, &mm, fx_department_name).await?;
+        let fx_department_id = _dev_utils::seed_department(&ctx, &mm, fx_department_name).await?;
 
         // -- Exec
         let schedule_c = ScheduleForCreate {
@@ -182,8 +207,9 @@ mod tests {
         let fx_group_id = -1;
 
         let fx_user_id = _dev_utils::seed_user(&ctx, &mm, fx_username).await?;
-        let fx_department_id= _dev_utils::seed_department(&ctx, &mm, fx_department_name).await?;
-        let fx_schedule_id = _dev_utils::seed_schedule(&ctx, &mm, fx_course, fx_user_id, fx_group_id).await?;
+        let fx_department_id = _dev_utils::seed_department(&ctx, &mm, fx_department_name).await?;
+        let fx_schedule_id =
+            _dev_utils::seed_schedule(&ctx, &mm, fx_course, fx_user_id, fx_group_id).await?;
 
         // -- Exec
         ScheduleBmc::update(
@@ -191,12 +217,12 @@ mod tests {
             &mm,
             fx_schedule_id,
             ScheduleForUpdate {
-                course: fx_course_new,
+                course: Some(fx_course_new),
                 group_id: None,
-                user_id: Some(fx_user_id)
+                user_id: Some(Some(fx_user_id).into()),
             },
         )
-            .await?;
+        .await?;
 
         // -- Check
         let schedule: Schedule = ScheduleBmc::get(&ctx, &mm, fx_schedule_id).await?;
@@ -216,7 +242,7 @@ mod tests {
         // -- Setup & Fixtures
         let mm = _dev_utils::init_test().await;
         let ctx = Ctx::root_ctx();
-        let fx_courses = &[2022, 2022] ;
+        let fx_courses = &[2022, 2022];
         let fx_username = "Usuario_schedule_list_by_name_ok";
         let fx_username_2 = "Usuario_schedule_list_by_name_ok_2";
         let fx_department_name = "Departamento_schedule_list_by_name_ok";
@@ -226,7 +252,8 @@ mod tests {
         let fx_user_id_2 = _dev_utils::seed_user(&ctx, &mm, fx_username_2).await?;
 
         let fx_id_01 = _dev_utils::seed_schedule(&ctx, &mm, fx_courses[0], fx_user_id, -1).await?;
-        let fx_id_02 = _dev_utils::seed_schedule(&ctx, &mm, fx_courses[1], fx_user_id_2, -1).await?;
+        let fx_id_02 =
+            _dev_utils::seed_schedule(&ctx, &mm, fx_courses[1], fx_user_id_2, -1).await?;
 
         // -- Exec
         let filter_json = json!({
@@ -237,8 +264,11 @@ mod tests {
         let schedules = ScheduleBmc::list(&ctx, &mm, Some(filter), None).await?;
 
         // -- Check
-        let schedules: Vec<String> = schedules.into_iter().map(|s| s.course.to_string()).collect();
-        let fx_schedules: Vec<String> = fx_courses.into_iter().map(|c| c.to_string()).collect();
+        let schedules: Vec<String> = schedules
+            .into_iter()
+            .map(|s| s.course.to_string())
+            .collect();
+        let fx_schedules: Vec<String> = fx_courses.iter().map(|c| c.to_string()).collect();
 
         assert_eq!(schedules.len(), 2);
         assert_eq!(&schedules, &fx_schedules);
@@ -248,7 +278,7 @@ mod tests {
         ScheduleBmc::delete(&ctx, &mm, fx_id_02).await?;
         UserBmc::delete(&ctx, &mm, fx_user_id).await?;
         UserBmc::delete(&ctx, &mm, fx_user_id_2).await?;
-        DepartmentBmc::delete(&ctx, &mm, fx_department_id).await?;        
+        DepartmentBmc::delete(&ctx, &mm, fx_department_id).await?;
 
         Ok(())
     }
@@ -1,21 +1,27 @@
+use std::collections::{BTreeSet, HashMap};
+
 use modql::field::{Fields, HasFields};
-use modql::filter::{FilterNodes, ListOptions, OpValsInt32, OpValsInt64, OpValsString, OpValsValue};
+use modql::filter::{
+    FilterNodes, ListOptions, OpValsInt32, OpValsInt64, OpValsString, OpValsValue,
+};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use sqlx::FromRow;
 use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::OffsetDateTime;
 
 use crate::ctx::Ctx;
-use crate::model::base::{self, PostgresDbBmc};
+use crate::model::base::{self, BulkMode, PostgresDbBmc};
+use crate::model::event::{event_center_id, ModelEvent};
+use crate::model::modql_utils::{nullable_value, time_to_sea_value, NullableValue};
 use crate::model::ModelManager;
-use crate::model::modql_utils::time_to_sea_value;
 use crate::model::Result;
 
 // region:    --- ScheduleHour Types
 
-
 #[serde_as]
-#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
 pub struct ScheduleHour {
     pub id: i64,
     pub schedule_id: i64,
@@ -24,10 +30,13 @@ pub struct ScheduleHour {
     pub week_day: i32,
     pub n_hour: i32,
     pub course: i32,
-    pub notes: Option<String>
+    pub notes: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
 }
 
-#[derive(Fields, Deserialize, Clone)]
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
 pub struct ScheduleHourForCreate {
     pub schedule_id: i64,
     pub subject_name: String,
@@ -35,7 +44,7 @@ pub struct ScheduleHourForCreate {
     pub week_day: i32,
     pub n_hour: i32,
     pub course: i32,
-    pub notes: Option<String>
+    pub notes: Option<String>,
 }
 
 #[derive(FilterNodes, Deserialize, Default, Debug)]
@@ -58,29 +67,17 @@ pub struct ScheduleHourFilter {
     pub mtime: Option<OpValsValue>,
 }
 
-impl Default for ScheduleHourForUpdate {
-    fn default() -> Self {
-        Self {
-            schedule_id: 0,
-            subject_name: "".to_string(), // default value
-            classroom_name: "".to_string(), // default value
-            week_day: 0,   // default value
-            n_hour: 0,     // default value
-            course: 0,     // default value
-            notes: Some("".to_string())
-        }
-    }
-}
-
-#[derive(Fields, Deserialize)]
+#[derive(Fields, Default, Deserialize, JsonSchema)]
 pub struct ScheduleHourForUpdate {
-    pub schedule_id: i64,
-    pub subject_name: String,
-    pub classroom_name: String,
-    pub week_day: i32,
-    pub n_hour: i32,
-    pub course: i32,
-    pub notes: Option<String>
+    pub schedule_id: Option<i64>,
+    pub subject_name: Option<String>,
+    pub classroom_name: Option<String>,
+    pub week_day: Option<i32>,
+    pub n_hour: Option<i32>,
+    pub course: Option<i32>,
+    #[serde(default, deserialize_with = "nullable_value")]
+    #[schemars(with = "Option<String>")]
+    pub notes: Option<NullableValue<String>>,
 }
 
 /// Marker trait
@@ -93,11 +90,21 @@ pub struct ScheduleHourBmc;
 
 impl PostgresDbBmc for ScheduleHourBmc {
     const TABLE: &'static str = "schedule_hours";
+    const CENTER_SCOPED: bool = true;
 }
 
 impl ScheduleHourBmc {
-    pub async fn create(ctx: &Ctx, mm: &ModelManager, schedule_hour_c: ScheduleHourForCreate) -> Result<i64> {
-        base::create::<Self, _>(ctx, mm, schedule_hour_c).await
+    pub async fn create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        schedule_hour_c: ScheduleHourForCreate,
+    ) -> Result<i64> {
+        let schedule_id = schedule_hour_c.schedule_id;
+        let id = base::create::<Self, _>(ctx, mm, schedule_hour_c).await?;
+
+        publish_schedules_changed(ctx, mm, [schedule_id]).await;
+
+        Ok(id)
     }
 
     pub async fn get<E>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
@@ -117,21 +124,154 @@ impl ScheduleHourBmc {
         base::list::<Self, _, _>(ctx, mm, filters, list_options).await
     }
 
-
     pub async fn update(
         ctx: &Ctx,
         mm: &ModelManager,
         id: i64,
         schedule_hour_u: ScheduleHourForUpdate,
     ) -> Result<()> {
-        base::update::<Self, _>(ctx, mm, id, schedule_hour_u).await
+        Self::update_if_unmodified(ctx, mm, id, schedule_hour_u, None).await
+    }
+
+    pub async fn update_if_unmodified(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        schedule_hour_u: ScheduleHourForUpdate,
+        expected_mtime: Option<OffsetDateTime>,
+    ) -> Result<()> {
+        let old_schedule_ids = schedule_ids_by_id(mm, &[id]).await?;
+        let new_schedule_id = schedule_hour_u.schedule_id;
+
+        base::update_if_unmodified::<Self, _>(ctx, mm, id, schedule_hour_u, expected_mtime).await?;
+
+        publish_schedules_changed(
+            ctx,
+            mm,
+            old_schedule_ids.into_values().chain(new_schedule_id),
+        )
+        .await;
+
+        Ok(())
     }
 
     pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
-        base::delete::<Self>(ctx, mm, id).await
+        let schedule_ids = schedule_ids_by_id(mm, &[id]).await?;
+
+        base::delete::<Self>(ctx, mm, id).await?;
+
+        publish_schedules_changed(ctx, mm, schedule_ids.into_values()).await;
+
+        Ok(())
+    }
+
+    pub async fn create_many(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        schedule_hours_c: Vec<ScheduleHourForCreate>,
+        mode: BulkMode,
+    ) -> Result<Vec<Result<i64>>> {
+        let schedule_ids: Vec<i64> = schedule_hours_c.iter().map(|c| c.schedule_id).collect();
+
+        let results = base::create_many::<Self, _>(ctx, mm, schedule_hours_c, mode).await?;
+
+        let changed = results
+            .iter()
+            .zip(schedule_ids)
+            .filter(|(res, _)| res.is_ok())
+            .map(|(_, schedule_id)| schedule_id);
+        publish_schedules_changed(ctx, mm, changed).await;
+
+        Ok(results)
+    }
+
+    pub async fn update_many(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        schedule_hours_u: Vec<(i64, ScheduleHourForUpdate, Option<OffsetDateTime>)>,
+        mode: BulkMode,
+    ) -> Result<Vec<Result<()>>> {
+        let ids: Vec<i64> = schedule_hours_u.iter().map(|(id, ..)| *id).collect();
+        let new_schedule_ids: Vec<Option<i64>> = schedule_hours_u
+            .iter()
+            .map(|(_, u, _)| u.schedule_id)
+            .collect();
+        let old_schedule_ids = schedule_ids_by_id(mm, &ids).await?;
+
+        let results = base::update_many::<Self, _>(ctx, mm, schedule_hours_u, mode).await?;
+
+        let changed = results
+            .iter()
+            .zip(ids.iter().zip(new_schedule_ids))
+            .filter(|(res, _)| res.is_ok())
+            .flat_map(|(_, (id, new_schedule_id))| {
+                old_schedule_ids
+                    .get(id)
+                    .copied()
+                    .into_iter()
+                    .chain(new_schedule_id)
+            });
+        publish_schedules_changed(ctx, mm, changed).await;
+
+        Ok(results)
+    }
+
+    pub async fn delete_many(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        ids: Vec<i64>,
+        mode: BulkMode,
+    ) -> Result<Vec<Result<()>>> {
+        let old_schedule_ids = schedule_ids_by_id(mm, &ids).await?;
+
+        let results = base::delete_many::<Self>(ctx, mm, ids.clone(), mode).await?;
+
+        let changed = results
+            .iter()
+            .zip(ids)
+            .filter(|(res, _)| res.is_ok())
+            .filter_map(|(_, id)| old_schedule_ids.get(&id).copied());
+        publish_schedules_changed(ctx, mm, changed).await;
+
+        Ok(results)
+    }
+}
+
+// region:    --- Events
+
+/// The `schedule_id` of each existing schedule hour `id`.
+async fn schedule_ids_by_id(mm: &ModelManager, ids: &[i64]) -> Result<HashMap<i64, i64>> {
+    let sql = format!(
+        "SELECT id, schedule_id FROM {} WHERE id = ANY($1)",
+        ScheduleHourBmc::TABLE
+    );
+    let rows = mm
+        .dbx()
+        .fetch_all(sqlx::query_as::<_, (i64, i64)>(&sql).bind(ids))
+        .await?;
+
+    Ok(rows.into_iter().collect())
+}
+
+/// One `ScheduleChanged` per distinct schedule.
+async fn publish_schedules_changed(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    schedule_ids: impl IntoIterator<Item = i64>,
+) {
+    let center_id = event_center_id(ctx);
+    let schedule_ids: BTreeSet<i64> = schedule_ids.into_iter().collect();
+    for schedule_id in schedule_ids {
+        mm.publish(ModelEvent::ScheduleChanged {
+            center_id,
+            schedule_id,
+        })
+        .await;
     }
 }
 
+// endregion: --- Events
+
 // region:    --- Tests
 #[cfg(test)]
 mod tests {
@@ -142,11 +282,16 @@ mod tests {
     use crate::_dev_utils;
     use crate::_dev_utils::{seed_department, seed_schedule, seed_subject, seed_user};
     use crate::ctx::Ctx;
+    use crate::model::base::BulkMode;
     use crate::model::department::DepartmentBmc;
     use crate::model::schedule::ScheduleBmc;
-    use crate::model::schedule_hour::{ScheduleHour, ScheduleHourBmc, ScheduleHourForCreate, ScheduleHourForUpdate};
+    use crate::model::schedule_hour::{
+        ScheduleHour, ScheduleHourBmc, ScheduleHourFilter, ScheduleHourForCreate,
+        ScheduleHourForUpdate,
+    };
     use crate::model::subject::SubjectBmc;
     use crate::model::user::UserBmc;
+    use crate::model::Error;
 
     #[serial]
     #[tokio::test]
@@ -158,7 +303,7 @@ mod tests {
         let fx_week_day = 1; // lunes
         let fx_n_hour = 1; // 08:00-08:50
         let fx_course = 2024;
-        
+
         let fx_username = "Prueba_schedule_hour_create_ok";
         let fx_department_name = "Departamento_schedule_hour_create_ok";
 
@@ -166,7 +311,7 @@ mod tests {
         let fx_department_id = seed_department(&ctx, &mm, fx_department_name).await?;
         let fx_classroom_name = "Info 2".to_string();
         let fx_notes = "".to_string();
-        let fx_schedule_id= seed_schedule(&ctx, &mm, fx_course, fx_user_id, -1).await?;
+        let fx_schedule_id = seed_schedule(&ctx, &mm, fx_course, fx_user_id, -1).await?;
 
         // -- Exec
         let schedule_hour_c = ScheduleHourForCreate {
@@ -176,7 +321,7 @@ mod tests {
             week_day: fx_week_day,
             n_hour: fx_n_hour,
             course: fx_course,
-            notes: Some(fx_notes)
+            notes: Some(fx_notes),
         };
 
         let id = ScheduleHourBmc::create(&ctx, &mm, schedule_hour_c).await?;
@@ -203,39 +348,51 @@ mod tests {
         let fx_subject_name = "subject_create_ok";
         let fx_classroom_name = "classroom_create_ok";
         let fx_week_day = 1; // Lunes
-        let fx_week_day_new= 2; // Martes
+        let fx_week_day_new = 2; // Martes
         let fx_n_hour = 1; // 08:00-08:50
         let fx_course = 2024;
-        
+
         let fx_username = "Prueba_schedule_hour_update_ok";
         let fx_department_name = "Departamento_schedule_hour_create_ok";
         let fx_notes = "Notas_schedule_hour_create_ok";
 
         let fx_user_id = seed_user(&ctx, &mm, fx_username).await?;
         let fx_department_id = seed_department(&ctx, &mm, fx_department_name).await?;
-        let fx_subject_id = seed_subject(&ctx, &mm, fx_subject_name, fx_department_id, false, false).await?;
-        let fx_schedule_id= seed_schedule(&ctx, &mm, fx_course, fx_user_id, -1).await?;
-        let fx_schedule_hour_id = _dev_utils::seed_schedule_hour(&ctx, &mm, fx_schedule_id, fx_subject_name, fx_classroom_name, fx_week_day, fx_n_hour, fx_course).await?;
-        
+        let fx_subject_id =
+            seed_subject(&ctx, &mm, fx_subject_name, fx_department_id, false, false).await?;
+        let fx_schedule_id = seed_schedule(&ctx, &mm, fx_course, fx_user_id, -1).await?;
+        let fx_schedule_hour_id = _dev_utils::seed_schedule_hour(
+            &ctx,
+            &mm,
+            fx_schedule_id,
+            fx_subject_name,
+            fx_classroom_name,
+            fx_week_day,
+            fx_n_hour,
+            fx_course,
+        )
+        .await?;
+
         // -- Exec
         ScheduleHourBmc::update(
             &ctx,
             &mm,
             fx_schedule_hour_id,
             ScheduleHourForUpdate {
-                schedule_id: fx_schedule_id,
-                subject_name: fx_subject_name.to_string(),
-                classroom_name: fx_classroom_name.to_string(),
-                week_day: fx_week_day_new,
-                course: fx_course,
-                n_hour: fx_n_hour,
-                notes: Some(fx_notes.to_string()),
+                schedule_id: Some(fx_schedule_id),
+                subject_name: Some(fx_subject_name.to_string()),
+                classroom_name: Some(fx_classroom_name.to_string()),
+                week_day: Some(fx_week_day_new),
+                course: Some(fx_course),
+                n_hour: Some(fx_n_hour),
+                notes: Some(Some(fx_notes.to_string()).into()),
             },
         )
-            .await?;
+        .await?;
 
         // -- Check
-        let schedule_hour: ScheduleHour = ScheduleHourBmc::get(&ctx, &mm, fx_schedule_hour_id).await?;
+        let schedule_hour: ScheduleHour =
+            ScheduleHourBmc::get(&ctx, &mm, fx_schedule_hour_id).await?;
         assert_eq!(schedule_hour.week_day, fx_week_day_new);
 
         // -- Clean
@@ -248,6 +405,139 @@ mod tests {
         Ok(())
     }
 
+    #[serial]
+    #[tokio::test]
+    async fn test_update_if_unmodified_err_concurrent() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_subject_name = "subject_update_concurrent";
+        let fx_classroom_name = "classroom_update_concurrent";
+        let fx_week_day = 1; // Lunes
+        let fx_n_hour = 1; // 08:00-08:50
+        let fx_course = 2024;
+        let fx_username = "Prueba_schedule_hour_update_concurrent";
+
+        let fx_user_id = seed_user(&ctx, &mm, fx_username).await?;
+        let fx_schedule_id = seed_schedule(&ctx, &mm, fx_course, fx_user_id, -1).await?;
+        let fx_schedule_hour_id = _dev_utils::seed_schedule_hour(
+            &ctx,
+            &mm,
+            fx_schedule_id,
+            fx_subject_name,
+            fx_classroom_name,
+            fx_week_day,
+            fx_n_hour,
+            fx_course,
+        )
+        .await?;
+        let fx_schedule_hour_u = |week_day: i32| ScheduleHourForUpdate {
+            week_day: Some(week_day),
+            ..Default::default()
+        };
+        let read: ScheduleHour = ScheduleHourBmc::get(&ctx, &mm, fx_schedule_hour_id).await?;
+
+        // -- Exec
+        // First admin, same mtime as read.
+        ScheduleHourBmc::update_if_unmodified(
+            &ctx,
+            &mm,
+            fx_schedule_hour_id,
+            fx_schedule_hour_u(2),
+            Some(read.mtime),
+        )
+        .await?;
+        // Second admin, stale mtime.
+        let res = ScheduleHourBmc::update_if_unmodified(
+            &ctx,
+            &mm,
+            fx_schedule_hour_id,
+            fx_schedule_hour_u(3),
+            Some(read.mtime),
+        )
+        .await;
+
+        // -- Check
+        match res {
+            Err(Error::ConcurrentModification {
+                entity,
+                id,
+                current,
+            }) => {
+                assert_eq!(entity, "schedule_hours");
+                assert_eq!(id, fx_schedule_hour_id);
+                assert_eq!(current["week_day"], json!(2));
+            }
+            other => panic!("Should be ConcurrentModification, was {other:?}"),
+        }
+        let schedule_hour: ScheduleHour =
+            ScheduleHourBmc::get(&ctx, &mm, fx_schedule_hour_id).await?;
+        assert_eq!(schedule_hour.week_day, 2);
+
+        // -- Clean
+        ScheduleHourBmc::delete(&ctx, &mm, fx_schedule_hour_id).await?;
+        ScheduleBmc::delete(&ctx, &mm, fx_schedule_id).await?;
+        UserBmc::delete(&ctx, &mm, fx_user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_many_modes_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_course = 2024;
+        let fx_username = "Prueba_schedule_hour_create_many";
+        let fx_user_id = seed_user(&ctx, &mm, fx_username).await?;
+        let fx_schedule_id = seed_schedule(&ctx, &mm, fx_course, fx_user_id, -1).await?;
+        // Same (week_day, n_hour) twice, so the 3rd item breaks the unique constraint.
+        let fx_schedule_hours_c = || -> Vec<ScheduleHourForCreate> {
+            [(1, 1), (1, 2), (1, 1)]
+                .map(|(week_day, n_hour)| ScheduleHourForCreate {
+                    schedule_id: fx_schedule_id,
+                    subject_name: "subject_create_many".to_string(),
+                    classroom_name: "classroom_create_many".to_string(),
+                    week_day,
+                    n_hour,
+                    course: fx_course,
+                    notes: None,
+                })
+                .into()
+        };
+        let fx_filter = || -> Result<Vec<ScheduleHourFilter>> {
+            Ok(vec![serde_json::from_value(
+                json!({"schedule_id": {"$eq": fx_schedule_id}}),
+            )?])
+        };
+
+        // -- Exec & Check - all or nothing
+        let res =
+            ScheduleHourBmc::create_many(&ctx, &mm, fx_schedule_hours_c(), BulkMode::AllOrNothing)
+                .await;
+        assert!(res.is_err(), "duplicate should fail the whole call");
+        let schedule_hours = ScheduleHourBmc::list(&ctx, &mm, Some(fx_filter()?), None).await?;
+        assert!(schedule_hours.is_empty());
+
+        // -- Exec & Check - per item
+        let results =
+            ScheduleHourBmc::create_many(&ctx, &mm, fx_schedule_hours_c(), BulkMode::PerItem)
+                .await?;
+        let oks: Vec<bool> = results.iter().map(|res| res.is_ok()).collect();
+        assert_eq!(oks, [true, true, false]);
+        let schedule_hours = ScheduleHourBmc::list(&ctx, &mm, Some(fx_filter()?), None).await?;
+        assert_eq!(schedule_hours.len(), 2);
+
+        // -- Clean
+        let ids = schedule_hours.iter().map(|s| s.id).collect();
+        ScheduleHourBmc::delete_many(&ctx, &mm, ids, BulkMode::AllOrNothing).await?;
+        ScheduleBmc::delete(&ctx, &mm, fx_schedule_id).await?;
+        UserBmc::delete(&ctx, &mm, fx_user_id).await?;
+
+        Ok(())
+    }
+
     #[serial]
     #[tokio::test]
     async fn test_list_by_course_ok() -> Result<()> {
@@ -257,7 +547,7 @@ mod tests {
         let fx_subject_name = "subject_list_by_course_ok";
         let fx_classroom_name = "classroom_list_by_course_ok";
         let fx_week_day = 1; // Lunes
-        let fx_week_day_2= 2; // Martes
+        let fx_week_day_2 = 2; // Martes
         let fx_n_hour = 1; // 08:00-08:50
         let fx_course = 2024;
 
@@ -266,26 +556,47 @@ mod tests {
 
         let fx_user_id = seed_user(&ctx, &mm, fx_username).await?;
         let fx_department_id = seed_department(&ctx, &mm, fx_department_name).await?;
-        let fx_subject_id = seed_subject(&ctx, &mm, fx_subject_name, fx_department_id, false, false).await?;
-        let fx_schedule_id= seed_schedule(&ctx, &mm, fx_course, fx_user_id, -1).await?;
-        let fx_schedule_hour_id_01 = _dev_utils::seed_schedule_hour(&ctx, &mm, fx_schedule_id, fx_subject_name, fx_classroom_name, fx_week_day, fx_n_hour, fx_course).await?;
-        let fx_schedule_hour_id_02 = _dev_utils::seed_schedule_hour(&ctx, &mm, fx_schedule_id, fx_subject_name, fx_classroom_name, fx_week_day_2, fx_n_hour, fx_course).await?;
+        let fx_subject_id =
+            seed_subject(&ctx, &mm, fx_subject_name, fx_department_id, false, false).await?;
+        let fx_schedule_id = seed_schedule(&ctx, &mm, fx_course, fx_user_id, -1).await?;
+        let fx_schedule_hour_id_01 = _dev_utils::seed_schedule_hour(
+            &ctx,
+            &mm,
+            fx_schedule_id,
+            fx_subject_name,
+            fx_classroom_name,
+            fx_week_day,
+            fx_n_hour,
+            fx_course,
+        )
+        .await?;
+        let fx_schedule_hour_id_02 = _dev_utils::seed_schedule_hour(
+            &ctx,
+            &mm,
+            fx_schedule_id,
+            fx_subject_name,
+            fx_classroom_name,
+            fx_week_day_2,
+            fx_n_hour,
+            fx_course,
+        )
+        .await?;
 
         // -- Exec
         let filter_json = json!({
             "schedule_id": {"$eq": fx_schedule_id},
         });
         let filter = vec![serde_json::from_value(filter_json)?];
-    
+
         let schedule_hours = ScheduleHourBmc::list(&ctx, &mm, Some(filter), None).await?;
-    
+
         // -- Check
         // let schedule_hours: Vec<String> = schedule_hours.into_iter().map(|s| s.course.to_string()).collect();
         // let fx_schedule_hours: Vec<String> = fx_courses.into_iter().map(|c| c.to_string()).collect();
-    
+
         assert_eq!(schedule_hours.len(), 2);
         // assert_eq!(&schedule_hours, &fx_schedule_hours);
-    
+
         // -- Cleanup
         ScheduleHourBmc::delete(&ctx, &mm, fx_schedule_hour_id_01).await?;
         ScheduleHourBmc::delete(&ctx, &mm, fx_schedule_hour_id_02).await?;
@@ -293,7 +604,7 @@ mod tests {
         UserBmc::delete(&ctx, &mm, fx_user_id).await?;
         SubjectBmc::delete(&ctx, &mm, fx_subject_id).await?;
         DepartmentBmc::delete(&ctx, &mm, fx_department_id).await?;
-    
+
         Ok(())
     }
 }
@@ -0,0 +1,219 @@
+//! Db executor, runs the queries on the pool or on the open transaction.
+//!
+//! A `Dbx` created `with_txn` shares one transaction between all its clones
+//! (i.e., all the `ModelManager` clones given to the `*Bmc` functions).
+//! `begin_txn`/`commit_txn` calls can be nested, only the outermost commit
+//! commits. When a step fails, just return the error, the transaction is
+//! rolled back when the last clone is dropped
+//! (Postgres aborts the whole transaction on a failed statement anyway).
+//! A nested `begin_txn` whose error can be caught by an outer one
+//! (e.g., after a savepoint rollback) ends with `rollback_txn` instead,
+//! so the outer `commit_txn` still commits.
+
+// region:    --- Modules
+
+use std::ops::DerefMut;
+use std::sync::Arc;
+
+use sqlx::postgres::{PgQueryResult, PgRow};
+use sqlx::query::{Query, QueryAs};
+use sqlx::{FromRow, IntoArguments, Postgres, Transaction};
+use tokio::sync::Mutex;
+
+use crate::model::store::PostgresDb;
+
+pub use self::error::{Error, Result};
+
+mod error;
+
+// endregion: --- Modules
+
+#[derive(Debug, Clone)]
+pub struct Dbx {
+    db_pool: PostgresDb,
+    txn_holder: Arc<Mutex<Option<TxnHolder>>>,
+    with_txn: bool,
+}
+
+impl Dbx {
+    pub fn new(db_pool: PostgresDb, with_txn: bool) -> Self {
+        Dbx {
+            db_pool,
+            txn_holder: Arc::default(),
+            with_txn,
+        }
+    }
+
+    pub fn with_txn(&self) -> bool {
+        self.with_txn
+    }
+
+    /// `begin_txn` called, and not yet (outermost) committed.
+    pub async fn has_open_txn(&self) -> bool {
+        self.txn_holder.lock().await.is_some()
+    }
+
+    pub fn db(&self) -> &PostgresDb {
+        &self.db_pool
+    }
+}
+
+// region:    --- Txn
+
+#[derive(Debug)]
+struct TxnHolder {
+    txn: Transaction<'static, Postgres>,
+    /// Number of `begin_txn` not yet committed (nested begins).
+    counter: i32,
+}
+
+impl Dbx {
+    pub async fn begin_txn(&self) -> Result<()> {
+        if !self.with_txn {
+            return Err(Error::CannotBeginTxnWithTxnFalse);
+        }
+
+        let mut txh_g = self.txn_holder.lock().await;
+        if let Some(txh) = txh_g.as_mut() {
+            txh.counter += 1;
+        } else {
+            let txn = self.db_pool.begin().await?;
+            let _ = txh_g.insert(TxnHolder { txn, counter: 1 });
+        }
+
+        Ok(())
+    }
+
+    pub async fn commit_txn(&self) -> Result<()> {
+        if !self.with_txn {
+            return Err(Error::CannotCommitTxnWithTxnFalse);
+        }
+
+        let mut txh_g = self.txn_holder.lock().await;
+        let Some(txh) = txh_g.as_mut() else {
+            return Err(Error::TxnCantCommitNoOpenTxn);
+        };
+
+        txh.counter -= 1;
+        if txh.counter == 0 {
+            if let Some(txh) = txh_g.take() {
+                txh.txn.commit().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ends a `begin_txn` on error, instead of `commit_txn`.
+    /// Only the outermost one rolls the transaction back.
+    pub async fn rollback_txn(&self) -> Result<()> {
+        if !self.with_txn {
+            return Err(Error::CannotRollbackTxnWithTxnFalse);
+        }
+
+        let mut txh_g = self.txn_holder.lock().await;
+        let Some(txh) = txh_g.as_mut() else {
+            return Err(Error::TxnCantRollbackNoOpenTxn);
+        };
+
+        txh.counter -= 1;
+        if txh.counter == 0 {
+            if let Some(txh) = txh_g.take() {
+                txh.txn.rollback().await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Dbx {
+    /// Savepoint in the open transaction
+    /// (e.g., to roll back one item of a bulk call).
+    pub async fn savepoint(&self, name: &str) -> Result<()> {
+        if self.txn_holder.lock().await.is_none() {
+            return Err(Error::SavepointNoOpenTxn);
+        }
+        self.execute(sqlx::query(&format!("SAVEPOINT {name}")))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn release_savepoint(&self, name: &str) -> Result<()> {
+        self.execute(sqlx::query(&format!("RELEASE SAVEPOINT {name}")))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn rollback_to_savepoint(&self, name: &str) -> Result<()> {
+        self.execute(sqlx::query(&format!("ROLLBACK TO SAVEPOINT {name}")))
+            .await?;
+        Ok(())
+    }
+}
+
+// endregion: --- Txn
+
+// region:    --- Executors
+
+impl Dbx {
+    pub async fn fetch_one<'q, O, A>(&self, query: QueryAs<'q, Postgres, O, A>) -> Result<O>
+    where
+        O: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+        A: IntoArguments<'q, Postgres> + 'q,
+    {
+        let mut txh_g = self.txn_holder.lock().await;
+        let data = match txh_g.as_mut() {
+            Some(txh) => query.fetch_one(txh.txn.deref_mut()).await?,
+            None => query.fetch_one(self.db()).await?,
+        };
+
+        Ok(data)
+    }
+
+    pub async fn fetch_optional<'q, O, A>(
+        &self,
+        query: QueryAs<'q, Postgres, O, A>,
+    ) -> Result<Option<O>>
+    where
+        O: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+        A: IntoArguments<'q, Postgres> + 'q,
+    {
+        let mut txh_g = self.txn_holder.lock().await;
+        let data = match txh_g.as_mut() {
+            Some(txh) => query.fetch_optional(txh.txn.deref_mut()).await?,
+            None => query.fetch_optional(self.db()).await?,
+        };
+
+        Ok(data)
+    }
+
+    pub async fn fetch_all<'q, O, A>(&self, query: QueryAs<'q, Postgres, O, A>) -> Result<Vec<O>>
+    where
+        O: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+        A: IntoArguments<'q, Postgres> + 'q,
+    {
+        let mut txh_g = self.txn_holder.lock().await;
+        let data = match txh_g.as_mut() {
+            Some(txh) => query.fetch_all(txh.txn.deref_mut()).await?,
+            None => query.fetch_all(self.db()).await?,
+        };
+
+        Ok(data)
+    }
+
+    pub async fn execute<'q, A>(&self, query: Query<'q, Postgres, A>) -> Result<PgQueryResult>
+    where
+        A: IntoArguments<'q, Postgres> + 'q,
+    {
+        let mut txh_g = self.txn_holder.lock().await;
+        let res = match txh_g.as_mut() {
+            Some(txh) => query.execute(txh.txn.deref_mut()).await?,
+            None => query.execute(self.db()).await?,
+        };
+
+        Ok(res)
+    }
+}
+
+// endregion: --- Executors
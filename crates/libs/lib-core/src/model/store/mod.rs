@@ -6,6 +6,7 @@ use crate::core_config;
 
 pub use self::error::{Error, Result};
 
+pub mod dbx;
 mod error;
 
 // endregion: --- Modules
@@ -13,10 +14,8 @@ mod error;
 // region: Postgres
 pub type PostgresDb = Pool<Postgres>;
 
-pub async fn new_db_pool() -> Result<PostgresDb> {
-    // * See NOTE 1) below
-    let max_connections = if cfg!(test) { 1 } else { 5 };
-
+/// Note: The tests use a single connection (see NOTE 1) below).
+pub async fn new_db_pool(max_connections: u32) -> Result<PostgresDb> {
     PgPoolOptions::new()
         .max_connections(max_connections)
         .connect(&core_config().DB_URL)
@@ -28,6 +27,8 @@ pub async fn new_db_pool() -> Result<PostgresDb> {
 //         rather low level (in the tokio scheduler). It appears to be a low-level thread/async issue, as removing/adding
 //         tests causes different tests to fail. The cause remains uncertain, but setting max_connections to 1 resolves the issue.
 //         The good news is that max_connections still function normally for a regular run.
+//         It is set by `_dev_utils::init_test()`, so it also applies to the tests of the crates using lib-core
+//         (`cfg!(test)` is only set for the lib-core own tests).
 //         This issue is likely due to the unique requirements unit tests impose on their execution, and therefore,
 //         while not ideal, it should serve as an acceptable temporary solution.
 //         It's a very challenging issue to investigate and narrow down. The alternative would have been to stick with sqlx 0.6.x, which
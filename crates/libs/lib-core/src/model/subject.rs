@@ -1,33 +1,38 @@
 use modql::field::{Fields, HasFields};
 use modql::filter::{FilterNodes, ListOptions, OpValsBool, OpValsInt64, OpValsString, OpValsValue};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use sqlx::FromRow;
 use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::OffsetDateTime;
 
 use crate::ctx::Ctx;
 use crate::model::base::{self, PostgresDbBmc};
-use crate::model::ModelManager;
 use crate::model::modql_utils::time_to_sea_value;
+use crate::model::ModelManager;
 use crate::model::Result;
 
 // region:    --- Subject Types
 #[serde_as]
-#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
 pub struct Subject {
     pub id: i64,
     pub name: String,
     pub department_id: i64,
     pub is_guard: bool,
-    pub is_complementary: bool
+    pub is_complementary: bool,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
 }
 
-#[derive(Fields, Deserialize, Clone)]
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
 pub struct SubjectForCreate {
     pub name: String,
     pub department_id: i64,
     pub is_guard: bool,
-    pub is_complementary: bool
+    pub is_complementary: bool,
 }
 
 #[derive(FilterNodes, Deserialize, Default, Debug)]
@@ -46,12 +51,12 @@ pub struct SubjectFilter {
     mtime: Option<OpValsValue>,
 }
 
-#[derive(Fields, Default, Deserialize)]
+#[derive(Fields, Default, Deserialize, JsonSchema)]
 pub struct SubjectForUpdate {
-    pub name: String,
-    pub department_id: i64,
-    pub is_guard: bool,
-    pub is_complementary: bool
+    pub name: Option<String>,
+    pub department_id: Option<i64>,
+    pub is_guard: Option<bool>,
+    pub is_complementary: Option<bool>,
 }
 
 /// Marker trait
@@ -65,6 +70,8 @@ pub struct SubjectBmc;
 
 impl PostgresDbBmc for SubjectBmc {
     const TABLE: &'static str = "subjects";
+    const SOFT_DELETE: bool = true;
+    const CENTER_SCOPED: bool = true;
 }
 
 impl SubjectBmc {
@@ -88,6 +95,14 @@ impl SubjectBmc {
         base::list::<Self, _, _>(ctx, mm, filters, list_options).await
     }
 
+    pub async fn list_including_deleted(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<SubjectFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<Subject>> {
+        base::list_including_deleted::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
 
     pub async fn update(
         ctx: &Ctx,
@@ -98,9 +113,23 @@ impl SubjectBmc {
         base::update::<Self, _>(ctx, mm, id, subject_u).await
     }
 
+    pub async fn update_if_unmodified(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        subject_u: SubjectForUpdate,
+        expected_mtime: Option<OffsetDateTime>,
+    ) -> Result<()> {
+        base::update_if_unmodified::<Self, _>(ctx, mm, id, subject_u, expected_mtime).await
+    }
+
     pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
         base::delete::<Self>(ctx, mm, id).await
     }
+
+    pub async fn restore(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        base::restore::<Self>(ctx, mm, id).await
+    }
 }
 
 // region:    --- Tests
@@ -126,14 +155,14 @@ mod tests {
         let fx_is_guard = false;
         let fx_is_complementary = false;
         let fx_department_name = "Departamento";
-        
+
         let fx_department_id = seed_department(&ctx, &mm, fx_department_name).await?;
         // -- Exec
         let subject_c = SubjectForCreate {
             name: fx_name.to_string(),
             department_id: fx_department_id,
             is_guard: fx_is_guard,
-            is_complementary: fx_is_complementary
+            is_complementary: fx_is_complementary,
         };
 
         let id = SubjectBmc::create(&ctx, &mm, subject_c).await?;
@@ -145,10 +174,10 @@ mod tests {
         // -- Clean
         SubjectBmc::delete(&ctx, &mm, id).await?;
         DepartmentBmc::delete(&ctx, &mm, fx_department_id).await?;
-        
+
         Ok(())
     }
-    
+
     #[serial]
     #[tokio::test]
     async fn test_update_ok() -> Result<()> {
@@ -160,7 +189,8 @@ mod tests {
         let fx_department_name = "Departamento prueba";
 
         let fx_department_id = seed_department(&ctx, &mm, fx_department_name).await?;
-        let fx_subject_id = _dev_utils::seed_subject(&ctx, &mm, fx_name, fx_department_id, false, false).await?;
+        let fx_subject_id =
+            _dev_utils::seed_subject(&ctx, &mm, fx_name, fx_department_id, false, false).await?;
 
         // -- Exec
         SubjectBmc::update(
@@ -168,13 +198,13 @@ mod tests {
             &mm,
             fx_subject_id,
             SubjectForUpdate {
-                is_guard: true,
-                is_complementary: true,
-                department_id: fx_department_id,
-                name: fx_name_new.to_string(),
+                is_guard: Some(true),
+                is_complementary: Some(true),
+                department_id: Some(fx_department_id),
+                name: Some(fx_name_new.to_string()),
             },
         )
-            .await?;
+        .await?;
 
         // -- Check
         let subject: Subject = SubjectBmc::get(&ctx, &mm, fx_subject_id).await?;
@@ -183,7 +213,7 @@ mod tests {
         // -- Clean
         SubjectBmc::delete(&ctx, &mm, fx_subject_id).await?;
         DepartmentBmc::delete(&ctx, &mm, fx_department_id).await?;
-        
+
         Ok(())
     }
 
@@ -197,8 +227,11 @@ mod tests {
         let fx_department_name = "Departamento prueba";
 
         let fx_department_id = seed_department(&ctx, &mm, fx_department_name).await?;
-        let fx_id_01 = _dev_utils::seed_subject(&ctx, &mm, fx_names[0], fx_department_id, false, false).await?;
-        let fx_id_02 = _dev_utils::seed_subject(&ctx, &mm, fx_names[1], fx_department_id, true, true).await?;
+        let fx_id_01 =
+            _dev_utils::seed_subject(&ctx, &mm, fx_names[0], fx_department_id, false, false)
+                .await?;
+        let fx_id_02 =
+            _dev_utils::seed_subject(&ctx, &mm, fx_names[1], fx_department_id, true, true).await?;
 
         // -- Exec
         let filter_json = json!({
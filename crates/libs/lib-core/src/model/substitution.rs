@@ -0,0 +1,579 @@
+//! Substitutions, who covered whom (one per absent teacher hour and date).
+//!
+//! - `ControlBmc::update_guards` records them, `covered` with the guard teacher
+//!   or `uncovered` when no guard was left.
+//! - The admins can plan them ahead (`planned`), an uncovered hour keeps its plan,
+//!   a guard assigned on the day replaces it.
+//! - The group is the one with a lesson in the classroom at that hour, if any.
+
+use modql::field::{Fields, HasFields};
+use modql::filter::{
+    FilterNodes, ListOptions, OpValsInt32, OpValsInt64, OpValsString, OpValsValue,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use time::{Date, OffsetDateTime};
+
+use lib_utils::time::iso_date;
+
+use crate::ctx::Ctx;
+use crate::model::base::{self, PostgresDbBmc};
+use crate::model::modql_utils::{
+    date_to_sea_value, nullable_value, time_to_sea_value, NullableValue,
+};
+use crate::model::schedule::ScheduleBmc;
+use crate::model::schedule_hour::ScheduleHourBmc;
+use crate::model::user::UserBmc;
+use crate::model::Result;
+use crate::model::{Error, ModelManager};
+
+pub const STATUS_PLANNED: &str = "planned";
+pub const STATUS_COVERED: &str = "covered";
+pub const STATUS_UNCOVERED: &str = "uncovered";
+
+const STATUSES: [&str; 3] = [STATUS_PLANNED, STATUS_COVERED, STATUS_UNCOVERED];
+
+/// Max rows of `most_absent_slots`.
+const MOST_ABSENT_SLOTS_MAX: i64 = 20;
+
+// region:    --- Substitution Types
+
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
+pub struct Substitution {
+    pub id: i64,
+    #[serde(with = "iso_date")]
+    #[schemars(with = "String")]
+    pub date: Date,
+    pub n_hour: i32,
+    pub absent_id: i64,
+    pub covering_id: Option<i64>,
+    pub group_id: Option<i64>,
+    pub classroom_name: String,
+    /// planned, covered or uncovered.
+    pub status: String,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
+}
+
+/// A planned substitution.
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
+pub struct SubstitutionForCreate {
+    #[serde(with = "iso_date")]
+    #[schemars(with = "String")]
+    pub date: Date,
+    pub n_hour: i32,
+    pub absent_id: i64,
+    pub covering_id: Option<i64>,
+    pub group_id: Option<i64>,
+    pub classroom_name: String,
+}
+
+#[derive(Fields, Default, Deserialize, JsonSchema)]
+pub struct SubstitutionForUpdate {
+    #[serde(default, deserialize_with = "nullable_value")]
+    #[schemars(with = "Option<i64>")]
+    pub covering_id: Option<NullableValue<i64>>,
+    #[serde(default, deserialize_with = "nullable_value")]
+    #[schemars(with = "Option<i64>")]
+    pub group_id: Option<NullableValue<i64>>,
+    pub classroom_name: Option<String>,
+    pub status: Option<String>,
+}
+
+/// An absent teacher hour seen by `update_guards`, covered when `covering_id`.
+#[derive(Debug, Clone)]
+pub struct SubstitutionForRecord {
+    pub date: Date,
+    pub course: i32,
+    pub week_day: i32,
+    pub n_hour: i32,
+    pub absent_id: i64,
+    pub covering_id: Option<i64>,
+    pub classroom_name: String,
+}
+
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct SubstitutionFilter {
+    pub id: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "date_to_sea_value")]
+    pub date: Option<OpValsValue>,
+    pub n_hour: Option<OpValsInt32>,
+    pub absent_id: Option<OpValsInt64>,
+    pub covering_id: Option<OpValsInt64>,
+    pub group_id: Option<OpValsInt64>,
+    pub classroom_name: Option<OpValsString>,
+    pub status: Option<OpValsString>,
+
+    pub cid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    pub ctime: Option<OpValsValue>,
+    pub mid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    pub mtime: Option<OpValsValue>,
+}
+
+/// The reports dates, both included.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SubstitutionPeriod {
+    #[serde(with = "iso_date")]
+    #[schemars(with = "String")]
+    pub from: Date,
+    #[serde(with = "iso_date")]
+    #[schemars(with = "String")]
+    pub to: Date,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, JsonSchema)]
+pub struct TeacherMonthSubstitutions {
+    pub username: String,
+    /// e.g., "2024-10"
+    pub month: String,
+    pub substitutions: i64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, JsonSchema)]
+pub struct DayUncoveredHours {
+    #[serde(with = "iso_date")]
+    #[schemars(with = "String")]
+    pub date: Date,
+    pub uncovered: i64,
+}
+
+/// The absences of a week day and hour.
+#[derive(Debug, Clone, FromRow, Serialize, JsonSchema)]
+pub struct AbsentSlot {
+    /// 0 is Monday.
+    pub week_day: i32,
+    pub n_hour: i32,
+    pub absences: i64,
+}
+
+/// Marker trait
+pub trait SubstitutionBy: HasFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
+
+impl SubstitutionBy for Substitution {}
+
+// endregion: --- Substitution Types
+
+pub struct SubstitutionBmc;
+
+impl PostgresDbBmc for SubstitutionBmc {
+    const TABLE: &'static str = "substitutions";
+    const CENTER_SCOPED: bool = true;
+}
+
+impl SubstitutionBmc {
+    pub async fn create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        substitution_c: SubstitutionForCreate,
+    ) -> Result<i64> {
+        base::create::<Self, _>(ctx, mm, substitution_c).await
+    }
+
+    pub async fn get<E>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
+    where
+        E: SubstitutionBy,
+    {
+        base::get::<Self, _>(ctx, mm, id).await
+    }
+
+    pub async fn list(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<SubstitutionFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<Substitution>> {
+        base::list::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
+
+    pub async fn update(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        substitution_u: SubstitutionForUpdate,
+    ) -> Result<()> {
+        Self::update_if_unmodified(ctx, mm, id, substitution_u, None).await
+    }
+
+    pub async fn update_if_unmodified(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        substitution_u: SubstitutionForUpdate,
+        expected_mtime: Option<OffsetDateTime>,
+    ) -> Result<()> {
+        if let Some(status) = &substitution_u.status {
+            if !STATUSES.contains(&status.as_str()) {
+                return Err(Error::SubstitutionStatusInvalid {
+                    status: status.clone(),
+                });
+            }
+        }
+
+        base::update_if_unmodified::<Self, _>(ctx, mm, id, substitution_u, expected_mtime).await
+    }
+
+    pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        base::delete::<Self>(ctx, mm, id).await
+    }
+
+    /// Upserts the absent teacher hour, of the absent teacher center.
+    /// False when left as is (e.g., already uncovered, or planned).
+    pub async fn record(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        substitution_r: SubstitutionForRecord,
+    ) -> Result<bool> {
+        let SubstitutionForRecord {
+            date,
+            course,
+            week_day,
+            n_hour,
+            absent_id,
+            covering_id,
+            classroom_name,
+        } = substitution_r;
+        let status = if covering_id.is_some() {
+            STATUS_COVERED
+        } else {
+            STATUS_UNCOVERED
+        };
+
+        let sql = format!(
+            "INSERT INTO {0} (center_id, date, n_hour, absent_id, covering_id, group_id, \
+                 classroom_name, status, cid, ctime, mid, mtime) \
+             SELECT u.center_id, $1, $2, u.id, $4, \
+                 (SELECT s.group_id FROM {2} h \
+                  JOIN {3} s ON s.id = h.schedule_id AND s.course = h.course \
+                  WHERE s.group_id IS NOT NULL AND h.center_id = u.center_id \
+                      AND h.course = $5 AND h.week_day = $6 AND h.n_hour = $2 \
+                      AND h.classroom_name = $7 \
+                  LIMIT 1), \
+                 $7, $8, $9, now(), $9, now() \
+             FROM {1} u WHERE u.id = $3 \
+             ON CONFLICT (center_id, date, n_hour, absent_id) DO UPDATE SET \
+                 covering_id = EXCLUDED.covering_id, group_id = EXCLUDED.group_id, \
+                 classroom_name = EXCLUDED.classroom_name, status = EXCLUDED.status, \
+                 mid = $9, mtime = now() \
+             WHERE EXCLUDED.status = '{STATUS_COVERED}'",
+            Self::TABLE,
+            UserBmc::TABLE,
+            ScheduleHourBmc::TABLE,
+            ScheduleBmc::TABLE
+        );
+        let res = mm
+            .dbx()
+            .execute(
+                sqlx::query(&sql)
+                    .bind(date)
+                    .bind(n_hour)
+                    .bind(absent_id)
+                    .bind(covering_id)
+                    .bind(course)
+                    .bind(week_day)
+                    .bind(classroom_name)
+                    .bind(status)
+                    .bind(ctx.user_id()),
+            )
+            .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    /// The covered substitutions of each teacher per month, by username then month.
+    pub async fn per_teacher_month(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        period: SubstitutionPeriod,
+    ) -> Result<Vec<TeacherMonthSubstitutions>> {
+        let sql = format!(
+            "SELECT u.username, to_char(s.date, 'YYYY-MM') AS month, \
+                    count(*) AS substitutions \
+             FROM {} s JOIN {} u ON u.id = s.covering_id \
+             WHERE s.status = '{STATUS_COVERED}' AND s.date BETWEEN $1 AND $2 \
+                 AND ($3::BIGINT IS NULL OR s.center_id = $3) \
+             GROUP BY u.username, month \
+             ORDER BY u.username, month",
+            Self::TABLE,
+            UserBmc::TABLE
+        );
+        let rows = mm
+            .dbx()
+            .fetch_all(
+                sqlx::query_as::<_, TeacherMonthSubstitutions>(&sql)
+                    .bind(period.from)
+                    .bind(period.to)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+
+        Ok(rows)
+    }
+
+    /// The days with uncovered hours, by date.
+    pub async fn uncovered_per_day(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        period: SubstitutionPeriod,
+    ) -> Result<Vec<DayUncoveredHours>> {
+        let sql = format!(
+            "SELECT date, count(*) AS uncovered FROM {} \
+             WHERE status = '{STATUS_UNCOVERED}' AND date BETWEEN $1 AND $2 \
+                 AND ($3::BIGINT IS NULL OR center_id = $3) \
+             GROUP BY date \
+             ORDER BY date",
+            Self::TABLE
+        );
+        let rows = mm
+            .dbx()
+            .fetch_all(
+                sqlx::query_as::<_, DayUncoveredHours>(&sql)
+                    .bind(period.from)
+                    .bind(period.to)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+
+        Ok(rows)
+    }
+
+    /// The week days and hours with the most absences (any status), most first.
+    pub async fn most_absent_slots(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        period: SubstitutionPeriod,
+    ) -> Result<Vec<AbsentSlot>> {
+        // Note: `isodow` is 1 for Monday, the `week_day` of the schedule hours is 0.
+        let sql = format!(
+            "SELECT (extract(isodow FROM date)::INT - 1) AS week_day, n_hour, \
+                    count(*) AS absences \
+             FROM {} \
+             WHERE date BETWEEN $1 AND $2 AND ($3::BIGINT IS NULL OR center_id = $3) \
+             GROUP BY week_day, n_hour \
+             ORDER BY absences DESC, week_day, n_hour \
+             LIMIT {MOST_ABSENT_SLOTS_MAX}",
+            Self::TABLE
+        );
+        let rows = mm
+            .dbx()
+            .fetch_all(
+                sqlx::query_as::<_, AbsentSlot>(&sql)
+                    .bind(period.from)
+                    .bind(period.to)
+                    .bind(ctx.center_id()),
+            )
+            .await?;
+
+        Ok(rows)
+    }
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use modql::filter::OpValsInt64;
+    use serde_json::json;
+    use serial_test::serial;
+    use time::macros::date;
+
+    use crate::_dev_utils;
+    use crate::ctx::Ctx;
+    use crate::model::substitution::{
+        Substitution, SubstitutionBmc, SubstitutionFilter, SubstitutionForCreate,
+        SubstitutionForRecord, SubstitutionForUpdate, SubstitutionPeriod, STATUS_COVERED,
+        STATUS_PLANNED, STATUS_UNCOVERED,
+    };
+    use crate::model::user::UserBmc;
+    use crate::model::Error;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_record_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_date = date!(2024 - 10 - 07);
+        let absent_id = _dev_utils::seed_user(&ctx, &mm, "test_record_ok absent").await?;
+        let guard_id = _dev_utils::seed_user(&ctx, &mm, "test_record_ok guard").await?;
+        let fx_record = |n_hour: i32, covering_id: Option<i64>| SubstitutionForRecord {
+            date: fx_date,
+            course: 2024,
+            week_day: 0,
+            n_hour,
+            absent_id,
+            covering_id,
+            classroom_name: "Info 2".to_string(),
+        };
+
+        // -- Exec
+        // Uncovered on the first run, covered on the next one.
+        let uncovered_first = SubstitutionBmc::record(&ctx, &mm, fx_record(1, None)).await?;
+        let covered = SubstitutionBmc::record(&ctx, &mm, fx_record(1, Some(guard_id))).await?;
+        // Stays uncovered (seen again on each run).
+        SubstitutionBmc::record(&ctx, &mm, fx_record(2, None)).await?;
+        let uncovered_again = SubstitutionBmc::record(&ctx, &mm, fx_record(2, None)).await?;
+
+        // -- Check
+        assert!(uncovered_first);
+        assert!(covered);
+        assert!(!uncovered_again, "Should be left as is");
+        let filters = vec![SubstitutionFilter {
+            absent_id: Some(OpValsInt64::from(absent_id)),
+            ..Default::default()
+        }];
+        let mut substitutions = SubstitutionBmc::list(&ctx, &mm, Some(filters), None).await?;
+        substitutions.sort_by_key(|substitution| substitution.n_hour);
+        assert_eq!(substitutions.len(), 2);
+        assert_eq!(substitutions[0].status, STATUS_COVERED);
+        assert_eq!(substitutions[0].covering_id, Some(guard_id));
+        assert_eq!(substitutions[1].status, STATUS_UNCOVERED);
+        assert_eq!(substitutions[1].covering_id, None);
+
+        let period = SubstitutionPeriod {
+            from: date!(2024 - 10 - 01),
+            to: date!(2024 - 10 - 31),
+        };
+        let months = SubstitutionBmc::per_teacher_month(&ctx, &mm, period.clone()).await?;
+        assert!(months
+            .iter()
+            .any(|row| row.username == "test_record_ok guard"
+                && row.month == "2024-10"
+                && row.substitutions == 1));
+        let days = SubstitutionBmc::uncovered_per_day(&ctx, &mm, period.clone()).await?;
+        assert!(days
+            .iter()
+            .any(|row| row.date == fx_date && row.uncovered >= 1));
+        let slots = SubstitutionBmc::most_absent_slots(&ctx, &mm, period).await?;
+        assert!(slots
+            .iter()
+            .any(|slot| slot.week_day == 0 && slot.n_hour == 1));
+
+        // -- Clean
+        for substitution in substitutions {
+            SubstitutionBmc::delete(&ctx, &mm, substitution.id).await?;
+        }
+        UserBmc::delete(&ctx, &mm, absent_id).await?;
+        UserBmc::delete(&ctx, &mm, guard_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_record_keeps_planned_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_date = date!(2024 - 10 - 08);
+        let absent_id =
+            _dev_utils::seed_user(&ctx, &mm, "test_record_keeps_planned_ok absent").await?;
+        let covering_id =
+            _dev_utils::seed_user(&ctx, &mm, "test_record_keeps_planned_ok covering").await?;
+        let substitution_c = SubstitutionForCreate {
+            date: fx_date,
+            n_hour: 3,
+            absent_id,
+            covering_id: Some(covering_id),
+            group_id: None,
+            classroom_name: "Info 2".to_string(),
+        };
+        let id = SubstitutionBmc::create(&ctx, &mm, substitution_c).await?;
+
+        // -- Exec
+        let substitution_r = SubstitutionForRecord {
+            date: fx_date,
+            course: 2024,
+            week_day: 1,
+            n_hour: 3,
+            absent_id,
+            covering_id: None,
+            classroom_name: "Info 2".to_string(),
+        };
+        let recorded = SubstitutionBmc::record(&ctx, &mm, substitution_r).await?;
+
+        // -- Check
+        assert!(!recorded);
+        let substitution: Substitution = SubstitutionBmc::get(&ctx, &mm, id).await?;
+        assert_eq!(substitution.status, STATUS_PLANNED);
+        assert_eq!(substitution.covering_id, Some(covering_id));
+
+        // -- Clean
+        SubstitutionBmc::delete(&ctx, &mm, id).await?;
+        UserBmc::delete(&ctx, &mm, absent_id).await?;
+        UserBmc::delete(&ctx, &mm, covering_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_clear_covering_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let absent_id =
+            _dev_utils::seed_user(&ctx, &mm, "test_update_clear_covering_ok absent").await?;
+        let covering_id =
+            _dev_utils::seed_user(&ctx, &mm, "test_update_clear_covering_ok covering").await?;
+        let substitution_c = SubstitutionForCreate {
+            date: date!(2024 - 10 - 09),
+            n_hour: 4,
+            absent_id,
+            covering_id: Some(covering_id),
+            group_id: None,
+            classroom_name: "Info 2".to_string(),
+        };
+        let id = SubstitutionBmc::create(&ctx, &mm, substitution_c).await?;
+
+        // -- Exec
+        let substitution_u: SubstitutionForUpdate = serde_json::from_value(json!({
+            "covering_id": null,
+            "status": STATUS_UNCOVERED
+        }))?;
+        SubstitutionBmc::update(&ctx, &mm, id, substitution_u).await?;
+
+        // -- Check
+        let substitution: Substitution = SubstitutionBmc::get(&ctx, &mm, id).await?;
+        assert_eq!(substitution.covering_id, None);
+        assert_eq!(substitution.status, STATUS_UNCOVERED);
+
+        // -- Clean
+        SubstitutionBmc::delete(&ctx, &mm, id).await?;
+        UserBmc::delete(&ctx, &mm, absent_id).await?;
+        UserBmc::delete(&ctx, &mm, covering_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_err_status_invalid() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_status = "done";
+
+        // -- Exec
+        let substitution_u = SubstitutionForUpdate {
+            status: Some(fx_status.to_string()),
+            ..Default::default()
+        };
+        let res = SubstitutionBmc::update(&ctx, &mm, 100, substitution_u).await;
+
+        // -- Check
+        assert!(
+            matches!(&res, Err(Error::SubstitutionStatusInvalid { status }) if status == fx_status),
+            "Should have matched `SubstitutionStatusInvalid` but was {res:?}"
+        );
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
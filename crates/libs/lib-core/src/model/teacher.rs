@@ -47,9 +47,9 @@ pub struct TeacherFilter {
 
 #[derive(Fields, Default, Deserialize)]
 pub struct TeacherForUpdate {
-    pub user_id: i64,
-    pub active: bool,
-    pub department_id: i64,
+    pub user_id: Option<i64>,
+    pub active: Option<bool>,
+    pub department_id: Option<i64>,
 }
 
 #[derive(Iden)]
@@ -134,7 +134,7 @@ impl TeacherBmc {
         mm: &ModelManager,
         department_id: i64,
     ) -> Result<i64> {
-        let db = mm.postgres_db();
+        let dbx = mm.dbx();
 
         // -- Build query
         let mut query = Query::select();
@@ -145,7 +145,7 @@ impl TeacherBmc {
 
         // -- Exec query
         let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-        let result: (i64,) = sqlx::query_as_with(&sql, values).fetch_one(db).await?;
+        let result: (i64,) = dbx.fetch_one(sqlx::query_as_with(&sql, values)).await?;
 
         Ok(result.0)
     }
@@ -155,7 +155,7 @@ impl TeacherBmc {
         mm: &ModelManager,
         department_id: i64,
     ) -> Result<Vec<Teacher>> {
-        let db = mm.postgres_db();
+        let dbx = mm.dbx();
 
         // -- Build query
         let mut query = Query::select();
@@ -172,8 +172,8 @@ impl TeacherBmc {
 
         // -- Exec query
         let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-        let result = sqlx::query_as_with::<_, Teacher, _>(&sql, values)
-            .fetch_all(db)
+        let result = dbx
+            .fetch_all(sqlx::query_as_with::<_, Teacher, _>(&sql, values))
             .await?;
 
         Ok(result)
@@ -249,9 +249,9 @@ mod tests {
             &mm,
             fx_teacher_id,
             TeacherForUpdate {
-                user_id: fx_user_id,
-                active: fx_new_active,
-                department_id: fx_department_id,
+                user_id: Some(fx_user_id),
+                active: Some(fx_new_active),
+                department_id: Some(fx_department_id),
             },
         )
         .await?;
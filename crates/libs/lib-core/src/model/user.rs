@@ -1,41 +1,51 @@
-use modql::field::{Field, Fields, HasFields};
+use modql::field::{Fields, HasFields};
 use modql::filter::{FilterNodes, ListOptions, OpValsBool, OpValsInt64, OpValsString, OpValsValue};
-use sea_query::{Expr, Iden, PostgresQueryBuilder, Query};
+use schemars::JsonSchema;
 use sea_query::extension::postgres::PgExpr;
+use sea_query::{Expr, Iden, PostgresQueryBuilder, Query};
 use sea_query_binder::SqlxBinder;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use sqlx::FromRow;
 use sqlx::postgres::PgRow;
-use time::Time;
+use sqlx::FromRow;
+use time::{OffsetDateTime, Time};
 use uuid::Uuid;
 
 use lib_auth::pwd::{self, ContentToHash};
 use lib_utils::time::now_utc;
 
 use crate::ctx::Ctx;
-use crate::model::{Error, ModelManager};
-use crate::model::base::{self, add_timestamps_for_update, CommonIden, PostgresDbBmc};
-use crate::model::modql_utils::time_to_sea_value;
+use crate::model::base::{self, center_scope, CenterIden, PostgresDbBmc, SoftDeleteIden};
+use crate::model::event::{event_center_id, ModelEvent};
+use crate::model::modql_utils::{nullable_value, time_to_sea_value, NullableValue};
+use crate::model::ModelManager;
 use crate::model::Result;
 
 // region:    --- User Types
 #[serde_as]
-#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
 pub struct User {
     pub id: i64,
     pub username: String,
     pub is_admin: bool,
+    /// Alerted of the classes left uncovered by `ControlBmc::update_guards`.
+    pub is_head_of_studies: bool,
     pub in_center: bool,
+    #[schemars(with = "Option<(u8, u8, u8, u32)>")]
     pub last_checkin: Option<Time>,
+    #[schemars(with = "Option<(u8, u8, u8, u32)>")]
     pub last_checkout: Option<Time>,
     pub active: bool,
     pub department_id: Option<i64>,
     pub substituting_id: Option<i64>,
     pub substitutions: i64,
+    pub auth_source: String,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub mtime: OffsetDateTime,
 }
 
-#[derive(Fields, Deserialize, Clone)]
+#[derive(Fields, Deserialize, Clone, JsonSchema)]
 pub struct UserForCreate {
     pub username: String,
     pub is_admin: bool,
@@ -45,18 +55,29 @@ pub struct UserForCreate {
     pub substituting_id: Option<i64>,
 }
 
+/// Users created on first login by an external authn backend (e.g., ldap).
+/// No `pwd`, so the local backend will never accept them.
+#[derive(Fields, Clone)]
+pub struct UserForProvision {
+    pub username: String,
+    pub is_admin: bool,
+    pub active: bool,
+    pub department_id: Option<i64>,
+    pub auth_source: String,
+}
+
 #[derive(FilterNodes, Deserialize, Default, Debug)]
 pub struct UserFilter {
     id: Option<OpValsInt64>,
     username: Option<OpValsString>,
     is_admin: Option<OpValsBool>,
+    is_head_of_studies: Option<OpValsBool>,
     in_center: Option<OpValsBool>,
     active: Option<OpValsBool>,
     department_id: Option<OpValsInt64>,
     substituting_id: Option<OpValsInt64>,
     substitutions: Option<OpValsInt64>,
 
-
     cid: Option<OpValsInt64>,
     #[modql(to_sea_value_fn = "time_to_sea_value")]
     ctime: Option<OpValsValue>,
@@ -65,17 +86,24 @@ pub struct UserFilter {
     mtime: Option<OpValsValue>,
 }
 
-#[derive(Fields, Default, Deserialize, Clone)]
+#[derive(Fields, Default, Deserialize, Clone, JsonSchema)]
 pub struct UserForUpdate {
-    pub username: String,
-    pub is_admin: bool,
-    pub in_center: bool,
+    pub username: Option<String>,
+    pub is_admin: Option<bool>,
+    pub is_head_of_studies: Option<bool>,
+    pub in_center: Option<bool>,
+    #[schemars(with = "Option<(u8, u8, u8, u32)>")]
     pub last_checkin: Option<Time>,
+    #[schemars(with = "Option<(u8, u8, u8, u32)>")]
     pub last_checkout: Option<Time>,
-    pub active: bool,
-    pub department_id: Option<i64>,
-    pub substituting_id: Option<i64>,
-    pub substitutions: i64,
+    pub active: Option<bool>,
+    #[serde(default, deserialize_with = "nullable_value")]
+    #[schemars(with = "Option<i64>")]
+    pub department_id: Option<NullableValue<i64>>,
+    #[serde(default, deserialize_with = "nullable_value")]
+    #[schemars(with = "Option<i64>")]
+    pub substituting_id: Option<NullableValue<i64>>,
+    pub substitutions: Option<i64>,
 }
 
 #[derive(Fields, Default, Deserialize, Clone)]
@@ -85,27 +113,44 @@ pub struct UserForCheckin {
     pub last_checkout: Option<Time>,
 }
 
-#[derive(Fields, Default, Deserialize, Clone)]
+/// The hashed `pwd`, see `UserBmc::update_pwd`.
+#[derive(Fields)]
+struct UserForPwd {
+    pwd: String,
+}
+
+#[derive(Fields, Default, Deserialize, Clone, JsonSchema)]
 pub struct UserForUpdatePwd {
-    pub username: String,
-    pub pwd: String,
-    pub is_admin: bool,
-    pub in_center: bool,
+    pub username: Option<String>,
+    pub pwd: Option<String>,
+    pub is_admin: Option<bool>,
+    pub is_head_of_studies: Option<bool>,
+    pub in_center: Option<bool>,
+    #[schemars(with = "Option<(u8, u8, u8, u32)>")]
     pub last_checkin: Option<Time>,
+    #[schemars(with = "Option<(u8, u8, u8, u32)>")]
     pub last_checkout: Option<Time>,
-    pub active: bool,
-    pub department_id: Option<i64>,
-    pub substituting_id: Option<i64>,
-    pub substitutions: i64,
+    pub active: Option<bool>,
+    #[serde(default, deserialize_with = "nullable_value")]
+    #[schemars(with = "Option<i64>")]
+    pub department_id: Option<NullableValue<i64>>,
+    #[serde(default, deserialize_with = "nullable_value")]
+    #[schemars(with = "Option<i64>")]
+    pub substituting_id: Option<NullableValue<i64>>,
+    pub substitutions: Option<i64>,
 }
 
 #[derive(Clone, FromRow, Fields, Debug)]
 pub struct UserForLogin {
     pub id: i64,
+    pub center_id: i64,
     pub username: String,
     pub is_admin: bool,
+    pub is_super_admin: bool,
 
     // -- pwd and token info
+    /// The backend the user authenticates with (`BackendKind::as_str`).
+    pub auth_source: String,
     pub pwd: Option<String>,
     // encrypted, #_scheme_id_#....
     pub pwd_salt: Uuid,
@@ -115,13 +160,28 @@ pub struct UserForLogin {
 #[derive(Clone, FromRow, Fields, Debug)]
 pub struct UserForAuth {
     pub id: i64,
+    pub center_id: i64,
     pub username: String,
     pub is_admin: bool,
+    pub is_super_admin: bool,
 
     // -- token info
     pub token_salt: Uuid,
 }
 
+#[derive(Clone, FromRow, Fields, Debug)]
+pub struct UserForCalendar {
+    pub id: i64,
+    pub center_id: i64,
+    pub calendar_salt: Uuid,
+}
+
+/// New `calendar_salt`, see `CalendarBmc::rotate_feed_token`.
+#[derive(Fields)]
+pub struct UserForCalendarRotate {
+    pub calendar_salt: Uuid,
+}
+
 /// Marker trait
 pub trait UserBy: HasFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
 
@@ -131,19 +191,13 @@ impl UserBy for UserForLogin {}
 
 impl UserBy for UserForAuth {}
 
+impl UserBy for UserForCalendar {}
+
 #[derive(Iden)]
 enum UserIden {
     Id,
     Username,
-    IsAdmin,
-    Pwd,
     DepartmentId,
-    InCenter,
-    LastCheckin,
-    LastCheckout,
-    Active,
-    SubstitutingId,
-    Substitutions
 }
 
 // endregion: --- User Types
@@ -152,6 +206,8 @@ pub struct UserBmc;
 
 impl PostgresDbBmc for UserBmc {
     const TABLE: &'static str = "users";
+    const SOFT_DELETE: bool = true;
+    const CENTER_SCOPED: bool = true;
 }
 
 impl UserBmc {
@@ -159,6 +215,10 @@ impl UserBmc {
         base::create::<Self, _>(ctx, mm, user_c).await
     }
 
+    pub async fn provision(ctx: &Ctx, mm: &ModelManager, user_p: UserForProvision) -> Result<i64> {
+        base::create::<Self, _>(ctx, mm, user_p).await
+    }
+
     pub async fn get<E>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
     where
         E: UserBy,
@@ -170,35 +230,38 @@ impl UserBmc {
         base::get::<Self, _>(ctx, mm, ctx.user_id()).await
     }
 
+    /// The username is unique per center, so in the ctx center.
     pub async fn first_by_username<E>(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
         username: &str,
     ) -> Result<Option<E>>
     where
         E: UserBy,
     {
-        let db = mm.postgres_db();
+        let dbx = mm.dbx();
 
         // -- Build query
         let mut query = Query::select();
         query
             .from(Self::table_ref())
             .columns(E::field_idens())
-            .and_where(Expr::col(UserIden::Username).eq(username));
+            .and_where(Expr::col(UserIden::Username).eq(username))
+            .and_where(Expr::col(SoftDeleteIden::DeletedAt).is_null());
+        if let Some(center_id) = center_scope::<Self>(ctx) {
+            query.and_where(Expr::col(CenterIden::CenterId).eq(center_id));
+        }
 
         // -- Exec query
         let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-        let user = sqlx::query_as_with::<_, E, _>(&sql, values)
-            .fetch_optional(db)
+        let user = dbx
+            .fetch_optional(sqlx::query_as_with::<_, E, _>(&sql, values))
             .await?;
 
         Ok(user)
     }
 
     pub async fn update_pwd(ctx: &Ctx, mm: &ModelManager, id: i64, pwd_clear: &str) -> Result<()> {
-        let db = mm.postgres_db();
-
         // -- Prep password
         let user: UserForLogin = Self::get(ctx, mm, id).await?;
         let pwd = pwd::hash_pwd(ContentToHash {
@@ -207,26 +270,8 @@ impl UserBmc {
         })
         .await?;
 
-        // -- Prep the data
-        let mut fields = Fields::new(vec![Field::new(UserIden::Pwd, pwd.into())]);
-        add_timestamps_for_update(&mut fields, ctx.user_id());
-
-        // -- Build query
-        let fields = fields.for_sea_update();
-        let mut query = Query::update();
-        query
-            .table(Self::table_ref())
-            .values(fields)
-            .and_where(Expr::col(UserIden::Id).eq(id));
-
-        // -- Exec query
-        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-        let _count = sqlx::query_with(&sql, values)
-            .execute(db)
-            .await?
-            .rows_affected();
-
-        Ok(())
+        // Note: Audited as any update (`pwd` redacted).
+        base::update::<Self, _>(ctx, mm, id, UserForPwd { pwd }).await
     }
 
     pub async fn list(
@@ -238,6 +283,15 @@ impl UserBmc {
         base::list::<Self, _, _>(ctx, mm, filters, list_options).await
     }
 
+    pub async fn list_including_deleted(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<UserFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<User>> {
+        base::list_including_deleted::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
+
     pub async fn update(
         ctx: &Ctx,
         mm: &ModelManager,
@@ -247,8 +301,17 @@ impl UserBmc {
         base::update::<Self, _>(ctx, mm, id, user_u).await
     }
 
+    pub async fn update_if_unmodified(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        user_u: UserForUpdate,
+        expected_mtime: Option<OffsetDateTime>,
+    ) -> Result<()> {
+        base::update_if_unmodified::<Self, _>(ctx, mm, id, user_u, expected_mtime).await
+    }
+
     pub async fn update_checkin(ctx: &Ctx, mm: &ModelManager, checkin: bool) -> Result<()> {
-        let db = mm.postgres_db();
         let now = now_utc();
         let mut user_c = UserForCheckin {
             in_center: checkin,
@@ -261,125 +324,111 @@ impl UserBmc {
             user_c.last_checkout = Some(now.time())
         }
 
-        let mut fields = user_c.not_none_fields();
+        base::update::<Self, _>(ctx, mm, ctx.user_id(), user_c).await?;
 
-        add_timestamps_for_update(&mut fields, ctx.user_id());
-        let fields = fields.for_sea_update();
-
-        // -- Build query
-        let mut query = Query::update();
-        query
-            .table(UserBmc::table_ref())
-            .values(fields)
-            .and_where(Expr::col(CommonIden::Id).eq(ctx.user_id()));
-
-        // -- Exec query
-        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-        let count = sqlx::query_with(&sql, values)
-            .execute(db)
-            .await?
-            .rows_affected();
-
-        // -- Check result
-        if count == 0 {
-            Err(Error::EntityNotFound {
-                entity: UserBmc::TABLE,
-                id: ctx.user_id(),
-            })
+        let center_id = event_center_id(ctx);
+        let user_id = ctx.user_id();
+        let event = if checkin {
+            ModelEvent::CheckIn { center_id, user_id }
         } else {
-            Ok(())
-        }
+            ModelEvent::CheckOut { center_id, user_id }
+        };
+        mm.publish(event).await;
+
+        Ok(())
     }
 
     pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
         base::delete::<Self>(ctx, mm, id).await
     }
 
+    pub async fn restore(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        base::restore::<Self>(ctx, mm, id).await
+    }
+
     pub async fn check_username<E>(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
         username: &str,
     ) -> Result<Option<E>>
     where
         E: UserBy,
     {
-        let db = mm.postgres_db();
+        let dbx = mm.dbx();
 
         // -- Build query
         let mut query = Query::select();
         query
             .from(Self::table_ref())
             .columns(E::field_idens())
-            .and_where(Expr::col(UserIden::Username).ilike(username));
+            .and_where(Expr::col(UserIden::Username).ilike(username))
+            .and_where(Expr::col(SoftDeleteIden::DeletedAt).is_null());
+        if let Some(center_id) = center_scope::<Self>(ctx) {
+            query.and_where(Expr::col(CenterIden::CenterId).eq(center_id));
+        }
 
         // -- Exec query
         let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-        let user = sqlx::query_as_with::<_, E, _>(&sql, values)
-            .fetch_optional(db)
+        let user = dbx
+            .fetch_optional(sqlx::query_as_with::<_, E, _>(&sql, values))
             .await?;
 
         Ok(user)
     }
 
     pub async fn count_users_by_department(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
         department_id: i64,
     ) -> Result<i64> {
-        let db = mm.postgres_db();
+        let dbx = mm.dbx();
 
         // -- Build query
         let mut query = Query::select();
         query
             .expr(Expr::col(UserIden::Id).count())
             .from(UserBmc::table_ref())
-            .and_where(Expr::col(UserIden::DepartmentId).eq(department_id));
+            .and_where(Expr::col(UserIden::DepartmentId).eq(department_id))
+            .and_where(Expr::col(SoftDeleteIden::DeletedAt).is_null());
+        if let Some(center_id) = center_scope::<Self>(ctx) {
+            query.and_where(Expr::col(CenterIden::CenterId).eq(center_id));
+        }
 
         // -- Exec query
         let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-        let result: (i64,) = sqlx::query_as_with(&sql, values).fetch_one(db).await?;
+        let result: (i64,) = dbx.fetch_one(sqlx::query_as_with(&sql, values)).await?;
 
         Ok(result.0)
     }
 
     pub async fn users_by_department(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
         department_id: i64,
     ) -> Result<Vec<User>> {
-        let db = mm.postgres_db();
+        let dbx = mm.dbx();
 
         // -- Build query
         let mut query = Query::select();
         query
             .from(UserBmc::table_ref())
-            .columns(vec![
-                UserIden::Id,
-                UserIden::Username,
-                UserIden::Pwd,
-                UserIden::IsAdmin,
-                UserIden::DepartmentId,
-                UserIden::InCenter,
-                UserIden::LastCheckin,
-                UserIden::LastCheckout,
-                UserIden::Active,
-                UserIden::SubstitutingId,
-                UserIden::Substitutions
-            ])
-            .and_where(Expr::col(UserIden::DepartmentId).eq(department_id));
+            .columns(User::field_idens())
+            .and_where(Expr::col(UserIden::DepartmentId).eq(department_id))
+            .and_where(Expr::col(SoftDeleteIden::DeletedAt).is_null());
+        if let Some(center_id) = center_scope::<Self>(ctx) {
+            query.and_where(Expr::col(CenterIden::CenterId).eq(center_id));
+        }
 
         // -- Exec query
         let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-        let result = sqlx::query_as_with::<_, User, _>(&sql, values)
-            .fetch_all(db)
+        let result = dbx
+            .fetch_all(sqlx::query_as_with::<_, User, _>(&sql, values))
             .await?;
 
         Ok(result)
     }
-
 }
 
-
 // region:    --- Tests
 #[cfg(test)]
 mod tests {
@@ -388,8 +437,11 @@ mod tests {
     use serial_test::serial;
 
     use crate::_dev_utils::{self, seed_department};
-    use crate::ctx::Ctx;
-    use crate::model::user::{User, UserBmc, UserForCreate, UserForUpdate};
+    use crate::ctx::{Ctx, DEFAULT_CENTER_ID};
+    use crate::model::audit_log::{AuditLogBmc, AuditLogFilter};
+    use crate::model::user::{
+        User, UserBmc, UserForCreate, UserForLogin, UserForProvision, UserForUpdate,
+    };
 
     #[serial]
     #[tokio::test]
@@ -443,6 +495,41 @@ mod tests {
         Ok(())
     }
 
+    #[serial]
+    #[tokio::test]
+    async fn test_provision_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_username = "Prueba_Provision";
+        let fx_department_name = "Department_test_provision_user_oK";
+        let fx_department_id = seed_department(&ctx, &mm, fx_department_name).await?;
+
+        // -- Exec
+        let user_p = UserForProvision {
+            username: fx_username.to_string(),
+            is_admin: false,
+            active: true,
+            department_id: Some(fx_department_id),
+            auth_source: "ldap".to_string(),
+        };
+
+        let id = UserBmc::provision(&ctx, &mm, user_p).await?;
+
+        // -- Check
+        let user: User = UserBmc::get(&ctx, &mm, id).await?;
+        assert_eq!(user.username, fx_username);
+        assert_eq!(user.auth_source, "ldap");
+        assert_eq!(user.department_id, Some(fx_department_id));
+        let user: UserForLogin = UserBmc::get(&ctx, &mm, id).await?;
+        assert!(user.pwd.is_none(), "provisioned user should have no pwd");
+
+        // -- Clean
+        UserBmc::delete(&ctx, &mm, id).await?;
+
+        Ok(())
+    }
+
     #[serial]
     #[tokio::test]
     async fn test_update_ok() -> Result<()> {
@@ -457,20 +544,20 @@ mod tests {
         let fx_department_name = "Department_test_update_user_oK";
         let fx_department_id = seed_department(&ctx, &mm, fx_department_name).await?;
 
-
         // -- Exec
         UserBmc::update(
             &ctx,
             &mm,
             fx_user_id,
             UserForUpdate {
-                username: fx_username_new.to_string(),
-                is_admin: fx_admin,
-                active: fx_active,
-                department_id: Some(fx_department_id),
+                username: Some(fx_username_new.to_string()),
+                is_admin: Some(fx_admin),
+                is_head_of_studies: None,
+                active: Some(fx_active),
+                department_id: Some(Some(fx_department_id).into()),
                 substituting_id: None,
-                substitutions: 0,
-                in_center: false,
+                substitutions: Some(0),
+                in_center: Some(false),
                 last_checkin: None,
                 last_checkout: None,
             },
@@ -487,6 +574,85 @@ mod tests {
         Ok(())
     }
 
+    #[serial]
+    #[tokio::test]
+    async fn test_update_patch_set_null_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_username = "Juanba test update patch";
+        let fx_department_name = "Department_test_update_patch_user_oK";
+        let fx_department_id = seed_department(&ctx, &mm, fx_department_name).await?;
+        let fx_user_id = _dev_utils::seed_user(&ctx, &mm, fx_username).await?;
+        UserBmc::update(
+            &ctx,
+            &mm,
+            fx_user_id,
+            UserForUpdate {
+                department_id: Some(Some(fx_department_id).into()),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        // -- Exec
+        // Absent fields are left as is, `null` sets NULL.
+        let user_u: UserForUpdate = serde_json::from_value(json!({
+            "active": false,
+            "department_id": null
+        }))?;
+        UserBmc::update(&ctx, &mm, fx_user_id, user_u).await?;
+
+        // -- Check
+        let user: User = UserBmc::get(&ctx, &mm, fx_user_id).await?;
+        assert_eq!(user.username, fx_username);
+        assert!(!user.active);
+        assert_eq!(user.department_id, None);
+
+        // -- Clean
+        UserBmc::delete(&ctx, &mm, fx_user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_pwd_checkin_audited() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let user_id =
+            _dev_utils::seed_user(&ctx, &mm, "test_update_pwd_checkin_audited user").await?;
+        let user_ctx = Ctx::new(user_id, false, DEFAULT_CENTER_ID);
+
+        // -- Exec
+        UserBmc::update_pwd(&ctx, &mm, user_id, "test_update_pwd_checkin_audited pwd").await?;
+        UserBmc::update_checkin(&user_ctx, &mm, true).await?;
+
+        // -- Check
+        let filters: Vec<AuditLogFilter> = serde_json::from_value(json!([{
+            "table_name": "users",
+            "entity_id": user_id,
+            "op": "update"
+        }]))?;
+        let entries = AuditLogBmc::list(&ctx, &mm, Some(filters), None).await?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0].diff["pwd"],
+            json!({"old": "#redacted#", "new": "#redacted#"})
+        );
+        assert_eq!(entries[1].actor_id, user_id);
+        assert_eq!(
+            entries[1].diff["in_center"],
+            json!({"old": false, "new": true})
+        );
+
+        // -- Clean
+        UserBmc::delete(&ctx, &mm, user_id).await?;
+
+        Ok(())
+    }
+
     #[serial]
     #[tokio::test]
     async fn test_list_by_name_ok() -> Result<()> {
@@ -518,5 +684,52 @@ mod tests {
 
         Ok(())
     }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_users_by_department_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_usernames = &["Juanba test by department", "Marta test by department"];
+        let fx_department_name = "Department_test_users_by_department_oK";
+        let fx_department_id = seed_department(&ctx, &mm, fx_department_name).await?;
+        let mut fx_ids = Vec::new();
+        for fx_username in fx_usernames {
+            let id = _dev_utils::seed_user(&ctx, &mm, fx_username).await?;
+            UserBmc::update(
+                &ctx,
+                &mm,
+                id,
+                UserForUpdate {
+                    department_id: Some(Some(fx_department_id).into()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+            fx_ids.push(id);
+        }
+
+        // -- Exec
+        let users = UserBmc::users_by_department(&ctx, &mm, fx_department_id).await?;
+        let count = UserBmc::count_users_by_department(&ctx, &mm, fx_department_id).await?;
+        let other_center_ctx = Ctx::root_ctx().with_center(DEFAULT_CENTER_ID + 1);
+        let other_center_count =
+            UserBmc::count_users_by_department(&other_center_ctx, &mm, fx_department_id).await?;
+
+        // -- Check
+        let mut usernames: Vec<String> = users.into_iter().map(|u| u.username).collect();
+        usernames.sort();
+        assert_eq!(&usernames, fx_usernames);
+        assert_eq!(count, 2);
+        assert_eq!(other_center_count, 0, "other center users counted");
+
+        // -- Clean
+        for id in fx_ids {
+            UserBmc::delete(&ctx, &mm, id).await?;
+        }
+
+        Ok(())
+    }
 }
 // endregion: --- Tests
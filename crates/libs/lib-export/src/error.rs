@@ -4,14 +4,19 @@ pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug, Serialize)]
 pub enum Error {
-	CtxCannotNewRootCtx,
+    // -- Xlsx
+    XlsxWrite(String),
+
+    // -- Pdf
+    PdfLogoInvalid(String),
+    ZipWrite(String),
 }
 
 // region:    --- Error Boilerplate
 impl core::fmt::Display for Error {
-	fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
-		write!(fmt, "{self:?}")
-	}
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{self:?}")
+    }
 }
 
 impl std::error::Error for Error {}
@@ -0,0 +1,16 @@
+//! File exports of the report data (see `lib_core::model::report`).
+//!
+//! - `xlsx` spreadsheets, one sheet per timetable grid or report.
+//! - `pdf` printable timetables, one page per grid (or a zip of documents).
+//!
+//! The web-server serves them as downloads (`/api/export/...`).
+
+// region:    --- Modules
+
+pub use self::error::{Error, Result};
+
+mod error;
+pub mod pdf;
+pub mod xlsx;
+
+// endregion: --- Modules
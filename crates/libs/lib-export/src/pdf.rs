@@ -0,0 +1,620 @@
+//! PDF rendering of the printable timetables (classroom doors, teacher mailboxes).
+//!
+//! - One A4 landscape page per `TimetableGrid`, with the school name and logo header.
+//! - Standard Type 1 fonts (Helvetica), so no font files, WinAnsi encoded text.
+//! - The logo can be PNG or JPEG, embedded once per document.
+
+use std::collections::HashSet;
+use std::io::{Cursor, Write};
+
+use image::GenericImageView;
+use miniz_oxide::deflate::compress_to_vec_zlib;
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use lib_core::model::report::{TimetableGrid, WEEK_DAYS};
+
+use crate::{Error, Result};
+
+pub const PDF_CONTENT_TYPE: &str = "application/pdf";
+pub const ZIP_CONTENT_TYPE: &str = "application/zip";
+
+/// The printed header of every page.
+#[derive(Debug, Clone, Default)]
+pub struct PdfBranding {
+    pub school_name: String,
+    /// PNG or JPEG file content.
+    pub logo: Option<Vec<u8>>,
+}
+
+/// All the grids in one document, one page each.
+pub fn timetable_grids_pdf(grids: &[TimetableGrid], branding: &PdfBranding) -> Result<Vec<u8>> {
+    let logo = branding.logo.as_deref().map(Logo::decode).transpose()?;
+
+    Ok(render(grids, &branding.school_name, logo.as_ref()))
+}
+
+/// One document per grid, named by its title (e.g., "1º ESO A.pdf").
+pub fn timetable_grids_zip(grids: &[TimetableGrid], branding: &PdfBranding) -> Result<Vec<u8>> {
+    let logo = branding.logo.as_deref().map(Logo::decode).transpose()?;
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let mut file_names = HashSet::new();
+    for grid in grids {
+        let pdf = render(
+            std::slice::from_ref(grid),
+            &branding.school_name,
+            logo.as_ref(),
+        );
+        let file_name = unique_file_name(&grid.title, &mut file_names);
+        zip.start_file(file_name, SimpleFileOptions::default())
+            .map_err(zip_err)?;
+        zip.write_all(&pdf)
+            .map_err(|err| Error::ZipWrite(err.to_string()))?;
+    }
+    let zip = zip.finish().map_err(zip_err)?;
+
+    Ok(zip.into_inner())
+}
+
+// region:    --- Layout
+
+/// A4 landscape, in points.
+const PAGE_WIDTH: f32 = 842.0;
+const PAGE_HEIGHT: f32 = 595.0;
+const MARGIN: f32 = 36.0;
+const HEADER_HEIGHT: f32 = 64.0;
+const DAYS_ROW_HEIGHT: f32 = 22.0;
+const HOUR_COL_WIDTH: f32 = 90.0;
+const MAX_ROW_HEIGHT: f32 = 60.0;
+
+const FONT: Name = Name(b"F1");
+const FONT_BOLD: Name = Name(b"F2");
+const LOGO: Name = Name(b"Im1");
+
+fn render(grids: &[TimetableGrid], school_name: &str, logo: Option<&Logo>) -> Vec<u8> {
+    let mut alloc = Ref::new(1);
+    let catalog_id = alloc.bump();
+    let pages_id = alloc.bump();
+    let font_id = alloc.bump();
+    let font_bold_id = alloc.bump();
+    let info_id = alloc.bump();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(pages_id);
+    pdf.type1_font(font_id)
+        .base_font(Name(b"Helvetica"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.type1_font(font_bold_id)
+        .base_font(Name(b"Helvetica-Bold"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.document_info(info_id)
+        .title(TextStr(school_name))
+        .producer(TextStr("Teacherinator"));
+
+    let logo_id = logo.map(|logo| logo.write(&mut pdf, &mut alloc));
+
+    // Note: An empty batch still prints the header page.
+    let pages: Vec<Option<&TimetableGrid>> = if grids.is_empty() {
+        vec![None]
+    } else {
+        grids.iter().map(Some).collect()
+    };
+
+    let mut page_ids = Vec::with_capacity(pages.len());
+    for grid in pages {
+        let page_id = alloc.bump();
+        let content_id = alloc.bump();
+        page_ids.push(page_id);
+
+        let mut page = pdf.page(page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+            .parent(pages_id)
+            .contents(content_id);
+        let mut resources = page.resources();
+        resources
+            .fonts()
+            .pair(FONT, font_id)
+            .pair(FONT_BOLD, font_bold_id);
+        if let Some(logo_id) = logo_id {
+            resources.x_objects().pair(LOGO, logo_id);
+        }
+        resources.finish();
+        page.finish();
+
+        let mut content = Content::new();
+        draw_header(&mut content, grid, school_name, logo);
+        if let Some(grid) = grid {
+            draw_grid(&mut content, grid);
+        }
+        pdf.stream(content_id, &content.finish());
+    }
+
+    pdf.pages(pages_id)
+        .kids(page_ids.iter().copied())
+        .count(page_ids.len() as i32);
+
+    pdf.finish()
+}
+
+fn draw_header(
+    content: &mut Content,
+    grid: Option<&TimetableGrid>,
+    school_name: &str,
+    logo: Option<&Logo>,
+) {
+    let top = PAGE_HEIGHT - MARGIN;
+    let mut text_x = MARGIN;
+
+    if let Some(logo) = logo {
+        let height = HEADER_HEIGHT - 16.0;
+        let width = height * logo.width as f32 / logo.height as f32;
+        content
+            .save_state()
+            .transform([width, 0.0, 0.0, height, MARGIN, top - height])
+            .x_object(LOGO)
+            .restore_state();
+        text_x += width + 12.0;
+    }
+
+    let title = grid
+        .map(|grid| grid.title.as_str())
+        .unwrap_or("Sin horarios");
+    draw_text(content, FONT, 12.0, text_x, top - 12.0, school_name);
+    draw_text(content, FONT_BOLD, 22.0, text_x, top - 40.0, title);
+
+    if let Some(grid) = grid {
+        let course = format!("Curso {}-{}", grid.course, grid.course + 1);
+        let width = text_width(&course, FONT, 12.0);
+        draw_text(
+            content,
+            FONT,
+            12.0,
+            PAGE_WIDTH - MARGIN - width,
+            top - 12.0,
+            &course,
+        );
+    }
+}
+
+/// Center hours as rows, `WEEK_DAYS` as columns.
+fn draw_grid(content: &mut Content, grid: &TimetableGrid) {
+    let top = PAGE_HEIGHT - MARGIN - HEADER_HEIGHT;
+    let day_col_width = (PAGE_WIDTH - 2.0 * MARGIN - HOUR_COL_WIDTH) / WEEK_DAYS.len() as f32;
+    let rows_height = top - DAYS_ROW_HEIGHT - MARGIN;
+    let row_height = (rows_height / grid.rows.len().max(1) as f32).min(MAX_ROW_HEIGHT);
+    let col_x = |col: usize| MARGIN + HOUR_COL_WIDTH + col as f32 * day_col_width;
+
+    content.set_line_width(0.5).set_stroke_rgb(0.4, 0.4, 0.4);
+
+    // -- Days row
+    let days_y = top - DAYS_ROW_HEIGHT;
+    content
+        .set_fill_rgb(0.85, 0.88, 0.95)
+        .rect(MARGIN, days_y, PAGE_WIDTH - 2.0 * MARGIN, DAYS_ROW_HEIGHT)
+        .fill_nonzero_and_stroke()
+        .set_fill_rgb(0.0, 0.0, 0.0);
+    draw_text_centered(
+        content,
+        FONT_BOLD,
+        11.0,
+        MARGIN + HOUR_COL_WIDTH / 2.0,
+        days_y + 7.0,
+        HOUR_COL_WIDTH,
+        "Hora",
+    );
+    for (col, day) in WEEK_DAYS.iter().enumerate() {
+        draw_text_centered(
+            content,
+            FONT_BOLD,
+            11.0,
+            col_x(col) + day_col_width / 2.0,
+            days_y + 7.0,
+            day_col_width,
+            day,
+        );
+    }
+
+    // -- Hour rows
+    for (i, row) in grid.rows.iter().enumerate() {
+        let y = days_y - (i + 1) as f32 * row_height;
+        let middle = y + row_height / 2.0;
+
+        content
+            .set_fill_rgb(0.94, 0.94, 0.94)
+            .rect(MARGIN, y, HOUR_COL_WIDTH, row_height)
+            .fill_nonzero_and_stroke()
+            .set_fill_rgb(0.0, 0.0, 0.0);
+        let n_hour = format!("{}ª", row.n_hour);
+        let times = format!(
+            "{} - {}",
+            hh_mm(row.start_time.hour(), row.start_time.minute()),
+            hh_mm(row.end_time.hour(), row.end_time.minute())
+        );
+        let hour_x = MARGIN + HOUR_COL_WIDTH / 2.0;
+        draw_text_centered(
+            content,
+            FONT_BOLD,
+            10.0,
+            hour_x,
+            middle + 2.0,
+            HOUR_COL_WIDTH,
+            &n_hour,
+        );
+        draw_text_centered(
+            content,
+            FONT,
+            9.0,
+            hour_x,
+            middle - 10.0,
+            HOUR_COL_WIDTH,
+            &times,
+        );
+
+        for (col, cell) in row.cells.iter().enumerate().take(WEEK_DAYS.len()) {
+            content
+                .rect(col_x(col), y, day_col_width, row_height)
+                .stroke();
+            if let Some(cell) = cell {
+                let cell_x = col_x(col) + day_col_width / 2.0;
+                draw_text_centered(
+                    content,
+                    FONT_BOLD,
+                    10.0,
+                    cell_x,
+                    middle + 2.0,
+                    day_col_width,
+                    &cell.subject_name,
+                );
+                draw_text_centered(
+                    content,
+                    FONT,
+                    9.0,
+                    cell_x,
+                    middle - 10.0,
+                    day_col_width,
+                    &cell.classroom_name,
+                );
+            }
+        }
+    }
+}
+
+fn draw_text(content: &mut Content, font: Name, size: f32, x: f32, y: f32, text: &str) {
+    content
+        .begin_text()
+        .set_font(font, size)
+        .next_line(x, y)
+        .show(Str(&win_ansi(text)))
+        .end_text();
+}
+
+/// Centered on `center_x`, cut with an ellipsis to fit `max_width` (minus padding).
+fn draw_text_centered(
+    content: &mut Content,
+    font: Name,
+    size: f32,
+    center_x: f32,
+    y: f32,
+    max_width: f32,
+    text: &str,
+) {
+    let text = fit_text(text, font, size, max_width - 8.0);
+    let width = text_width(&text, font, size);
+    draw_text(content, font, size, center_x - width / 2.0, y, &text);
+}
+
+// endregion: --- Layout
+
+// region:    --- Logo
+
+struct Logo {
+    width: u32,
+    height: u32,
+    /// Zlib compressed RGB samples.
+    rgb: Vec<u8>,
+    /// Zlib compressed alpha samples, when not opaque.
+    alpha: Option<Vec<u8>>,
+}
+
+impl Logo {
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        let image =
+            image::load_from_memory(bytes).map_err(|err| Error::PdfLogoInvalid(err.to_string()))?;
+        let (width, height) = image.dimensions();
+        if width == 0 || height == 0 {
+            return Err(Error::PdfLogoInvalid("empty image".to_string()));
+        }
+
+        let alpha = image.color().has_alpha().then(|| {
+            let alpha: Vec<u8> = image
+                .to_luma_alpha8()
+                .pixels()
+                .map(|pixel| pixel[1])
+                .collect();
+            compress_to_vec_zlib(&alpha, 6)
+        });
+
+        Ok(Logo {
+            width,
+            height,
+            rgb: compress_to_vec_zlib(image.to_rgb8().as_raw(), 6),
+            alpha,
+        })
+    }
+
+    /// The image XObject id.
+    fn write(&self, pdf: &mut Pdf, alloc: &mut Ref) -> Ref {
+        let image_id = alloc.bump();
+        let mask_id = self.alpha.as_ref().map(|_| alloc.bump());
+
+        let mut image = pdf.image_xobject(image_id, &self.rgb);
+        image.filter(Filter::FlateDecode);
+        image
+            .width(self.width as i32)
+            .height(self.height as i32)
+            .bits_per_component(8);
+        image.color_space().device_rgb();
+        if let Some(mask_id) = mask_id {
+            image.s_mask(mask_id);
+        }
+        image.finish();
+
+        if let (Some(mask_id), Some(alpha)) = (mask_id, &self.alpha) {
+            let mut mask = pdf.image_xobject(mask_id, alpha);
+            mask.filter(Filter::FlateDecode);
+            mask.width(self.width as i32)
+                .height(self.height as i32)
+                .bits_per_component(8);
+            mask.color_space().device_gray();
+        }
+
+        image_id
+    }
+}
+
+// endregion: --- Logo
+
+// region:    --- Text
+
+/// WinAnsiEncoding (the Latin-1 range, and a few punctuation marks), `?` otherwise.
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{A0}'..='\u{FF}' => c as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// In points, from the Helvetica AFM widths.
+fn text_width(text: &str, font: Name, size: f32) -> f32 {
+    let widths = if font == FONT_BOLD {
+        &HELVETICA_BOLD_WIDTHS
+    } else {
+        &HELVETICA_WIDTHS
+    };
+    let units: u32 = text
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => widths[c as usize - ' ' as usize] as u32,
+            'í' | 'ì' | 'î' | 'ï' | 'Í' | 'Ì' | 'Î' | 'Ï' => 278,
+            'Á' | 'É' | 'Ó' | 'Ú' | 'Ñ' | 'Ü' => 722,
+            '…' | '—' => 1000,
+            _ => 556,
+        })
+        .sum();
+
+    units as f32 * size / 1000.0
+}
+
+fn fit_text(text: &str, font: Name, size: f32, max_width: f32) -> String {
+    if text_width(text, font, size) <= max_width {
+        return text.to_string();
+    }
+
+    let mut fitted: String = text.to_string();
+    while !fitted.is_empty() && text_width(&format!("{fitted}…"), font, size) > max_width {
+        fitted.pop();
+    }
+
+    format!("{}…", fitted.trim_end())
+}
+
+/// ' ' to '~'.
+#[rustfmt::skip]
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// ' ' to '~'.
+#[rustfmt::skip]
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+// endregion: --- Text
+
+// region:    --- Utils
+
+fn hh_mm(hour: u8, minute: u8) -> String {
+    format!("{hour:02}:{minute:02}")
+}
+
+/// "<title>.pdf", without path separators, unique (case insensitive).
+fn unique_file_name(title: &str, used: &mut HashSet<String>) -> String {
+    let base: String = title
+        .chars()
+        .map(|c| {
+            if "/\\:*?\"<>|".contains(c) || c.is_control() {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let base = base.trim().trim_matches('.');
+    let base = if base.is_empty() { "horario" } else { base };
+
+    let mut n = 1;
+    loop {
+        let name = if n == 1 {
+            format!("{base}.pdf")
+        } else {
+            format!("{base} ({n}).pdf")
+        };
+        if used.insert(name.to_lowercase()) {
+            return name;
+        }
+        n += 1;
+    }
+}
+
+fn zip_err(err: zip::result::ZipError) -> Error {
+    Error::ZipWrite(err.to_string())
+}
+
+// endregion: --- Utils
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use lib_core::model::report::{TimetableGridCell, TimetableGridRow};
+
+    use super::*;
+
+    #[test]
+    fn test_fit_text_ok() -> Result<()> {
+        // -- Exec
+        let short = fit_text("Matemáticas", FONT_BOLD, 10.0, 120.0);
+        let long = fit_text(
+            "Educación para la Ciudadanía y los Derechos Humanos",
+            FONT_BOLD,
+            10.0,
+            120.0,
+        );
+
+        // -- Check
+        assert_eq!(short, "Matemáticas");
+        assert!(long.ends_with('…'), "Should be cut, was {long}");
+        assert!(text_width(&long, FONT_BOLD, 10.0) <= 120.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_win_ansi_ok() -> Result<()> {
+        // -- Exec & Check
+        assert_eq!(win_ansi("1º ESO Ñ…"), b"1\xBA ESO \xD1\x85");
+        assert_eq!(win_ansi("日"), b"?");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timetable_grids_pdf_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_time = |hour: u8| time::Time::from_hms(hour, 0, 0);
+        let fx_cell = TimetableGridCell {
+            subject_name: "Matemáticas".to_string(),
+            classroom_name: "Info 2".to_string(),
+        };
+        let fx_grid = TimetableGrid {
+            title: "1º ESO A".to_string(),
+            course: 2025,
+            rows: vec![TimetableGridRow {
+                n_hour: 1,
+                start_time: fx_time(8)?,
+                end_time: fx_time(9)?,
+                cells: vec![Some(fx_cell), None, None, None, None],
+            }],
+        };
+        let mut fx_logo = Vec::new();
+        image::RgbaImage::from_pixel(4, 2, image::Rgba([200, 0, 0, 128]))
+            .write_to(&mut Cursor::new(&mut fx_logo), image::ImageFormat::Png)?;
+        let fx_branding = PdfBranding {
+            school_name: "IES Teacherinator".to_string(),
+            logo: Some(fx_logo),
+        };
+
+        // -- Exec
+        let pdf = timetable_grids_pdf(&[fx_grid.clone(), fx_grid], &fx_branding)?;
+
+        // -- Check
+        assert!(pdf.starts_with(b"%PDF-"));
+        let pdf = String::from_utf8_lossy(&pdf);
+        assert!(pdf.contains("/Count 2"), "Should have 2 pages");
+        assert!(pdf.contains("/SMask"), "Should keep the logo alpha");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timetable_grids_zip_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_grid = |title: &str| TimetableGrid {
+            title: title.to_string(),
+            course: 2025,
+            rows: Vec::new(),
+        };
+        let fx_grids = [fx_grid("1º ESO A"), fx_grid("1º ESO A"), fx_grid("2/B")];
+        let fx_branding = PdfBranding {
+            school_name: "IES Teacherinator".to_string(),
+            logo: None,
+        };
+
+        // -- Exec
+        let zip = timetable_grids_zip(&fx_grids, &fx_branding)?;
+
+        // -- Check
+        let mut archive = zip::ZipArchive::new(Cursor::new(zip))?;
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(names.len(), 3);
+        for name in ["1º ESO A.pdf", "1º ESO A (2).pdf", "2-B.pdf"] {
+            assert!(names.contains(&name), "Should contain {name}");
+        }
+        let pdf = archive.by_name("2-B.pdf")?;
+        assert!(pdf.size() > 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timetable_grids_pdf_logo_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_branding = PdfBranding {
+            school_name: "IES Teacherinator".to_string(),
+            logo: Some(b"not an image".to_vec()),
+        };
+
+        // -- Exec
+        let res = timetable_grids_pdf(&[], &fx_branding);
+
+        // -- Check
+        assert!(matches!(res, Err(Error::PdfLogoInvalid(_))));
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
@@ -0,0 +1,304 @@
+//! XLSX workbooks (Office Open XML spreadsheets).
+
+use std::collections::HashSet;
+
+use rust_xlsxwriter::{Format, FormatAlign, FormatBorder, Workbook, Worksheet, XlsxError};
+
+use lib_core::model::report::{AttendanceRow, SubstitutionRow, TimetableGrid, WEEK_DAYS};
+
+use crate::{Error, Result};
+
+pub const XLSX_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// Excel limit.
+const SHEET_NAME_MAX_CHARS: usize = 31;
+
+/// One sheet per grid, named by its title.
+pub fn timetable_grids_xlsx(grids: &[TimetableGrid]) -> Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let formats = Formats::new();
+    let mut sheet_names = HashSet::new();
+
+    for grid in grids {
+        let sheet = workbook.add_worksheet();
+        sheet
+            .set_name(unique_sheet_name(&grid.title, &mut sheet_names))
+            .map_err(xlsx_err)?;
+        write_timetable_grid(sheet, grid, &formats).map_err(xlsx_err)?;
+    }
+    if grids.is_empty() {
+        workbook.add_worksheet();
+    }
+
+    workbook.save_to_buffer().map_err(xlsx_err)
+}
+
+pub fn attendance_xlsx(rows: &[AttendanceRow]) -> Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let formats = Formats::new();
+    let sheet = workbook.add_worksheet();
+
+    write_attendance(sheet, rows, &formats).map_err(xlsx_err)?;
+
+    workbook.save_to_buffer().map_err(xlsx_err)
+}
+
+pub fn substitutions_xlsx(rows: &[SubstitutionRow]) -> Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let formats = Formats::new();
+    let sheet = workbook.add_worksheet();
+
+    write_substitutions(sheet, rows, &formats).map_err(xlsx_err)?;
+
+    workbook.save_to_buffer().map_err(xlsx_err)
+}
+
+// region:    --- Sheets
+
+struct Formats {
+    title: Format,
+    header: Format,
+    cell: Format,
+}
+
+impl Formats {
+    fn new() -> Self {
+        Formats {
+            title: Format::new().set_bold().set_font_size(14),
+            header: Format::new()
+                .set_bold()
+                .set_border(FormatBorder::Thin)
+                .set_align(FormatAlign::Center)
+                .set_background_color("#D9E1F2"),
+            cell: Format::new()
+                .set_text_wrap()
+                .set_border(FormatBorder::Thin)
+                .set_align(FormatAlign::Center)
+                .set_align(FormatAlign::VerticalCenter),
+        }
+    }
+}
+
+/// Title, then the days header row, then one row per center hour.
+fn write_timetable_grid(
+    sheet: &mut Worksheet,
+    grid: &TimetableGrid,
+    formats: &Formats,
+) -> core::result::Result<(), XlsxError> {
+    sheet.write_string_with_format(
+        0,
+        0,
+        format!("{} ({})", grid.title, grid.course),
+        &formats.title,
+    )?;
+
+    sheet.write_string_with_format(1, 0, "Hora", &formats.header)?;
+    for (col, day) in (1..).zip(WEEK_DAYS) {
+        sheet.write_string_with_format(1, col, day, &formats.header)?;
+        sheet.set_column_width(col, 24)?;
+    }
+    sheet.set_column_width(0, 16)?;
+
+    for (row_num, row) in (2..).zip(&grid.rows) {
+        let hour = format!(
+            "{}ª\n{} - {}",
+            row.n_hour,
+            hh_mm(row.start_time.hour(), row.start_time.minute()),
+            hh_mm(row.end_time.hour(), row.end_time.minute())
+        );
+        sheet.write_string_with_format(row_num, 0, hour, &formats.header)?;
+        for (col, cell) in (1..).zip(&row.cells) {
+            let text = cell
+                .as_ref()
+                .map(|cell| format!("{}\n{}", cell.subject_name, cell.classroom_name))
+                .unwrap_or_default();
+            sheet.write_string_with_format(row_num, col, text, &formats.cell)?;
+        }
+        sheet.set_row_height(row_num, 32)?;
+    }
+    sheet.set_freeze_panes(2, 1)?;
+
+    Ok(())
+}
+
+fn write_attendance(
+    sheet: &mut Worksheet,
+    rows: &[AttendanceRow],
+    formats: &Formats,
+) -> core::result::Result<(), XlsxError> {
+    sheet.set_name("Asistencia")?;
+    write_header(
+        sheet,
+        &[
+            "Usuario",
+            "Departamento",
+            "En el centro",
+            "Última entrada",
+            "Última salida",
+        ],
+        formats,
+    )?;
+
+    for (row_num, row) in (1..).zip(rows) {
+        sheet.write_string(row_num, 0, &row.username)?;
+        sheet.write_string(
+            row_num,
+            1,
+            row.department_name.as_deref().unwrap_or_default(),
+        )?;
+        sheet.write_string(row_num, 2, if row.in_center { "Sí" } else { "No" })?;
+        sheet.write_string(
+            row_num,
+            3,
+            hh_mm(row.last_checkin.hour(), row.last_checkin.minute()),
+        )?;
+        sheet.write_string(
+            row_num,
+            4,
+            hh_mm(row.last_checkout.hour(), row.last_checkout.minute()),
+        )?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+
+    Ok(())
+}
+
+fn write_substitutions(
+    sheet: &mut Worksheet,
+    rows: &[SubstitutionRow],
+    formats: &Formats,
+) -> core::result::Result<(), XlsxError> {
+    sheet.set_name("Sustituciones")?;
+    write_header(
+        sheet,
+        &["Usuario", "Sustituciones", "Sustituyendo a"],
+        formats,
+    )?;
+
+    for (row_num, row) in (1..).zip(rows) {
+        sheet.write_string(row_num, 0, &row.username)?;
+        sheet.write_number(row_num, 1, row.substitutions as f64)?;
+        sheet.write_string(row_num, 2, row.substituting.as_deref().unwrap_or_default())?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+
+    Ok(())
+}
+
+fn write_header(
+    sheet: &mut Worksheet,
+    headers: &[&str],
+    formats: &Formats,
+) -> core::result::Result<(), XlsxError> {
+    for (col, header) in (0..).zip(headers) {
+        sheet.write_string_with_format(0, col, *header, &formats.header)?;
+        sheet.set_column_width(col, 20)?;
+    }
+
+    Ok(())
+}
+
+// endregion: --- Sheets
+
+// region:    --- Utils
+
+fn hh_mm(hour: u8, minute: u8) -> String {
+    format!("{hour:02}:{minute:02}")
+}
+
+/// Excel sheet name rules: at most 31 chars, none of `[]:*?/\`,
+/// no leading or trailing `'`, unique (case insensitive).
+fn unique_sheet_name(title: &str, used: &mut HashSet<String>) -> String {
+    let base: String = title
+        .chars()
+        .map(|c| if "[]:*?/\\".contains(c) { '-' } else { c })
+        .collect();
+    let base = base.trim().trim_matches('\'');
+    let base = if base.is_empty() { "Hoja" } else { base };
+
+    let mut n = 1;
+    loop {
+        let suffix = if n == 1 {
+            String::new()
+        } else {
+            format!(" ({n})")
+        };
+        let name: String = base
+            .chars()
+            .take(SHEET_NAME_MAX_CHARS - suffix.chars().count())
+            .collect::<String>()
+            + &suffix;
+        if used.insert(name.to_lowercase()) {
+            return name;
+        }
+        n += 1;
+    }
+}
+
+fn xlsx_err(err: XlsxError) -> Error {
+    Error::XlsxWrite(err.to_string())
+}
+
+// endregion: --- Utils
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn test_unique_sheet_name_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut used = HashSet::new();
+        let fx_long = "a".repeat(40);
+
+        // -- Exec
+        let names = [
+            unique_sheet_name("1º ESO A", &mut used),
+            unique_sheet_name("1º eso a", &mut used),
+            unique_sheet_name("Dpto. Física/Química [2]", &mut used),
+            unique_sheet_name(&fx_long, &mut used),
+            unique_sheet_name(&fx_long, &mut used),
+            unique_sheet_name("''", &mut used),
+        ];
+
+        // -- Check
+        assert_eq!(names[0], "1º ESO A");
+        assert_eq!(names[1], "1º eso a (2)");
+        assert_eq!(names[2], "Dpto. Física-Química -2-");
+        assert_eq!(names[3], "a".repeat(31));
+        assert_eq!(names[4], format!("{} (2)", "a".repeat(27)));
+        assert_eq!(names[5], "Hoja");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timetable_grids_xlsx_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_grids = [
+            TimetableGrid {
+                title: "1º ESO A".to_string(),
+                course: 2025,
+                rows: Vec::new(),
+            },
+            TimetableGrid {
+                title: "1º ESO A".to_string(),
+                course: 2025,
+                rows: Vec::new(),
+            },
+        ];
+
+        // -- Exec
+        let xlsx = timetable_grids_xlsx(&fx_grids)?;
+
+        // -- Check
+        assert!(xlsx.starts_with(b"PK"), "Should be a zip container");
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
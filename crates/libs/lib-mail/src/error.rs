@@ -0,0 +1,28 @@
+use serde::Serialize;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Serialize)]
+pub enum Error {
+    MailerNotFound(String),
+
+    InvalidAddress(String),
+    MessageBuild(String),
+
+    // -- Smtp
+    SmtpTransport(String),
+    SmtpSend(String),
+
+    // -- File
+    FileWrite(String),
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
@@ -0,0 +1,84 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use lib_utils::time::{format_time, now_utc};
+
+use crate::{Error, Mail, Mailer, Result};
+
+/// Writes each mail as `<dir>/<uuid>.eml` (e.g., to check the templates in local dev).
+pub struct FileMailer {
+    dir: PathBuf,
+    from: String,
+}
+
+impl FileMailer {
+    pub fn new(dir: impl Into<PathBuf>, from: &str) -> Self {
+        FileMailer {
+            dir: dir.into(),
+            from: from.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: &Mail) -> Result<()> {
+        let content = format!(
+            "Date: {}\r\nFrom: {}\r\nTo: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+            format_time(now_utc()),
+            self.from,
+            mail.to,
+            mail.subject,
+            mail.body
+        );
+        let path = self.dir.join(format!("{}.eml", Uuid::new_v4()));
+
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|ex| Error::FileWrite(ex.to_string()))?;
+        tokio::fs::write(&path, content)
+            .await
+            .map_err(|ex| Error::FileWrite(ex.to_string()))?;
+
+        Ok(())
+    }
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_mailer_send_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_dir = std::env::temp_dir().join(format!("lib-mail-test-{}", Uuid::new_v4()));
+        let fx_mail = Mail {
+            to: "teacher@ies.example.org".to_string(),
+            subject: "Guardia asignada".to_string(),
+            body: "Info 2".to_string(),
+        };
+        let mailer = FileMailer::new(&fx_dir, "no-reply@ies.example.org");
+
+        // -- Exec
+        mailer.send(&fx_mail).await?;
+
+        // -- Check
+        let mut entries = std::fs::read_dir(&fx_dir)?;
+        let path = entries.next().expect("Should have one file")?.path();
+        let content = std::fs::read_to_string(path)?;
+        assert!(content.contains("To: teacher@ies.example.org\r\n"));
+        assert!(content.contains("Subject: Guardia asignada\r\n"));
+        assert!(content.ends_with("\r\n\r\nInfo 2\r\n"));
+
+        // -- Clean
+        std::fs::remove_dir_all(&fx_dir)?;
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
@@ -0,0 +1,75 @@
+//! Pluggable mail delivery (e.g., for the notifications outbox).
+//!
+//! - `smtp` sends through the `SERVICE_SMTP_URL` relay.
+//! - `file` writes each mail as an `.eml` file in `SERVICE_MAIL_DIR` (local dev).
+//! - `memory` keeps the mails in memory (tests).
+//!
+//! The mailer is selected with `SERVICE_MAILER` (see `get_mailer`).
+
+// region:    --- Modules
+
+use async_trait::async_trait;
+
+pub use self::config::mail_config;
+pub use self::error::{Error, Result};
+pub use self::file::FileMailer;
+pub use self::memory::MemoryMailer;
+pub use self::smtp::SmtpMailer;
+
+use self::config::smtp_config;
+
+mod config;
+mod error;
+mod file;
+mod memory;
+mod smtp;
+
+// endregion: --- Modules
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    /// Plain text.
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: &Mail) -> Result<()>;
+}
+
+enum MailerDispatcher {
+    Smtp(SmtpMailer),
+    File(FileMailer),
+    Memory(MemoryMailer),
+}
+
+#[async_trait]
+impl Mailer for MailerDispatcher {
+    async fn send(&self, mail: &Mail) -> Result<()> {
+        match self {
+            MailerDispatcher::Smtp(mailer) => mailer.send(mail).await,
+            MailerDispatcher::File(mailer) => mailer.send(mail).await,
+            MailerDispatcher::Memory(mailer) => mailer.send(mail).await,
+        }
+    }
+}
+
+/// The `SERVICE_MAILER` mailer.
+pub fn get_mailer() -> Result<impl Mailer> {
+    let config = mail_config();
+
+    match config.MAILER.as_str() {
+        "smtp" => Ok(MailerDispatcher::Smtp(SmtpMailer::new(
+            &smtp_config().URL,
+            &config.MAIL_FROM,
+        )?)),
+        "file" => Ok(MailerDispatcher::File(FileMailer::new(
+            &config.MAIL_DIR,
+            &config.MAIL_FROM,
+        ))),
+        "memory" => Ok(MailerDispatcher::Memory(MemoryMailer::default())),
+        _ => Err(Error::MailerNotFound(config.MAILER.clone())),
+    }
+}
@@ -0,0 +1,31 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::{Mail, Mailer, Result};
+
+/// Keeps the sent mails, clones share them (e.g., one clone given to the code under test).
+#[derive(Debug, Clone, Default)]
+pub struct MemoryMailer {
+    sent: Arc<Mutex<Vec<Mail>>>,
+}
+
+impl MemoryMailer {
+    pub fn sent(&self) -> Vec<Mail> {
+        self.sent
+            .lock()
+            .map(|sent| sent.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl Mailer for MemoryMailer {
+    async fn send(&self, mail: &Mail) -> Result<()> {
+        if let Ok(mut sent) = self.sent.lock() {
+            sent.push(mail.clone());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use std::sync::OnceLock;
+
+use lib_utils::envs::get_env;
+
+pub fn push_config() -> &'static PushConfig {
+    static INSTANCE: OnceLock<PushConfig> = OnceLock::new();
+
+    INSTANCE.get_or_init(|| {
+        PushConfig::load_from_env()
+            .unwrap_or_else(|ex| panic!("FATAL - WHILE LOADING CONF - Cause: {ex:?}"))
+    })
+}
+
+#[allow(non_snake_case)]
+pub struct PushConfig {
+    /// "webpush" or "memory".
+    pub PUSH_SENDER: String,
+    /// b64u of the P-256 private key scalar (e.g., from `gen-key vapid`).
+    pub VAPID_PRIVATE_KEY: String,
+    /// Contact of the push services, e.g., "mailto:admin@ies.example.org".
+    pub VAPID_SUBJECT: String,
+}
+
+impl PushConfig {
+    fn load_from_env() -> lib_utils::envs::Result<PushConfig> {
+        Ok(PushConfig {
+            PUSH_SENDER: get_env("SERVICE_PUSH_SENDER")?,
+            VAPID_PRIVATE_KEY: get_env("SERVICE_VAPID_PRIVATE_KEY")?,
+            VAPID_SUBJECT: get_env("SERVICE_VAPID_SUBJECT")?,
+        })
+    }
+}
@@ -0,0 +1,151 @@
+//! RFC 8291 message encryption (`aes128gcm` content coding, RFC 8188),
+//! one record, with a new ephemeral key and salt for each push.
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes128Gcm, Nonce};
+use hkdf::Hkdf;
+use p256::ecdh::diffie_hellman;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::Sha256;
+
+use lib_utils::b64::b64u_decode;
+
+use crate::{Error, Result};
+
+/// Record size of the header, the push services accept up to 4096 bytes bodies.
+const RECORD_SIZE: u32 = 4096;
+
+/// Header (86) + padding delimiter (1) + tag (16).
+const OVERHEAD: usize = 86 + 1 + 16;
+
+/// Max plaintext of one 4096 bytes record.
+pub const PAYLOAD_MAX: usize = RECORD_SIZE as usize - OVERHEAD;
+
+/// The encrypted push body, for the subscription `p256dh` and `auth` (b64u).
+pub fn encrypt(p256dh: &str, auth: &str, payload: &[u8]) -> Result<Vec<u8>> {
+    let as_secret = SecretKey::random(&mut OsRng);
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+
+    encrypt_with(&as_secret, salt, p256dh, auth, payload)
+}
+
+fn encrypt_with(
+    as_secret: &SecretKey,
+    salt: [u8; 16],
+    p256dh: &str,
+    auth: &str,
+    payload: &[u8],
+) -> Result<Vec<u8>> {
+    if payload.len() > PAYLOAD_MAX {
+        return Err(Error::PayloadTooLarge(payload.len()));
+    }
+
+    // -- Subscription keys
+    let ua_public_bytes = b64u_decode(p256dh).map_err(|_| Error::SubscriptionKeyInvalid)?;
+    let ua_public =
+        PublicKey::from_sec1_bytes(&ua_public_bytes).map_err(|_| Error::SubscriptionKeyInvalid)?;
+    let auth_secret = b64u_decode(auth).map_err(|_| Error::SubscriptionKeyInvalid)?;
+    let as_public_point = as_secret.public_key().to_encoded_point(false);
+    let as_public_bytes = as_public_point.as_bytes();
+
+    // -- Input keying material (RFC 8291, section 3.4)
+    let ecdh_secret = diffie_hellman(as_secret.to_nonzero_scalar(), ua_public.as_affine());
+    let mut key_info = Vec::with_capacity(144);
+    key_info.extend_from_slice(b"WebPush: info\0");
+    key_info.extend_from_slice(&ua_public_bytes);
+    key_info.extend_from_slice(as_public_bytes);
+    let mut ikm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&auth_secret), ecdh_secret.raw_secret_bytes())
+        .expand(&key_info, &mut ikm)
+        .map_err(|_| Error::PayloadEncrypt)?;
+
+    // -- Content encryption key and nonce (RFC 8188, section 2.2)
+    let hkdf = Hkdf::<Sha256>::new(Some(&salt), &ikm);
+    let mut cek = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    hkdf.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .map_err(|_| Error::PayloadEncrypt)?;
+    hkdf.expand(b"Content-Encoding: nonce\0", &mut nonce)
+        .map_err(|_| Error::PayloadEncrypt)?;
+
+    // -- Last (and only) record, padding delimiter 0x02
+    let mut record = Vec::with_capacity(payload.len() + 1);
+    record.extend_from_slice(payload);
+    record.push(2);
+    let ciphertext = Aes128Gcm::new_from_slice(&cek)
+        .map_err(|_| Error::PayloadEncrypt)?
+        .encrypt(&Nonce::from(nonce), record.as_slice())
+        .map_err(|_| Error::PayloadEncrypt)?;
+
+    // -- Header: salt | rs | idlen | keyid (the ephemeral public key)
+    let mut body = Vec::with_capacity(OVERHEAD + payload.len());
+    body.extend_from_slice(&salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    let idlen = u8::try_from(as_public_bytes.len()).map_err(|_| Error::PayloadEncrypt)?;
+    body.push(idlen);
+    body.extend_from_slice(as_public_bytes);
+    body.extend_from_slice(&ciphertext);
+
+    Ok(body)
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use lib_utils::b64::{b64u_decode, b64u_encode};
+
+    use super::*;
+
+    /// RFC 8291, Appendix A.
+    #[test]
+    fn test_encrypt_rfc8291_vector_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_as_private = b64u_decode("yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw")?;
+        let fx_salt = b64u_decode("DGv6ra1nlYgDCS1FRnbzlw")?;
+        let fx_ua_public = "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4";
+        let fx_auth = "BTBZMqHH6r4Tts7J_aSIgg";
+        let fx_plaintext = "When I grow up, I want to be a watermelon";
+        let fx_body = "DGv6ra1nlYgDCS1FRnbzlwAAEABBBP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A_yl95bQpu6cVPTpK4Mqgkf1CXztLVBSt2Ks3oZwbuwXPXLWyouBWLVWGNWQexSgSxsj_Qulcy4a-fN";
+
+        // -- Exec
+        let as_secret = SecretKey::from_slice(&fx_as_private)?;
+        let salt: [u8; 16] = fx_salt.as_slice().try_into()?;
+        let body = encrypt_with(
+            &as_secret,
+            salt,
+            fx_ua_public,
+            fx_auth,
+            fx_plaintext.as_bytes(),
+        )?;
+
+        // -- Check
+        assert_eq!(b64u_encode(body), fx_body);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypt_err_too_large() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_ua_public = "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4";
+        let fx_payload = vec![b'a'; PAYLOAD_MAX + 1];
+
+        // -- Exec
+        let res = encrypt(fx_ua_public, "BTBZMqHH6r4Tts7J_aSIgg", &fx_payload);
+
+        // -- Check
+        assert!(
+            matches!(res, Err(Error::PayloadTooLarge(_))),
+            "Should have matched `Err(PayloadTooLarge)` but was `{res:?}`"
+        );
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
@@ -0,0 +1,36 @@
+use serde::Serialize;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Serialize)]
+pub enum Error {
+    PushSenderNotFound(String),
+
+    // -- Keys
+    VapidKeyInvalid,
+    SubscriptionKeyInvalid,
+
+    // -- Payload
+    PayloadTooLarge(usize),
+    PayloadEncrypt,
+    VapidSign,
+
+    // -- Send
+    Request(String),
+    /// 404 or 410, the subscription is no longer valid.
+    EndpointGone,
+    Rejected {
+        status: u16,
+        body: String,
+    },
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
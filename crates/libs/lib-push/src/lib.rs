@@ -0,0 +1,81 @@
+//! Web Push to the teacher PWA (e.g., the guard assigned alerts).
+//!
+//! - `webpush` sends to the subscription endpoint, with the RFC 8291 encrypted payload
+//!   and the RFC 8292 VAPID authorization (`SERVICE_VAPID_PRIVATE_KEY`, see `gen-key vapid`).
+//! - `memory` keeps the pushes in memory (tests).
+//!
+//! The sender is selected with `SERVICE_PUSH_SENDER` (see `get_push_sender`).
+//! An `Error::EndpointGone` means the subscription expired or was revoked
+//! by the browser, and must be deleted.
+
+// region:    --- Modules
+
+use async_trait::async_trait;
+
+pub use self::config::push_config;
+pub use self::error::{Error, Result};
+pub use self::memory::{MemoryPushSender, SentPush};
+pub use self::vapid::{VapidKeys, VapidSigner};
+pub use self::webpush::WebPushSender;
+
+mod config;
+mod ece;
+mod error;
+mod memory;
+mod vapid;
+mod webpush;
+
+// endregion: --- Modules
+
+/// A browser push subscription (the `PushSubscription.toJSON()` of the PWA).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushTarget {
+    pub endpoint: String,
+    /// b64u of the user agent P-256 public key.
+    pub p256dh: String,
+    /// b64u of the 16 bytes auth secret.
+    pub auth: String,
+}
+
+#[async_trait]
+pub trait PushSender: Send + Sync {
+    async fn send(&self, target: &PushTarget, payload: &[u8]) -> Result<()>;
+}
+
+enum PushSenderDispatcher {
+    WebPush(WebPushSender),
+    Memory(MemoryPushSender),
+}
+
+#[async_trait]
+impl PushSender for PushSenderDispatcher {
+    async fn send(&self, target: &PushTarget, payload: &[u8]) -> Result<()> {
+        match self {
+            PushSenderDispatcher::WebPush(sender) => sender.send(target, payload).await,
+            PushSenderDispatcher::Memory(sender) => sender.send(target, payload).await,
+        }
+    }
+}
+
+/// The `SERVICE_PUSH_SENDER` sender.
+pub fn get_push_sender() -> Result<impl PushSender> {
+    let config = push_config();
+
+    match config.PUSH_SENDER.as_str() {
+        "webpush" => Ok(PushSenderDispatcher::WebPush(WebPushSender::new(
+            VapidSigner::new(
+                VapidKeys::from_private_key(&config.VAPID_PRIVATE_KEY)?,
+                &config.VAPID_SUBJECT,
+            ),
+        ))),
+        "memory" => Ok(PushSenderDispatcher::Memory(MemoryPushSender::default())),
+        _ => Err(Error::PushSenderNotFound(config.PUSH_SENDER.clone())),
+    }
+}
+
+/// The `applicationServerKey` for the PWA `pushManager.subscribe`.
+pub fn vapid_public_key() -> Result<String> {
+    let keys = VapidKeys::from_private_key(&push_config().VAPID_PRIVATE_KEY)?;
+
+    Ok(keys.public_key())
+}
@@ -0,0 +1,59 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::{Error, PushSender, PushTarget, Result};
+
+/// Keeps the pushes, clones share them (e.g., one clone given to the code under test).
+#[derive(Debug, Clone, Default)]
+pub struct MemoryPushSender {
+    sent: Arc<Mutex<Vec<SentPush>>>,
+    gone: Arc<Mutex<HashSet<String>>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SentPush {
+    pub endpoint: String,
+    /// Not encrypted.
+    pub payload: Vec<u8>,
+}
+
+impl MemoryPushSender {
+    pub fn sent(&self) -> Vec<SentPush> {
+        self.sent
+            .lock()
+            .map(|sent| sent.clone())
+            .unwrap_or_default()
+    }
+
+    /// The next pushes to `endpoint` fail with `Error::EndpointGone`.
+    pub fn set_gone(&self, endpoint: &str) {
+        if let Ok(mut gone) = self.gone.lock() {
+            gone.insert(endpoint.to_string());
+        }
+    }
+}
+
+#[async_trait]
+impl PushSender for MemoryPushSender {
+    async fn send(&self, target: &PushTarget, payload: &[u8]) -> Result<()> {
+        let gone = self
+            .gone
+            .lock()
+            .map(|gone| gone.contains(&target.endpoint))
+            .unwrap_or_default();
+        if gone {
+            return Err(Error::EndpointGone);
+        }
+
+        if let Ok(mut sent) = self.sent.lock() {
+            sent.push(SentPush {
+                endpoint: target.endpoint.clone(),
+                payload: payload.to_vec(),
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+//! RFC 8292 VAPID, the push services authorization of the server.
+//!
+//! - `VapidKeys` is the server P-256 key pair, the public key is the PWA
+//!   `applicationServerKey`, so changing the keys invalidates all the subscriptions.
+//! - Each push is authorized with an ES256 JWT for the endpoint origin.
+
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::SecretKey;
+use rand::rngs::OsRng;
+use serde_json::json;
+
+use lib_utils::b64::{b64u_decode, b64u_encode};
+use lib_utils::time::now_utc;
+
+use crate::{Error, Result};
+
+/// JWT validity, the push services reject more than 24h.
+const JWT_DURATION_SEC: i64 = 12 * 3600;
+
+#[derive(Clone)]
+pub struct VapidKeys {
+    secret: SecretKey,
+}
+
+impl VapidKeys {
+    pub fn generate() -> Self {
+        VapidKeys {
+            secret: SecretKey::random(&mut OsRng),
+        }
+    }
+
+    /// `private_key` as returned by `private_key`.
+    pub fn from_private_key(private_key: &str) -> Result<Self> {
+        let bytes = b64u_decode(private_key).map_err(|_| Error::VapidKeyInvalid)?;
+        let secret = SecretKey::from_slice(&bytes).map_err(|_| Error::VapidKeyInvalid)?;
+
+        Ok(VapidKeys { secret })
+    }
+
+    /// b64u of the 32 bytes scalar (e.g., for `SERVICE_VAPID_PRIVATE_KEY`).
+    pub fn private_key(&self) -> String {
+        b64u_encode(self.secret.to_bytes())
+    }
+
+    /// b64u of the uncompressed point (65 bytes).
+    pub fn public_key(&self) -> String {
+        b64u_encode(self.secret.public_key().to_encoded_point(false).as_bytes())
+    }
+}
+
+pub struct VapidSigner {
+    keys: VapidKeys,
+    signing_key: SigningKey,
+    subject: String,
+}
+
+impl VapidSigner {
+    /// `subject` e.g., "mailto:admin@ies.example.org".
+    pub fn new(keys: VapidKeys, subject: &str) -> Self {
+        VapidSigner {
+            signing_key: SigningKey::from(&keys.secret),
+            keys,
+            subject: subject.to_string(),
+        }
+    }
+
+    /// The `Authorization` header value for a push to `endpoint`.
+    pub fn authorization(&self, endpoint: &str) -> Result<String> {
+        let jwt = self.jwt(&endpoint_origin(endpoint)?)?;
+
+        Ok(format!("vapid t={jwt}, k={}", self.keys.public_key()))
+    }
+
+    fn jwt(&self, audience: &str) -> Result<String> {
+        let header = json!({ "typ": "JWT", "alg": "ES256" });
+        let claims = json!({
+            "aud": audience,
+            "exp": now_utc().unix_timestamp() + JWT_DURATION_SEC,
+            "sub": self.subject,
+        });
+        let content = format!(
+            "{}.{}",
+            b64u_encode(header.to_string()),
+            b64u_encode(claims.to_string())
+        );
+
+        let signature: Signature = self
+            .signing_key
+            .try_sign(content.as_bytes())
+            .map_err(|_| Error::VapidSign)?;
+
+        Ok(format!("{content}.{}", b64u_encode(signature.to_bytes())))
+    }
+}
+
+/// e.g., "https://fcm.googleapis.com" for "https://fcm.googleapis.com/fcm/send/abc".
+fn endpoint_origin(endpoint: &str) -> Result<String> {
+    let url = reqwest::Url::parse(endpoint)
+        .map_err(|ex| Error::Request(format!("invalid endpoint {endpoint} - {ex}")))?;
+
+    Ok(url.origin().ascii_serialization())
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::{Context, Result};
+    use p256::ecdsa::signature::Verifier;
+    use p256::ecdsa::VerifyingKey;
+
+    use lib_utils::b64::{b64u_decode, b64u_decode_to_string};
+
+    use super::*;
+
+    #[test]
+    fn test_authorization_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_keys = VapidKeys::generate();
+        let fx_endpoint = "https://updates.push.services.mozilla.com/wpush/v2/gAAAAA";
+        let signer = VapidSigner::new(fx_keys.clone(), "mailto:admin@ies.example.org");
+
+        // -- Exec
+        let authorization = signer.authorization(fx_endpoint)?;
+
+        // -- Check
+        let (jwt, public_key) = authorization
+            .strip_prefix("vapid t=")
+            .and_then(|rest| rest.split_once(", k="))
+            .context("Should be `vapid t=.., k=..`")?;
+        assert_eq!(public_key, fx_keys.public_key());
+
+        let (content, signature) = jwt.rsplit_once('.').context("Should have a signature")?;
+        let (_, claims) = content.split_once('.').context("Should have claims")?;
+        let claims: serde_json::Value = serde_json::from_str(&b64u_decode_to_string(claims)?)?;
+        assert_eq!(claims["aud"], "https://updates.push.services.mozilla.com");
+        assert_eq!(claims["sub"], "mailto:admin@ies.example.org");
+
+        let verifying_key = VerifyingKey::from_sec1_bytes(&b64u_decode(public_key)?)?;
+        let signature = Signature::from_slice(&b64u_decode(signature)?)?;
+        verifying_key.verify(content.as_bytes(), &signature)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keys_private_key_roundtrip_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_keys = VapidKeys::generate();
+
+        // -- Exec
+        let keys = VapidKeys::from_private_key(&fx_keys.private_key())?;
+
+        // -- Check
+        assert_eq!(keys.public_key(), fx_keys.public_key());
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
@@ -0,0 +1,54 @@
+use async_trait::async_trait;
+use reqwest::header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest::{Client, StatusCode};
+
+use crate::vapid::VapidSigner;
+use crate::{ece, Error, PushSender, PushTarget, Result};
+
+/// Kept by the push service while the device is offline,
+/// a guard alert is useless after the hour anyway.
+const TTL_SEC: u32 = 3600;
+
+pub struct WebPushSender {
+    client: Client,
+    vapid: VapidSigner,
+}
+
+impl WebPushSender {
+    pub fn new(vapid: VapidSigner) -> Self {
+        WebPushSender {
+            client: Client::new(),
+            vapid,
+        }
+    }
+}
+
+#[async_trait]
+impl PushSender for WebPushSender {
+    async fn send(&self, target: &PushTarget, payload: &[u8]) -> Result<()> {
+        let body = ece::encrypt(&target.p256dh, &target.auth, payload)?;
+        let authorization = self.vapid.authorization(&target.endpoint)?;
+
+        let res = self
+            .client
+            .post(&target.endpoint)
+            .header(AUTHORIZATION, authorization)
+            .header(CONTENT_ENCODING, "aes128gcm")
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .header("TTL", TTL_SEC)
+            .header("Urgency", "high")
+            .body(body)
+            .send()
+            .await
+            .map_err(|ex| Error::Request(ex.to_string()))?;
+
+        match res.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::NOT_FOUND | StatusCode::GONE => Err(Error::EndpointGone),
+            status => Err(Error::Rejected {
+                status: status.as_u16(),
+                body: res.text().await.unwrap_or_default(),
+            }),
+        }
+    }
+}
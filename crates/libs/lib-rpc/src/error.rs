@@ -1,6 +1,6 @@
 use derive_more::From;
 use serde::Serialize;
-use serde_with::{DisplayFromStr, serde_as};
+use serde_with::{serde_as, DisplayFromStr};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -16,6 +16,7 @@ pub enum Error {
     // -- Login
     LoginFailUsernameNotFound,
     UserNotAdmin,
+    UserNotSuperAdmin,
     LoginFailUserHasNoPwd {
         user_id: i64,
     },
@@ -26,6 +27,8 @@ pub enum Error {
     // -- Modules
     #[from]
     Model(lib_core::model::Error),
+    #[from]
+    Push(lib_push::Error),
 
     // -- External Modules
     #[from]
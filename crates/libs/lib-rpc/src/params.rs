@@ -7,17 +7,21 @@
 //! `IntoParams` or `IntoDefaultParams` are implemented to ensure these Params conform to the
 //! `RpcRouter` (i.e., `rpc::router`) model.
 
+use lib_core::model::BulkMode;
+use log::warn;
 use modql::filter::ListOptions;
+use schemars::JsonSchema;
 use serde::de::DeserializeOwned;
-use serde::Deserialize;
-use serde_json::Value;
-use serde_with::{OneOrMany, serde_as};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use serde_with::{serde_as, OneOrMany};
+use time::OffsetDateTime;
 
-use crate::Result;
 use crate::router::{IntoDefaultParams, IntoParams};
+use crate::Result;
 
 /// Params structure for any RPC Create call.
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct ParamsForCreate<D> {
     pub data: D,
 }
@@ -25,22 +29,29 @@ pub struct ParamsForCreate<D> {
 impl<D> IntoParams for ParamsForCreate<D> where D: DeserializeOwned + Send {}
 
 /// Params structure for any RPC Update call.
-#[derive(Deserialize)]
+#[serde_as]
+#[derive(Deserialize, JsonSchema)]
 pub struct ParamsForUpdate<D> {
     pub id: i64,
     pub data: D,
+    /// The `mtime` of the entity as last read by the client.
+    /// When given, the update fails (409) if someone else updated it since.
+    #[serde(with = "time::serde::rfc3339::option")]
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub expected_mtime: Option<OffsetDateTime>,
 }
 
 impl<D> IntoParams for ParamsForUpdate<D> where D: DeserializeOwned + Send {}
 
 /// Params structure for any RPC Update call.
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct ParamsIded {
     pub id: i64,
 }
 impl IntoParams for ParamsIded {}
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct ParamsIdedString {
     pub data: String,
 }
@@ -48,19 +59,133 @@ pub struct ParamsIdedString {
 impl IntoParams for ParamsIdedString {}
 
 /// Params structure for any RPC List call.
+///
+/// Note: The modql `filters` and `list_options` are only described as json objects
+///       in the schema (see `RpcRouter::discover`).
 #[serde_as]
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Default, JsonSchema)]
+#[schemars(bound = "F: DeserializeOwned")]
 pub struct ParamsList<F>
 where
     F: DeserializeOwned,
 {
     #[serde_as(deserialize_as = "Option<OneOrMany<_>>")]
+    #[schemars(with = "Option<Vec<Map<String, Value>>>")]
     pub filters: Option<Vec<F>>,
+    #[schemars(with = "Option<Map<String, Value>>")]
     pub list_options: Option<ListOptions>,
+    /// Only for soft delete entities (e.g., `list_users`), admin only.
+    #[serde(default)]
+    pub include_deleted: bool,
 }
 
 impl<D> IntoDefaultParams for ParamsList<D> where D: DeserializeOwned + Send + Default {}
 
+// region:    --- Bulk
+
+/// Params structure for any RPC bulk Create call (e.g., `create_schedule_hours`).
+#[derive(Deserialize, JsonSchema)]
+pub struct ParamsForCreateMany<D> {
+    pub data: Vec<D>,
+    #[serde(default)]
+    pub mode: BulkMode,
+}
+
+impl<D> IntoParams for ParamsForCreateMany<D> where D: DeserializeOwned + Send {}
+
+/// Params structure for any RPC bulk Update call, one `ParamsForUpdate` per item.
+#[derive(Deserialize, JsonSchema)]
+pub struct ParamsForUpdateMany<D> {
+    pub data: Vec<ParamsForUpdate<D>>,
+    #[serde(default)]
+    pub mode: BulkMode,
+}
+
+impl<D> IntoParams for ParamsForUpdateMany<D> where D: DeserializeOwned + Send {}
+
+/// Params structure for any RPC bulk Delete call.
+#[derive(Deserialize, JsonSchema)]
+pub struct ParamsIdedMany {
+    pub ids: Vec<i64>,
+    #[serde(default)]
+    pub mode: BulkMode,
+}
+
+impl IntoParams for ParamsIdedMany {}
+
+/// Result of one item of a bulk call, `data` or `error`.
+#[derive(Serialize, JsonSchema)]
+pub struct BulkItemResult<T> {
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<BulkItemError>,
+}
+
+impl<T> BulkItemResult<T> {
+    pub fn new(index: usize, res: lib_core::model::Result<T>) -> Self {
+        match res {
+            Ok(data) => BulkItemResult {
+                index,
+                data: Some(data),
+                error: None,
+            },
+            Err(error) => {
+                // Note: The model error (e.g., sqlx message) only goes to the server log.
+                warn!("{:<12} - bulk item {index} - {error:?}", "RPC");
+                BulkItemResult {
+                    index,
+                    data: None,
+                    error: Some(BulkItemError::from(&error)),
+                }
+            }
+        }
+    }
+}
+
+/// Client error of a bulk item, as the `error` object of a JSON-RPC response
+/// (same `code`, `message` and `data.detail` as the web-server `ClientError`).
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BulkItemError {
+    pub code: i64,
+    pub message: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl From<&lib_core::model::Error> for BulkItemError {
+    fn from(error: &lib_core::model::Error) -> Self {
+        use lib_core::model::Error::*;
+
+        match error {
+            EntityNotFound { entity, id } => BulkItemError {
+                code: -32003,
+                message: "ENTITY_NOT_FOUND",
+                data: Some(json!({"detail": {"entity": entity, "id": id}})),
+            },
+            ConcurrentModification {
+                entity,
+                id,
+                current,
+            } => BulkItemError {
+                code: -32004,
+                message: "CONCURRENT_MODIFICATION",
+                data: Some(json!({"detail": {"entity": entity, "id": id, "current": current}})),
+            },
+
+            // -- Fallback.
+            _ => BulkItemError {
+                code: -32603,
+                message: "SERVICE_ERROR",
+                data: None,
+            },
+        }
+    }
+}
+
+// endregion: --- Bulk
+
 // region:    --- General Implementations
 
 /// Implements `IntoParams` for any type that also implements `IntoParams`.
@@ -1,9 +1,10 @@
 use lib_core::ctx::Ctx;
 use lib_core::model::ModelManager;
 
-use crate::{Error, Result};
 use crate::router::FromResources;
+use crate::{Error, Result};
 
+#[derive(Clone)]
 pub struct RpcResources {
     pub mm: ModelManager,
     pub ctx: Option<Ctx>,
@@ -0,0 +1,147 @@
+//! `rpc.discover` support, the OpenRPC document of the registered methods.
+//!
+//! - Each route carries its `RpcRole`, and the params and result schemas of its
+//!   handler (derived with `schemars` from the serde types, see `RpcHandler::schemas`).
+//! - The params object properties are the OpenRPC params (`"paramStructure": "by-name"`).
+//! - The named types are shared in `components.schemas`.
+//! - The required role is the `x-required-role` method extension.
+
+use schemars::generate::SchemaSettings;
+use schemars::{Schema, SchemaGenerator};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::{Error, Result, RpcResources};
+
+/// The OpenRPC service discovery method name.
+pub const DISCOVER_METHOD: &str = "rpc.discover";
+
+const OPENRPC_VERSION: &str = "1.2.6";
+const DEFINITIONS_PATH: &str = "/components/schemas";
+
+/// Role required to call a method, checked by `RpcRouter::call`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RpcRole {
+    /// Any authenticated user.
+    #[default]
+    User,
+    Admin,
+    /// Provisions the centers (tenants), see `CenterBmc`.
+    SuperAdmin,
+}
+
+impl RpcRole {
+    pub fn check(&self, rpc_resources: &RpcResources) -> Result<()> {
+        match self {
+            RpcRole::User => Ok(()),
+            RpcRole::Admin => {
+                let ctx = rpc_resources.ctx.as_ref().ok_or(Error::MissingCtx)?;
+                if ctx.admin() {
+                    Ok(())
+                } else {
+                    Err(Error::UserNotAdmin)
+                }
+            }
+            RpcRole::SuperAdmin => {
+                let ctx = rpc_resources.ctx.as_ref().ok_or(Error::MissingCtx)?;
+                if ctx.super_admin() {
+                    Ok(())
+                } else {
+                    Err(Error::UserNotSuperAdmin)
+                }
+            }
+        }
+    }
+}
+
+/// Schemas of one handler, `params` is `None` for handlers without params.
+pub struct RpcSchemas {
+    pub params: Option<Schema>,
+    pub result: Schema,
+}
+
+pub(super) fn new_generator() -> SchemaGenerator {
+    SchemaSettings::draft07()
+        .with(|settings| settings.definitions_path = DEFINITIONS_PATH.into())
+        .into_generator()
+}
+
+pub(super) fn method_doc(
+    generator: &SchemaGenerator,
+    name: &str,
+    role: RpcRole,
+    schemas: RpcSchemas,
+) -> Value {
+    let params = schemas
+        .params
+        .map(|params| params_descriptors(generator, &params))
+        .unwrap_or_default();
+
+    json!({
+        "name": name,
+        "paramStructure": "by-name",
+        "params": params,
+        "result": {
+            "name": "result",
+            "schema": schemas.result,
+        },
+        "x-required-role": role,
+    })
+}
+
+pub(super) fn document(mut generator: SchemaGenerator, methods: Vec<Value>) -> Value {
+    json!({
+        "openrpc": OPENRPC_VERSION,
+        "info": {
+            "title": "Teacherinator RPC",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "methods": methods,
+        "components": {
+            "schemas": generator.take_definitions(true),
+        },
+    })
+}
+
+/// One OpenRPC content descriptor per property of the params object.
+fn params_descriptors(generator: &SchemaGenerator, params: &Schema) -> Vec<Value> {
+    let empty = Map::new();
+    let params = resolve(generator, params)
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    let required: Vec<&str> = params
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    params
+        .get("properties")
+        .and_then(Value::as_object)
+        .map(|properties| {
+            properties
+                .iter()
+                .map(|(name, schema)| {
+                    json!({
+                        "name": name,
+                        "required": required.contains(&name.as_str()),
+                        "schema": schema,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The schema, or its definition when it is a `$ref`.
+fn resolve<'a>(generator: &'a SchemaGenerator, schema: &'a Schema) -> Option<&'a Value> {
+    let prefix = format!("#{DEFINITIONS_PATH}/");
+    match schema.get("$ref").and_then(Value::as_str) {
+        Some(reference) => {
+            let name = reference.strip_prefix(&prefix)?;
+            generator.definitions().get(name)
+        }
+        None => Some(schema.as_value()),
+    }
+}
@@ -3,6 +3,6 @@ use crate::RpcResources;
 
 pub trait FromResources {
     fn from_resources(rpc_resources: &RpcResources) -> Result<Self>
-        where
-            Self: Sized;
+    where
+        Self: Sized;
 }
@@ -22,8 +22,8 @@ pub trait IntoParams: DeserializeOwned + Send {
 pub trait IntoDefaultParams: DeserializeOwned + Send + Default {}
 
 impl<P> IntoParams for P
-    where
-        P: IntoDefaultParams,
+where
+    P: IntoDefaultParams,
 {
     fn into_params(value: Option<Value>) -> Result<Self> {
         match value {
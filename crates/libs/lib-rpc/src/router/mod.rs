@@ -3,10 +3,10 @@
 //!
 //! It has the following constructs:
 //!
-//! - `RpcRouter` holds the HashMap of `method_name: Box<dyn RpcHandlerWrapperTrait>`.
+//! - `RpcRouter` holds the HashMap of `method_name: RpcRoute` (`Box<dyn RpcHandlerWrapperTrait>` and its `RpcRole`).
 //! - `RpcHandler` trait is implemented for any async function that, with
 //!   `(S1, S2, ...[impl IntoParams])`, returns `web::Result<Serialize>` where S1, S2, ... are
-//!    types that implement `FromResources` (see router/from_resources.rs and src/resources.rs).
+//!   types that implement `FromResources` (see router/from_resources.rs and src/resources.rs).
 //! - `IntoParams` is the trait to implement to instruct how to go from `Option<Value>` json-rpc params
 //!   to the handler's param types.
 //! - `IntoParams` has a default `into_params` implementation that will return an error if the params are missing.
@@ -23,6 +23,8 @@
 //! - For custom `IntoParams` behavior, implement the `IntoParams::into_params` function.
 //! - Implementing `IntoDefaultParams` on a type that implements `Default` will auto-implement `IntoParams`
 //!   and call `T::default()` when the params `Option<Value>` is None.
+//! - Each route has a `RpcRole` (checked before the handler call), and the `rpc.discover`
+//!   method returns the OpenRPC document of all the routes (see router/discover.rs).
 //!
 
 // region:    --- Modules
@@ -34,14 +36,16 @@ use futures::Future;
 use serde::Deserialize;
 use serde_json::Value;
 
+pub use discover::{RpcRole, RpcSchemas, DISCOVER_METHOD};
 pub use from_resources::FromResources;
 pub use into_params::{IntoDefaultParams, IntoParams};
 pub use rpc_handler::RpcHandler;
 pub use rpc_handler_wrapper::{RpcHandlerWrapper, RpcHandlerWrapperTrait};
 
-use crate::{Error, Result};
 use crate::RpcResources;
+use crate::{Error, Result};
 
+mod discover;
 mod from_resources;
 mod into_params;
 mod rpc_handler;
@@ -50,20 +54,40 @@ mod rpc_handler_wrapper;
 // endregion: --- Modules
 
 /// The raw JSON-RPC request object, serving as the foundation for RPC routing.
+///
+/// Note: `jsonrpc` is optional for the existing clients, but when present must be `"2.0"`
+///       (see `RpcRequest::JSONRPC_VERSION`).
 #[derive(Deserialize)]
 pub struct RpcRequest {
+    pub jsonrpc: Option<String>,
     pub id: Option<Value>,
     pub method: String,
     pub params: Option<Value>,
 }
 
-pub type PinFutureValue = Pin<Box<dyn Future<Output=Result<Value>> + Send>>;
+impl RpcRequest {
+    pub const JSONRPC_VERSION: &'static str = "2.0";
+
+    /// Only `"2.0"`, or no version for the pre JSON-RPC 2.0 clients.
+    pub fn is_valid_version(&self) -> bool {
+        self.jsonrpc
+            .as_deref()
+            .is_none_or(|v| v == Self::JSONRPC_VERSION)
+    }
+}
+
+pub type PinFutureValue = Pin<Box<dyn Future<Output = Result<Value>> + Send>>;
 
 /// method, which calls the appropriate handler matching the method_name.
 ///
 /// RpcRouter can be extended with other RpcRouters for composability.
 pub struct RpcRouter {
-    route_by_name: HashMap<&'static str, Box<dyn RpcHandlerWrapperTrait>>,
+    route_by_name: HashMap<&'static str, RpcRoute>,
+}
+
+struct RpcRoute {
+    handler: Box<dyn RpcHandlerWrapperTrait>,
+    role: RpcRole,
 }
 
 impl RpcRouter {
@@ -84,12 +108,22 @@ impl RpcRouter {
     ///       avoids monomorphization of the add function.
     ///       The RpcRouter also has a `.add()` as a convenience function to just pass the function.
     ///       See `RpcRouter::add` for more details.
-    pub fn add_dyn(
+    pub fn add_dyn(self, name: &'static str, dyn_handler: Box<dyn RpcHandlerWrapperTrait>) -> Self {
+        self.add_dyn_with_role(name, RpcRole::default(), dyn_handler)
+    }
+
+    /// Same as `add_dyn`, for a method restricted to `role`.
+    pub fn add_dyn_with_role(
         mut self,
         name: &'static str,
+        role: RpcRole,
         dyn_handler: Box<dyn RpcHandlerWrapperTrait>,
     ) -> Self {
-        self.route_by_name.insert(name, dyn_handler);
+        let route = RpcRoute {
+            handler: dyn_handler,
+            role,
+        };
+        self.route_by_name.insert(name, route);
         self
     }
 
@@ -103,11 +137,11 @@ impl RpcRouter {
     ///       and there will be monomorphed versions of this function
     ///       for each type passed. Use `RpcRouter::add_dyn` to avoid this.
     pub fn add<F, T, P, R>(self, name: &'static str, handler: F) -> Self
-        where
-            F: RpcHandler<T, P, R> + Clone + Send + Sync + 'static,
-            T: Send + Sync + 'static,
-            P: Send + Sync + 'static,
-            R: Send + Sync + 'static,
+    where
+        F: RpcHandler<T, P, R> + Clone + Send + Sync + 'static,
+        T: Send + Sync + 'static,
+        P: Send + Sync + 'static,
+        R: Send + Sync + 'static,
     {
         self.add_dyn(name, handler.into_dyn())
     }
@@ -123,43 +157,73 @@ impl RpcRouter {
         rpc_resources: RpcResources,
         params: Option<Value>,
     ) -> Result<Value> {
+        if method == DISCOVER_METHOD {
+            return Ok(self.discover());
+        }
+
         if let Some(route) = self.route_by_name.get(method) {
-            route.call(rpc_resources, params).await
+            route.role.check(&rpc_resources)?;
+            route.handler.call(rpc_resources, params).await
         } else {
             Err(Error::RpcMethodUnknown(method.to_string()))
         }
     }
+
+    /// The OpenRPC document of the routes (methods sorted by name).
+    pub fn discover(&self) -> Value {
+        let mut generator = discover::new_generator();
+
+        let mut routes: Vec<_> = self.route_by_name.iter().collect();
+        routes.sort_by_key(|(name, _)| **name);
+
+        let methods = routes
+            .into_iter()
+            .map(|(name, route)| {
+                let schemas = route.handler.schemas(&mut generator);
+                discover::method_doc(&generator, name, route.role, schemas)
+            })
+            .collect();
+
+        discover::document(generator, methods)
+    }
 }
 
 /// A simple macro to create a new RpcRouter
-/// and add each rpc handler-compatible function along with their corresponding names.
+/// and add each rpc handler-compatible function along with their corresponding names,
+/// and optionally their `RpcRole` (default `User`).
 ///
 /// e.g.,
 ///
 /// ```
 /// rpc_router!(
-///   create_project,
+///   create_project: Admin,
 ///   list_projects,
-///   update_project,
-///   delete_project
+///   update_project: Admin,
+///   delete_project: Admin
 /// );
 /// ```
 /// Is equivalent to:
 /// ```
 /// RpcRouter::new()
-///     .add_dyn("create_project", create_project.into_box())
-///     .add_dyn("list_projects", list_projects.into_box())
-///     .add_dyn("update_project", update_project.into_box())
-///     .add_dyn("delete_project", delete_project.into_box())
+///     .add_dyn_with_role("create_project", RpcRole::Admin, create_project.into_box())
+///     .add_dyn_with_role("list_projects", RpcRole::User, list_projects.into_box())
+///     .add_dyn_with_role("update_project", RpcRole::Admin, update_project.into_box())
+///     .add_dyn_with_role("delete_project", RpcRole::Admin, delete_project.into_box())
 /// ```
 #[macro_export]
 macro_rules! rpc_router {
-    ($($fn_name:ident),+ $(,)?) => {
+    (@role) => { $crate::router::RpcRole::User };
+    (@role $role:ident) => { $crate::router::RpcRole::$role };
+    ($($fn_name:ident $(: $role:ident)?),+ $(,)?) => {
         {
 					use $crate::router::{RpcHandler, RpcRouter};
             let mut router = RpcRouter::new();
             $(
-                router = router.add_dyn(stringify!($fn_name), $fn_name.into_dyn());
+                router = router.add_dyn_with_role(
+                    stringify!($fn_name),
+                    $crate::rpc_router!(@role $($role)?),
+                    $fn_name.into_dyn(),
+                );
             )+
             router
         }
@@ -1,13 +1,15 @@
 use futures::Future;
+use schemars::{JsonSchema, SchemaGenerator};
 use serde::Serialize;
 use serde_json::Value;
 
-use crate::router::FromResources;
 use crate::router::into_params::IntoParams;
+use crate::router::FromResources;
 use crate::router::PinFutureValue;
 use crate::router::Result;
 use crate::router::RpcHandlerWrapper;
 use crate::router::RpcHandlerWrapperTrait;
+use crate::router::RpcSchemas;
 use crate::RpcResources;
 
 /// The `Handler` trait that will be implemented by rpc handler functions.
@@ -22,24 +24,29 @@ use crate::RpcResources;
 ///   thus facilitating the use of RpcRoute dynamic dispatch.
 /// - `T` is the tuple of `impl FromResources` arguments.
 /// - `P` is the `impl IntoParams` argument.
+/// - `P` and `R` must be `JsonSchema`, for the `rpc.discover` document (see `RpcRouter::discover`).
 ///
 pub trait RpcHandler<T, P, R>: Clone
-    where
-        T: Send + Sync + 'static,
-        P: Send + Sync + 'static,
-        R: Send + Sync + 'static,
+where
+    T: Send + Sync + 'static,
+    P: Send + Sync + 'static,
+    R: Send + Sync + 'static,
 {
     /// The type of future calling this handler returns.
-    type Future: Future<Output=Result<Value>> + Send + 'static;
+    type Future: Future<Output = Result<Value>> + Send + 'static;
 
     /// Call the handler.
     fn call(self, rpc_resources: RpcResources, params: Option<Value>) -> Self::Future;
 
+    /// The params and result schemas,
+    /// the named types are added to the generator definitions.
+    fn schemas(generator: &mut SchemaGenerator) -> RpcSchemas;
+
     /// Convert this RpcHandler into a Boxed dyn RpcHandlerWrapperTrait,
     /// for dynamic dispatch by the Router.
     fn into_dyn(self) -> Box<dyn RpcHandlerWrapperTrait>
-        where
-            Self: Sized + Send + Sync + 'static,
+    where
+        Self: Sized + Send + Sync + 'static,
     {
         Box::new(RpcHandlerWrapper::new(self)) as Box<dyn RpcHandlerWrapperTrait>
     }
@@ -55,12 +62,19 @@ macro_rules! impl_rpc_handler_pair {
         where
             F: FnOnce($($T,)* P) -> Fut + Clone + Send + 'static,
             $( $T: FromResources + Send + Sync + 'static, )*
-            P: IntoParams + Send + Sync + 'static,
-            R: Serialize + Send + Sync + 'static,
+            P: IntoParams + JsonSchema + Send + Sync + 'static,
+            R: Serialize + JsonSchema + Send + Sync + 'static,
             Fut: Future<Output = Result<R>> + Send,
         {
             type Future = PinFutureValue;
 
+            fn schemas(generator: &mut SchemaGenerator) -> RpcSchemas {
+                RpcSchemas {
+                    params: Some(generator.subschema_for::<P>()),
+                    result: generator.subschema_for::<R>(),
+                }
+            }
+
 						#[allow(unused)] // somehow rpc_resources will be marked as unused
             fn call(
                 self,
@@ -85,11 +99,18 @@ macro_rules! impl_rpc_handler_pair {
 				where
 						F: FnOnce($($T,)*) -> Fut + Clone + Send + 'static,
 						$( $T: FromResources + Send + Sync + 'static, )*
-						R: Serialize + Send + Sync + 'static,
+						R: Serialize + JsonSchema + Send + Sync + 'static,
 						Fut: Future<Output = Result<R>> + Send,
 				{
 						type Future = PinFutureValue;
 
+						fn schemas(generator: &mut SchemaGenerator) -> RpcSchemas {
+								RpcSchemas {
+										params: None,
+										result: generator.subschema_for::<R>(),
+								}
+						}
+
 						#[allow(unused)] // somehow rpc_resources will be marked as unused
 						fn call(
 								self,
@@ -2,10 +2,11 @@ use std::marker::PhantomData;
 use std::pin::Pin;
 
 use futures::Future;
+use schemars::SchemaGenerator;
 use serde_json::Value;
 
-use crate::router::{PinFutureValue, RpcHandler};
 use crate::router::Result;
+use crate::router::{PinFutureValue, RpcHandler, RpcSchemas};
 use crate::RpcResources;
 
 /// `RpcHanlderWrapper` is a `RpcHandler` wrapper which implements
@@ -28,11 +29,11 @@ impl<H, T, P, R> RpcHandlerWrapper<H, T, P, R> {
 
 // Call Impl
 impl<H, T, P, R> RpcHandlerWrapper<H, T, P, R>
-    where
-        H: RpcHandler<T, P, R> + Send + Sync + 'static,
-        T: Send + Sync + 'static,
-        P: Send + Sync + 'static,
-        R: Send + Sync + 'static,
+where
+    H: RpcHandler<T, P, R> + Send + Sync + 'static,
+    T: Send + Sync + 'static,
+    P: Send + Sync + 'static,
+    R: Send + Sync + 'static,
 {
     pub fn call(&self, rpc_resources: RpcResources, params: Option<Value>) -> H::Future {
         // Note: Since handler is a FnOnce, we can use it only once, so we clone it.
@@ -46,20 +47,26 @@ impl<H, T, P, R> RpcHandlerWrapper<H, T, P, R>
 /// allowing for dynamic dispatch.
 pub trait RpcHandlerWrapperTrait: Send + Sync {
     fn call(&self, rpc_resources: RpcResources, params: Option<Value>) -> PinFutureValue;
+
+    fn schemas(&self, generator: &mut SchemaGenerator) -> RpcSchemas;
 }
 
 impl<H, T, P, R> RpcHandlerWrapperTrait for RpcHandlerWrapper<H, T, P, R>
-    where
-        H: RpcHandler<T, P, R> + Clone + Send + Sync + 'static,
-        T: Send + Sync + 'static,
-        P: Send + Sync + 'static,
-        R: Send + Sync + 'static,
+where
+    H: RpcHandler<T, P, R> + Clone + Send + Sync + 'static,
+    T: Send + Sync + 'static,
+    P: Send + Sync + 'static,
+    R: Send + Sync + 'static,
 {
     fn call(
         &self,
         rpc_resources: RpcResources,
         params: Option<Value>,
-    ) -> Pin<Box<dyn Future<Output=Result<Value>> + Send>> {
+    ) -> Pin<Box<dyn Future<Output = Result<Value>> + Send>> {
         Box::pin(self.call(rpc_resources, params))
     }
+
+    fn schemas(&self, generator: &mut SchemaGenerator) -> RpcSchemas {
+        H::schemas(generator)
+    }
 }
@@ -0,0 +1,33 @@
+use lib_core::ctx::Ctx;
+use lib_core::model::event::{event_center_id, AnnouncementForCreate, ModelEvent};
+use lib_core::model::ModelManager;
+
+use crate::router::RpcRouter;
+use crate::rpc_router;
+use crate::ParamsForCreate;
+use crate::Result;
+
+pub fn rpc_router() -> RpcRouter {
+    rpc_router!(
+        // Same as RpcRouter::new().add...
+        send_announcement: Admin,
+    )
+}
+
+/// Pushed to all the `/api/ws` subscribers of the ctx center (not stored).
+pub async fn send_announcement(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<AnnouncementForCreate>,
+) -> Result<ModelEvent> {
+    let ParamsForCreate { data } = params;
+
+    let event = ModelEvent::Announcement {
+        center_id: event_center_id(&ctx),
+        author_id: ctx.user_id(),
+        message: data.message,
+    };
+    mm.publish(event.clone()).await;
+
+    Ok(event)
+}
@@ -0,0 +1,25 @@
+use lib_core::ctx::Ctx;
+use lib_core::model::audit_log::{AuditLog, AuditLogBmc, AuditLogFilter};
+use lib_core::model::ModelManager;
+
+use crate::router::RpcRouter;
+use crate::rpc_router;
+use crate::ParamsList;
+use crate::Result;
+
+pub fn rpc_router() -> RpcRouter {
+    rpc_router!(
+        // Same as RpcRouter::new().add...
+        list_audit_log: Admin,
+    )
+}
+
+pub async fn list_audit_log(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsList<AuditLogFilter>,
+) -> Result<Vec<AuditLog>> {
+    let entries = AuditLogBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+
+    Ok(entries)
+}
@@ -1,11 +1,13 @@
 use lib_core::ctx::Ctx;
-use lib_core::model::building::{Building, BuildingBmc, BuildingFilter, BuildingForCreate, BuildingForUpdate};
+use lib_core::model::building::{
+    Building, BuildingBmc, BuildingFilter, BuildingForCreate, BuildingForUpdate,
+};
 use lib_core::model::ModelManager;
 
-use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
-use crate::Result;
 use crate::router::RpcRouter;
 use crate::rpc_router;
+use crate::Result;
+use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
 
 pub fn rpc_router() -> RpcRouter {
     rpc_router!(
@@ -53,9 +55,13 @@ pub async fn update_building(
     mm: ModelManager,
     params: ParamsForUpdate<BuildingForUpdate>,
 ) -> Result<Building> {
-    let ParamsForUpdate { id, data } = params;
+    let ParamsForUpdate {
+        id,
+        data,
+        expected_mtime,
+    } = params;
 
-    BuildingBmc::update(&ctx, &mm, id, data).await?;
+    BuildingBmc::update_if_unmodified(&ctx, &mm, id, data, expected_mtime).await?;
 
     let building = BuildingBmc::get(&ctx, &mm, id).await?;
 
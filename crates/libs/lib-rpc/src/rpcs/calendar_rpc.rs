@@ -0,0 +1,84 @@
+use lib_core::ctx::Ctx;
+use lib_core::model::calendar::{
+    CalendarBmc, CourseCalendar, Holiday, HolidayBmc, HolidayFilter, HolidayForCreate,
+};
+use lib_core::model::ModelManager;
+
+use crate::router::RpcRouter;
+use crate::rpc_router;
+use crate::Result;
+use crate::{ParamsForCreate, ParamsIded, ParamsList};
+
+pub fn rpc_router() -> RpcRouter {
+    rpc_router!(
+        // Same as RpcRouter::new().add...
+        get_calendar_feed_token,
+        rotate_calendar_feed_token,
+        set_course_calendar: Admin,
+        create_holiday: Admin,
+        list_holidays,
+        delete_holiday: Admin,
+    )
+}
+
+/// The current user token, for the `/api/calendar/teacher?token=...`
+/// and `/api/calendar/group/:group_id?token=...` urls.
+pub async fn get_calendar_feed_token(ctx: Ctx, mm: ModelManager) -> Result<String> {
+    let token = CalendarBmc::feed_token(&ctx, &mm, ctx.user_id()).await?;
+
+    Ok(token)
+}
+
+/// Revokes the current user feed urls, and returns the new token.
+pub async fn rotate_calendar_feed_token(ctx: Ctx, mm: ModelManager) -> Result<String> {
+    CalendarBmc::rotate_feed_token(&ctx, &mm, ctx.user_id()).await?;
+    let token = CalendarBmc::feed_token(&ctx, &mm, ctx.user_id()).await?;
+
+    Ok(token)
+}
+
+pub async fn set_course_calendar(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<CourseCalendar>,
+) -> Result<CourseCalendar> {
+    let ParamsForCreate { data } = params;
+
+    let course = data.course;
+    CalendarBmc::set_course(&ctx, &mm, data).await?;
+    let course_calendar = CalendarBmc::get_course(&ctx, &mm, course).await?;
+
+    Ok(course_calendar)
+}
+
+pub async fn create_holiday(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<HolidayForCreate>,
+) -> Result<Holiday> {
+    let ParamsForCreate { data } = params;
+
+    let id = HolidayBmc::create(&ctx, &mm, data).await?;
+    let holiday = HolidayBmc::get(&ctx, &mm, id).await?;
+
+    Ok(holiday)
+}
+
+pub async fn list_holidays(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsList<HolidayFilter>,
+) -> Result<Vec<Holiday>> {
+    let holidays = HolidayBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+
+    Ok(holidays)
+}
+
+pub async fn delete_holiday(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Holiday> {
+    let ParamsIded { id } = params;
+
+    let holiday = HolidayBmc::get(&ctx, &mm, id).await?;
+    HolidayBmc::delete(&ctx, &mm, id).await?;
+
+    Ok(holiday)
+}
@@ -0,0 +1,41 @@
+use lib_core::ctx::Ctx;
+use lib_core::model::center::{
+    Center, CenterBmc, CenterFilter, CenterForProvision, CenterProvisioned,
+};
+use lib_core::model::ModelManager;
+
+use crate::router::RpcRouter;
+use crate::rpc_router;
+use crate::Result;
+use crate::{ParamsForCreate, ParamsList};
+
+pub fn rpc_router() -> RpcRouter {
+    rpc_router!(
+        // Same as RpcRouter::new().add...
+        create_center: SuperAdmin,
+        list_centers: SuperAdmin,
+    )
+}
+
+/// New center, with its first admin (who then sets it up).
+pub async fn create_center(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<CenterForProvision>,
+) -> Result<CenterProvisioned> {
+    let ParamsForCreate { data } = params;
+
+    let provisioned = CenterBmc::provision(&ctx, &mm, data).await?;
+
+    Ok(provisioned)
+}
+
+pub async fn list_centers(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsList<CenterFilter>,
+) -> Result<Vec<Center>> {
+    let centers = CenterBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+
+    Ok(centers)
+}
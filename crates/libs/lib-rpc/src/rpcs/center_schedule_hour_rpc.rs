@@ -1,23 +1,25 @@
-use serde_json::json;
 use lib_core::ctx::Ctx;
+use lib_core::model::center_schedule_hour::{
+    CenterScheduleHour, CenterScheduleHourBmc, CenterScheduleHourFilter,
+    CenterScheduleHourForCheck, CenterScheduleHourForCreate, CenterScheduleHourForUpdate,
+};
 use lib_core::model::ModelManager;
-use lib_core::model::center_schedule_hour::{CenterScheduleHour, CenterScheduleHourBmc, CenterScheduleHourFilter, CenterScheduleHourForCheck, CenterScheduleHourForCreate, CenterScheduleHourForUpdate};
+use serde_json::json;
 
-use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
-use crate::Error::UserNotAdmin;
-use crate::Result;
 use crate::router::RpcRouter;
 use crate::rpc_router;
+use crate::Result;
+use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
 
 pub fn rpc_router() -> RpcRouter {
     rpc_router!(
         // Same as RpcRouter::new().add...
-        create_center_schedule_hour,
-        get_center_schedule_hour,
+        create_center_schedule_hour: Admin,
+        get_center_schedule_hour: Admin,
         list_center_schedule_hours,
-        update_center_schedule_hour,
-        delete_center_schedule_hour,
-        check_hour_exists,
+        update_center_schedule_hour: Admin,
+        delete_center_schedule_hour: Admin,
+        check_hour_exists: Admin,
     )
 }
 
@@ -26,7 +28,6 @@ pub async fn create_center_schedule_hour(
     mm: ModelManager,
     params: ParamsForCreate<CenterScheduleHourForCreate>,
 ) -> Result<CenterScheduleHour> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
     let ParamsForCreate { data } = params;
     let id = CenterScheduleHourBmc::create(&ctx, &mm, data.clone()).await?;
     let center_schedule_hour = CenterScheduleHourBmc::get(&ctx, &mm, id).await?;
@@ -34,21 +35,24 @@ pub async fn create_center_schedule_hour(
     Ok(center_schedule_hour)
 }
 
-pub async fn get_center_schedule_hour(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<CenterScheduleHour> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
+pub async fn get_center_schedule_hour(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<CenterScheduleHour> {
     let ParamsIded { id } = params;
     let center_schedule_hour = CenterScheduleHourBmc::get(&ctx, &mm, id).await?;
     Ok(center_schedule_hour)
 }
 
-
 pub async fn list_center_schedule_hours(
     ctx: Ctx,
     mm: ModelManager,
     params: ParamsList<CenterScheduleHourFilter>,
 ) -> Result<Vec<CenterScheduleHour>> {
     // if !&ctx.admin() { return Err(UserNotAdmin); }
-    let center_schedule_hours = CenterScheduleHourBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+    let center_schedule_hours =
+        CenterScheduleHourBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
 
     Ok(center_schedule_hours)
 }
@@ -58,18 +62,24 @@ pub async fn update_center_schedule_hour(
     mm: ModelManager,
     params: ParamsForUpdate<CenterScheduleHourForUpdate>,
 ) -> Result<CenterScheduleHour> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
-    let ParamsForUpdate { id, data } = params;
+    let ParamsForUpdate {
+        id,
+        data,
+        expected_mtime,
+    } = params;
 
-    CenterScheduleHourBmc::update(&ctx, &mm, id, data).await?;
+    CenterScheduleHourBmc::update_if_unmodified(&ctx, &mm, id, data, expected_mtime).await?;
 
     let center_schedule_hour = CenterScheduleHourBmc::get(&ctx, &mm, id).await?;
 
     Ok(center_schedule_hour)
 }
 
-pub async fn delete_center_schedule_hour(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<CenterScheduleHour> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
+pub async fn delete_center_schedule_hour(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<CenterScheduleHour> {
     let ParamsIded { id } = params;
 
     let center_schedule_hour = CenterScheduleHourBmc::get(&ctx, &mm, id).await?;
@@ -83,9 +93,6 @@ pub async fn check_hour_exists(
     mm: ModelManager,
     params: ParamsForCreate<CenterScheduleHourForCheck>,
 ) -> Result<bool> {
-    if !&ctx.admin() {
-        return Err(UserNotAdmin);
-    }
     let ParamsForCreate { data } = params;
 
     let filter_json = json!({
@@ -95,5 +102,5 @@ pub async fn check_hour_exists(
 
     let hours = CenterScheduleHourBmc::list(&ctx, &mm, Some(filter), None).await?;
 
-    Ok(hours.len() > 0)
+    Ok(!hours.is_empty())
 }
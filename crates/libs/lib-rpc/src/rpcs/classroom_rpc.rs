@@ -1,11 +1,13 @@
 use lib_core::ctx::Ctx;
-use lib_core::model::classroom::{Classroom, ClassroomBmc, ClassroomFilter, ClassroomForCreate, ClassroomForUpdate};
+use lib_core::model::classroom::{
+    Classroom, ClassroomBmc, ClassroomFilter, ClassroomForCreate, ClassroomForUpdate,
+};
 use lib_core::model::ModelManager;
 
-use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
-use crate::Result;
 use crate::router::RpcRouter;
 use crate::rpc_router;
+use crate::{Error, Result};
+use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
 
 pub fn rpc_router() -> RpcRouter {
     rpc_router!(
@@ -14,7 +16,8 @@ pub fn rpc_router() -> RpcRouter {
         get_classroom,
         list_classrooms,
         update_classroom,
-        delete_classroom,        
+        delete_classroom,
+        restore_classroom: Admin,
         count_classroom_by_classroom_type,
     )
 }
@@ -45,7 +48,14 @@ pub async fn list_classrooms(
     mm: ModelManager,
     params: ParamsList<ClassroomFilter>,
 ) -> Result<Vec<Classroom>> {
-    let classrooms = ClassroomBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+    if params.include_deleted && !ctx.admin() {
+        return Err(Error::UserNotAdmin);
+    }
+    let classrooms = if params.include_deleted {
+        ClassroomBmc::list_including_deleted(&ctx, &mm, params.filters, params.list_options).await?
+    } else {
+        ClassroomBmc::list(&ctx, &mm, params.filters, params.list_options).await?
+    };
 
     Ok(classrooms)
 }
@@ -55,9 +65,13 @@ pub async fn update_classroom(
     mm: ModelManager,
     params: ParamsForUpdate<ClassroomForUpdate>,
 ) -> Result<Classroom> {
-    let ParamsForUpdate { id, data } = params;
+    let ParamsForUpdate {
+        id,
+        data,
+        expected_mtime,
+    } = params;
 
-    ClassroomBmc::update(&ctx, &mm, id, data).await?;
+    ClassroomBmc::update_if_unmodified(&ctx, &mm, id, data, expected_mtime).await?;
 
     let classroom = ClassroomBmc::get(&ctx, &mm, id).await?;
 
@@ -73,6 +87,19 @@ pub async fn delete_classroom(ctx: Ctx, mm: ModelManager, params: ParamsIded) ->
     Ok(classroom)
 }
 
+pub async fn restore_classroom(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<Classroom> {
+    let ParamsIded { id } = params;
+
+    ClassroomBmc::restore(&ctx, &mm, id).await?;
+    let classroom = ClassroomBmc::get(&ctx, &mm, id).await?;
+
+    Ok(classroom)
+}
+
 pub async fn count_classroom_by_classroom_type(
     ctx: Ctx,
     mm: ModelManager,
@@ -81,4 +108,4 @@ pub async fn count_classroom_by_classroom_type(
     let ParamsIded { id } = params;
     let classroom_number = ClassroomBmc::count_classrooms_by_classroom_type(&ctx, &mm, id).await?;
     Ok(classroom_number)
-}
\ No newline at end of file
+}
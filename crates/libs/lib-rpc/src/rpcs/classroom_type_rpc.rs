@@ -1,11 +1,14 @@
 use lib_core::ctx::Ctx;
-use lib_core::model::classroom_type::{ClassroomType, ClassroomTypeBmc, ClassroomTypeFilter, ClassroomTypeForCreate, ClassroomTypeForUpdate};
+use lib_core::model::classroom_type::{
+    ClassroomType, ClassroomTypeBmc, ClassroomTypeFilter, ClassroomTypeForCreate,
+    ClassroomTypeForUpdate,
+};
 use lib_core::model::ModelManager;
 
-use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
-use crate::Result;
 use crate::router::RpcRouter;
 use crate::rpc_router;
+use crate::Result;
+use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
 
 pub fn rpc_router() -> RpcRouter {
     rpc_router!(
@@ -30,7 +33,11 @@ pub async fn create_classroom_type(
     Ok(classroom_type)
 }
 
-pub async fn get_classroom_type(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<ClassroomType> {
+pub async fn get_classroom_type(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<ClassroomType> {
     let ParamsIded { id } = params;
 
     let classroom_type = ClassroomTypeBmc::get(&ctx, &mm, id).await?;
@@ -43,7 +50,8 @@ pub async fn list_classroom_types(
     mm: ModelManager,
     params: ParamsList<ClassroomTypeFilter>,
 ) -> Result<Vec<ClassroomType>> {
-    let classroom_types = ClassroomTypeBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+    let classroom_types =
+        ClassroomTypeBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
 
     Ok(classroom_types)
 }
@@ -53,16 +61,24 @@ pub async fn update_classroom_type(
     mm: ModelManager,
     params: ParamsForUpdate<ClassroomTypeForUpdate>,
 ) -> Result<ClassroomType> {
-    let ParamsForUpdate { id, data } = params;
+    let ParamsForUpdate {
+        id,
+        data,
+        expected_mtime,
+    } = params;
 
-    ClassroomTypeBmc::update(&ctx, &mm, id, data).await?;
+    ClassroomTypeBmc::update_if_unmodified(&ctx, &mm, id, data, expected_mtime).await?;
 
     let classroom_type = ClassroomTypeBmc::get(&ctx, &mm, id).await?;
 
     Ok(classroom_type)
 }
 
-pub async fn delete_classroom_type(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<ClassroomType> {
+pub async fn delete_classroom_type(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<ClassroomType> {
     let ParamsIded { id } = params;
 
     let classroom_type = ClassroomTypeBmc::get(&ctx, &mm, id).await?;
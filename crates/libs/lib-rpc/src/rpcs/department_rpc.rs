@@ -1,21 +1,23 @@
 use lib_core::ctx::Ctx;
-use lib_core::model::department::{Department, DepartmentBmc, DepartmentFilter, DepartmentForCreate, DepartmentForUpdate};
+use lib_core::model::department::{
+    Department, DepartmentBmc, DepartmentFilter, DepartmentForCreate, DepartmentForUpdate,
+};
 use lib_core::model::ModelManager;
 
-use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
-use crate::Error::UserNotAdmin;
-use crate::Result;
 use crate::router::RpcRouter;
 use crate::rpc_router;
+use crate::{Error, Result};
+use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
 
 pub fn rpc_router() -> RpcRouter {
     rpc_router!(
         // Same as RpcRouter::new().add...
-        create_department,
-        get_department,
-        list_departments,
-        update_department,
-        delete_department,
+        create_department: Admin,
+        get_department: Admin,
+        list_departments: Admin,
+        update_department: Admin,
+        delete_department: Admin,
+        restore_department: Admin,
     )
 }
 
@@ -24,18 +26,16 @@ pub async fn create_department(
     mm: ModelManager,
     params: ParamsForCreate<DepartmentForCreate>,
 ) -> Result<Department> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
     let ParamsForCreate { data } = params;
 
     let id = DepartmentBmc::create(&ctx, &mm, data.clone()).await?;
-    
+
     let department = DepartmentBmc::get(&ctx, &mm, id).await?;
 
     Ok(department)
 }
 
 pub async fn get_department(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Department> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
     let ParamsIded { id } = params;
 
     let department = DepartmentBmc::get(&ctx, &mm, id).await?;
@@ -48,8 +48,15 @@ pub async fn list_departments(
     mm: ModelManager,
     params: ParamsList<DepartmentFilter>,
 ) -> Result<Vec<Department>> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
-    let departments = DepartmentBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+    if params.include_deleted && !ctx.admin() {
+        return Err(Error::UserNotAdmin);
+    }
+    let departments = if params.include_deleted {
+        DepartmentBmc::list_including_deleted(&ctx, &mm, params.filters, params.list_options)
+            .await?
+    } else {
+        DepartmentBmc::list(&ctx, &mm, params.filters, params.list_options).await?
+    };
 
     Ok(departments)
 }
@@ -59,18 +66,24 @@ pub async fn update_department(
     mm: ModelManager,
     params: ParamsForUpdate<DepartmentForUpdate>,
 ) -> Result<Department> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
-    let ParamsForUpdate { id, data } = params;
+    let ParamsForUpdate {
+        id,
+        data,
+        expected_mtime,
+    } = params;
 
-    DepartmentBmc::update(&ctx, &mm, id, data).await?;
+    DepartmentBmc::update_if_unmodified(&ctx, &mm, id, data, expected_mtime).await?;
 
     let department = DepartmentBmc::get(&ctx, &mm, id).await?;
 
     Ok(department)
 }
 
-pub async fn delete_department(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Department> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
+pub async fn delete_department(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<Department> {
     let ParamsIded { id } = params;
 
     let department = DepartmentBmc::get(&ctx, &mm, id).await?;
@@ -78,3 +91,16 @@ pub async fn delete_department(ctx: Ctx, mm: ModelManager, params: ParamsIded) -
 
     Ok(department)
 }
+
+pub async fn restore_department(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<Department> {
+    let ParamsIded { id } = params;
+
+    DepartmentBmc::restore(&ctx, &mm, id).await?;
+    let department = DepartmentBmc::get(&ctx, &mm, id).await?;
+
+    Ok(department)
+}
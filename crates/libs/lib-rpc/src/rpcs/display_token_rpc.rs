@@ -0,0 +1,62 @@
+use lib_core::ctx::Ctx;
+use lib_core::model::display_token::{
+    DisplayToken, DisplayTokenBmc, DisplayTokenFilter, DisplayTokenForCreate, DisplayTokenIssued,
+};
+use lib_core::model::ModelManager;
+
+use crate::router::RpcRouter;
+use crate::rpc_router;
+use crate::Result;
+use crate::{ParamsForCreate, ParamsIded, ParamsList};
+
+pub fn rpc_router() -> RpcRouter {
+    rpc_router!(
+        // Same as RpcRouter::new().add...
+        create_display_token: Admin,
+        list_display_tokens: Admin,
+        delete_display_token: Admin,
+    )
+}
+
+/// The token is only returned here, for the display `/api/display/feed?token=...` url.
+pub async fn create_display_token(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<DisplayTokenForCreate>,
+) -> Result<DisplayTokenIssued> {
+    let ParamsForCreate { data } = params;
+
+    let id = DisplayTokenBmc::create(&ctx, &mm, data).await?;
+    let display_token = DisplayTokenBmc::get(&ctx, &mm, id).await?;
+    let token = DisplayTokenBmc::token(&ctx, &mm, id).await?;
+
+    Ok(DisplayTokenIssued {
+        display_token,
+        token,
+    })
+}
+
+pub async fn list_display_tokens(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsList<DisplayTokenFilter>,
+) -> Result<Vec<DisplayToken>> {
+    let display_tokens =
+        DisplayTokenBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+
+    Ok(display_tokens)
+}
+
+/// Revokes the token.
+pub async fn delete_display_token(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<DisplayToken> {
+    let ParamsIded { id } = params;
+
+    let display_token = DisplayTokenBmc::get(&ctx, &mm, id).await?;
+    DisplayTokenBmc::delete(&ctx, &mm, id).await?;
+
+    Ok(display_token)
+}
@@ -6,19 +6,19 @@ use serde_json::json;
 
 use crate::router::RpcRouter;
 use crate::rpc_router;
-use crate::Error::UserNotAdmin;
-use crate::Result;
+use crate::{Error, Result};
 use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
 
 pub fn rpc_router() -> RpcRouter {
     rpc_router!(
         // Same as RpcRouter::new().add...
-        create_group,
-        get_group,
-        list_groups,
-        update_group,
-        delete_group,
-        check_group_exists,
+        create_group: Admin,
+        get_group: Admin,
+        list_groups: Admin,
+        update_group: Admin,
+        delete_group: Admin,
+        restore_group: Admin,
+        check_group_exists: Admin,
     )
 }
 
@@ -27,11 +27,12 @@ pub async fn create_group(
     mm: ModelManager,
     params: ParamsForCreate<GroupForCreate>,
 ) -> Result<Group> {
-    if !&ctx.admin() {
-        return Err(UserNotAdmin);
-    }
     let ParamsForCreate { data } = params;
 
+    // Group and schedule are all-or-nothing.
+    let mm = mm.new_with_txn();
+    mm.begin_txn().await?;
+
     let id = GroupBmc::create(&ctx, &mm, data).await?;
     let group: Group = GroupBmc::get(&ctx, &mm, id).await?;
 
@@ -42,13 +43,12 @@ pub async fn create_group(
     };
     ScheduleBmc::create(&ctx, &mm, schedule).await?;
 
+    mm.commit_txn().await?;
+
     Ok(group)
 }
 
 pub async fn get_group(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Group> {
-    if !&ctx.admin() {
-        return Err(UserNotAdmin);
-    }
     let ParamsIded { id } = params;
 
     let group = GroupBmc::get(&ctx, &mm, id).await?;
@@ -61,10 +61,14 @@ pub async fn list_groups(
     mm: ModelManager,
     params: ParamsList<GroupFilter>,
 ) -> Result<Vec<Group>> {
-    if !&ctx.admin() {
-        return Err(UserNotAdmin);
+    if params.include_deleted && !ctx.admin() {
+        return Err(Error::UserNotAdmin);
     }
-    let groups = GroupBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+    let groups = if params.include_deleted {
+        GroupBmc::list_including_deleted(&ctx, &mm, params.filters, params.list_options).await?
+    } else {
+        GroupBmc::list(&ctx, &mm, params.filters, params.list_options).await?
+    };
 
     Ok(groups)
 }
@@ -74,12 +78,13 @@ pub async fn update_group(
     mm: ModelManager,
     params: ParamsForUpdate<GroupForUpdate>,
 ) -> Result<Group> {
-    if !&ctx.admin() {
-        return Err(UserNotAdmin);
-    }
-    let ParamsForUpdate { id, data } = params;
+    let ParamsForUpdate {
+        id,
+        data,
+        expected_mtime,
+    } = params;
 
-    GroupBmc::update(&ctx, &mm, id, data).await?;
+    GroupBmc::update_if_unmodified(&ctx, &mm, id, data, expected_mtime).await?;
 
     let group = GroupBmc::get(&ctx, &mm, id).await?;
 
@@ -87,9 +92,6 @@ pub async fn update_group(
 }
 
 pub async fn delete_group(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Group> {
-    if !&ctx.admin() {
-        return Err(UserNotAdmin);
-    }
     let ParamsIded { id } = params;
 
     let group = GroupBmc::get(&ctx, &mm, id).await?;
@@ -98,14 +100,20 @@ pub async fn delete_group(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Res
     Ok(group)
 }
 
+pub async fn restore_group(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Group> {
+    let ParamsIded { id } = params;
+
+    GroupBmc::restore(&ctx, &mm, id).await?;
+    let group = GroupBmc::get(&ctx, &mm, id).await?;
+
+    Ok(group)
+}
+
 pub async fn check_group_exists(
     ctx: Ctx,
     mm: ModelManager,
     params: ParamsForCreate<GroupForCreate>,
 ) -> Result<bool> {
-    if !&ctx.admin() {
-        return Err(UserNotAdmin);
-    }
     let ParamsForCreate { data } = params;
 
     let filter_json = json!({
@@ -116,7 +124,8 @@ pub async fn check_group_exists(
     });
     let filter = vec![serde_json::from_value(filter_json)?];
 
-    let groups = GroupBmc::list(&ctx, &mm, Some(filter), None).await?;
+    // Soft deleted groups still hold the unique key.
+    let groups = GroupBmc::list_including_deleted(&ctx, &mm, Some(filter), None).await?;
 
-    Ok(groups.len() > 0)
+    Ok(!groups.is_empty())
 }
@@ -0,0 +1,44 @@
+use lib_core::ctx::Ctx;
+use lib_core::model::import::itaca::{TimetableImport, TimetableImportReport};
+use lib_core::model::import::{CsvImport, ImportBmc, ImportReport};
+use lib_core::model::ModelManager;
+
+use crate::router::RpcRouter;
+use crate::rpc_router;
+use crate::ParamsForCreate;
+use crate::Result;
+
+pub fn rpc_router() -> RpcRouter {
+    rpc_router!(
+        // Same as RpcRouter::new().add...
+        import_csv: Admin,
+        import_itaca_timetable: Admin,
+    )
+}
+
+/// Check the file with `dry_run` first, the report has the errors of each line.
+pub async fn import_csv(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<CsvImport>,
+) -> Result<ImportReport> {
+    let ParamsForCreate { data } = params;
+
+    let report = ImportBmc::import_csv(&ctx, &mm, data).await?;
+
+    Ok(report)
+}
+
+/// The sessions with a teacher, group, subject or classroom not matched are skipped,
+/// and reported in `unmatched`.
+pub async fn import_itaca_timetable(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<TimetableImport>,
+) -> Result<TimetableImportReport> {
+    let ParamsForCreate { data } = params;
+
+    let report = ImportBmc::import_itaca_timetable(&ctx, &mm, data).await?;
+
+    Ok(report)
+}
@@ -0,0 +1,141 @@
+use time::OffsetDateTime;
+
+use lib_core::ctx::Ctx;
+use lib_core::model::lesson_plan::{
+    CoverLessonPlan, LessonPlan, LessonPlanAttachment, LessonPlanAttachmentForCreate,
+    LessonPlanBmc, LessonPlanFilter, LessonPlanForCreate, LessonPlanForUpdate,
+};
+use lib_core::model::ModelManager;
+
+use crate::router::RpcRouter;
+use crate::rpc_router;
+use crate::Result;
+use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
+
+pub fn rpc_router() -> RpcRouter {
+    rpc_router!(
+        // Same as RpcRouter::new().add...
+        create_lesson_plan,
+        get_lesson_plan,
+        list_lesson_plans,
+        update_lesson_plan,
+        delete_lesson_plan,
+        add_lesson_plan_attachment,
+        list_lesson_plan_attachments,
+        delete_lesson_plan_attachment,
+        list_cover_lesson_plans,
+    )
+}
+
+/// Only for the own schedule hours (or by an admin).
+pub async fn create_lesson_plan(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<LessonPlanForCreate>,
+) -> Result<LessonPlan> {
+    let ParamsForCreate { data } = params;
+
+    let id = LessonPlanBmc::create(&ctx, &mm, data).await?;
+    let lesson_plan = LessonPlanBmc::get(&ctx, &mm, id).await?;
+
+    Ok(lesson_plan)
+}
+
+pub async fn get_lesson_plan(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<LessonPlan> {
+    let ParamsIded { id } = params;
+
+    let lesson_plan = LessonPlanBmc::get(&ctx, &mm, id).await?;
+
+    Ok(lesson_plan)
+}
+
+pub async fn list_lesson_plans(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsList<LessonPlanFilter>,
+) -> Result<Vec<LessonPlan>> {
+    let lesson_plans = LessonPlanBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+
+    Ok(lesson_plans)
+}
+
+pub async fn update_lesson_plan(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForUpdate<LessonPlanForUpdate>,
+) -> Result<LessonPlan> {
+    let ParamsForUpdate {
+        id,
+        data,
+        expected_mtime,
+    } = params;
+
+    LessonPlanBmc::update_if_unmodified(&ctx, &mm, id, data, expected_mtime).await?;
+
+    let lesson_plan = LessonPlanBmc::get(&ctx, &mm, id).await?;
+
+    Ok(lesson_plan)
+}
+
+pub async fn delete_lesson_plan(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<LessonPlan> {
+    let ParamsIded { id } = params;
+
+    let lesson_plan = LessonPlanBmc::get(&ctx, &mm, id).await?;
+    LessonPlanBmc::delete(&ctx, &mm, id).await?;
+
+    Ok(lesson_plan)
+}
+
+/// The `content` is base64url encoded (the download is on
+/// `/api/lesson-plans/attachments/:id`).
+pub async fn add_lesson_plan_attachment(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<LessonPlanAttachmentForCreate>,
+) -> Result<LessonPlanAttachment> {
+    let ParamsForCreate { data } = params;
+
+    let id = LessonPlanBmc::add_attachment(&ctx, &mm, data).await?;
+    let (attachment, _) = LessonPlanBmc::attachment_content(&ctx, &mm, id).await?;
+
+    Ok(attachment)
+}
+
+/// The attachments of the lesson plan `id`.
+pub async fn list_lesson_plan_attachments(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<Vec<LessonPlanAttachment>> {
+    let ParamsIded { id } = params;
+
+    let attachments = LessonPlanBmc::attachments(&ctx, &mm, &[id]).await?;
+
+    Ok(attachments)
+}
+
+pub async fn delete_lesson_plan_attachment(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<LessonPlanAttachment> {
+    let ParamsIded { id } = params;
+
+    let (attachment, _) = LessonPlanBmc::attachment_content(&ctx, &mm, id).await?;
+    LessonPlanBmc::delete_attachment(&ctx, &mm, id).await?;
+
+    Ok(attachment)
+}
+
+/// The lesson plans of the classes the ctx user covers today.
+pub async fn list_cover_lesson_plans(ctx: Ctx, mm: ModelManager) -> Result<Vec<CoverLessonPlan>> {
+    let today = OffsetDateTime::now_utc().date();
+
+    let cover_lesson_plans = LessonPlanBmc::for_covering(&ctx, &mm, ctx.user_id(), today).await?;
+
+    Ok(cover_lesson_plans)
+}
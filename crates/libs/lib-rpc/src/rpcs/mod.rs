@@ -1,10 +1,48 @@
-pub mod user_rpc;
+pub mod announcement_rpc;
+pub mod audit_log_rpc;
+pub mod building_rpc;
+pub mod calendar_rpc;
+pub mod center_rpc;
+pub mod center_schedule_hour_rpc;
+pub mod classroom_rpc;
+pub mod classroom_type_rpc;
 pub mod department_rpc;
-pub mod subject_rpc;
+pub mod display_token_rpc;
 pub mod group_rpc;
-pub mod classroom_rpc;
-pub mod schedule_rpc;
+pub mod import_rpc;
+pub mod lesson_plan_rpc;
+pub mod notification_rpc;
+pub mod push_rpc;
 pub mod schedule_hour_rpc;
-pub mod center_schedule_hour_rpc;
-pub mod classroom_type_rpc;
-pub mod building_rpc;
+pub mod schedule_rpc;
+pub mod subject_rpc;
+pub mod substitution_rpc;
+pub mod user_rpc;
+
+use crate::router::RpcRouter;
+
+/// All the rpc methods, as served on `/api/rpc`
+/// (and described by `rpc.discover`).
+pub fn all_rpc_router() -> RpcRouter {
+    RpcRouter::new()
+        .extend(user_rpc::rpc_router())
+        .extend(department_rpc::rpc_router())
+        .extend(subject_rpc::rpc_router())
+        .extend(classroom_rpc::rpc_router())
+        .extend(schedule_rpc::rpc_router())
+        .extend(schedule_hour_rpc::rpc_router())
+        .extend(center_schedule_hour_rpc::rpc_router())
+        .extend(group_rpc::rpc_router())
+        .extend(building_rpc::rpc_router())
+        .extend(classroom_type_rpc::rpc_router())
+        .extend(audit_log_rpc::rpc_router())
+        .extend(announcement_rpc::rpc_router())
+        .extend(display_token_rpc::rpc_router())
+        .extend(notification_rpc::rpc_router())
+        .extend(push_rpc::rpc_router())
+        .extend(calendar_rpc::rpc_router())
+        .extend(import_rpc::rpc_router())
+        .extend(center_rpc::rpc_router())
+        .extend(substitution_rpc::rpc_router())
+        .extend(lesson_plan_rpc::rpc_router())
+}
@@ -0,0 +1,38 @@
+use lib_core::ctx::Ctx;
+use lib_core::model::notification::{
+    NotificationBmc, NotificationPrefs, NotificationPrefsForUpdate,
+};
+use lib_core::model::ModelManager;
+
+use crate::router::RpcRouter;
+use crate::rpc_router;
+use crate::ParamsForCreate;
+use crate::Result;
+
+pub fn rpc_router() -> RpcRouter {
+    rpc_router!(
+        // Same as RpcRouter::new().add...
+        get_notification_prefs,
+        update_notification_prefs,
+    )
+}
+
+/// The current user email notification preferences.
+pub async fn get_notification_prefs(ctx: Ctx, mm: ModelManager) -> Result<NotificationPrefs> {
+    let prefs = NotificationBmc::get_prefs(&ctx, &mm, ctx.user_id()).await?;
+
+    Ok(prefs)
+}
+
+pub async fn update_notification_prefs(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<NotificationPrefsForUpdate>,
+) -> Result<NotificationPrefs> {
+    let ParamsForCreate { data } = params;
+
+    NotificationBmc::update_prefs(&ctx, &mm, ctx.user_id(), data).await?;
+    let prefs = NotificationBmc::get_prefs(&ctx, &mm, ctx.user_id()).await?;
+
+    Ok(prefs)
+}
@@ -0,0 +1,61 @@
+use lib_core::ctx::Ctx;
+use lib_core::model::push_subscription::{
+    PushSubscription, PushSubscriptionBmc, PushSubscriptionForCreate,
+};
+use lib_core::model::ModelManager;
+
+use crate::router::RpcRouter;
+use crate::rpc_router;
+use crate::Result;
+use crate::{ParamsForCreate, ParamsIded};
+
+pub fn rpc_router() -> RpcRouter {
+    rpc_router!(
+        // Same as RpcRouter::new().add...
+        get_vapid_public_key,
+        subscribe_push,
+        list_push_subscriptions,
+        unsubscribe_push,
+    )
+}
+
+/// The `applicationServerKey` of the PWA `pushManager.subscribe`.
+pub async fn get_vapid_public_key(_ctx: Ctx, _mm: ModelManager) -> Result<String> {
+    let public_key = lib_push::vapid_public_key()?;
+
+    Ok(public_key)
+}
+
+/// Subscribes the current user device.
+pub async fn subscribe_push(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<PushSubscriptionForCreate>,
+) -> Result<PushSubscription> {
+    let ParamsForCreate { data } = params;
+
+    let id = PushSubscriptionBmc::subscribe(&ctx, &mm, ctx.user_id(), data).await?;
+    let push_subscription = PushSubscriptionBmc::get(&ctx, &mm, id).await?;
+
+    Ok(push_subscription)
+}
+
+/// The current user devices.
+pub async fn list_push_subscriptions(ctx: Ctx, mm: ModelManager) -> Result<Vec<PushSubscription>> {
+    let push_subscriptions = PushSubscriptionBmc::list_for_user(&ctx, &mm, ctx.user_id()).await?;
+
+    Ok(push_subscriptions)
+}
+
+pub async fn unsubscribe_push(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<PushSubscription> {
+    let ParamsIded { id } = params;
+
+    let push_subscription = PushSubscriptionBmc::get(&ctx, &mm, id).await?;
+    PushSubscriptionBmc::unsubscribe(&ctx, &mm, ctx.user_id(), id).await?;
+
+    Ok(push_subscription)
+}
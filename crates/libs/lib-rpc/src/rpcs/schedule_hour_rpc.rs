@@ -1,24 +1,31 @@
-use modql::filter::{ListOptions, OpValsInt64};
 use lib_core::ctx::Ctx;
-use lib_core::model::ModelManager;
 use lib_core::model::schedule::ScheduleBmc;
-use lib_core::model::schedule_hour::{ScheduleHour, ScheduleHourBmc, ScheduleHourFilter, ScheduleHourForCreate, ScheduleHourForUpdate};
+use lib_core::model::schedule_hour::{
+    ScheduleHour, ScheduleHourBmc, ScheduleHourFilter, ScheduleHourForCreate, ScheduleHourForUpdate,
+};
+use lib_core::model::ModelManager;
+use modql::filter::{ListOptions, OpValsInt64};
 
-use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
-use crate::Error::UserNotAdmin;
-use crate::Result;
 use crate::router::RpcRouter;
 use crate::rpc_router;
+use crate::Result;
+use crate::{
+    BulkItemResult, ParamsForCreate, ParamsForCreateMany, ParamsForUpdate, ParamsForUpdateMany,
+    ParamsIded, ParamsIdedMany, ParamsList,
+};
 
 pub fn rpc_router() -> RpcRouter {
     rpc_router!(
         // Same as RpcRouter::new().add...
-        create_schedule_hour,
-        get_schedule_hour,
+        create_schedule_hour: Admin,
+        get_schedule_hour: Admin,
         get_user_schedule_hours,
         list_schedule_hours,
-        update_schedule_hour,
-        delete_schedule_hour,
+        update_schedule_hour: Admin,
+        delete_schedule_hour: Admin,
+        create_schedule_hours: Admin,
+        update_schedule_hours: Admin,
+        delete_schedule_hours: Admin,
     )
 }
 
@@ -27,7 +34,6 @@ pub async fn create_schedule_hour(
     mm: ModelManager,
     params: ParamsForCreate<ScheduleHourForCreate>,
 ) -> Result<ScheduleHour> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
     let ParamsForCreate { data } = params;
     let id = ScheduleHourBmc::create(&ctx, &mm, data.clone()).await?;
     let schedule_hour = ScheduleHourBmc::get(&ctx, &mm, id).await?;
@@ -35,23 +41,39 @@ pub async fn create_schedule_hour(
     Ok(schedule_hour)
 }
 
-pub async fn get_schedule_hour(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<ScheduleHour> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
+pub async fn get_schedule_hour(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<ScheduleHour> {
     let ParamsIded { id } = params;
     let schedule_hour = ScheduleHourBmc::get(&ctx, &mm, id).await?;
     Ok(schedule_hour)
 }
-pub async fn get_user_schedule_hours(ctx: Ctx, mm: ModelManager) -> Result<Vec<ScheduleHour>>{
+pub async fn get_user_schedule_hours(ctx: Ctx, mm: ModelManager) -> Result<Vec<ScheduleHour>> {
     let schedules = ScheduleBmc::get_teacher_schedule(&ctx, &mm, ctx.user_id()).await?;
     let schedule = schedules.first().unwrap().clone();
 
     let filters = Some(vec![ScheduleHourFilter {
         id: None,
         schedule_id: Some(OpValsInt64::from(schedule.id)),
-        classroom_name: None, subject_name: None, week_day: None, course: None, n_hour: None, notes: None, cid: None, ctime: None, mid: None, mtime: None
+        classroom_name: None,
+        subject_name: None,
+        week_day: None,
+        course: None,
+        n_hour: None,
+        notes: None,
+        cid: None,
+        ctime: None,
+        mid: None,
+        mtime: None,
     }]);
 
-    let list_options = Some(ListOptions { limit: None, offset: None, order_bys: None });
+    let list_options = Some(ListOptions {
+        limit: None,
+        offset: None,
+        order_bys: None,
+    });
 
     let schedule_hours = ScheduleHourBmc::list(&ctx, &mm, filters, list_options).await?;
     Ok(schedule_hours)
@@ -63,7 +85,8 @@ pub async fn list_schedule_hours(
     params: ParamsList<ScheduleHourFilter>,
 ) -> Result<Vec<ScheduleHour>> {
     // if !&ctx.admin() { return Err(UserNotAdmin); }
-    let schedule_hours = ScheduleHourBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+    let schedule_hours =
+        ScheduleHourBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
 
     Ok(schedule_hours)
 }
@@ -73,18 +96,24 @@ pub async fn update_schedule_hour(
     mm: ModelManager,
     params: ParamsForUpdate<ScheduleHourForUpdate>,
 ) -> Result<ScheduleHour> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
-    let ParamsForUpdate { id, data } = params;
+    let ParamsForUpdate {
+        id,
+        data,
+        expected_mtime,
+    } = params;
 
-    ScheduleHourBmc::update(&ctx, &mm, id, data).await?;
+    ScheduleHourBmc::update_if_unmodified(&ctx, &mm, id, data, expected_mtime).await?;
 
     let schedule_hour = ScheduleHourBmc::get(&ctx, &mm, id).await?;
 
     Ok(schedule_hour)
 }
 
-pub async fn delete_schedule_hour(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<ScheduleHour> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
+pub async fn delete_schedule_hour(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<ScheduleHour> {
     let ParamsIded { id } = params;
 
     let schedule_hour = ScheduleHourBmc::get(&ctx, &mm, id).await?;
@@ -92,3 +121,79 @@ pub async fn delete_schedule_hour(ctx: Ctx, mm: ModelManager, params: ParamsIded
 
     Ok(schedule_hour)
 }
+
+// region:    --- Bulk
+
+pub async fn create_schedule_hours(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreateMany<ScheduleHourForCreate>,
+) -> Result<Vec<BulkItemResult<ScheduleHour>>> {
+    let ParamsForCreateMany { data, mode } = params;
+
+    let results = ScheduleHourBmc::create_many(&ctx, &mm, data, mode).await?;
+
+    let mut items = Vec::with_capacity(results.len());
+    for (index, res) in results.into_iter().enumerate() {
+        let res = match res {
+            Ok(id) => ScheduleHourBmc::get(&ctx, &mm, id).await,
+            Err(err) => Err(err),
+        };
+        items.push(BulkItemResult::new(index, res));
+    }
+
+    Ok(items)
+}
+
+pub async fn update_schedule_hours(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForUpdateMany<ScheduleHourForUpdate>,
+) -> Result<Vec<BulkItemResult<ScheduleHour>>> {
+    let ParamsForUpdateMany { data, mode } = params;
+
+    let ids: Vec<i64> = data.iter().map(|item| item.id).collect();
+    let data = data
+        .into_iter()
+        .map(
+            |ParamsForUpdate {
+                 id,
+                 data,
+                 expected_mtime,
+             }| (id, data, expected_mtime),
+        )
+        .collect();
+    let results = ScheduleHourBmc::update_many(&ctx, &mm, data, mode).await?;
+
+    let mut items = Vec::with_capacity(results.len());
+    for (index, (id, res)) in ids.into_iter().zip(results).enumerate() {
+        let res = match res {
+            Ok(()) => ScheduleHourBmc::get(&ctx, &mm, id).await,
+            Err(err) => Err(err),
+        };
+        items.push(BulkItemResult::new(index, res));
+    }
+
+    Ok(items)
+}
+
+pub async fn delete_schedule_hours(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIdedMany,
+) -> Result<Vec<BulkItemResult<i64>>> {
+    let ParamsIdedMany { ids, mode } = params;
+
+    let results = ScheduleHourBmc::delete_many(&ctx, &mm, ids.clone(), mode).await?;
+
+    let items = ids
+        .into_iter()
+        .zip(results)
+        .enumerate()
+        .map(|(index, (id, res))| BulkItemResult::new(index, res.map(|()| id)))
+        .collect();
+
+    Ok(items)
+}
+
+// endregion: --- Bulk
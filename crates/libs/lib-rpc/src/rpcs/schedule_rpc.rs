@@ -1,21 +1,22 @@
 use lib_core::ctx::Ctx;
+use lib_core::model::schedule::{
+    Schedule, ScheduleBmc, ScheduleFilter, ScheduleForCreate, ScheduleForUpdate,
+};
 use lib_core::model::ModelManager;
-use lib_core::model::schedule::{Schedule, ScheduleBmc, ScheduleFilter, ScheduleForCreate, ScheduleForUpdate};
 
-use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
-use crate::Error::UserNotAdmin;
-use crate::Result;
 use crate::router::RpcRouter;
 use crate::rpc_router;
+use crate::Result;
+use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
 
 pub fn rpc_router() -> RpcRouter {
     rpc_router!(
         // Same as RpcRouter::new().add...
-        create_schedule,
-        get_schedule,
-        list_schedules,
-        update_schedule,
-        delete_schedule,
+        create_schedule: Admin,
+        get_schedule: Admin,
+        list_schedules: Admin,
+        update_schedule: Admin,
+        delete_schedule: Admin,
         get_user_schedule,
     )
 }
@@ -25,7 +26,6 @@ pub async fn create_schedule(
     mm: ModelManager,
     params: ParamsForCreate<ScheduleForCreate>,
 ) -> Result<Schedule> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
     let ParamsForCreate { data } = params;
     let id = ScheduleBmc::create(&ctx, &mm, data.clone()).await?;
     let schedule = ScheduleBmc::get(&ctx, &mm, id).await?;
@@ -34,7 +34,6 @@ pub async fn create_schedule(
 }
 
 pub async fn get_schedule(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Schedule> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
     let ParamsIded { id } = params;
 
     let schedule = ScheduleBmc::get(&ctx, &mm, id).await?;
@@ -47,13 +46,11 @@ pub async fn get_user_schedule(ctx: Ctx, mm: ModelManager) -> Result<Schedule> {
     Ok(schedule)
 }
 
-
 pub async fn list_schedules(
     ctx: Ctx,
     mm: ModelManager,
     params: ParamsList<ScheduleFilter>,
 ) -> Result<Vec<Schedule>> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
     let schedules = ScheduleBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
 
     Ok(schedules)
@@ -64,10 +61,13 @@ pub async fn update_schedule(
     mm: ModelManager,
     params: ParamsForUpdate<ScheduleForUpdate>,
 ) -> Result<Schedule> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
-    let ParamsForUpdate { id, data } = params;
+    let ParamsForUpdate {
+        id,
+        data,
+        expected_mtime,
+    } = params;
 
-    ScheduleBmc::update(&ctx, &mm, id, data).await?;
+    ScheduleBmc::update_if_unmodified(&ctx, &mm, id, data, expected_mtime).await?;
 
     let schedule = ScheduleBmc::get(&ctx, &mm, id).await?;
 
@@ -75,7 +75,6 @@ pub async fn update_schedule(
 }
 
 pub async fn delete_schedule(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Schedule> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
     let ParamsIded { id } = params;
 
     let schedule = ScheduleBmc::get(&ctx, &mm, id).await?;
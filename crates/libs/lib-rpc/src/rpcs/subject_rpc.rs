@@ -1,21 +1,23 @@
 use lib_core::ctx::Ctx;
+use lib_core::model::subject::{
+    Subject, SubjectBmc, SubjectFilter, SubjectForCreate, SubjectForUpdate,
+};
 use lib_core::model::ModelManager;
-use lib_core::model::subject::{Subject, SubjectBmc, SubjectFilter, SubjectForCreate, SubjectForUpdate};
 
-use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
-use crate::Error::UserNotAdmin;
-use crate::Result;
 use crate::router::RpcRouter;
 use crate::rpc_router;
+use crate::{Error, Result};
+use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
 
 pub fn rpc_router() -> RpcRouter {
     rpc_router!(
         // Same as RpcRouter::new().add...
-        create_subject,
-        get_subject,
-        list_subjects,
-        update_subject,
-        delete_subject,
+        create_subject: Admin,
+        get_subject: Admin,
+        list_subjects: Admin,
+        update_subject: Admin,
+        delete_subject: Admin,
+        restore_subject: Admin,
     )
 }
 
@@ -24,7 +26,6 @@ pub async fn create_subject(
     mm: ModelManager,
     params: ParamsForCreate<SubjectForCreate>,
 ) -> Result<Subject> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
     let ParamsForCreate { data } = params;
 
     let id = SubjectBmc::create(&ctx, &mm, data).await?;
@@ -34,7 +35,6 @@ pub async fn create_subject(
 }
 
 pub async fn get_subject(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Subject> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
     let ParamsIded { id } = params;
 
     let subject = SubjectBmc::get(&ctx, &mm, id).await?;
@@ -47,8 +47,14 @@ pub async fn list_subjects(
     mm: ModelManager,
     params: ParamsList<SubjectFilter>,
 ) -> Result<Vec<Subject>> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
-    let subjects = SubjectBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+    if params.include_deleted && !ctx.admin() {
+        return Err(Error::UserNotAdmin);
+    }
+    let subjects = if params.include_deleted {
+        SubjectBmc::list_including_deleted(&ctx, &mm, params.filters, params.list_options).await?
+    } else {
+        SubjectBmc::list(&ctx, &mm, params.filters, params.list_options).await?
+    };
 
     Ok(subjects)
 }
@@ -58,10 +64,13 @@ pub async fn update_subject(
     mm: ModelManager,
     params: ParamsForUpdate<SubjectForUpdate>,
 ) -> Result<Subject> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
-    let ParamsForUpdate { id, data } = params;
+    let ParamsForUpdate {
+        id,
+        data,
+        expected_mtime,
+    } = params;
 
-    SubjectBmc::update(&ctx, &mm, id, data).await?;
+    SubjectBmc::update_if_unmodified(&ctx, &mm, id, data, expected_mtime).await?;
 
     let subject = SubjectBmc::get(&ctx, &mm, id).await?;
 
@@ -69,7 +78,6 @@ pub async fn update_subject(
 }
 
 pub async fn delete_subject(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Subject> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
     let ParamsIded { id } = params;
 
     let subject = SubjectBmc::get(&ctx, &mm, id).await?;
@@ -77,3 +85,12 @@ pub async fn delete_subject(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> R
 
     Ok(subject)
 }
+
+pub async fn restore_subject(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Subject> {
+    let ParamsIded { id } = params;
+
+    SubjectBmc::restore(&ctx, &mm, id).await?;
+    let subject = SubjectBmc::get(&ctx, &mm, id).await?;
+
+    Ok(subject)
+}
@@ -0,0 +1,116 @@
+use lib_core::ctx::Ctx;
+use lib_core::model::substitution::{
+    AbsentSlot, DayUncoveredHours, Substitution, SubstitutionBmc, SubstitutionFilter,
+    SubstitutionForCreate, SubstitutionForUpdate, SubstitutionPeriod, TeacherMonthSubstitutions,
+};
+use lib_core::model::ModelManager;
+
+use crate::router::RpcRouter;
+use crate::rpc_router;
+use crate::Result;
+use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList};
+
+pub fn rpc_router() -> RpcRouter {
+    rpc_router!(
+        // Same as RpcRouter::new().add...
+        create_substitution: Admin,
+        list_substitutions,
+        update_substitution: Admin,
+        delete_substitution: Admin,
+        substitutions_per_teacher_month: Admin,
+        uncovered_hours_per_day: Admin,
+        most_absent_slots: Admin,
+    )
+}
+
+/// Plans a substitution (`planned` status).
+pub async fn create_substitution(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<SubstitutionForCreate>,
+) -> Result<Substitution> {
+    let ParamsForCreate { data } = params;
+
+    let id = SubstitutionBmc::create(&ctx, &mm, data).await?;
+    let substitution = SubstitutionBmc::get(&ctx, &mm, id).await?;
+
+    Ok(substitution)
+}
+
+pub async fn list_substitutions(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsList<SubstitutionFilter>,
+) -> Result<Vec<Substitution>> {
+    let substitutions =
+        SubstitutionBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+
+    Ok(substitutions)
+}
+
+pub async fn update_substitution(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForUpdate<SubstitutionForUpdate>,
+) -> Result<Substitution> {
+    let ParamsForUpdate {
+        id,
+        data,
+        expected_mtime,
+    } = params;
+
+    SubstitutionBmc::update_if_unmodified(&ctx, &mm, id, data, expected_mtime).await?;
+
+    let substitution = SubstitutionBmc::get(&ctx, &mm, id).await?;
+
+    Ok(substitution)
+}
+
+pub async fn delete_substitution(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<Substitution> {
+    let ParamsIded { id } = params;
+
+    let substitution = SubstitutionBmc::get(&ctx, &mm, id).await?;
+    SubstitutionBmc::delete(&ctx, &mm, id).await?;
+
+    Ok(substitution)
+}
+
+pub async fn substitutions_per_teacher_month(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<SubstitutionPeriod>,
+) -> Result<Vec<TeacherMonthSubstitutions>> {
+    let ParamsForCreate { data } = params;
+
+    let rows = SubstitutionBmc::per_teacher_month(&ctx, &mm, data).await?;
+
+    Ok(rows)
+}
+
+pub async fn uncovered_hours_per_day(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<SubstitutionPeriod>,
+) -> Result<Vec<DayUncoveredHours>> {
+    let ParamsForCreate { data } = params;
+
+    let rows = SubstitutionBmc::uncovered_per_day(&ctx, &mm, data).await?;
+
+    Ok(rows)
+}
+
+pub async fn most_absent_slots(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsForCreate<SubstitutionPeriod>,
+) -> Result<Vec<AbsentSlot>> {
+    let ParamsForCreate { data } = params;
+
+    let rows = SubstitutionBmc::most_absent_slots(&ctx, &mm, data).await?;
+
+    Ok(rows)
+}
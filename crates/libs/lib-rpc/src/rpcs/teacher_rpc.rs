@@ -57,7 +57,7 @@ pub async fn update_teacher(
     mm: ModelManager,
     params: ParamsForUpdate<TeacherForUpdate>,
 ) -> Result<Teacher> {
-    let ParamsForUpdate { id, data } = params;
+    let ParamsForUpdate { id, data, .. } = params;
 
     TeacherBmc::update(&ctx, &mm, id, data).await?;
 
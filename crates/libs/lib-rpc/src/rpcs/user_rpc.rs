@@ -1,26 +1,28 @@
 use chrono::{Datelike, Utc};
-use log::debug;
 use lib_core::ctx::Ctx;
-use lib_core::model::ModelManager;
 use lib_core::model::schedule::{ScheduleBmc, ScheduleForCreate};
-use lib_core::model::user::{User, UserBmc, UserFilter, UserForCreate, UserForUpdate, UserForUpdatePwd};
+use lib_core::model::user::{
+    User, UserBmc, UserFilter, UserForCreate, UserForUpdate, UserForUpdatePwd,
+};
+use lib_core::model::ModelManager;
+use log::debug;
 
-use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsIdedString, ParamsList};
-use crate::Error::UserNotAdmin;
-use crate::Result;
 use crate::router::RpcRouter;
 use crate::rpc_router;
+use crate::{Error, Result};
+use crate::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsIdedString, ParamsList};
 
 pub fn rpc_router() -> RpcRouter {
     rpc_router!(
         // Same as RpcRouter::new().add...
-        create_user,
-        get_user,
+        create_user: Admin,
+        get_user: Admin,
         get_current_user,
-        list_users,
-        update_user,
-        update_user_pwd,
-        delete_user,
+        list_users: Admin,
+        update_user: Admin,
+        update_user_pwd: Admin,
+        delete_user: Admin,
+        restore_user: Admin,
         check_duplicate_username,
         user_checkin,
         user_checkout,
@@ -34,14 +36,17 @@ pub async fn create_user(
     mm: ModelManager,
     params: ParamsForCreate<UserForCreate>,
 ) -> Result<User> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
     let ParamsForCreate { data } = params;
 
+    // User, password and schedule are all-or-nothing.
+    let mm = mm.new_with_txn();
+    mm.begin_txn().await?;
+
     let id = UserBmc::create(&ctx, &mm, data.clone()).await?;
     let pwd = data.pwd;
     UserBmc::update_pwd(&ctx, &mm, id, &pwd).await?;
     let user = UserBmc::get(&ctx, &mm, id).await?;
-    
+
     let current_year = Utc::now().year();
 
     let schedule = ScheduleForCreate {
@@ -50,12 +55,13 @@ pub async fn create_user(
         course: current_year,
     };
     ScheduleBmc::create(&ctx, &mm, schedule).await?;
-    
+
+    mm.commit_txn().await?;
+
     Ok(user)
 }
 
 pub async fn get_user(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<User> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
     let ParamsIded { id } = params;
 
     let user = UserBmc::get(&ctx, &mm, id).await?;
@@ -68,8 +74,14 @@ pub async fn list_users(
     mm: ModelManager,
     params: ParamsList<UserFilter>,
 ) -> Result<Vec<User>> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
-    let users = UserBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
+    if params.include_deleted && !ctx.admin() {
+        return Err(Error::UserNotAdmin);
+    }
+    let users = if params.include_deleted {
+        UserBmc::list_including_deleted(&ctx, &mm, params.filters, params.list_options).await?
+    } else {
+        UserBmc::list(&ctx, &mm, params.filters, params.list_options).await?
+    };
 
     Ok(users)
 }
@@ -79,10 +91,13 @@ pub async fn update_user(
     mm: ModelManager,
     params: ParamsForUpdate<UserForUpdate>,
 ) -> Result<User> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
-    let ParamsForUpdate { id, data } = params;
+    let ParamsForUpdate {
+        id,
+        data,
+        expected_mtime,
+    } = params;
 
-    UserBmc::update(&ctx, &mm, id, data.clone()).await?;
+    UserBmc::update_if_unmodified(&ctx, &mm, id, data.clone(), expected_mtime).await?;
     // let pwd = data.pwd;
     // UserBmc::update_pwd(&ctx, &mm, id, &pwd).await?;
     let user = UserBmc::get(&ctx, &mm, id).await?;
@@ -90,28 +105,19 @@ pub async fn update_user(
     Ok(user)
 }
 
-pub async fn get_current_user(
-    ctx: Ctx,
-    mm: ModelManager,
-) -> Result<User> {
+pub async fn get_current_user(ctx: Ctx, mm: ModelManager) -> Result<User> {
     let user = UserBmc::get_current(&ctx, &mm).await?;
     Ok(user)
 }
 
-pub async fn user_checkin(
-    ctx: Ctx,
-    mm: ModelManager,
-) -> Result<User> {
+pub async fn user_checkin(ctx: Ctx, mm: ModelManager) -> Result<User> {
     UserBmc::update_checkin(&ctx, &mm, true).await?;
     let user = UserBmc::get(&ctx, &mm, ctx.user_id()).await?;
 
     Ok(user)
 }
 
-pub async fn user_checkout(
-    ctx: Ctx,
-    mm: ModelManager,
-) -> Result<User> {
+pub async fn user_checkout(ctx: Ctx, mm: ModelManager) -> Result<User> {
     UserBmc::update_checkin(&ctx, &mm, false).await?;
     let user = UserBmc::get(&ctx, &mm, ctx.user_id()).await?;
 
@@ -123,37 +129,41 @@ pub async fn update_user_pwd(
     mm: ModelManager,
     params: ParamsForUpdate<UserForUpdatePwd>,
 ) -> Result<User> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
-    let ParamsForUpdate { id, data } = params;
+    let ParamsForUpdate { id, data, .. } = params;
 
     let user_for_update = UserForUpdate {
         username: data.username,
         is_admin: data.is_admin,
+        is_head_of_studies: data.is_head_of_studies,
         active: data.active,
         department_id: data.department_id,
         last_checkin: data.last_checkin,
         last_checkout: data.last_checkout,
         in_center: data.in_center,
         substituting_id: data.substituting_id,
-        substitutions: data.substitutions
+        substitutions: data.substitutions,
     };
 
-    if data.department_id.is_some(){
-        debug!("Departamento: {}", data.department_id.unwrap())
+    if let Some(department_id) = data.department_id {
+        debug!("Departamento: {department_id:?}")
     }
 
+    // User and password are all-or-nothing.
+    let mm = mm.new_with_txn();
+    mm.begin_txn().await?;
+
     UserBmc::update(&ctx, &mm, id, user_for_update).await?;
-    let pwd = data.pwd;
-    if pwd != "" {
+    if let Some(pwd) = data.pwd.filter(|pwd| !pwd.is_empty()) {
         UserBmc::update_pwd(&ctx, &mm, id, &pwd).await?;
     }
     let user = UserBmc::get(&ctx, &mm, id).await?;
 
+    mm.commit_txn().await?;
+
     Ok(user)
 }
 
 pub async fn delete_user(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<User> {
-    if !&ctx.admin() { return Err(UserNotAdmin); }
     let ParamsIded { id } = params;
 
     let user = UserBmc::get(&ctx, &mm, id).await?;
@@ -162,6 +172,15 @@ pub async fn delete_user(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Resu
     Ok(user)
 }
 
+pub async fn restore_user(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<User> {
+    let ParamsIded { id } = params;
+
+    UserBmc::restore(&ctx, &mm, id).await?;
+    let user = UserBmc::get(&ctx, &mm, id).await?;
+
+    Ok(user)
+}
+
 pub async fn check_duplicate_username(
     ctx: Ctx,
     mm: ModelManager,
@@ -171,7 +190,6 @@ pub async fn check_duplicate_username(
     Ok(user.is_some())
 }
 
-
 pub async fn count_users_by_department(
     ctx: Ctx,
     mm: ModelManager,
@@ -190,4 +208,4 @@ pub async fn users_by_department(
     let ParamsIded { id } = params;
     let teacher_number = UserBmc::users_by_department(&ctx, &mm, id).await?;
     Ok(teacher_number)
-}
\ No newline at end of file
+}
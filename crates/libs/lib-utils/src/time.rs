@@ -1,5 +1,5 @@
-use time::{Duration, OffsetDateTime};
 pub use time::format_description::well_known::Rfc3339;
+use time::{Duration, OffsetDateTime};
 
 pub fn now_utc() -> OffsetDateTime {
     OffsetDateTime::now_utc()
@@ -14,6 +14,9 @@ pub fn now_utc_plus_sec_str(sec: f64) -> String {
     format_time(new_time)
 }
 
+// `YYYY-MM-DD` serde of `time::Date`, e.g., `#[serde(with = "lib_utils::time::iso_date")]`.
+time::serde::format_description!(pub iso_date, Date, "[year]-[month]-[day]");
+
 pub fn parse_utc(moment: &str) -> Result<OffsetDateTime> {
     OffsetDateTime::parse(moment, &Rfc3339).map_err(|_| Error::FailToDateParse(moment.to_string()))
 }
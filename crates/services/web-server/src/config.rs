@@ -14,12 +14,39 @@ pub fn web_config() -> &'static WebConfig {
 #[allow(non_snake_case)]
 pub struct WebConfig {
     pub WEB_FOLDER: String,
+
+    // -- Printed timetables
+    pub SCHOOL_NAME: String,
+    /// PNG or JPEG, empty for no logo.
+    pub SCHOOL_LOGO_PATH: String,
+
+    // -- OpenID Connect (disabled when `OIDC_ISSUER_URL` is empty)
+    /// e.g., "https://accounts.google.com"
+    /// or "https://login.microsoftonline.com/{tenant}/v2.0"
+    pub OIDC_ISSUER_URL: String,
+    pub OIDC_CLIENT_ID: String,
+    pub OIDC_CLIENT_SECRET: String,
+    /// Must point to `/api/oidc/callback` and be registered in the provider.
+    pub OIDC_REDIRECT_URL: String,
+    /// Id token claim mapped to `users.username`, "email" or "preferred_username".
+    pub OIDC_USERNAME_CLAIM: String,
 }
 
 impl WebConfig {
     fn load_from_env() -> lib_utils::envs::Result<WebConfig> {
         Ok(WebConfig {
             WEB_FOLDER: get_env("SERVICE_WEB_FOLDER")?,
+
+            // -- Printed timetables
+            SCHOOL_NAME: get_env("SERVICE_SCHOOL_NAME")?,
+            SCHOOL_LOGO_PATH: get_env("SERVICE_SCHOOL_LOGO_PATH")?,
+
+            // -- OpenID Connect
+            OIDC_ISSUER_URL: get_env("SERVICE_OIDC_ISSUER_URL")?,
+            OIDC_CLIENT_ID: get_env("SERVICE_OIDC_CLIENT_ID")?,
+            OIDC_CLIENT_SECRET: get_env("SERVICE_OIDC_CLIENT_SECRET")?,
+            OIDC_REDIRECT_URL: get_env("SERVICE_OIDC_REDIRECT_URL")?,
+            OIDC_USERNAME_CLAIM: get_env("SERVICE_OIDC_USERNAME_CLAIM")?,
         })
     }
 }
@@ -9,6 +9,10 @@ pub enum Error {
     // -- Modules
     #[from]
     Model(model::Error),
+    #[from]
+    Mail(lib_mail::Error),
+    #[from]
+    Push(lib_push::Error),
 }
 
 // region:    --- Error Boilerplate
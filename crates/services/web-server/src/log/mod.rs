@@ -8,10 +8,10 @@ use tracing::debug;
 use lib_core::ctx::Ctx;
 use lib_utils::time::{format_time, now_utc};
 
-use crate::Result;
-use crate::web::{self, ClientError};
 use crate::web::mw_stamp::ReqStamp;
 use crate::web::routes_rpc::RpcInfo;
+use crate::web::{self, ClientError};
+use crate::Result;
 
 pub async fn log_request(
     http_method: Method,
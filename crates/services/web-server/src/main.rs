@@ -2,41 +2,58 @@
 
 // region:    --- Modules
 
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration as StdDuration;
 
-use axum::{middleware, Router};
 use axum::http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_TYPE};
 use axum::http::Method;
+use axum::{middleware, Router};
 // endregion: --- Modules
 use chrono::{Duration, Timelike, Utc};
 use clokwerk::{Scheduler, TimeUnits};
 use tokio::net::TcpListener;
 use tower_cookies::CookieManagerLayer;
 use tower_http::cors::CorsLayer;
-use tracing::info;
+use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
 
 use config::web_config;
 use lib_core::_dev_utils;
 use lib_core::ctx::Ctx;
+use lib_core::model::center::CenterBmc;
 use lib_core::model::control::ControlBmc;
+use lib_core::model::leader::{LeaderLock, SCHEDULER_LOCK_KEY};
+use lib_core::model::notification::NotificationBmc;
 use lib_core::model::ModelManager;
+use lib_mail::Mailer;
 
-use crate::web::{routes_login, routes_static};
-use crate::web::mw_auth::{ mw_ctx_require, mw_ctx_resolve};
+use crate::web::mw_auth::{mw_ctx_require, mw_ctx_resolve};
 use crate::web::mw_res_map::mw_reponse_map;
 use crate::web::mw_stamp::mw_req_stamp;
+use crate::web::routes_oidc::{OidcConfig, OidcState};
 use crate::web::routes_rpc::RpcState;
+use crate::web::{routes_login, routes_oidc, routes_static};
 
 pub use self::error::Result;
 
 mod config;
 mod error;
 mod log;
+mod push;
 mod web;
 
-fn iniciar_programador_tareas() {
+/// Outbox mails sent per scheduler run.
+const MAIL_BATCH_SIZE: i64 = 50;
+
+/// Note: Uses the server `ModelManager` (and runtime), so its events reach the `/api/ws` subscribers.
+///       With several instances, only the `LeaderLock` holder runs the jobs.
+fn iniciar_programador_tareas(
+    mm: ModelManager,
+    mailer: impl Mailer + 'static,
+    leader: Arc<LeaderLock>,
+    handle: tokio::runtime::Handle,
+) {
     let mut scheduler = Scheduler::new();
 
     // Supongamos que quieres que la tarea se ejecute todos los días a las 03:00
@@ -54,34 +71,77 @@ fn iniciar_programador_tareas() {
         println!("Ejecutando tarea diaria...");
     });
 
+    // Avisos de salida no registrada, al final de la jornada
+    let (checkout_leader, checkout_mm, checkout_handle) =
+        (leader.clone(), mm.clone(), handle.clone());
+    scheduler.every(1.day()).at("10:00 pm").run(move || {
+        checkout_handle.block_on(async {
+            if !is_scheduler_leader(&checkout_leader).await {
+                return;
+            }
+            let ctx = Ctx::root_ctx();
+            if let Err(err) = NotificationBmc::enqueue_missing_checkouts(&ctx, &checkout_mm).await {
+                error!("{:<12} - enqueue_missing_checkouts - {err:?}", "SCHEDULER");
+            }
+        });
+    });
+
     // Programa la tarea recurrente para que se ejecute cada 24 horas
     scheduler.every(10.seconds()).run(move || {
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        rt.block_on(async {
+        handle.block_on(async {
+            if !is_scheduler_leader(&leader).await {
+                return;
+            }
             println!("Ejecutando tarea diaria...");
-            let mm = ModelManager::new().await.unwrap();
-            let ctx = Ctx::root_ctx(); // o Ctx::new(user_id).unwrap(); si tienes un user_id específico
-            ControlBmc::update_guards(&ctx, &mm).await.unwrap();
+            let ctx = Ctx::root_ctx(); // o Ctx::new(user_id, isadmin, center_id); si tienes un user_id específico
+            if let Err(err) = update_center_guards(&ctx, &mm).await {
+                error!("{:<12} - update_guards - {err:?}", "SCHEDULER");
+            }
+            if let Err(err) =
+                NotificationBmc::deliver_pending(&ctx, &mm, &mailer, MAIL_BATCH_SIZE).await
+            {
+                error!("{:<12} - deliver_pending - {err:?}", "SCHEDULER");
+            }
             // for hour in hours {
             //     println!("{}", hour.start_time)
             // }
         });
-        
     });
 
-
     // Ejecuta el programador en un nuevo hilo
-    let _thread_handle = thread::spawn(move || {
-        loop {
-            scheduler.run_pending();
-            thread::sleep(StdDuration::from_secs(10));
-        }
+    let _thread_handle = thread::spawn(move || loop {
+        scheduler.run_pending();
+        thread::sleep(StdDuration::from_secs(10));
     });
 }
 
+/// The guards of each center, with its own center hours.
+async fn update_center_guards(ctx: &Ctx, mm: &ModelManager) -> lib_core::model::Result<()> {
+    for center in CenterBmc::list(ctx, mm, None, None).await? {
+        let center_ctx = ctx.clone().with_center(center.id);
+        if let Err(err) = ControlBmc::update_guards(&center_ctx, mm).await {
+            error!(
+                "{:<12} - update_guards center {} - {err:?}",
+                "SCHEDULER", center.code
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn is_scheduler_leader(leader: &LeaderLock) -> bool {
+    match leader.is_leader().await {
+        Ok(leader) => leader,
+        Err(err) => {
+            error!("{:<12} - leader lock - {err:?}", "SCHEDULER");
+            false
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    iniciar_programador_tareas();
     tracing_subscriber::fmt()
         .without_time() // For early local development.
         .with_target(false)
@@ -93,11 +153,31 @@ async fn main() -> Result<()> {
 
     // Initialize ModelManager.
     let mm = ModelManager::new().await?;
+    let leader = Arc::new(LeaderLock::new(SCHEDULER_LOCK_KEY));
+    let mailer = lib_mail::get_mailer()?;
+    iniciar_programador_tareas(
+        mm.clone(),
+        mailer,
+        leader.clone(),
+        tokio::runtime::Handle::current(),
+    );
+    let push_sender = lib_push::get_push_sender()?;
+    tokio::spawn(push::push_events(mm.clone(), push_sender, leader));
 
     // -- Define Routes
     let rpc_state = RpcState { mm: mm.clone() };
     let routes_rpc =
         web::routes_rpc::routes(rpc_state).route_layer(middleware::from_fn(mw_ctx_require));
+    let routes_ws =
+        web::routes_ws::routes(mm.clone()).route_layer(middleware::from_fn(mw_ctx_require));
+    let routes_export =
+        web::routes_export::routes(mm.clone()).route_layer(middleware::from_fn(mw_ctx_require));
+    let routes_lesson_plan = web::routes_lesson_plan::routes(mm.clone())
+        .route_layer(middleware::from_fn(mw_ctx_require));
+    // Note: Display token auth, no ctx required.
+    let routes_display = web::routes_display::routes(mm.clone());
+    // Note: Calendar feed token auth, no ctx required.
+    let routes_calendar = web::routes_calendar::routes(mm.clone());
 
     let origins = [
         "http://192.168.3.3:8080".parse().unwrap(),
@@ -115,9 +195,21 @@ async fn main() -> Result<()> {
         .allow_credentials(true)
         .allow_headers([CONTENT_TYPE, ACCESS_CONTROL_ALLOW_ORIGIN]);
 
-    let routes_all = Router::new()
-        .merge(routes_login::routes(mm.clone()))
-        .nest("/api", routes_rpc)
+    let mut routes_all = Router::new().merge(routes_login::routes(mm.clone()));
+    if let Some(oidc_config) = OidcConfig::from_web_config() {
+        routes_all = routes_all.merge(routes_oidc::routes(OidcState::new(mm.clone(), oidc_config)));
+    }
+
+    let routes_all = routes_all
+        .nest(
+            "/api",
+            routes_rpc
+                .merge(routes_ws)
+                .merge(routes_export)
+                .merge(routes_lesson_plan)
+                .merge(routes_display)
+                .merge(routes_calendar),
+        )
         .layer(middleware::map_response(mw_reponse_map))
         .layer(middleware::from_fn_with_state(mm.clone(), mw_ctx_resolve))
         .layer(middleware::from_fn(mw_req_stamp))
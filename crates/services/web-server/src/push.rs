@@ -0,0 +1,101 @@
+//! Web Push of the model events to the teacher devices
+//! (see `lib_core::model::push_subscription`).
+//!
+//! - The schedule, lesson plan and devices are looked up in the event center
+//!   (the root ctx of that center), so a push never goes to another center.
+//!
+//! Note: With the `pg` event bus every instance receives the events,
+//!       so only the `LeaderLock` holder pushes them.
+
+use std::sync::Arc;
+
+use time::OffsetDateTime;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, warn};
+
+use lib_core::ctx::Ctx;
+use lib_core::model::event::ModelEvent;
+use lib_core::model::leader::LeaderLock;
+use lib_core::model::lesson_plan::LessonPlanBmc;
+use lib_core::model::notification::Notification;
+use lib_core::model::push_subscription::PushSubscriptionBmc;
+use lib_core::model::schedule::{Schedule, ScheduleBmc};
+use lib_core::model::ModelManager;
+use lib_push::PushSender;
+
+/// Runs until the event bus is closed.
+pub async fn push_events(mm: ModelManager, sender: impl PushSender, leader: Arc<LeaderLock>) {
+    let mut events = mm.subscribe();
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("{:<12} - lagged, {skipped} events not pushed", "PUSH");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        let ModelEvent::GuardAssigned {
+            center_id,
+            guard_schedule_id,
+            covered_schedule_hour_id,
+            classroom_name,
+            week_day,
+            n_hour,
+            ..
+        } = event
+        else {
+            continue;
+        };
+
+        match leader.is_leader().await {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(err) => {
+                error!("{:<12} - leader lock - {err:?}", "PUSH");
+                continue;
+            }
+        }
+
+        let ctx = Ctx::root_ctx().with_center(center_id);
+        let today = OffsetDateTime::now_utc().date();
+        let lesson_plan =
+            match LessonPlanBmc::note(&ctx, &mm, covered_schedule_hour_id, today).await {
+                Ok(lesson_plan) => lesson_plan,
+                Err(err) => {
+                    warn!("{:<12} - lesson plan - {err:?}", "PUSH");
+                    None
+                }
+            };
+        let notification = Notification::GuardAssigned {
+            classroom_name,
+            week_day,
+            n_hour,
+            lesson_plan,
+        };
+        if let Err(err) =
+            push_to_schedule_teacher(&ctx, &mm, &sender, guard_schedule_id, &notification).await
+        {
+            error!("{:<12} - guard assigned - {err:?}", "PUSH");
+        }
+    }
+}
+
+async fn push_to_schedule_teacher(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    sender: &impl PushSender,
+    schedule_id: i64,
+    notification: &Notification,
+) -> lib_core::model::Result<()> {
+    let schedule: Schedule = ScheduleBmc::get(ctx, mm, schedule_id).await?;
+    if let Some(user_id) = schedule.user_id {
+        let pushed =
+            PushSubscriptionBmc::push_to_user(ctx, mm, sender, user_id, notification).await?;
+        debug!("{:<12} - user {user_id} - {pushed} devices", "PUSH");
+    }
+
+    Ok(())
+}
@@ -4,10 +4,12 @@ use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use derive_more::From;
 use serde::Serialize;
-use serde_with::{DisplayFromStr, serde_as};
+use serde_json::{json, to_value, Value};
+use serde_with::{serde_as, DisplayFromStr};
 use tracing::debug;
+use uuid::Uuid;
 
-use lib_auth::{pwd, token};
+use lib_auth::{authn, pwd, token};
 use lib_core::model;
 
 use crate::web;
@@ -20,12 +22,43 @@ pub type Result<T> = core::result::Result<T, Error>;
 pub enum Error {
     // -- Login
     LoginFailUsernameNotFound,
+    LoginFailCenterNotFound {
+        code: String,
+    },
     LoginFailUserHasNoPwd {
         user_id: i64,
     },
     LoginFailPwdNotMatching {
         user_id: i64,
     },
+    LoginFailAuthSourceNotConfigured {
+        user_id: i64,
+        auth_source: String,
+    },
+    LoginFailProvisionCenterNotAllowed {
+        code: String,
+    },
+
+    // -- OpenID Connect
+    OidcConfig(String),
+    OidcDiscovery(String),
+    OidcStateNotFound,
+    OidcStateMismatch,
+    OidcCodeExchange(String),
+    OidcIdTokenMissing,
+    OidcIdTokenInvalid(String),
+    OidcClaimMissing,
+    OidcEmailNotVerified,
+
+    // -- Display
+    DisplayTokenInvalid,
+
+    // -- Calendar
+    CalendarTokenInvalid,
+
+    // -- Export
+    ExportUserNotAdmin,
+    ExportLogoRead(String),
 
     // -- CtxExtError
     #[from]
@@ -34,15 +67,23 @@ pub enum Error {
     // -- ReqStamp
     ReqStampNotInResponseExt,
 
+    // -- JSON-RPC
+    RpcRequestParsing(String),
+    RpcInvalidRequest(String),
+
     // -- Modules
     #[from]
     Model(model::Error),
     #[from]
+    Authn(authn::Error),
+    #[from]
     Pwd(pwd::Error),
     #[from]
     Token(token::Error),
     #[from]
     Rpc(lib_rpc::Error),
+    #[from]
+    Export(lib_export::Error),
 
     // -- External Modules
     #[from]
@@ -86,17 +127,64 @@ impl Error {
         match self {
             // -- Login
             LoginFailUsernameNotFound
+            | LoginFailCenterNotFound { .. }
             | LoginFailUserHasNoPwd { .. }
-            | LoginFailPwdNotMatching { .. } => (StatusCode::FORBIDDEN, ClientError::LOGIN_FAIL),
+            | LoginFailPwdNotMatching { .. }
+            | LoginFailAuthSourceNotConfigured { .. }
+            | LoginFailProvisionCenterNotAllowed { .. } => {
+                (StatusCode::FORBIDDEN, ClientError::LOGIN_FAIL)
+            }
+
+            // -- OpenID Connect
+            OidcStateNotFound
+            | OidcStateMismatch
+            | OidcCodeExchange(_)
+            | OidcIdTokenMissing
+            | OidcIdTokenInvalid(_)
+            | OidcClaimMissing
+            | OidcEmailNotVerified => (StatusCode::FORBIDDEN, ClientError::LOGIN_FAIL),
 
             // -- Auth
-            CtxExt(_) => (StatusCode::FORBIDDEN, ClientError::NO_AUTH),
+            CtxExt(_) | DisplayTokenInvalid | CalendarTokenInvalid | ExportUserNotAdmin => {
+                (StatusCode::FORBIDDEN, ClientError::NO_AUTH)
+            }
+
+            // -- JSON-RPC
+            RpcRequestParsing(_) => (StatusCode::BAD_REQUEST, ClientError::RPC_PARSE_ERROR),
+            RpcInvalidRequest(_) => (StatusCode::BAD_REQUEST, ClientError::RPC_INVALID_REQUEST),
+            Rpc(lib_rpc::Error::RpcMethodUnknown(method)) => (
+                StatusCode::NOT_FOUND,
+                ClientError::RPC_METHOD_NOT_FOUND {
+                    method: method.clone(),
+                },
+            ),
+            Rpc(lib_rpc::Error::RpcIntoParamsMissing | lib_rpc::Error::SerdeJson(_)) => {
+                (StatusCode::BAD_REQUEST, ClientError::RPC_INVALID_PARAMS)
+            }
 
             // -- Model
-            Model(model::Error::EntityNotFound { entity, id }) => (
+            Model(model::Error::EntityNotFound { entity, id })
+            | Rpc(lib_rpc::Error::Model(model::Error::EntityNotFound { entity, id })) => (
                 StatusCode::BAD_REQUEST,
                 ClientError::ENTITY_NOT_FOUND { entity, id: *id },
             ),
+            Model(model::Error::ConcurrentModification {
+                entity,
+                id,
+                current,
+            })
+            | Rpc(lib_rpc::Error::Model(model::Error::ConcurrentModification {
+                entity,
+                id,
+                current,
+            })) => (
+                StatusCode::CONFLICT,
+                ClientError::CONCURRENT_MODIFICATION {
+                    entity,
+                    id: *id,
+                    current: current.clone(),
+                },
+            ),
 
             // -- Fallback.
             _ => (
@@ -113,8 +201,63 @@ impl Error {
 pub enum ClientError {
     LOGIN_FAIL,
     NO_AUTH,
-    ENTITY_NOT_FOUND { entity: &'static str, id: i64 },
+    ENTITY_NOT_FOUND {
+        entity: &'static str,
+        id: i64,
+    },
+    CONCURRENT_MODIFICATION {
+        entity: &'static str,
+        id: i64,
+        current: Value,
+    },
+
+    RPC_PARSE_ERROR,
+    RPC_INVALID_REQUEST,
+    RPC_METHOD_NOT_FOUND {
+        method: String,
+    },
+    RPC_INVALID_PARAMS,
 
     SERVICE_ERROR,
 }
+
+impl ClientError {
+    /// JSON-RPC 2.0 error code.
+    /// (-32700..-32600 reserved by the spec, -32099..-32000 for the app errors)
+    pub fn code(&self) -> i64 {
+        match self {
+            ClientError::RPC_PARSE_ERROR => -32700,
+            ClientError::RPC_INVALID_REQUEST => -32600,
+            ClientError::RPC_METHOD_NOT_FOUND { .. } => -32601,
+            ClientError::RPC_INVALID_PARAMS => -32602,
+            ClientError::SERVICE_ERROR => -32603,
+
+            ClientError::LOGIN_FAIL => -32001,
+            ClientError::NO_AUTH => -32002,
+            ClientError::ENTITY_NOT_FOUND { .. } => -32003,
+            ClientError::CONCURRENT_MODIFICATION { .. } => -32004,
+        }
+    }
+
+    /// JSON-RPC 2.0 error response object
+    /// (`id` is `null` when the request id could not be read).
+    pub fn to_rpc_response(&self, id: Option<&Value>, req_uuid: &Uuid) -> Value {
+        let client_error = to_value(self).ok();
+        let message = client_error.as_ref().and_then(|v| v.get("message"));
+        let detail = client_error.as_ref().and_then(|v| v.get("detail"));
+
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {
+                "code": self.code(),
+                "message": message, // Variant name
+                "data": {
+                    "req_uuid": req_uuid.to_string(),
+                    "detail": detail
+                },
+            }
+        })
+    }
+}
 // endregion: --- Client Error
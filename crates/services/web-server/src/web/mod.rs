@@ -5,16 +5,24 @@ use uuid::Uuid;
 
 use lib_auth::token::generate_web_token;
 
-pub use self::error::{Error, Result};
 pub use self::error::ClientError;
+pub use self::error::{Error, Result};
 
 mod error;
+#[cfg(test)]
+mod mock_oidc;
 pub mod mw_auth;
 pub mod mw_res_map;
 pub mod mw_stamp;
+pub mod routes_calendar;
+pub mod routes_display;
+pub mod routes_export;
+pub mod routes_lesson_plan;
 pub mod routes_login;
+pub mod routes_oidc;
 pub mod routes_rpc;
 pub mod routes_static;
+pub mod routes_ws;
 
 // endregion: --- Modules
 
@@ -1,20 +1,20 @@
 use async_trait::async_trait;
 use axum::body::Body;
 use axum::extract::{FromRequestParts, State};
-use axum::http::Request;
 use axum::http::request::Parts;
+use axum::http::Request;
 use axum::middleware::Next;
 use axum::response::Response;
 use serde::Serialize;
 use tower_cookies::{Cookie, Cookies};
 use tracing::debug;
 
-use lib_auth::token::{Token, validate_web_token};
+use lib_auth::token::{validate_web_token, Token};
 use lib_core::ctx::Ctx;
-use lib_core::model::ModelManager;
 use lib_core::model::user::{UserBmc, UserForAuth};
+use lib_core::model::{self, ModelManager};
 
-use crate::web::{AUTH_TOKEN, set_token_cookie};
+use crate::web::{set_token_cookie, AUTH_TOKEN};
 use crate::web::{Error, Result};
 
 pub async fn mw_ctx_require(ctx: Result<CtxW>, req: Request<Body>, next: Next) -> Result<Response> {
@@ -56,23 +56,30 @@ async fn _ctx_resolve(mm: State<ModelManager>, cookies: &Cookies) -> CtxExtResul
     // -- Parse Token
     let token: Token = token.parse().map_err(|_| CtxExtError::TokenWrongFormat)?;
 
-    // -- Get UserForAuth
-    let user: UserForAuth = UserBmc::first_by_username(&Ctx::root_ctx(), &mm, &token.ident)
+    // -- Get UserForAuth (the token ident is the user id, usernames are per center)
+    let user_id: i64 = token
+        .ident
+        .parse()
+        .map_err(|_| CtxExtError::TokenWrongFormat)?;
+    let user: UserForAuth = UserBmc::get(&Ctx::root_ctx(), &mm, user_id)
         .await
-        .map_err(|ex| CtxExtError::ModelAccessError(ex.to_string()))?
-        .ok_or(CtxExtError::UserNotFound)?;
+        .map_err(|ex| match ex {
+            model::Error::EntityNotFound { .. } => CtxExtError::UserNotFound,
+            ex => CtxExtError::ModelAccessError(ex.to_string()),
+        })?;
 
     // -- Validate Token
     validate_web_token(&token, user.token_salt).map_err(|_| CtxExtError::FailValidate)?;
 
     // -- Update Token
-    set_token_cookie(cookies, &user.username, user.token_salt)
+    set_token_cookie(cookies, &user.id.to_string(), user.token_salt)
         .map_err(|_| CtxExtError::CannotSetTokenCookie)?;
 
     // -- Create CtxExtResult
-    Ctx::new(user.id, user.is_admin)
-        .map(CtxW)
-        .map_err(|ex| CtxExtError::CtxCreateFail(ex.to_string()))
+    let ctx =
+        Ctx::new(user.id, user.is_admin, user.center_id).with_super_admin(user.is_super_admin);
+
+    Ok(CtxW(ctx))
 }
 
 // region:    --- Ctx Extractor
@@ -110,6 +117,64 @@ pub enum CtxExtError {
     CannotSetTokenCookie,
 
     CtxNotInRequestExt,
-    CtxCreateFail(String),
 }
 // endregion: --- Ctx Extractor Result/Error
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::body::to_bytes;
+    use axum::http::{header, StatusCode};
+    use axum::middleware;
+    use axum::routing::get;
+    use axum::Router;
+    use serial_test::serial;
+    use tower::ServiceExt;
+    use tower_cookies::CookieManagerLayer;
+
+    use lib_auth::token::generate_web_token;
+    use lib_core::_dev_utils;
+    use lib_core::ctx::DEFAULT_CENTER_ID;
+
+    use super::*;
+
+    async fn ctx_handler(CtxW(ctx): CtxW) -> String {
+        format!(
+            "{} {:?} {}",
+            ctx.user_id(),
+            ctx.center_id(),
+            ctx.super_admin()
+        )
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_ctx_resolve_root_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let root: UserForAuth = UserBmc::get(&Ctx::root_ctx(), &mm, 0).await?;
+        let fx_token = generate_web_token("0", root.token_salt)?;
+        let routes = Router::new()
+            .route("/ctx", get(ctx_handler))
+            .layer(middleware::from_fn_with_state(mm.clone(), mw_ctx_resolve))
+            .layer(CookieManagerLayer::new());
+
+        // -- Exec
+        let res = routes
+            .oneshot(
+                Request::get("/ctx")
+                    .header(header::COOKIE, format!("{AUTH_TOKEN}={fx_token}"))
+                    .body(Body::empty())?,
+            )
+            .await?;
+
+        // -- Check
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = to_bytes(res.into_body(), usize::MAX).await?;
+        assert_eq!(body, format!("0 Some({DEFAULT_CENTER_ID}) true"));
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
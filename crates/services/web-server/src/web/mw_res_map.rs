@@ -1,11 +1,9 @@
 use std::sync::Arc;
 
-use axum::http::{Method, Uri};
+use axum::http::{Method, StatusCode, Uri};
 use axum::Json;
 use axum::response::{IntoResponse, Response};
-use serde_json::{json, to_value};
 use tracing::debug;
-use uuid::Uuid;
 
 use crate::log::log_request;
 use crate::web::{self};
@@ -23,7 +21,8 @@ pub async fn mw_reponse_map(
     let ctx = ctx.map(|ctx| ctx.0);
 
     debug!("{:<12} - mw_reponse_map", "RES_MAPPER");
-    let uuid = Uuid::new_v4();
+    // Same uuid as the request log line.
+    let uuid = req_stamp.uuid;
 
     let rpc_info = res.extensions().get::<Arc<RpcInfo>>().map(Arc::as_ref);
 
@@ -35,20 +34,13 @@ pub async fn mw_reponse_map(
     let error_response = client_status_error
         .as_ref()
         .map(|(status_code, client_error)| {
-            let client_error = to_value(client_error).ok();
-            let message = client_error.as_ref().and_then(|v| v.get("message"));
-            let detail = client_error.as_ref().and_then(|v| v.get("detail"));
+            // JSON-RPC notifications never get a response body, even on error.
+            if rpc_info.is_some_and(|rpc| rpc.notification) {
+                return StatusCode::NO_CONTENT.into_response();
+            }
 
-            let client_error_body = json!({
-                "id": rpc_info.as_ref().map(|rpc| rpc.id.clone()),
-                "error": {
-                    "message": message, // Variant name
-                    "data": {
-                        "req_uuid": uuid.to_string(),
-                        "detail": detail
-                    },
-                }
-            });
+            let rpc_id = rpc_info.and_then(|rpc| rpc.id.as_ref());
+            let client_error_body = client_error.to_rpc_response(rpc_id, &uuid);
 
             debug!("CLIENT ERROR BODY:\n{client_error_body}");
 
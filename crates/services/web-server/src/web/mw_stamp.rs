@@ -1,8 +1,8 @@
 use async_trait::async_trait;
 use axum::body::Body;
 use axum::extract::FromRequestParts;
-use axum::http::Request;
 use axum::http::request::Parts;
+use axum::http::Request;
 use axum::middleware::Next;
 use axum::response::Response;
 use time::OffsetDateTime;
@@ -0,0 +1,322 @@
+//! `/api/calendar/...` RFC 5545 (iCalendar) feeds of the timetables,
+//! for the teachers phone calendars (see `CalendarBmc::timetable`).
+//!
+//! - Authenticated by the user feed token (`?token=...`, from `get_calendar_feed_token`),
+//!   as the calendar apps do not send the session cookie.
+//! - One weekly recurring event per schedule hour, over the course dates,
+//!   with the holidays as `EXDATE`.
+//! - Floating times (no `TZID`), the center hours are local times.
+
+use axum::extract::{Path, Query, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use serde::Deserialize;
+use time::{Date, Duration, OffsetDateTime, Time, UtcOffset};
+use tracing::debug;
+
+use lib_auth::token::{validate_calendar_token, Token};
+use lib_core::ctx::Ctx;
+use lib_core::model::calendar::{CalendarBmc, Timetable, TimetableOwner};
+use lib_core::model::user::{User, UserBmc, UserForCalendar};
+use lib_core::model::ModelManager;
+
+use crate::web::{Error, Result};
+
+const PRODID: &str = "-//Teacherinator//Horario//ES";
+
+// Axum router for '/api/calendar/...'
+pub fn routes(mm: ModelManager) -> Router {
+    Router::new()
+        .route("/calendar/teacher", get(teacher_feed_handler))
+        .route("/calendar/group/:group_id", get(group_feed_handler))
+        .with_state(mm)
+}
+
+#[derive(Deserialize)]
+struct FeedParams {
+    token: String,
+    /// Current year by default.
+    course: Option<i32>,
+}
+
+/// The timetable of the token user.
+async fn teacher_feed_handler(
+    State(mm): State<ModelManager>,
+    Query(params): Query<FeedParams>,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - teacher_feed_handler", "HANDLER");
+
+    let (user_id, ctx) = check_calendar_token(&mm, &params.token).await?;
+    let user: User = UserBmc::get(&ctx, &mm, user_id).await?;
+
+    let name = format!("Horario {}", user.username);
+    feed(
+        &ctx,
+        &mm,
+        TimetableOwner::Teacher(user_id),
+        params.course,
+        &name,
+    )
+    .await
+}
+
+async fn group_feed_handler(
+    State(mm): State<ModelManager>,
+    Path(group_id): Path<i64>,
+    Query(params): Query<FeedParams>,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - group_feed_handler", "HANDLER");
+
+    let (_, ctx) = check_calendar_token(&mm, &params.token).await?;
+
+    let name = format!("Horario grupo {group_id}");
+    feed(
+        &ctx,
+        &mm,
+        TimetableOwner::Group(group_id),
+        params.course,
+        &name,
+    )
+    .await
+}
+
+/// The token user id, and the root ctx of its center.
+async fn check_calendar_token(mm: &ModelManager, token: &str) -> Result<(i64, Ctx)> {
+    let token: Token = token.parse().map_err(|_| Error::CalendarTokenInvalid)?;
+    let user_id: i64 = token
+        .ident
+        .parse()
+        .map_err(|_| Error::CalendarTokenInvalid)?;
+
+    let user: UserForCalendar = UserBmc::get(&Ctx::root_ctx(), mm, user_id)
+        .await
+        .map_err(|_| Error::CalendarTokenInvalid)?;
+    validate_calendar_token(&token, user.calendar_salt).map_err(|_| Error::CalendarTokenInvalid)?;
+
+    Ok((user_id, Ctx::root_ctx().with_center(user.center_id)))
+}
+
+async fn feed(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    owner: TimetableOwner,
+    course: Option<i32>,
+    name: &str,
+) -> Result<impl IntoResponse> {
+    let course = course.unwrap_or_else(|| OffsetDateTime::now_utc().year());
+    let timetable = CalendarBmc::timetable(ctx, mm, owner, course).await?;
+
+    Ok((
+        [(CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        render_ics(&timetable, name),
+    ))
+}
+
+// region:    --- iCalendar
+
+fn render_ics(timetable: &Timetable, name: &str) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{PRODID}"),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ];
+
+    let course = &timetable.course;
+    for hour in &timetable.hours {
+        let schedule_hour = &hour.schedule_hour;
+        let Some(first_date) = first_week_day(course.start_date, schedule_hour.week_day) else {
+            continue;
+        };
+        if first_date > course.end_date {
+            continue;
+        }
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!(
+            "UID:schedule-hour-{}-{}@teacherinator",
+            schedule_hour.id, schedule_hour.course
+        ));
+        lines.push(format!("DTSTAMP:{}", format_utc(schedule_hour.mtime)));
+        lines.push(format!(
+            "DTSTART:{}",
+            format_local(first_date, hour.start_time)
+        ));
+        lines.push(format!("DTEND:{}", format_local(first_date, hour.end_time)));
+        lines.push(format!(
+            "RRULE:FREQ=WEEKLY;UNTIL={}",
+            format_local(
+                course.end_date,
+                Time::from_hms(23, 59, 59).unwrap_or(Time::MIDNIGHT)
+            )
+        ));
+
+        let exdates: Vec<String> = timetable
+            .holidays
+            .iter()
+            .filter(|holiday| holiday.weekday() == first_date.weekday())
+            .map(|holiday| format_local(*holiday, hour.start_time))
+            .collect();
+        if !exdates.is_empty() {
+            lines.push(format!("EXDATE:{}", exdates.join(",")));
+        }
+
+        lines.push(format!(
+            "SUMMARY:{}",
+            escape_text(&schedule_hour.subject_name)
+        ));
+        lines.push(format!(
+            "LOCATION:{}",
+            escape_text(&schedule_hour.classroom_name)
+        ));
+        if let Some(notes) = schedule_hour
+            .notes
+            .as_deref()
+            .filter(|notes| !notes.is_empty())
+        {
+            lines.push(format!("DESCRIPTION:{}", escape_text(notes)));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold_line(line)).collect()
+}
+
+/// First date from `start`, on `week_day` (0 is Monday).
+fn first_week_day(start: Date, week_day: i32) -> Option<Date> {
+    if !(0..7).contains(&week_day) {
+        return None;
+    }
+    let start_week_day = start.weekday().number_days_from_monday() as i32;
+    let days = (week_day - start_week_day).rem_euclid(7);
+
+    start.checked_add(Duration::days(days as i64))
+}
+
+/// e.g., `20250908T085500`
+fn format_local(date: Date, time: Time) -> String {
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}",
+        date.year(),
+        date.month() as u8,
+        date.day(),
+        time.hour(),
+        time.minute(),
+        time.second()
+    )
+}
+
+/// e.g., `20250908T085500Z`
+fn format_utc(moment: OffsetDateTime) -> String {
+    let moment = moment.to_offset(UtcOffset::UTC);
+    format!("{}Z", format_local(moment.date(), moment.time()))
+}
+
+/// RFC 5545, section 3.3.11.
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// RFC 5545, section 3.1: lines of 75 octets at most, continued after a CRLF and a space.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut octets = 0;
+    for ch in line.chars() {
+        // The continuation lines start with the space, one octet less for the content.
+        if octets + ch.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(ch);
+        octets += ch.len_utf8();
+    }
+    folded.push_str("\r\n");
+
+    folded
+}
+
+// endregion: --- iCalendar
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, datetime, time};
+
+    use lib_core::model::calendar::{CourseCalendar, TimetableHour};
+    use lib_core::model::schedule_hour::ScheduleHour;
+
+    use super::*;
+
+    fn fx_timetable() -> Timetable {
+        Timetable {
+            course: CourseCalendar {
+                course: 2025,
+                start_date: date!(2025 - 09 - 08), // Monday
+                end_date: date!(2026 - 06 - 19),
+            },
+            holidays: vec![
+                date!(2025 - 10 - 13),
+                date!(2025 - 12 - 08),
+                date!(2025 - 12 - 24),
+            ],
+            hours: vec![TimetableHour {
+                schedule_hour: ScheduleHour {
+                    id: 1000,
+                    schedule_id: 1000,
+                    subject_name: "Matemáticas, 1º ESO".to_string(),
+                    classroom_name: "Info 2".to_string(),
+                    week_day: 2, // Wednesday
+                    n_hour: 1,
+                    course: 2025,
+                    notes: Some(String::new()),
+                    mtime: datetime!(2025-09-01 10:00 UTC),
+                },
+                start_time: time!(08:55),
+                end_time: time!(09:50),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_render_ics_ok() {
+        // -- Exec
+        let ics = render_ics(&fx_timetable(), "Horario teacher");
+
+        // -- Check
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("\r\nUID:schedule-hour-1000-2025@teacherinator\r\n"));
+        assert!(ics.contains("\r\nDTSTAMP:20250901T100000Z\r\n"));
+        assert!(ics.contains("\r\nDTSTART:20250910T085500\r\n"));
+        assert!(ics.contains("\r\nDTEND:20250910T095000\r\n"));
+        assert!(ics.contains("\r\nRRULE:FREQ=WEEKLY;UNTIL=20260619T235959\r\n"));
+        // Only the Wednesday holidays.
+        assert!(ics.contains("\r\nEXDATE:20251224T085500\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:Matemáticas\\, 1º ESO\r\n"));
+        assert!(!ics.contains("DESCRIPTION"), "Should skip the empty notes");
+    }
+
+    #[test]
+    fn test_fold_line_ok() {
+        // -- Setup & Fixtures
+        let fx_line = format!("SUMMARY:{}", "á".repeat(80));
+
+        // -- Exec
+        let folded = fold_line(&fx_line);
+
+        // -- Check
+        assert!(folded.split("\r\n").all(|line| line.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), format!("{fx_line}\r\n"));
+    }
+}
+// endregion: --- Tests
@@ -0,0 +1,150 @@
+//! `/api/display/feed` Server-Sent Events of the staff-room display board
+//! (see `ControlBmc::display_board`).
+//!
+//! - Authenticated by a display token (`?token=...`, issued by `create_display_token`),
+//!   no teacher login, and checked again before each board (so a revoke ends the feed).
+//! - The board of the display token center (only refreshed by the changes of that center).
+//! - A `board` event on connect, then after the model changes,
+//!   and at least every `REFRESH_INTERVAL` (e.g., for the day change).
+
+use std::convert::Infallible;
+use std::time::Duration;
+
+use axum::extract::{Query, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::get;
+use axum::Router;
+use futures::{stream, Stream};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio::sync::broadcast::Receiver;
+use tracing::{debug, error};
+
+use lib_auth::token::{validate_display_token, Token};
+use lib_core::ctx::Ctx;
+use lib_core::model::control::ControlBmc;
+use lib_core::model::display_token::{DisplayTokenBmc, DisplayTokenForAuth};
+use lib_core::model::event::ModelEvent;
+use lib_core::model::ModelManager;
+
+use crate::web::{Error, Result};
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Wait after a change, so a burst of events (e.g., `update_guards`) is one board.
+const CHANGE_DEBOUNCE: Duration = Duration::from_secs(1);
+
+// Axum router for '/api/display/feed'
+pub fn routes(mm: ModelManager) -> Router {
+    Router::new()
+        .route("/display/feed", get(feed_handler))
+        .with_state(mm)
+}
+
+#[derive(Deserialize)]
+struct FeedParams {
+    token: String,
+}
+
+struct FeedState {
+    mm: ModelManager,
+    /// Of the display token center.
+    ctx: Ctx,
+    token: String,
+    events: Receiver<ModelEvent>,
+    first: bool,
+}
+
+async fn feed_handler(
+    State(mm): State<ModelManager>,
+    Query(params): Query<FeedParams>,
+) -> Result<Sse<impl Stream<Item = core::result::Result<Event, Infallible>>>> {
+    debug!("{:<12} - feed_handler", "HANDLER");
+
+    let ctx = check_display_token(&mm, &params.token).await?;
+
+    let state = FeedState {
+        events: mm.subscribe(),
+        mm,
+        ctx,
+        token: params.token,
+        first: true,
+    };
+    let board_stream = stream::unfold(state, |mut state| async move {
+        if !state.first && !wait_for_change(&mut state.events, state.ctx.center_id()).await {
+            return None;
+        }
+        state.first = false;
+
+        if let Err(err) = check_display_token(&state.mm, &state.token).await {
+            debug!("{:<12} - feed closed - {err:?}", "DISPLAY");
+            return None;
+        }
+
+        let event = board_event(&state.ctx, &state.mm).await;
+        Some((Ok(event), state))
+    });
+
+    Ok(Sse::new(board_stream).keep_alive(KeepAlive::default()))
+}
+
+/// The root ctx of the display token center.
+async fn check_display_token(mm: &ModelManager, token: &str) -> Result<Ctx> {
+    let token: Token = token.parse().map_err(|_| Error::DisplayTokenInvalid)?;
+    let id: i64 = token
+        .ident
+        .parse()
+        .map_err(|_| Error::DisplayTokenInvalid)?;
+
+    let display_token: DisplayTokenForAuth = DisplayTokenBmc::get(&Ctx::root_ctx(), mm, id)
+        .await
+        .map_err(|_| Error::DisplayTokenInvalid)?;
+    validate_display_token(&token, display_token.token_salt)
+        .map_err(|_| Error::DisplayTokenInvalid)?;
+
+    Ok(Ctx::root_ctx().with_center(display_token.center_id))
+}
+
+async fn board_event(ctx: &Ctx, mm: &ModelManager) -> Event {
+    let board = match ControlBmc::display_board(ctx, mm).await {
+        Ok(board) => board,
+        Err(err) => {
+            error!("{:<12} - display_board - {err:?}", "DISPLAY");
+            return Event::default().event("error").data("SERVICE_ERROR");
+        }
+    };
+
+    Event::default()
+        .event("board")
+        .json_data(board)
+        .unwrap_or_else(|err| {
+            error!("{:<12} - board event - {err:?}", "DISPLAY");
+            Event::default().event("error").data("SERVICE_ERROR")
+        })
+}
+
+/// False when the event bus is closed.
+async fn wait_for_change(events: &mut Receiver<ModelEvent>, center_id: Option<i64>) -> bool {
+    let change = async {
+        loop {
+            match events.recv().await {
+                Ok(event) if !event.is_for_center(center_id) => continue,
+                // Not on the board.
+                Ok(ModelEvent::Announcement { .. }) => continue,
+                Ok(_) | Err(RecvError::Lagged(_)) => return true,
+                Err(RecvError::Closed) => return false,
+            }
+        }
+    };
+
+    let changed = tokio::select! {
+        changed = change => changed,
+        _ = tokio::time::sleep(REFRESH_INTERVAL) => true,
+    };
+
+    // -- Skip the rest of the burst.
+    tokio::time::sleep(CHANGE_DEBOUNCE).await;
+    while let Ok(_) | Err(TryRecvError::Lagged(_)) = events.try_recv() {}
+
+    changed
+}
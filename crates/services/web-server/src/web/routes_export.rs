@@ -0,0 +1,372 @@
+//! `/api/export/...` spreadsheet and PDF downloads of the timetables and reports
+//! (see `ReportBmc`, `lib_export`).
+//!
+//! - Session cookie auth, as the rpc (`mw_ctx_require`).
+//! - Teachers can export their own timetable, the rest is admin only.
+//! - The printed timetables header is the `SCHOOL_NAME` and `SCHOOL_LOGO_PATH` config
+//!   for the default center, the center name for the others.
+
+use axum::extract::{Query, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use serde::Deserialize;
+use time::OffsetDateTime;
+use tracing::debug;
+
+use lib_core::ctx::{Ctx, DEFAULT_CENTER_ID};
+use lib_core::model::center::{Center, CenterBmc};
+use lib_core::model::report::{ReportBmc, TimetableGrid};
+use lib_core::model::ModelManager;
+use lib_export::pdf::{
+    timetable_grids_pdf, timetable_grids_zip, PdfBranding, PDF_CONTENT_TYPE, ZIP_CONTENT_TYPE,
+};
+use lib_export::xlsx::{
+    attendance_xlsx, substitutions_xlsx, timetable_grids_xlsx, XLSX_CONTENT_TYPE,
+};
+
+use crate::web::mw_auth::CtxW;
+use crate::web::{Error, Result};
+use crate::web_config;
+
+// Axum router for '/api/export/...'
+pub fn routes(mm: ModelManager) -> Router {
+    Router::new()
+        .route(
+            "/export/timetable/teacher.xlsx",
+            get(teacher_timetable_handler),
+        )
+        .route(
+            "/export/timetable/groups.xlsx",
+            get(groups_timetable_handler),
+        )
+        .route(
+            "/export/timetable/teacher.pdf",
+            get(teacher_timetable_pdf_handler),
+        )
+        .route(
+            "/export/timetable/teachers.pdf",
+            get(teachers_timetable_pdf_handler),
+        )
+        .route(
+            "/export/timetable/teachers.zip",
+            get(teachers_timetable_zip_handler),
+        )
+        .route(
+            "/export/timetable/groups.pdf",
+            get(groups_timetable_pdf_handler),
+        )
+        .route(
+            "/export/timetable/groups.zip",
+            get(groups_timetable_zip_handler),
+        )
+        .route("/export/attendance.xlsx", get(attendance_handler))
+        .route("/export/substitutions.xlsx", get(substitutions_handler))
+        .with_state(mm)
+}
+
+#[derive(Deserialize)]
+struct TimetableParams {
+    /// The ctx user by default.
+    user_id: Option<i64>,
+    /// Current year by default.
+    course: Option<i32>,
+}
+
+async fn teacher_timetable_handler(
+    State(mm): State<ModelManager>,
+    CtxW(ctx): CtxW,
+    Query(params): Query<TimetableParams>,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - teacher_timetable_handler", "HANDLER");
+
+    let grid = teacher_grid(&ctx, &mm, &params).await?;
+    let filename = format!("Horario {} {}.xlsx", grid.title, grid.course);
+    let xlsx = timetable_grids_xlsx(&[grid])?;
+
+    Ok(xlsx_response(&filename, xlsx))
+}
+
+async fn groups_timetable_handler(
+    State(mm): State<ModelManager>,
+    CtxW(ctx): CtxW,
+    Query(params): Query<TimetableParams>,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - groups_timetable_handler", "HANDLER");
+
+    let (course, grids) = group_grids(&ctx, &mm, &params).await?;
+    let xlsx = timetable_grids_xlsx(&grids)?;
+
+    Ok(xlsx_response(
+        &format!("Horarios grupos {course}.xlsx"),
+        xlsx,
+    ))
+}
+
+async fn attendance_handler(
+    State(mm): State<ModelManager>,
+    CtxW(ctx): CtxW,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - attendance_handler", "HANDLER");
+
+    require_admin(&ctx)?;
+
+    let rows = ReportBmc::attendance(&ctx, &mm).await?;
+    let xlsx = attendance_xlsx(&rows)?;
+
+    Ok(xlsx_response(&format!("Asistencia {}.xlsx", today()), xlsx))
+}
+
+async fn substitutions_handler(
+    State(mm): State<ModelManager>,
+    CtxW(ctx): CtxW,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - substitutions_handler", "HANDLER");
+
+    require_admin(&ctx)?;
+
+    let rows = ReportBmc::substitutions(&ctx, &mm).await?;
+    let xlsx = substitutions_xlsx(&rows)?;
+
+    Ok(xlsx_response(
+        &format!("Sustituciones {}.xlsx", today()),
+        xlsx,
+    ))
+}
+
+// region:    --- Printed Timetables
+
+async fn teacher_timetable_pdf_handler(
+    State(mm): State<ModelManager>,
+    CtxW(ctx): CtxW,
+    Query(params): Query<TimetableParams>,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - teacher_timetable_pdf_handler", "HANDLER");
+
+    let grid = teacher_grid(&ctx, &mm, &params).await?;
+    let filename = format!("Horario {} {}.pdf", grid.title, grid.course);
+    let pdf = timetable_grids_pdf(&[grid], &pdf_branding(&ctx, &mm).await?)?;
+
+    Ok(file_response(PDF_CONTENT_TYPE, &filename, pdf))
+}
+
+async fn teachers_timetable_pdf_handler(
+    State(mm): State<ModelManager>,
+    CtxW(ctx): CtxW,
+    Query(params): Query<TimetableParams>,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - teachers_timetable_pdf_handler", "HANDLER");
+
+    let (course, grids) = teacher_grids(&ctx, &mm, &params).await?;
+    let pdf = timetable_grids_pdf(&grids, &pdf_branding(&ctx, &mm).await?)?;
+
+    let filename = format!("Horarios profesores {course}.pdf");
+    Ok(file_response(PDF_CONTENT_TYPE, &filename, pdf))
+}
+
+async fn teachers_timetable_zip_handler(
+    State(mm): State<ModelManager>,
+    CtxW(ctx): CtxW,
+    Query(params): Query<TimetableParams>,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - teachers_timetable_zip_handler", "HANDLER");
+
+    let (course, grids) = teacher_grids(&ctx, &mm, &params).await?;
+    let zip = timetable_grids_zip(&grids, &pdf_branding(&ctx, &mm).await?)?;
+
+    let filename = format!("Horarios profesores {course}.zip");
+    Ok(file_response(ZIP_CONTENT_TYPE, &filename, zip))
+}
+
+async fn groups_timetable_pdf_handler(
+    State(mm): State<ModelManager>,
+    CtxW(ctx): CtxW,
+    Query(params): Query<TimetableParams>,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - groups_timetable_pdf_handler", "HANDLER");
+
+    let (course, grids) = group_grids(&ctx, &mm, &params).await?;
+    let pdf = timetable_grids_pdf(&grids, &pdf_branding(&ctx, &mm).await?)?;
+
+    let filename = format!("Horarios grupos {course}.pdf");
+    Ok(file_response(PDF_CONTENT_TYPE, &filename, pdf))
+}
+
+async fn groups_timetable_zip_handler(
+    State(mm): State<ModelManager>,
+    CtxW(ctx): CtxW,
+    Query(params): Query<TimetableParams>,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - groups_timetable_zip_handler", "HANDLER");
+
+    let (course, grids) = group_grids(&ctx, &mm, &params).await?;
+    let zip = timetable_grids_zip(&grids, &pdf_branding(&ctx, &mm).await?)?;
+
+    let filename = format!("Horarios grupos {course}.zip");
+    Ok(file_response(ZIP_CONTENT_TYPE, &filename, zip))
+}
+
+async fn pdf_branding(ctx: &Ctx, mm: &ModelManager) -> Result<PdfBranding> {
+    if let Some(center_id) = ctx.center_id().filter(|&id| id != DEFAULT_CENTER_ID) {
+        let center: Center = CenterBmc::get(ctx, mm, center_id).await?;
+        return Ok(PdfBranding {
+            school_name: center.name,
+            logo: None,
+        });
+    }
+
+    let config = web_config();
+    let logo = if config.SCHOOL_LOGO_PATH.is_empty() {
+        None
+    } else {
+        let logo = tokio::fs::read(&config.SCHOOL_LOGO_PATH)
+            .await
+            .map_err(|err| Error::ExportLogoRead(err.to_string()))?;
+        Some(logo)
+    };
+
+    Ok(PdfBranding {
+        school_name: config.SCHOOL_NAME.clone(),
+        logo,
+    })
+}
+
+// endregion: --- Printed Timetables
+
+/// The `params.user_id` timetable, own or admin only.
+async fn teacher_grid(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    params: &TimetableParams,
+) -> Result<TimetableGrid> {
+    let user_id = params.user_id.unwrap_or(ctx.user_id());
+    if user_id != ctx.user_id() {
+        require_admin(ctx)?;
+    }
+    let course = params.course.unwrap_or_else(current_course);
+
+    Ok(ReportBmc::teacher_grid(ctx, mm, user_id, course).await?)
+}
+
+async fn teacher_grids(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    params: &TimetableParams,
+) -> Result<(i32, Vec<TimetableGrid>)> {
+    require_admin(ctx)?;
+    let course = params.course.unwrap_or_else(current_course);
+
+    Ok((course, ReportBmc::teacher_grids(ctx, mm, course).await?))
+}
+
+async fn group_grids(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    params: &TimetableParams,
+) -> Result<(i32, Vec<TimetableGrid>)> {
+    require_admin(ctx)?;
+    let course = params.course.unwrap_or_else(current_course);
+
+    Ok((course, ReportBmc::group_grids(ctx, mm, course).await?))
+}
+
+fn require_admin(ctx: &Ctx) -> Result<()> {
+    if ctx.admin() {
+        Ok(())
+    } else {
+        Err(Error::ExportUserNotAdmin)
+    }
+}
+
+fn xlsx_response(filename: &str, xlsx: Vec<u8>) -> impl IntoResponse {
+    file_response(XLSX_CONTENT_TYPE, filename, xlsx)
+}
+
+pub(super) fn file_response(
+    content_type: &str,
+    filename: &str,
+    body: Vec<u8>,
+) -> impl IntoResponse {
+    (
+        [
+            (CONTENT_TYPE, content_type.to_string()),
+            (CONTENT_DISPOSITION, content_disposition(filename)),
+        ],
+        body,
+    )
+}
+
+fn current_course() -> i32 {
+    OffsetDateTime::now_utc().year()
+}
+
+fn today() -> String {
+    let today = OffsetDateTime::now_utc().date();
+    format!(
+        "{}-{:02}-{:02}",
+        today.year(),
+        u8::from(today.month()),
+        today.day()
+    )
+}
+
+// region:    --- Content-Disposition
+
+/// RFC 6266 attachment, with an ASCII `filename` fallback
+/// and the UTF-8 `filename*` (RFC 5987) for the accented names.
+fn content_disposition(filename: &str) -> String {
+    let ascii: String = filename
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' => '_',
+            c if c.is_ascii() && !c.is_ascii_control() => c,
+            _ => '_',
+        })
+        .collect();
+
+    format!(
+        "attachment; filename=\"{ascii}\"; filename*=UTF-8''{}",
+        percent_encode(filename)
+    )
+}
+
+/// RFC 5987 `attr-char` kept, the rest percent encoded.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    encoded
+}
+
+// endregion: --- Content-Disposition
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn test_content_disposition_ok() -> Result<()> {
+        // -- Exec
+        let header = content_disposition("Horario \"Begoña\" 2025.xlsx");
+
+        // -- Check
+        assert_eq!(
+            header,
+            "attachment; filename=\"Horario _Bego_a_ 2025.xlsx\"; \
+             filename*=UTF-8''Horario%20%22Bego%C3%B1a%22%202025.xlsx"
+        );
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
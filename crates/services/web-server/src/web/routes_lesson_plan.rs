@@ -0,0 +1,41 @@
+//! `/api/lesson-plans/...` downloads of the lesson plan attachments
+//! (see `LessonPlanBmc`, the uploads are by rpc).
+//!
+//! - Session cookie auth, as the rpc (`mw_ctx_require`).
+//! - Any teacher of the center can download them (e.g., the covering teacher).
+
+use axum::extract::{Path, State};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use tracing::debug;
+
+use lib_core::model::lesson_plan::LessonPlanBmc;
+use lib_core::model::ModelManager;
+
+use crate::web::mw_auth::CtxW;
+use crate::web::routes_export::file_response;
+use crate::web::Result;
+
+// Axum router for '/api/lesson-plans/...'
+pub fn routes(mm: ModelManager) -> Router {
+    Router::new()
+        .route("/lesson-plans/attachments/:id", get(attachment_handler))
+        .with_state(mm)
+}
+
+async fn attachment_handler(
+    State(mm): State<ModelManager>,
+    CtxW(ctx): CtxW,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - attachment_handler", "HANDLER");
+
+    let (attachment, content) = LessonPlanBmc::attachment_content(&ctx, &mm, id).await?;
+
+    Ok(file_response(
+        &attachment.content_type,
+        &attachment.filename,
+        content,
+    ))
+}
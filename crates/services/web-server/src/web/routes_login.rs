@@ -1,17 +1,20 @@
-use axum::{Json, Router};
 use axum::extract::State;
 use axum::routing::post;
+use axum::{Json, Router};
 use serde::Deserialize;
 use serde_json::{json, Value};
 use tower_cookies::Cookies;
 use tracing::debug;
 
-use lib_auth::pwd::{self, ContentToHash, SchemeStatus};
-use lib_core::ctx::Ctx;
+use lib_auth::authn::{self, AuthIdentity, LocalAccount};
+use lib_auth::pwd::SchemeStatus;
+use lib_core::ctx::{Ctx, DEFAULT_CENTER_ID};
+use lib_core::model::center::{Center, CenterBmc};
+use lib_core::model::department::{Department, DepartmentBmc};
+use lib_core::model::user::{UserBmc, UserForLogin, UserForProvision};
 use lib_core::model::ModelManager;
-use lib_core::model::user::{UserBmc, UserForLogin};
 
-use crate::web::{self, Error, remove_token_cookie, Result};
+use crate::web::{self, remove_token_cookie, Error, Result};
 
 pub fn routes(mm: ModelManager) -> Router {
     Router::new()
@@ -31,55 +34,145 @@ async fn api_login_handler(
     let LoginPayload {
         username,
         pwd: pwd_clear,
+        center,
     } = payload;
-    let root_ctx = Ctx::root_ctx();
 
-    // -- Get the user.
-    let user: UserForLogin = UserBmc::first_by_username(&root_ctx, &mm, &username)
-        .await?
-        .ok_or(Error::LoginFailUsernameNotFound)?;
-    let user_id = user.id;
-    let is_admin = user.is_admin;
+    // -- Get the center (the username is unique per center).
+    let center = login_center(&mm, center).await?;
+    let root_ctx = Ctx::root_ctx().with_center(center.id);
 
-    // -- Validate the password.
-    let Some(pwd) = user.pwd else {
-        return Err(Error::LoginFailUserHasNoPwd { user_id });
-    };
+    // -- Get the user (might not exist yet for external backends).
+    let user: Option<UserForLogin> = UserBmc::first_by_username(&root_ctx, &mm, &username).await?;
+    let local = user.as_ref().map(|user| LocalAccount {
+        pwd: user.pwd.clone(),
+        pwd_salt: user.pwd_salt,
+    });
 
-    let scheme_status = pwd::validate_pwd(
-        ContentToHash {
-            salt: user.pwd_salt,
-            content: pwd_clear.clone(),
-        },
-        pwd,
-    )
+    // -- Authenticate against the configured backends (only its own one for an existing user).
+    let auth_source = user.as_ref().map(|user| user.auth_source.as_str());
+    let identity = authn::authenticate(&username, &pwd_clear, local.as_ref(), auth_source)
         .await
-    .map_err(|_| Error::LoginFailPwdNotMatching { user_id })?;
+        .map_err(|ex| login_fail_error(ex, user.as_ref()))?;
 
-    // -- Update password scheme if needed
-    if let SchemeStatus::Outdated = scheme_status {
-        debug!("pwd encrypt scheme outdated, upgrading.");
-        UserBmc::update_pwd(&root_ctx, &mm, user.id, &pwd_clear).await?;
-    }
+    let user: UserForLogin = match user {
+        Some(user) => {
+            // -- Update password scheme if needed
+            if let Some(SchemeStatus::Outdated) = identity.scheme_status {
+                debug!("pwd encrypt scheme outdated, upgrading.");
+                UserBmc::update_pwd(&root_ctx, &mm, user.id, &pwd_clear).await?;
+            }
+            user
+        }
+        None => provision_user(&root_ctx, &mm, &center, &identity).await?,
+    };
 
     // -- Set web token.
-    web::set_token_cookie(&cookies, &user.username, user.token_salt)?;
+    web::set_token_cookie(&cookies, &user.id.to_string(), user.token_salt)?;
 
     // Create the success body.
     let body = Json(json!({
         "result": {
             "success": true,
-            "is_admin": is_admin
+            "is_admin": user.is_admin,
+            "is_super_admin": user.is_super_admin
         }
     }));
 
     Ok(body)
 }
 
+/// The center of the login `center` code, the default center when absent
+/// (also for the OpenID Connect login).
+pub(super) async fn login_center(mm: &ModelManager, center: Option<String>) -> Result<Center> {
+    let root_ctx = Ctx::root_ctx();
+    let center = match center {
+        Some(code) => CenterBmc::first_by_code::<Center>(&root_ctx, mm, &code)
+            .await?
+            .ok_or(Error::LoginFailCenterNotFound { code })?,
+        None => CenterBmc::get::<Center>(&root_ctx, mm, DEFAULT_CENTER_ID).await?,
+    };
+
+    Ok(center)
+}
+
+/// Create the `users` row on the first login of an externally authenticated user
+/// (in the `root_ctx` center, only when one of the backend `provision_centers`).
+async fn provision_user(
+    root_ctx: &Ctx,
+    mm: &ModelManager,
+    center: &Center,
+    identity: &AuthIdentity,
+) -> Result<UserForLogin> {
+    if !identity.provision_centers.contains(&center.code) {
+        return Err(Error::LoginFailProvisionCenterNotAllowed {
+            code: center.code.clone(),
+        });
+    }
+
+    debug!(
+        "{:<12} - provisioning user '{}' from {}",
+        "LOGIN",
+        identity.username,
+        identity.backend.as_str()
+    );
+
+    // Unknown (or no) department, keep the table default.
+    let department_id = match identity.department.as_deref() {
+        Some(name) => DepartmentBmc::first_by_name::<Department>(root_ctx, mm, name)
+            .await?
+            .map(|department| department.id),
+        None => None,
+    };
+
+    let id = UserBmc::provision(
+        root_ctx,
+        mm,
+        UserForProvision {
+            username: identity.username.clone(),
+            is_admin: false,
+            active: true,
+            department_id,
+            auth_source: identity.backend.as_str().to_string(),
+        },
+    )
+    .await?;
+
+    Ok(UserBmc::get(root_ctx, mm, id).await?)
+}
+
+/// Credential errors become login failures,
+/// backend errors (e.g., ldap server down) stay service errors.
+fn login_fail_error(ex: authn::Error, user: Option<&UserForLogin>) -> Error {
+    let Some(user) = user else {
+        return match ex {
+            authn::Error::LocalAccountNotFound
+            | authn::Error::LdapUserNotFound
+            | authn::Error::PwdNotMatching => Error::LoginFailUsernameNotFound,
+            ex => Error::Authn(ex),
+        };
+    };
+
+    let user_id = user.id;
+    match ex {
+        authn::Error::NoBackendConfigured => Error::LoginFailAuthSourceNotConfigured {
+            user_id,
+            auth_source: user.auth_source.clone(),
+        },
+        authn::Error::UserHasNoPwd => Error::LoginFailUserHasNoPwd { user_id },
+        authn::Error::PwdNotMatching | authn::Error::LdapUserNotFound | authn::Error::Pwd(_) => {
+            Error::LoginFailPwdNotMatching { user_id }
+        }
+        ex => Error::Authn(ex),
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct LoginPayload {
     username: String,
     pwd: String,
+    /// Center code, the default center when absent.
+    #[serde(default)]
+    center: Option<String>,
 }
 // endregion: --- Login
 
@@ -110,3 +203,162 @@ struct LogoffPayload {
     logoff: bool,
 }
 // endregion: --- Logoff
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use anyhow::Result;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use axum::response::Response;
+    use serial_test::serial;
+    use tower::ServiceExt;
+    use tower_cookies::CookieManagerLayer;
+
+    use lib_auth::authn::BackendKind;
+    use lib_core::_dev_utils;
+
+    use super::*;
+
+    async fn exec_login(mm: ModelManager, username: &str, pwd: &str) -> Result<Response> {
+        let routes = routes(mm).layer(CookieManagerLayer::new());
+        let body = json!({"username": username, "pwd": pwd}).to_string();
+
+        let res = routes
+            .oneshot(
+                Request::post("/api/login")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body))?,
+            )
+            .await?;
+
+        Ok(res)
+    }
+
+    fn fx_ldap_identity(username: &str, provision_centers: &[&str]) -> AuthIdentity {
+        AuthIdentity {
+            username: username.to_string(),
+            backend: BackendKind::Ldap,
+            department: None,
+            provision_centers: provision_centers
+                .iter()
+                .map(|code| code.to_string())
+                .collect(),
+            scheme_status: None,
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_login_local_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_username = "login_test_local_ok";
+        let fx_pwd = "login_test_local_ok pwd";
+        let fx_user_id = _dev_utils::seed_user(&ctx, &mm, fx_username).await?;
+        UserBmc::update_pwd(&ctx, &mm, fx_user_id, fx_pwd).await?;
+
+        // -- Exec
+        let res = exec_login(mm.clone(), fx_username, fx_pwd).await?;
+
+        // -- Check
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // -- Clean
+        UserBmc::delete(&ctx, &mm, fx_user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_login_err_auth_source_not_configured() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_username = "login_test_auth_source_not_configured";
+        let fx_pwd = "login_test_auth_source_not_configured pwd";
+        // An ldap user, with a (stale) local pwd (only the local backend configured).
+        let fx_user_id = UserBmc::provision(
+            &ctx,
+            &mm,
+            UserForProvision {
+                username: fx_username.to_string(),
+                is_admin: false,
+                active: true,
+                department_id: None,
+                auth_source: "ldap".to_string(),
+            },
+        )
+        .await?;
+        UserBmc::update_pwd(&ctx, &mm, fx_user_id, fx_pwd).await?;
+
+        // -- Exec
+        let res = exec_login(mm.clone(), fx_username, fx_pwd).await?;
+
+        // -- Check
+        let error = res.extensions().get::<Arc<Error>>().map(Arc::as_ref);
+        assert!(
+            matches!(error, Some(Error::LoginFailAuthSourceNotConfigured { user_id, .. }) if *user_id == fx_user_id),
+            "Should have matched `Error::LoginFailAuthSourceNotConfigured` but was `{error:?}`"
+        );
+
+        // -- Clean
+        UserBmc::delete(&ctx, &mm, fx_user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_provision_user_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let root_ctx = Ctx::root_ctx().with_center(DEFAULT_CENTER_ID);
+        let center = login_center(&mm, None).await?;
+        let fx_username = "login_test_provision_ok";
+        let identity = fx_ldap_identity(fx_username, &[&center.code]);
+
+        // -- Exec
+        let user = provision_user(&root_ctx, &mm, &center, &identity).await?;
+
+        // -- Check
+        assert_eq!(user.username, fx_username);
+        assert_eq!(user.center_id, DEFAULT_CENTER_ID);
+        assert_eq!(user.auth_source, "ldap");
+
+        // -- Clean
+        UserBmc::delete(&root_ctx, &mm, user.id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_provision_user_err_center_not_allowed() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let root_ctx = Ctx::root_ctx().with_center(DEFAULT_CENTER_ID);
+        let center = login_center(&mm, None).await?;
+        let fx_username = "login_test_provision_not_allowed";
+        let identity = fx_ldap_identity(fx_username, &["ies-other"]);
+
+        // -- Exec
+        let res = provision_user(&root_ctx, &mm, &center, &identity).await;
+
+        // -- Check
+        assert!(
+            matches!(&res, Err(Error::LoginFailProvisionCenterNotAllowed { code }) if *code == center.code),
+            "Should have matched `Error::LoginFailProvisionCenterNotAllowed` but was `{res:?}`"
+        );
+        let user: Option<UserForLogin> =
+            UserBmc::first_by_username(&root_ctx, &mm, fx_username).await?;
+        assert!(user.is_none(), "Should not have provisioned the user");
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
@@ -0,0 +1,658 @@
+//! OpenID Connect single sign-on (authorization code flow with PKCE).
+//!
+//! - `GET /api/oidc/login?center=<code>` redirects to the provider authorization endpoint
+//!   (the center as on `/api/login`, the default center when absent).
+//! - `GET /api/oidc/callback` exchanges the code, validates the id token,
+//!   and maps the `OIDC_USERNAME_CLAIM` claim to an existing `users.username`
+//!   (the `email` claim only when verified by the provider).
+//!
+//! The `state` is also set in the `OIDC_STATE` cookie on login, and checked on
+//! the callback, so the login can only be completed by the browser that started it.
+//!
+//! On success, the same `AUTH_TOKEN` cookie as `/api/login` is set.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{Query, State};
+use axum::response::Redirect;
+use axum::routing::get;
+use axum::Router;
+use openidconnect::core::{CoreAuthenticationFlow, CoreClient, CoreProviderMetadata};
+use openidconnect::reqwest::async_http_client;
+use openidconnect::{
+    AuthorizationCode, ClientId, ClientSecret, CsrfToken, IssuerUrl, Nonce, PkceCodeChallenge,
+    PkceCodeVerifier, RedirectUrl, Scope, TokenResponse,
+};
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+use tower_cookies::cookie::SameSite;
+use tower_cookies::{Cookie, Cookies};
+use tracing::debug;
+
+use lib_core::ctx::Ctx;
+use lib_core::model::user::{UserBmc, UserForLogin};
+use lib_core::model::ModelManager;
+
+use crate::config::web_config;
+use crate::web::routes_login::login_center;
+use crate::web::{self, Error, Result};
+
+/// Time given to the user to complete the login on the provider side.
+const PENDING_LOGIN_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Where the browser lands after a successful login.
+const LOGIN_SUCCESS_REDIRECT: &str = "/";
+
+/// The `state` of the pending login of the browser.
+const OIDC_STATE: &str = "oidc-state";
+const OIDC_STATE_PATH: &str = "/api/oidc";
+
+// region:    --- OidcState
+
+#[derive(Clone)]
+pub struct OidcConfig {
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_url: String,
+    pub username_claim: String,
+}
+
+impl OidcConfig {
+    /// `None` when OpenID Connect is not configured (empty issuer).
+    pub fn from_web_config() -> Option<Self> {
+        let config = web_config();
+        if config.OIDC_ISSUER_URL.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            issuer_url: config.OIDC_ISSUER_URL.clone(),
+            client_id: config.OIDC_CLIENT_ID.clone(),
+            client_secret: config.OIDC_CLIENT_SECRET.clone(),
+            redirect_url: config.OIDC_REDIRECT_URL.clone(),
+            username_claim: config.OIDC_USERNAME_CLAIM.clone(),
+        })
+    }
+}
+
+/// Login started on `/api/oidc/login`, waiting for the provider callback.
+struct PendingLogin {
+    pkce_verifier: PkceCodeVerifier,
+    nonce: Nonce,
+    /// The center the username is looked up in.
+    center_id: i64,
+    created: Instant,
+}
+
+#[derive(Clone)]
+pub struct OidcState {
+    pub mm: ModelManager,
+    pub config: OidcConfig,
+    /// Keyed by the csrf `state` parameter.
+    pending: Arc<Mutex<HashMap<String, PendingLogin>>>,
+    /// Discovered on the first login.
+    provider_metadata: Arc<OnceCell<CoreProviderMetadata>>,
+}
+
+impl OidcState {
+    pub fn new(mm: ModelManager, config: OidcConfig) -> Self {
+        Self {
+            mm,
+            config,
+            pending: Arc::default(),
+            provider_metadata: Arc::default(),
+        }
+    }
+
+    fn insert_pending(&self, csrf: CsrfToken, pending: PendingLogin) {
+        let mut pendings = self.pending.lock().unwrap();
+        pendings.retain(|_, pending| pending.created.elapsed() < PENDING_LOGIN_TTL);
+        pendings.insert(csrf.secret().clone(), pending);
+    }
+
+    /// One time use, the pending login is removed.
+    fn take_pending(&self, csrf: &str) -> Result<PendingLogin> {
+        self.pending
+            .lock()
+            .unwrap()
+            .remove(csrf)
+            .filter(|pending| pending.created.elapsed() < PENDING_LOGIN_TTL)
+            .ok_or(Error::OidcStateNotFound)
+    }
+
+    async fn client(&self) -> Result<CoreClient> {
+        let config = &self.config;
+
+        let issuer_url = IssuerUrl::new(config.issuer_url.clone())
+            .map_err(|ex| Error::OidcConfig(ex.to_string()))?;
+        let redirect_url = RedirectUrl::new(config.redirect_url.clone())
+            .map_err(|ex| Error::OidcConfig(ex.to_string()))?;
+
+        // Note: A failed discovery is not cached, the next login retries it.
+        let provider_metadata = self
+            .provider_metadata
+            .get_or_try_init(|| async {
+                CoreProviderMetadata::discover_async(issuer_url, async_http_client)
+                    .await
+                    .map_err(|ex| Error::OidcDiscovery(ex.to_string()))
+            })
+            .await?;
+
+        let client = CoreClient::from_provider_metadata(
+            provider_metadata.clone(),
+            ClientId::new(config.client_id.clone()),
+            Some(ClientSecret::new(config.client_secret.clone())),
+        )
+        .set_redirect_uri(redirect_url);
+
+        Ok(client)
+    }
+}
+
+// endregion: --- OidcState
+
+pub fn routes(oidc_state: OidcState) -> Router {
+    Router::new()
+        .route("/api/oidc/login", get(api_oidc_login_handler))
+        .route("/api/oidc/callback", get(api_oidc_callback_handler))
+        .with_state(oidc_state)
+}
+
+// region:    --- Login
+async fn api_oidc_login_handler(
+    State(oidc_state): State<OidcState>,
+    cookies: Cookies,
+    Query(params): Query<LoginParams>,
+) -> Result<Redirect> {
+    debug!("{:<12} - api_oidc_login_handler", "HANDLER");
+
+    let center_id = login_center(&oidc_state.mm, params.center).await?.id;
+    let client = oidc_state.client().await?;
+
+    let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+    let (auth_url, csrf, nonce) = client
+        .authorize_url(
+            CoreAuthenticationFlow::AuthorizationCode,
+            CsrfToken::new_random,
+            Nonce::new_random,
+        )
+        .add_scope(Scope::new("email".to_string()))
+        .add_scope(Scope::new("profile".to_string()))
+        .set_pkce_challenge(pkce_challenge)
+        .url();
+
+    // Lax, so it is sent on the provider redirect back to the callback.
+    let mut state_cookie = Cookie::new(OIDC_STATE, csrf.secret().clone());
+    state_cookie.set_http_only(true);
+    state_cookie.set_path(OIDC_STATE_PATH);
+    state_cookie.set_same_site(SameSite::Lax);
+    state_cookie.set_max_age(time::Duration::seconds(PENDING_LOGIN_TTL.as_secs() as i64));
+    cookies.add(state_cookie);
+
+    oidc_state.insert_pending(
+        csrf,
+        PendingLogin {
+            pkce_verifier,
+            nonce,
+            center_id,
+            created: Instant::now(),
+        },
+    );
+
+    Ok(Redirect::to(auth_url.as_str()))
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginParams {
+    /// Center code, the default center when absent.
+    center: Option<String>,
+}
+// endregion: --- Login
+
+// region:    --- Callback
+async fn api_oidc_callback_handler(
+    State(oidc_state): State<OidcState>,
+    cookies: Cookies,
+    Query(params): Query<CallbackParams>,
+) -> Result<Redirect> {
+    debug!("{:<12} - api_oidc_callback_handler", "HANDLER");
+
+    let CallbackParams { code, state } = params;
+
+    // -- Check the state is of this browser (login csrf).
+    let state_cookie = cookies
+        .get(OIDC_STATE)
+        .map(|cookie| cookie.value().to_string());
+    let mut removal_cookie = Cookie::from(OIDC_STATE);
+    removal_cookie.set_path(OIDC_STATE_PATH);
+    cookies.remove(removal_cookie);
+    if state_cookie.as_deref() != Some(state.as_str()) {
+        return Err(Error::OidcStateMismatch);
+    }
+
+    let pending = oidc_state.take_pending(&state)?;
+    let client = oidc_state.client().await?;
+
+    // -- Exchange the code (the verifier proves we started the flow).
+    let token_response = client
+        .exchange_code(AuthorizationCode::new(code))
+        .set_pkce_verifier(pending.pkce_verifier)
+        .request_async(async_http_client)
+        .await
+        .map_err(|ex| Error::OidcCodeExchange(ex.to_string()))?;
+
+    // -- Validate the id token (signature, issuer, audience, expiry, nonce).
+    let id_token = token_response.id_token().ok_or(Error::OidcIdTokenMissing)?;
+    let claims = id_token
+        .claims(&client.id_token_verifier(), &pending.nonce)
+        .map_err(|ex| Error::OidcIdTokenInvalid(ex.to_string()))?;
+
+    let username = match oidc_state.config.username_claim.as_str() {
+        "email" => {
+            // An unverified email could be anyone's (e.g., self registered on the provider).
+            if claims.email_verified() != Some(true) {
+                return Err(Error::OidcEmailNotVerified);
+            }
+            claims.email().map(|email| email.as_str())
+        }
+        "preferred_username" => claims
+            .preferred_username()
+            .map(|username| username.as_str()),
+        other => return Err(Error::OidcConfig(format!("unsupported claim '{other}'"))),
+    }
+    .ok_or(Error::OidcClaimMissing)?;
+
+    // -- Get the user (no provisioning, must exist) in the login center.
+    let root_ctx = Ctx::root_ctx().with_center(pending.center_id);
+    let user: UserForLogin = UserBmc::first_by_username(&root_ctx, &oidc_state.mm, username)
+        .await?
+        .ok_or(Error::LoginFailUsernameNotFound)?;
+
+    // -- Set web token.
+    web::set_token_cookie(&cookies, &user.id.to_string(), user.token_salt)?;
+
+    Ok(Redirect::to(LOGIN_SUCCESS_REDIRECT))
+}
+
+#[derive(Debug, Deserialize)]
+struct CallbackParams {
+    code: String,
+    state: String,
+}
+// endregion: --- Callback
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::{Context, Result};
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use axum::response::Response;
+    use serial_test::serial;
+    use tower::ServiceExt;
+    use tower_cookies::CookieManagerLayer;
+    use url::Url;
+
+    use lib_core::_dev_utils;
+    use lib_core::model::center::{CenterBmc, CenterForCreate};
+    use lib_core::model::user::UserBmc;
+
+    use crate::web::mock_oidc::{self, MockOidcUser};
+    use crate::web::AUTH_TOKEN;
+
+    use super::*;
+
+    const FX_CLIENT_ID: &str = "teacherinator";
+    const FX_CLIENT_SECRET: &str = "client_secret";
+    const FX_REDIRECT_URL: &str = "http://localhost:8081/api/oidc/callback";
+
+    async fn fx_routes(
+        mm: ModelManager,
+        user: MockOidcUser,
+        username_claim: &str,
+    ) -> Result<Router> {
+        let issuer_url = mock_oidc::start(FX_CLIENT_ID, user).await?;
+        let config = OidcConfig {
+            issuer_url,
+            client_id: FX_CLIENT_ID.to_string(),
+            client_secret: FX_CLIENT_SECRET.to_string(),
+            redirect_url: FX_REDIRECT_URL.to_string(),
+            username_claim: username_claim.to_string(),
+        };
+
+        Ok(routes(OidcState::new(mm, config)).layer(CookieManagerLayer::new()))
+    }
+
+    /// Login -> provider authorize -> callback, returns the callback response.
+    async fn exec_flow(routes: &Router, login_uri: &str) -> Result<Response> {
+        // -- Login, redirects to the provider
+        let res = routes
+            .clone()
+            .oneshot(Request::get(login_uri).body(Body::empty())?)
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        let auth_url = location(&res)?;
+        let state_cookie = state_cookie(&res)?;
+
+        // -- Provider, user consents and is redirected back with a code
+        let callback_url = mock_oidc::authorize(&auth_url).await?;
+        let callback_url = Url::parse(&callback_url)?;
+        let callback_uri = format!(
+            "{}?{}",
+            callback_url.path(),
+            callback_url.query().context("Should have query")?
+        );
+
+        // -- Callback, from the same browser
+        let res = routes
+            .clone()
+            .oneshot(
+                Request::get(callback_uri)
+                    .header(header::COOKIE, state_cookie)
+                    .body(Body::empty())?,
+            )
+            .await?;
+
+        Ok(res)
+    }
+
+    fn location(res: &Response) -> Result<String> {
+        Ok(res
+            .headers()
+            .get(header::LOCATION)
+            .context("Should have location header")?
+            .to_str()?
+            .to_string())
+    }
+
+    fn set_cookie(res: &Response, name: &str) -> Option<String> {
+        res.headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find(|value| value.starts_with(&format!("{name}=")))
+            .map(|value| value.to_string())
+    }
+
+    fn auth_token_cookie(res: &Response) -> Option<String> {
+        set_cookie(res, AUTH_TOKEN)
+    }
+
+    /// The `Cookie` header value to send back the login state.
+    fn state_cookie(res: &Response) -> Result<String> {
+        let set_cookie = set_cookie(res, OIDC_STATE).context("Should have set the state")?;
+        let cookie = set_cookie.split(';').next().context("Should have value")?;
+
+        Ok(cookie.to_string())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_oidc_flow_email_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_username = "oidc_test_email@ies.test";
+        let fx_user_id = _dev_utils::seed_user(&ctx, &mm, fx_username).await?;
+        let routes = fx_routes(
+            mm.clone(),
+            MockOidcUser {
+                email: Some(fx_username.to_string()),
+                email_verified: Some(true),
+                preferred_username: None,
+            },
+            "email",
+        )
+        .await?;
+
+        // -- Exec
+        let res = exec_flow(&routes, "/api/oidc/login").await?;
+
+        // -- Check
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(location(&res)?, LOGIN_SUCCESS_REDIRECT);
+        assert!(
+            auth_token_cookie(&res).is_some(),
+            "Should have set the auth token"
+        );
+
+        // -- Clean
+        UserBmc::delete(&ctx, &mm, fx_user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_oidc_flow_preferred_username_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_username = "oidc_test_preferred";
+        let fx_user_id = _dev_utils::seed_user(&ctx, &mm, fx_username).await?;
+        let routes = fx_routes(
+            mm.clone(),
+            MockOidcUser {
+                email: Some("someone_else@ies.test".to_string()),
+                email_verified: None,
+                preferred_username: Some(fx_username.to_string()),
+            },
+            "preferred_username",
+        )
+        .await?;
+
+        // -- Exec
+        let res = exec_flow(&routes, "/api/oidc/login").await?;
+
+        // -- Check
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert!(
+            auth_token_cookie(&res).is_some(),
+            "Should have set the auth token"
+        );
+
+        // -- Clean
+        UserBmc::delete(&ctx, &mm, fx_user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_oidc_flow_center_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_code = "oidc_test_center";
+        let fx_username = "oidc_test_center@ies.test";
+        let center_c = CenterForCreate {
+            code: fx_code.to_string(),
+            name: "IES oidc_test_center".to_string(),
+        };
+        let center_id = CenterBmc::create(&ctx, &mm, center_c).await?;
+        let center_ctx = Ctx::root_ctx().with_center(center_id);
+        let fx_user_id = _dev_utils::seed_user(&center_ctx, &mm, fx_username).await?;
+        let routes = fx_routes(
+            mm.clone(),
+            MockOidcUser {
+                email: Some(fx_username.to_string()),
+                email_verified: Some(true),
+                preferred_username: None,
+            },
+            "email",
+        )
+        .await?;
+
+        // -- Exec
+        let res = exec_flow(&routes, &format!("/api/oidc/login?center={fx_code}")).await?;
+        let default_res = exec_flow(&routes, "/api/oidc/login").await?;
+
+        // -- Check
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert!(
+            auth_token_cookie(&res).is_some(),
+            "Should have set the auth token"
+        );
+        // Not in the default center.
+        let error = default_res
+            .extensions()
+            .get::<Arc<Error>>()
+            .map(Arc::as_ref);
+        assert!(
+            matches!(error, Some(Error::LoginFailUsernameNotFound)),
+            "Should have matched `Error::LoginFailUsernameNotFound` but was `{error:?}`"
+        );
+
+        // -- Clean
+        UserBmc::delete(&center_ctx, &mm, fx_user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_oidc_flow_err_username_not_found() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let routes = fx_routes(
+            mm,
+            MockOidcUser {
+                email: Some("oidc_test_unknown@ies.test".to_string()),
+                email_verified: Some(true),
+                preferred_username: None,
+            },
+            "email",
+        )
+        .await?;
+
+        // -- Exec
+        let res = exec_flow(&routes, "/api/oidc/login").await?;
+
+        // -- Check
+        let error = res.extensions().get::<Arc<Error>>().map(Arc::as_ref);
+        assert!(
+            matches!(error, Some(Error::LoginFailUsernameNotFound)),
+            "Should have matched `Error::LoginFailUsernameNotFound` but was `{error:?}`"
+        );
+        assert!(auth_token_cookie(&res).is_none());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_oidc_flow_err_email_not_verified() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_username = "oidc_test_not_verified@ies.test";
+        let fx_user_id = _dev_utils::seed_user(&ctx, &mm, fx_username).await?;
+        let routes = fx_routes(
+            mm.clone(),
+            MockOidcUser {
+                email: Some(fx_username.to_string()),
+                email_verified: Some(false),
+                preferred_username: None,
+            },
+            "email",
+        )
+        .await?;
+
+        // -- Exec
+        let res = exec_flow(&routes, "/api/oidc/login").await?;
+
+        // -- Check
+        let error = res.extensions().get::<Arc<Error>>().map(Arc::as_ref);
+        assert!(
+            matches!(error, Some(Error::OidcEmailNotVerified)),
+            "Should have matched `Error::OidcEmailNotVerified` but was `{error:?}`"
+        );
+        assert!(auth_token_cookie(&res).is_none());
+
+        // -- Clean
+        UserBmc::delete(&ctx, &mm, fx_user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_oidc_callback_err_state_mismatch() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let routes = fx_routes(
+            mm,
+            MockOidcUser {
+                email: Some("admin".to_string()),
+                email_verified: Some(true),
+                preferred_username: None,
+            },
+            "email",
+        )
+        .await?;
+
+        // -- Exec
+        // A login started by this browser, but a callback with another state
+        // (e.g., the attacker one, to log the victim in its account).
+        let res = routes
+            .clone()
+            .oneshot(Request::get("/api/oidc/login").body(Body::empty())?)
+            .await?;
+        let state_cookie = state_cookie(&res)?;
+        let res = routes
+            .oneshot(
+                Request::get("/api/oidc/callback?code=forged&state=forged")
+                    .header(header::COOKIE, state_cookie)
+                    .body(Body::empty())?,
+            )
+            .await?;
+
+        // -- Check
+        let error = res.extensions().get::<Arc<Error>>().map(Arc::as_ref);
+        assert!(
+            matches!(error, Some(Error::OidcStateMismatch)),
+            "Should have matched `Error::OidcStateMismatch` but was `{error:?}`"
+        );
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_oidc_callback_err_unknown_state() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let routes = fx_routes(
+            mm,
+            MockOidcUser {
+                email: Some("admin".to_string()),
+                email_verified: Some(true),
+                preferred_username: None,
+            },
+            "email",
+        )
+        .await?;
+
+        // -- Exec
+        let res = routes
+            .oneshot(
+                Request::get("/api/oidc/callback?code=forged&state=forged")
+                    .header(header::COOKIE, format!("{OIDC_STATE}=forged"))
+                    .body(Body::empty())?,
+            )
+            .await?;
+
+        // -- Check
+        let error = res.extensions().get::<Arc<Error>>().map(Arc::as_ref);
+        assert!(
+            matches!(error, Some(Error::OidcStateNotFound)),
+            "Should have matched `Error::OidcStateNotFound` but was `{error:?}`"
+        );
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
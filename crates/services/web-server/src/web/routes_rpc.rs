@@ -1,16 +1,24 @@
 use std::sync::Arc;
 
-use axum::{Json, Router};
-use axum::extract::State;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::routing::post;
+use axum::{Json, Router};
+use futures::future::join_all;
 use serde_json::{json, Value};
+use tracing::debug;
+use uuid::Uuid;
 
+use lib_core::model::lesson_plan::ATTACHMENT_MAX_SIZE;
 use lib_core::model::ModelManager;
-use lib_rpc::{building_rpc, center_schedule_hour_rpc, classroom_rpc, classroom_type_rpc, department_rpc, group_rpc, schedule_hour_rpc, schedule_rpc, subject_rpc, user_rpc, RpcRequest, RpcResources};
 use lib_rpc::router::RpcRouter;
+use lib_rpc::{RpcRequest, RpcResources};
 
+use crate::web;
 use crate::web::mw_auth::CtxW;
+use crate::web::mw_stamp::ReqStamp;
 
 /// The RpcState is the Axum State that will
 /// be used for the Axum RPC router handler.
@@ -27,66 +35,392 @@ pub struct RpcState {
 pub struct RpcInfo {
     pub id: Option<Value>,
     pub method: String,
+    /// No `id` member, so no response body (even on error).
+    pub notification: bool,
 }
 
+/// Up to an `ATTACHMENT_MAX_SIZE` attachment base64url encoded (4/3 of it),
+/// plus the json around it (axum default is 2 MB).
+const RPC_BODY_MAX_SIZE: usize = ATTACHMENT_MAX_SIZE.div_ceil(3) * 4 + 1024 * 1024;
+
 // Axum router for '/api/rpc'
 pub fn routes(rpc_state: RpcState) -> Router {
-
-    // Build the combined RpcRouter.
-    let rpc_router = RpcRouter::new()
-        .extend(user_rpc::rpc_router())
-        .extend(department_rpc::rpc_router())
-        .extend(subject_rpc::rpc_router())
-        .extend(classroom_rpc::rpc_router())
-        .extend(schedule_rpc::rpc_router())
-        .extend(schedule_hour_rpc::rpc_router())
-        .extend(center_schedule_hour_rpc::rpc_router())
-        .extend(group_rpc::rpc_router())
-        .extend(building_rpc::rpc_router())
-        .extend(classroom_type_rpc::rpc_router());
+    // The combined RpcRouter (see `lib_rpc::all_rpc_router`).
+    let rpc_router = lib_rpc::all_rpc_router();
 
     // Build the Axum Router for '/rpc'
     Router::new()
         .route("/rpc", post(rpc_axum_handler))
+        .layer(DefaultBodyLimit::max(RPC_BODY_MAX_SIZE))
         .with_state((rpc_state, Arc::new(rpc_router)))
 }
 
+/// JSON-RPC 2.0 handler, for a single request object or a batch (array) of them.
+///
+/// - Batch items are executed concurrently, and answered in one array
+///   (per item errors are in the array, the http status stays 200).
+/// - Notifications (no `id`) are executed but never answered
+///   (`204 No Content` when nothing is left to answer).
 async fn rpc_axum_handler(
     State((rpc_state, rpc_router)): State<(RpcState, Arc<RpcRouter>)>,
     ctx: CtxW,
-    Json(rpc_req): Json<RpcRequest>,
+    req_stamp: ReqStamp,
+    body: core::result::Result<Json<Value>, JsonRejection>,
 ) -> Response {
     let ctx = ctx.0;
+    let rpc_resources = RpcResources {
+        ctx: Some(ctx),
+        mm: rpc_state.mm,
+    };
+
+    let body = match body {
+        Ok(Json(body)) => body,
+        Err(rej) => return web::Error::RpcRequestParsing(rej.body_text()).into_response(),
+    };
+
+    match body {
+        Value::Array(items) if items.is_empty() => {
+            web::Error::RpcInvalidRequest("Empty batch".to_string()).into_response()
+        }
+        Value::Array(items) => rpc_batch(&rpc_router, rpc_resources, items, req_stamp.uuid).await,
+        item => rpc_single(&rpc_router, rpc_resources, item).await,
+    }
+}
+
+/// Single request, errors are answered by `mw_reponse_map` (from the `RpcInfo`).
+async fn rpc_single(rpc_router: &RpcRouter, rpc_resources: RpcResources, item: Value) -> Response {
+    let (rpc_req, notification) = match parse_rpc_request(item) {
+        Ok(parsed) => parsed,
+        Err(err) => return err.into_response(),
+    };
 
     // -- Create the RPC Info
     //    (will be set to the response.extensions)
     let rpc_info = RpcInfo {
         id: rpc_req.id.clone(),
         method: rpc_req.method.clone(),
-    };
-    let rpc_method = &rpc_info.method;
-    let rpc_params = rpc_req.params;
-    let rpc_resources = RpcResources {
-        ctx: Some(ctx),
-        mm: rpc_state.mm,
+        notification,
     };
 
     // -- Exec Rpc Route
-    let res = rpc_router.call(rpc_method, rpc_resources, rpc_params).await;
+    let res = rpc_router
+        .call(&rpc_info.method, rpc_resources, rpc_req.params)
+        .await;
 
     // -- Build Rpc Success Response
     let res = res.map(|v| {
-        let body_response = json!({
-            "id": rpc_info.id,
-            "result": v
-        });
-        Json(body_response)
+        if notification {
+            return StatusCode::NO_CONTENT.into_response();
+        }
+        Json(rpc_result_response(rpc_info.id.as_ref(), v)).into_response()
     });
 
     // -- Create and Update Axum Response
-    let res: crate::web::Result<_> = res.map_err(crate::web::Error::from);
+    let res: web::Result<_> = res.map_err(web::Error::from);
     let mut res = res.into_response();
     res.extensions_mut().insert(Arc::new(rpc_info));
 
     res
 }
+
+async fn rpc_batch(
+    rpc_router: &RpcRouter,
+    rpc_resources: RpcResources,
+    items: Vec<Value>,
+    req_uuid: Uuid,
+) -> Response {
+    let calls = items.into_iter().map(|item| {
+        let rpc_resources = rpc_resources.clone();
+        async move { rpc_batch_item(rpc_router, rpc_resources, item, &req_uuid).await }
+    });
+    let responses: Vec<Value> = join_all(calls).await.into_iter().flatten().collect();
+
+    if responses.is_empty() {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        Json(Value::Array(responses)).into_response()
+    }
+}
+
+/// Response object of one batch item, `None` for notifications.
+async fn rpc_batch_item(
+    rpc_router: &RpcRouter,
+    rpc_resources: RpcResources,
+    item: Value,
+    req_uuid: &Uuid,
+) -> Option<Value> {
+    let (rpc_req, notification) = match parse_rpc_request(item) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            let (_, client_error) = err.client_status_and_error();
+            return Some(client_error.to_rpc_response(None, req_uuid));
+        }
+    };
+
+    let res = rpc_router
+        .call(&rpc_req.method, rpc_resources, rpc_req.params)
+        .await
+        .map_err(web::Error::from);
+
+    if notification {
+        if let Err(err) = &res {
+            debug!(
+                "{:<12} - notification {} - {err:?}",
+                "RPC_BATCH", rpc_req.method
+            );
+        }
+        return None;
+    }
+
+    let response = match res {
+        Ok(result) => rpc_result_response(rpc_req.id.as_ref(), result),
+        Err(err) => {
+            debug!("{:<12} - {} - {err:?}", "RPC_BATCH", rpc_req.method);
+            let (_, client_error) = err.client_status_and_error();
+            client_error.to_rpc_response(rpc_req.id.as_ref(), req_uuid)
+        }
+    };
+
+    Some(response)
+}
+
+/// The request, and whether it is a notification (no `id` member).
+fn parse_rpc_request(item: Value) -> web::Result<(RpcRequest, bool)> {
+    let notification = item.is_object() && item.get("id").is_none();
+
+    let rpc_req: RpcRequest =
+        serde_json::from_value(item).map_err(|ex| web::Error::RpcInvalidRequest(ex.to_string()))?;
+    if !rpc_req.is_valid_version() {
+        return Err(web::Error::RpcInvalidRequest(format!(
+            "jsonrpc must be \"{}\"",
+            RpcRequest::JSONRPC_VERSION
+        )));
+    }
+
+    Ok((rpc_req, notification))
+}
+
+fn rpc_result_response(id: Option<&Value>, result: Value) -> Value {
+    json!({
+        "jsonrpc": RpcRequest::JSONRPC_VERSION,
+        "id": id,
+        "result": result
+    })
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request};
+    use axum::middleware;
+    use serial_test::serial;
+    use time::macros::date;
+    use tower::ServiceExt;
+    use tower_cookies::CookieManagerLayer;
+
+    use lib_auth::token::generate_web_token;
+    use lib_core::_dev_utils;
+    use lib_core::ctx::Ctx;
+    use lib_core::model::lesson_plan::{LessonPlanBmc, LessonPlanForCreate};
+    use lib_core::model::schedule::ScheduleBmc;
+    use lib_core::model::user::{UserBmc, UserForAuth, UserForProvision};
+    use lib_utils::b64::b64u_encode;
+
+    use crate::web::mw_auth::mw_ctx_resolve;
+    use crate::web::mw_res_map::mw_reponse_map;
+    use crate::web::mw_stamp::mw_req_stamp;
+    use crate::web::AUTH_TOKEN;
+
+    use super::*;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_rpc_attachment_max_size_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let root: UserForAuth = UserBmc::get(&ctx, &mm, 0).await?;
+        let fx_token = generate_web_token("0", root.token_salt)?;
+        let schedule_id = _dev_utils::seed_schedule(&ctx, &mm, 2025, 0, -1).await?;
+        let schedule_hour_id = _dev_utils::seed_schedule_hour(
+            &ctx,
+            &mm,
+            schedule_id,
+            "Matemáticas",
+            "Info 2",
+            0,
+            1,
+            2025,
+        )
+        .await?;
+        let lesson_plan_c = LessonPlanForCreate {
+            schedule_hour_id,
+            course: 2025,
+            date: date!(2025 - 10 - 06),
+            text: "test_rpc_attachment_max_size_ok".to_string(),
+        };
+        let lesson_plan_id = LessonPlanBmc::create(&ctx, &mm, lesson_plan_c).await?;
+        let fx_content = b64u_encode(vec![b'a'; ATTACHMENT_MAX_SIZE]);
+        let fx_body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "add_lesson_plan_attachment",
+            "params": {"data": {
+                "lesson_plan_id": lesson_plan_id,
+                "filename": "Ejercicios.txt",
+                "content_type": "text/plain",
+                "content": fx_content
+            }}
+        });
+        let routes = routes(RpcState { mm: mm.clone() })
+            .layer(middleware::from_fn_with_state(mm.clone(), mw_ctx_resolve))
+            .layer(CookieManagerLayer::new())
+            .layer(middleware::from_fn(mw_req_stamp));
+
+        // -- Exec
+        let res = routes
+            .oneshot(
+                Request::post("/rpc")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(header::COOKIE, format!("{AUTH_TOKEN}={fx_token}"))
+                    .body(Body::from(serde_json::to_vec(&fx_body)?))?,
+            )
+            .await?;
+
+        // -- Check
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = serde_json::from_slice(&to_bytes(res.into_body(), usize::MAX).await?)?;
+        assert_eq!(body["result"]["filename"], "Ejercicios.txt", "{body}");
+
+        // -- Clean
+        LessonPlanBmc::delete(&ctx, &mm, lesson_plan_id).await?;
+        ScheduleBmc::delete(&ctx, &mm, schedule_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_rpc_get_err_not_found() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let root: UserForAuth = UserBmc::get(&ctx, &mm, 0).await?;
+        let fx_token = generate_web_token("0", root.token_salt)?;
+        let fx_id = 100;
+        let fx_body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "get_lesson_plan",
+            "params": {"id": fx_id}
+        });
+        let routes = routes(RpcState { mm: mm.clone() })
+            .layer(middleware::map_response(mw_reponse_map))
+            .layer(middleware::from_fn_with_state(mm.clone(), mw_ctx_resolve))
+            .layer(middleware::from_fn(mw_req_stamp))
+            .layer(CookieManagerLayer::new());
+
+        // -- Exec
+        let res = routes
+            .oneshot(
+                Request::post("/rpc")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(header::COOKIE, format!("{AUTH_TOKEN}={fx_token}"))
+                    .body(Body::from(serde_json::to_vec(&fx_body)?))?,
+            )
+            .await?;
+
+        // -- Check
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body: Value = serde_json::from_slice(&to_bytes(res.into_body(), usize::MAX).await?)?;
+        assert_eq!(body["error"]["code"], -32003, "{body}");
+        assert_eq!(body["error"]["message"], "ENTITY_NOT_FOUND", "{body}");
+        assert_eq!(body["error"]["data"]["detail"]["id"], fx_id, "{body}");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_rpc_bulk_item_err_client_error() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_user_id = UserBmc::provision(
+            &ctx,
+            &mm,
+            UserForProvision {
+                username: "rpc_test_bulk_item_err_admin".to_string(),
+                is_admin: true,
+                active: true,
+                department_id: None,
+                auth_source: "local".to_string(),
+            },
+        )
+        .await?;
+        let user: UserForAuth = UserBmc::get(&ctx, &mm, fx_user_id).await?;
+        let fx_token = generate_web_token(&fx_user_id.to_string(), user.token_salt)?;
+        let fx_create_body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "create_schedule_hours",
+            "params": {"mode": "per_item", "data": [{
+                "schedule_id": 100, // Unknown schedule (foreign key violation)
+                "subject_name": "Matemáticas",
+                "classroom_name": "Info 2",
+                "week_day": 0,
+                "n_hour": 1,
+                "course": 2025
+            }]}
+        });
+        let fx_delete_body = json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "delete_schedule_hours",
+            "params": {"mode": "per_item", "ids": [100]}
+        });
+        let routes = routes(RpcState { mm: mm.clone() })
+            .layer(middleware::from_fn_with_state(mm.clone(), mw_ctx_resolve))
+            .layer(middleware::from_fn(mw_req_stamp))
+            .layer(CookieManagerLayer::new());
+
+        // -- Exec
+        let mut bodies = Vec::new();
+        for fx_body in [fx_create_body, fx_delete_body] {
+            let res = routes
+                .clone()
+                .oneshot(
+                    Request::post("/rpc")
+                        .header(header::CONTENT_TYPE, "application/json")
+                        .header(header::COOKIE, format!("{AUTH_TOKEN}={fx_token}"))
+                        .body(Body::from(serde_json::to_vec(&fx_body)?))?,
+                )
+                .await?;
+            assert_eq!(res.status(), StatusCode::OK);
+            let body: Value =
+                serde_json::from_slice(&to_bytes(res.into_body(), usize::MAX).await?)?;
+            bodies.push(body);
+        }
+
+        // -- Check
+        let create_error = &bodies[0]["result"][0]["error"];
+        assert_eq!(
+            create_error,
+            &json!({"code": -32603, "message": "SERVICE_ERROR"}),
+            "{}",
+            bodies[0]
+        );
+        let delete_error = &bodies[1]["result"][0]["error"];
+        assert_eq!(delete_error["code"], -32003, "{}", bodies[1]);
+        assert_eq!(delete_error["message"], "ENTITY_NOT_FOUND", "{}", bodies[1]);
+        assert_eq!(delete_error["data"]["detail"]["id"], 100, "{}", bodies[1]);
+
+        // -- Clean
+        UserBmc::delete(&ctx, &mm, fx_user_id).await?;
+
+        Ok(())
+    }
+}
+// endregion: --- Tests
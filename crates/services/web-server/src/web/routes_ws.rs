@@ -0,0 +1,151 @@
+//! `/api/ws` push channel of the model events (see `lib_core::model::event`).
+//!
+//! - Same auth cookie as `/api/rpc` (`mw_ctx_require`).
+//! - Each event is one json text message,
+//!   e.g., `{"type": "CheckIn", "data": {"center_id": 1, "user_id": 1000}}`.
+//! - Only the events of the subscriber's center, and `ScheduleChanged` only for its own
+//!   schedules.
+//! - Messages from the client are ignored, only the close ends the push.
+
+use std::collections::HashSet;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::debug;
+
+use lib_core::model::event::ModelEvent;
+use lib_core::model::schedule::ScheduleBmc;
+use lib_core::model::ModelManager;
+
+use crate::web::mw_auth::CtxW;
+use crate::web::Result;
+
+// Axum router for '/api/ws'
+pub fn routes(mm: ModelManager) -> Router {
+    Router::new().route("/ws", get(ws_handler)).with_state(mm)
+}
+
+async fn ws_handler(
+    State(mm): State<ModelManager>,
+    ctx: CtxW,
+    ws: WebSocketUpgrade,
+) -> Result<Response> {
+    let ctx = ctx.0;
+
+    // Resolved once, for the `ScheduleChanged` filter.
+    let schedule_ids = ScheduleBmc::get_teacher_schedule(&ctx, &mm, ctx.user_id())
+        .await?
+        .into_iter()
+        .map(|schedule| schedule.id)
+        .collect();
+
+    let center_id = ctx.center_id();
+
+    Ok(ws.on_upgrade(move |socket| push_events(socket, mm, center_id, schedule_ids)))
+}
+
+async fn push_events(
+    mut socket: WebSocket,
+    mm: ModelManager,
+    center_id: Option<i64>,
+    schedule_ids: HashSet<i64>,
+) {
+    let mut events = mm.subscribe();
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        debug!("{:<12} - lagged, {skipped} events skipped", "WS");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if !is_for_subscriber(&event, center_id, &schedule_ids) {
+                    continue;
+                }
+                let Ok(text) = serde_json::to_string(&event) else {
+                    continue;
+                };
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    debug!("{:<12} - subscriber closed", "WS");
+}
+
+fn is_for_subscriber(
+    event: &ModelEvent,
+    center_id: Option<i64>,
+    schedule_ids: &HashSet<i64>,
+) -> bool {
+    if !event.is_for_center(center_id) {
+        return false;
+    }
+
+    match event {
+        ModelEvent::ScheduleChanged { schedule_id, .. } => schedule_ids.contains(schedule_id),
+        _ => true,
+    }
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use lib_core::ctx::DEFAULT_CENTER_ID;
+
+    use super::*;
+
+    #[test]
+    fn test_is_for_subscriber_other_center_skipped() {
+        // -- Setup & Fixtures
+        let fx_other_center_id = 2;
+        let fx_schedule_ids = HashSet::from([1000]);
+        let fx_events = [
+            ModelEvent::CheckIn {
+                center_id: DEFAULT_CENTER_ID,
+                user_id: 1000,
+            },
+            ModelEvent::CheckIn {
+                center_id: fx_other_center_id,
+                user_id: 1001,
+            },
+            ModelEvent::ScheduleChanged {
+                center_id: DEFAULT_CENTER_ID,
+                schedule_id: 1000,
+            },
+            ModelEvent::ScheduleChanged {
+                center_id: fx_other_center_id,
+                schedule_id: 1000,
+            },
+            ModelEvent::Announcement {
+                center_id: fx_other_center_id,
+                author_id: 1001,
+                message: "test_is_for_subscriber_other_center_skipped".to_string(),
+            },
+        ];
+
+        // -- Exec
+        let delivered: Vec<bool> = fx_events
+            .iter()
+            .map(|event| is_for_subscriber(event, Some(DEFAULT_CENTER_ID), &fx_schedule_ids))
+            .collect();
+
+        // -- Check
+        assert_eq!(delivered, [true, false, true, false, false]);
+    }
+}
+// endregion: --- Tests
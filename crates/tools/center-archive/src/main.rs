@@ -0,0 +1,68 @@
+use std::fs;
+
+use anyhow::{bail, Result};
+
+use lib_core::ctx::{Ctx, DEFAULT_CENTER_ID};
+use lib_core::model::archive::{ArchiveBmc, CenterArchive};
+use lib_core::model::center::{Center, CenterBmc, CenterForCreate};
+use lib_core::model::ModelManager;
+
+const USAGE: &str = "usage: center-archive (export|import) <archive.json> [<center code>]";
+
+/// `cargo run -p center-archive -- export center.json` writes the center data
+/// of the `SERVICE_DB_URL` database, `import` restores it into an empty center
+/// (see `lib_core::model::archive`).
+///
+/// Without `<center code>` it is the default center, `import` creates the center when missing.
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let (Some(command), Some(path)) = (args.next(), args.next()) else {
+        bail!(USAGE);
+    };
+    let center_code = args.next();
+
+    let mm = ModelManager::new().await?;
+    let root_ctx = Ctx::root_ctx();
+
+    match command.as_str() {
+        "export" => {
+            let center_id = match &center_code {
+                Some(code) => match CenterBmc::first_by_code::<Center>(&root_ctx, &mm, code).await?
+                {
+                    Some(center) => center.id,
+                    None => bail!("center '{code}' not found"),
+                },
+                None => DEFAULT_CENTER_ID,
+            };
+            let ctx = root_ctx.with_center(center_id);
+            let archive = ArchiveBmc::export(&ctx, &mm).await?;
+            fs::write(&path, serde_json::to_string_pretty(&archive)?)?;
+            println!("Center archive written to {path}");
+        }
+        "import" => {
+            let center_id = match &center_code {
+                Some(code) => match CenterBmc::first_by_code::<Center>(&root_ctx, &mm, code).await?
+                {
+                    Some(center) => center.id,
+                    None => {
+                        let center_c = CenterForCreate {
+                            code: code.clone(),
+                            name: code.clone(),
+                        };
+                        CenterBmc::create(&root_ctx, &mm, center_c).await?
+                    }
+                },
+                None => DEFAULT_CENTER_ID,
+            };
+            let ctx = root_ctx.with_center(center_id);
+            let archive: CenterArchive = serde_json::from_str(&fs::read_to_string(&path)?)?;
+            let report = ArchiveBmc::import(&ctx, &mm, archive).await?;
+            println!("Center archive {path} imported:");
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        _ => bail!(USAGE),
+    }
+
+    Ok(())
+}
@@ -1,9 +1,26 @@
 use anyhow::Result;
 use rand::RngCore;
 
+use lib_push::VapidKeys;
 use lib_utils::b64::b64u_encode;
 
+/// `cargo run -p gen-key` for the pwd/token keys,
+/// `cargo run -p gen-key -- vapid` for the Web Push VAPID keys.
 fn main() -> Result<()> {
+    if std::env::args().nth(1).as_deref() == Some("vapid") {
+        let keys = VapidKeys::generate();
+        println!(
+            "\nVAPID private key (SERVICE_VAPID_PRIVATE_KEY):\n{}",
+            keys.private_key()
+        );
+        println!(
+            "\nVAPID public key (PWA applicationServerKey):\n{}",
+            keys.public_key()
+        );
+
+        return Ok(());
+    }
+
     let mut key = [0u8; 64]; // 512 bits = 64 bytes
     rand::thread_rng().fill_bytes(&mut key);
     println!("\nGenerated key from rand::thread_rng():\n{key:?}");
@@ -0,0 +1,22 @@
+use std::fs;
+
+use anyhow::Result;
+
+use lib_rpc::all_rpc_router;
+
+/// Writes the `rpc.discover` OpenRPC document to the given path (or stdout),
+/// e.g., for the TypeScript client generation (see `open-rpc-generator-config.json`).
+fn main() -> Result<()> {
+    let doc = all_rpc_router().discover();
+    let doc = serde_json::to_string_pretty(&doc)?;
+
+    match std::env::args().nth(1) {
+        Some(path) => {
+            fs::write(&path, doc)?;
+            println!("OpenRPC document written to {path}");
+        }
+        None => println!("{doc}"),
+    }
+
+    Ok(())
+}
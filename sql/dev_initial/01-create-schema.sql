@@ -1,10 +1,10 @@
 ---- Base app schema
--- Departments
-CREATE TABLE "departments"
+-- Centers (tenants, one school each, see `CenterBmc`)
+CREATE TABLE "centers"
 (
     id    BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
-    name  varchar(256)             NOT NULL UNIQUE,
-
+    code  varchar(64)              NOT NULL UNIQUE, -- login center, e.g., "ies-jerte"
+    name  varchar(256)             NOT NULL,
 
     -- Timestamps
     cid   bigint                   NOT NULL,
@@ -13,12 +13,41 @@ CREATE TABLE "departments"
     mtime timestamp with time zone NOT NULL
 );
 
+-- Default center (at id = 1), of the single school deployments.
+INSERT INTO "centers" (id, code, name, cid, ctime, mid, mtime)
+VALUES (1, 'default', 'Centro', 0, now(), 0, now());
+
+-- Departments
+CREATE TABLE "departments"
+(
+    id         BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    center_id  BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
+    name       varchar(256)             NOT NULL,
+
+
+    -- Soft delete (see `PostgresDbBmc::SOFT_DELETE`)
+    deleted_at timestamp with time zone DEFAULT null,
+    deleted_by bigint                   DEFAULT null,
+
+    -- Timestamps
+    cid        bigint                   NOT NULL,
+    ctime      timestamp with time zone NOT NULL,
+    mid        bigint                   NOT NULL,
+    mtime      timestamp with time zone NOT NULL
+);
+
+-- Note: The soft deleted rows do not keep their unique keys (e.g., the name can be reused).
+CREATE UNIQUE INDEX departments_center_id_name_key ON departments (center_id, name) WHERE deleted_at IS NULL;
+
 -- Users
 CREATE TABLE "users"
 (
     id              BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
-    username        varchar(128)             NOT NULL UNIQUE,
+    center_id       BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
+    username        varchar(128)             NOT NULL,
     is_admin        bool                     NOT NULL DEFAULT false,
+    is_super_admin  bool                     NOT NULL DEFAULT false, -- provisions the centers
+    is_head_of_studies bool                  NOT NULL DEFAULT false, -- alerted of the uncovered classes
     last_checkin    TIME                     NOT NULL DEFAULT '00:00:00',
     last_checkout   TIME                     NOT NULL DEFAULT '00:00:00',
     in_center       bool                     NOT NULL DEFAULT false,
@@ -29,9 +58,15 @@ CREATE TABLE "users"
 
 
     -- Auth
+    auth_source     varchar(32)              NOT NULL DEFAULT 'local', -- local, ldap
     pwd             varchar(256),
     pwd_salt        uuid                     NOT NULL DEFAULT gen_random_uuid(),
     token_salt      uuid                     NOT NULL DEFAULT gen_random_uuid(),
+    calendar_salt   uuid                     NOT NULL DEFAULT gen_random_uuid(), -- iCalendar feed token
+
+    -- Soft delete (see `PostgresDbBmc::SOFT_DELETE`)
+    deleted_at      timestamp with time zone DEFAULT null,
+    deleted_by      bigint                   DEFAULT null,
 
     -- Timestamps
     cid             bigint                   NOT NULL,
@@ -42,15 +77,22 @@ CREATE TABLE "users"
     FOREIGN KEY (department_id) REFERENCES departments (id)
 );
 
+CREATE UNIQUE INDEX users_center_id_username_key ON users (center_id, username) WHERE deleted_at IS NULL;
+
 -- Subjects
 CREATE TABLE "subjects"
 (
     id               BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    center_id        BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
     name             varchar(256)             NOT NULL,
     department_id    BIGINT                   NOT NULL DEFAULT 0,
     is_guard         bool                     NOT NULL DEFAULT false,
     is_complementary bool                     NOT NULL DEFAULT false,
 
+    -- Soft delete (see `PostgresDbBmc::SOFT_DELETE`)
+    deleted_at       timestamp with time zone DEFAULT null,
+    deleted_by       bigint                   DEFAULT null,
+
     -- Timestamps
     cid              bigint                   NOT NULL,
     ctime            timestamp with time zone NOT NULL,
@@ -65,26 +107,33 @@ CREATE TABLE "subjects"
 CREATE TABLE "groups"
 (
     id         BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    center_id  BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
     course     int                      NOT NULL, -- 1º, 2º
     stage      int                      NOT NULL, -- ESO, BACHILLER, FP
     year       int                      NOT NULL, -- 2024/2025
     letter     varchar(20), -- A, B, C
     tutor_name varchar(128),
 
+    -- Soft delete (see `PostgresDbBmc::SOFT_DELETE`)
+    deleted_at timestamp with time zone DEFAULT null,
+    deleted_by bigint                   DEFAULT null,
+
     -- Timestamps
     cid        bigint                   NOT NULL,
     ctime      timestamp with time zone NOT NULL,
     mid        bigint                   NOT NULL,
-    mtime      timestamp with time zone NOT NULL,
-
-    UNIQUE (course, stage, year, letter)
+    mtime      timestamp with time zone NOT NULL
 );
 
+CREATE UNIQUE INDEX groups_center_id_course_stage_year_letter_key
+    ON groups (center_id, course, stage, year, letter) WHERE deleted_at IS NULL;
+
 
 
 CREATE TABLE buildings
 (
     id         BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000),
+    center_id  BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
     building_name        varchar(256),
 
     -- Timestamps
@@ -93,13 +142,14 @@ CREATE TABLE buildings
     mid        bigint                   NOT NULL,
     mtime      timestamp with time zone NOT NULL,
     PRIMARY KEY (id),
-    UNIQUE (building_name)
+    UNIQUE (center_id, building_name)
 );
 
 
 CREATE TABLE classroom_types
 (
     id         BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000),
+    center_id  BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
     type_name        varchar(256),
 
     -- Timestamps
@@ -108,20 +158,25 @@ CREATE TABLE classroom_types
     mid        bigint                   NOT NULL,
     mtime      timestamp with time zone NOT NULL,
     PRIMARY KEY (id),
-    UNIQUE (type_name)
+    UNIQUE (center_id, type_name)
 );
 
 -- Room
 CREATE TABLE "classrooms"
 (
     id          BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    center_id   BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
     building    BIGINT,
     floor       int,
     number      int,
-    name        varchar(256) UNIQUE,
+    name        varchar(256),
     type_c      BIGINT,
     description varchar(256),
 
+    -- Soft delete (see `PostgresDbBmc::SOFT_DELETE`)
+    deleted_at  timestamp with time zone DEFAULT null,
+    deleted_by  bigint                   DEFAULT null,
+
     -- Timestamps
     cid         bigint                   NOT NULL,
     ctime       timestamp with time zone NOT NULL,
@@ -129,15 +184,19 @@ CREATE TABLE "classrooms"
     mtime       timestamp with time zone NOT NULL,
 
     FOREIGN KEY (building) REFERENCES buildings (id),
-    FOREIGN KEY (type_c) REFERENCES classroom_types (id),
-    UNIQUE (building, floor, number)
+    FOREIGN KEY (type_c) REFERENCES classroom_types (id)
 );
 
+CREATE UNIQUE INDEX classrooms_center_id_name_key ON classrooms (center_id, name) WHERE deleted_at IS NULL;
+CREATE UNIQUE INDEX classrooms_building_floor_number_key
+    ON classrooms (building, floor, number) WHERE deleted_at IS NULL;
+
 
 -- Schedules
 CREATE TABLE schedules
 (
     id       BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000),
+    center_id BIGINT                  NOT NULL DEFAULT 1 REFERENCES centers (id),
     user_id  BIGINT REFERENCES users (id) ON DELETE CASCADE,
     group_id BIGINT REFERENCES groups (id),
     course   INT                      NOT NULL, -- 2024/2025
@@ -162,6 +221,7 @@ CREATE TABLE schedules_2026 PARTITION OF schedules FOR VALUES FROM (2026) TO (20
 CREATE TABLE schedule_hours
 (
     id             BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000),
+    center_id      BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
     schedule_id    BIGINT                   NOT NULL,
     subject_name   varchar(256)             NOT NULL,
     classroom_name varchar(256)             NOT NULL,
@@ -183,6 +243,7 @@ CREATE TABLE schedule_hours
 CREATE TABLE center_schedule_hours
 (
     id         BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000),
+    center_id  BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
     n_hour     INT                      NOT NULL,
     start_time TIME                     NOT NULL,
     end_time   TIME                     NOT NULL,
@@ -193,7 +254,7 @@ CREATE TABLE center_schedule_hours
     mid        bigint                   NOT NULL,
     mtime      timestamp with time zone NOT NULL,
     PRIMARY KEY (id),
-    UNIQUE (n_hour)
+    UNIQUE (center_id, n_hour)
 );
 
 
@@ -203,3 +264,186 @@ CREATE TABLE schedule_hours_2024 PARTITION OF schedule_hours FOR VALUES FROM (20
 CREATE TABLE schedule_hours_2025 PARTITION OF schedule_hours FOR VALUES FROM (2025) TO (2026);
 CREATE TABLE schedule_hours_2026 PARTITION OF schedule_hours FOR VALUES FROM (2026) TO (2027);
 
+
+-- Substitutions, one per absent teacher hour (written by `ControlBmc::update_guards`, see `SubstitutionBmc`)
+CREATE TABLE substitutions
+(
+    id             BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    center_id      BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
+    date           date                     NOT NULL,
+    n_hour         INT                      NOT NULL,
+    absent_id      BIGINT                   NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    covering_id    BIGINT REFERENCES users (id) ON DELETE SET NULL,
+    group_id       BIGINT REFERENCES groups (id) ON DELETE SET NULL,
+    classroom_name varchar(256)             NOT NULL,
+    status         varchar(16)              NOT NULL DEFAULT 'planned', -- planned, covered, uncovered
+
+    -- Timestamps
+    cid            bigint                   NOT NULL,
+    ctime          timestamp with time zone NOT NULL,
+    mid            bigint                   NOT NULL,
+    mtime          timestamp with time zone NOT NULL,
+
+    UNIQUE (center_id, date, n_hour, absent_id)
+);
+
+CREATE INDEX substitutions_covering_idx ON substitutions (covering_id, date);
+
+-- Lesson plans, the work left by an absent teacher for a class (see `LessonPlanBmc`)
+CREATE TABLE lesson_plans
+(
+    id               BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    center_id        BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
+    schedule_hour_id BIGINT                   NOT NULL,
+    course           INT                      NOT NULL,
+    date             date                     NOT NULL,
+    text             text                     NOT NULL,
+
+    -- Timestamps
+    cid              bigint                   NOT NULL,
+    ctime            timestamp with time zone NOT NULL,
+    mid              bigint                   NOT NULL,
+    mtime            timestamp with time zone NOT NULL,
+
+    FOREIGN KEY (schedule_hour_id, course) REFERENCES schedule_hours (id, course) ON DELETE CASCADE,
+    UNIQUE (schedule_hour_id, date)
+);
+
+CREATE TABLE lesson_plan_attachments
+(
+    id             BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    center_id      BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
+    lesson_plan_id BIGINT                   NOT NULL REFERENCES lesson_plans (id) ON DELETE CASCADE,
+    filename       varchar(256)             NOT NULL,
+    content_type   varchar(128)             NOT NULL,
+    content        bytea                    NOT NULL,
+
+    -- Timestamps
+    cid            bigint                   NOT NULL,
+    ctime          timestamp with time zone NOT NULL
+);
+
+-- Display tokens (staff-room display terminals, see `DisplayTokenBmc`)
+CREATE TABLE display_tokens
+(
+    id         BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    center_id  BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
+    name       varchar(128)             NOT NULL,
+    expires_at timestamp with time zone NOT NULL,
+    token_salt uuid                     NOT NULL DEFAULT gen_random_uuid(),
+
+    -- Timestamps
+    cid        bigint                   NOT NULL,
+    ctime      timestamp with time zone NOT NULL,
+    mid        bigint                   NOT NULL,
+    mtime      timestamp with time zone NOT NULL
+);
+
+
+-- School calendar, dates of each course (see `CalendarBmc`)
+CREATE TABLE course_calendars
+(
+    center_id  BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
+    course     INT                      NOT NULL,
+    start_date date                     NOT NULL,
+    end_date   date                     NOT NULL,
+
+    -- Timestamps
+    cid        bigint                   NOT NULL,
+    ctime      timestamp with time zone NOT NULL,
+    mid        bigint                   NOT NULL,
+    mtime      timestamp with time zone NOT NULL,
+    PRIMARY KEY (center_id, course),
+    CHECK (start_date <= end_date)
+);
+
+-- School holidays (no lessons)
+CREATE TABLE holidays
+(
+    id        BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    center_id BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
+    date      date                     NOT NULL,
+    name      varchar(128)             NOT NULL,
+
+    -- Timestamps
+    cid       bigint                   NOT NULL,
+    ctime     timestamp with time zone NOT NULL,
+    mid       bigint                   NOT NULL,
+    mtime     timestamp with time zone NOT NULL,
+
+    UNIQUE (center_id, date)
+);
+
+
+-- Notification preferences (one row per user, defaults when none, see `NotificationBmc`)
+CREATE TABLE notification_prefs
+(
+    user_id          BIGINT PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    center_id        BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
+    email            varchar(256),
+    guard_assigned   bool                     NOT NULL DEFAULT true,
+    leave_approved   bool                     NOT NULL DEFAULT true,
+    missing_checkout bool                     NOT NULL DEFAULT true,
+
+    -- Timestamps
+    cid              bigint                   NOT NULL,
+    ctime            timestamp with time zone NOT NULL,
+    mid              bigint                   NOT NULL,
+    mtime            timestamp with time zone NOT NULL
+);
+
+-- Notifications outbox (written with the change, delivered by `NotificationBmc::deliver_pending`)
+CREATE TABLE notification_outbox
+(
+    id              BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    user_id         BIGINT                   NOT NULL,
+    center_id       BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
+    kind            varchar(32)              NOT NULL, -- guard_assigned, leave_approved, password_reset, missing_checkout, class_uncovered
+    to_addr         varchar(256)             NOT NULL,
+    subject         varchar(256)             NOT NULL,
+    body            text                     NOT NULL,
+    status          varchar(16)              NOT NULL DEFAULT 'pending', -- pending, sent, failed
+    attempts        int                      NOT NULL DEFAULT 0,
+    last_error      text,
+    next_attempt_at timestamp with time zone NOT NULL,
+    sent_at         timestamp with time zone,
+    ctime           timestamp with time zone NOT NULL
+);
+
+CREATE INDEX notification_outbox_pending_idx ON notification_outbox (next_attempt_at) WHERE status = 'pending';
+
+-- Web Push subscriptions of the teacher PWA (one per user device, see `PushSubscriptionBmc`)
+CREATE TABLE push_subscriptions
+(
+    id          BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    user_id     BIGINT                   NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    center_id   BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
+    endpoint    varchar(1024)            NOT NULL,
+    p256dh      varchar(128)             NOT NULL,
+    auth        varchar(64)              NOT NULL,
+    device_name varchar(128),
+
+    -- Timestamps
+    cid         bigint                   NOT NULL,
+    ctime       timestamp with time zone NOT NULL,
+    mid         bigint                   NOT NULL,
+    mtime       timestamp with time zone NOT NULL,
+
+    UNIQUE (center_id, endpoint)
+);
+
+
+-- Audit log (written by the model `base` functions, same transaction as the mutation)
+CREATE TABLE audit_log
+(
+    id         BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    center_id  BIGINT                   NOT NULL DEFAULT 1 REFERENCES centers (id),
+    table_name varchar(64)              NOT NULL,
+    entity_id  BIGINT                   NOT NULL,
+    actor_id   BIGINT                   NOT NULL, -- ctx.user_id (0 for root)
+    op         varchar(16)              NOT NULL, -- create, update, delete, restore
+    diff       jsonb                    NOT NULL, -- {"column": {"old": .., "new": ..}}
+    ctime      timestamp with time zone NOT NULL
+);
+
+CREATE INDEX audit_log_entity_idx ON audit_log (table_name, entity_id);
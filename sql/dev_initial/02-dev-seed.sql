@@ -22,10 +22,10 @@ VALUES ('Francés', 0, now(), 0, now());
 
 -- Users
 -- Admin user (at id = 0)
--- root user (at id = 0)
+-- root user (at id = 0), super admin of the centers
 INSERT INTO "users"
-(id, username, cid, ctime, mid, mtime)
-VALUES (0, 'root', 0, now(), 0, now());
+(id, username, is_super_admin, cid, ctime, mid, mtime)
+VALUES (0, 'root', true, 0, now(), 0, now());
 
 INSERT INTO "users" (username, is_admin, active, department_id, cid, ctime, mid, mtime)
 VALUES ('admin', true, true, 1, 0, now(), 0, now());